@@ -0,0 +1,279 @@
+//! Pooled resource allocation
+
+use crate::{
+    CommittedResourceBuilder, D3DResult, Device, Heap, HeapFlags, HeapProperties, Resource,
+    ResourceStates,
+};
+use std::ops::Range;
+use winapi::{shared::winerror, um::d3d12};
+
+struct PoolHeap {
+    heap: Heap,
+    /// Free ranges of the heap, sorted and never adjacent.
+    free: Vec<Range<u64>>,
+}
+
+impl PoolHeap {
+    fn allocate(&mut self, size: u64, alignment: u64) -> Option<Range<u64>> {
+        let (index, start) = self.free.iter().enumerate().find_map(|(index, range)| {
+            let start = (range.start + alignment - 1) / alignment * alignment;
+            if start + size <= range.end {
+                Some((index, start))
+            } else {
+                None
+            }
+        })?;
+
+        let range = self.free.remove(index);
+        let end = start + size;
+        if end < range.end {
+            self.free.insert(index, end..range.end);
+        }
+        if range.start < start {
+            self.free.insert(index, range.start..start);
+        }
+        Some(start..end)
+    }
+
+    fn free(&mut self, mut range: Range<u64>) {
+        let index = self
+            .free
+            .iter()
+            .position(|other| other.start > range.start)
+            .unwrap_or(self.free.len());
+        if index < self.free.len() && self.free[index].start == range.end {
+            range.end = self.free.remove(index).end;
+        }
+        if index > 0 && self.free[index - 1].end == range.start {
+            self.free[index - 1].end = range.end;
+        } else {
+            self.free.insert(index, range);
+        }
+    }
+}
+
+struct Placement {
+    heap_index: usize,
+    range: Range<u64>,
+}
+
+/// Resource returned by a [`ResourceAllocator`], either committed or placed into a pooled heap.
+pub struct Allocation {
+    resource: Resource,
+    placement: Option<Placement>,
+}
+
+impl Allocation {
+    pub fn resource(&self) -> &Resource {
+        &self.resource
+    }
+
+    pub fn is_placed(&self) -> bool {
+        self.placement.is_some()
+    }
+
+    /// Releases the resource, returning its heap range to `allocator` if it was placed.
+    ///
+    /// The GPU must be done with the resource.
+    pub fn free(self, allocator: &mut ResourceAllocator) {
+        drop(self.resource);
+        if let Some(placement) = self.placement {
+            allocator.heaps[placement.heap_index].free(placement.range);
+        }
+    }
+}
+
+/// Allocates resources up to `placement_threshold` bytes as placed resources in pooled heaps,
+/// and larger ones as committed resources.
+///
+/// All heaps share the same properties and flags, which must be compatible with
+/// the resources on resource heap tier 1 hardware, e.g. `HeapFlags::ALLOW_ONLY_BUFFERS`.
+pub struct ResourceAllocator {
+    heap_properties: d3d12::D3D12_HEAP_PROPERTIES,
+    heap_flags: HeapFlags,
+    heap_size: u64,
+    placement_threshold: u64,
+    heaps: Vec<PoolHeap>,
+}
+
+impl ResourceAllocator {
+    /// Returns `None` if `placement_threshold` exceeds `heap_size`, as such resources
+    /// wouldn't fit into a pooled heap.
+    pub fn new(
+        heap_properties: HeapProperties,
+        heap_flags: HeapFlags,
+        heap_size: u64,
+        placement_threshold: u64,
+    ) -> Option<Self> {
+        if placement_threshold > heap_size {
+            return None;
+        }
+        Some(ResourceAllocator {
+            heap_properties: heap_properties.0,
+            heap_flags,
+            heap_size,
+            placement_threshold,
+            heaps: Vec::new(),
+        })
+    }
+
+    pub fn allocate(
+        &mut self,
+        device: &Device,
+        desc: &d3d12::D3D12_RESOURCE_DESC,
+        initial_state: ResourceStates,
+        clear_value: Option<&d3d12::D3D12_CLEAR_VALUE>,
+    ) -> D3DResult<Allocation> {
+        let info = unsafe { device.GetResourceAllocationInfo(0, 1, desc) };
+        if info.SizeInBytes == u64::MAX {
+            let allocation = Allocation {
+                resource: Resource::null(),
+                placement: None,
+            };
+            return (allocation, winerror::E_INVALIDARG);
+        }
+
+        if info.SizeInBytes > self.placement_threshold {
+            let mut builder =
+                CommittedResourceBuilder::new(HeapProperties(self.heap_properties), *desc)
+                    .heap_flags(self.heap_flags)
+                    .initial_state(initial_state);
+            if let Some(value) = clear_value {
+                builder = builder.clear_value(*value);
+            }
+            let (resource, hr) = device.create_committed_resource(builder);
+            let allocation = Allocation {
+                resource,
+                placement: None,
+            };
+            return (allocation, hr);
+        }
+
+        let found = self.heaps.iter_mut().enumerate().find_map(|(index, heap)| {
+            heap.allocate(info.SizeInBytes, info.Alignment)
+                .map(|range| (index, range))
+        });
+        let (heap_index, range) = match found {
+            Some(found) => found,
+            None => {
+                let (heap, hr) = device.create_heap(
+                    self.heap_size,
+                    HeapProperties(self.heap_properties),
+                    0,
+                    self.heap_flags,
+                );
+                if winerror::FAILED(hr) {
+                    let allocation = Allocation {
+                        resource: Resource::null(),
+                        placement: None,
+                    };
+                    return (allocation, hr);
+                }
+                let mut heap = PoolHeap {
+                    heap,
+                    free: vec![0..self.heap_size],
+                };
+                // Sizes up to `placement_threshold` always fit into an empty heap.
+                let range = match heap.allocate(info.SizeInBytes, info.Alignment) {
+                    Some(range) => range,
+                    None => {
+                        let allocation = Allocation {
+                            resource: Resource::null(),
+                            placement: None,
+                        };
+                        return (allocation, winerror::E_INVALIDARG);
+                    }
+                };
+                self.heaps.push(heap);
+                (self.heaps.len() - 1, range)
+            }
+        };
+
+        let heap = &mut self.heaps[heap_index];
+        let (resource, hr) = device.create_placed_resource(
+            &heap.heap,
+            range.start,
+            desc,
+            initial_state,
+            clear_value,
+        );
+        if winerror::FAILED(hr) {
+            heap.free(range);
+            let allocation = Allocation {
+                resource,
+                placement: None,
+            };
+            return (allocation, hr);
+        }
+
+        let allocation = Allocation {
+            resource,
+            placement: Some(Placement { heap_index, range }),
+        };
+        (allocation, hr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_above_heap_size() {
+        let properties = HeapProperties::standard(crate::HeapType::Default);
+        assert!(ResourceAllocator::new(properties, HeapFlags::empty(), 1 << 16, 1 << 17).is_none());
+        assert!(ResourceAllocator::new(properties, HeapFlags::empty(), 1 << 16, 1 << 16).is_some());
+    }
+
+    #[test]
+    fn pool_heap_ranges() {
+        let mut heap = PoolHeap {
+            heap: Heap::null(),
+            free: vec![0..1024],
+        };
+        let a = heap.allocate(100, 256).unwrap();
+        let b = heap.allocate(100, 256).unwrap();
+        assert_eq!(a, 0..100);
+        assert_eq!(b, 256..356);
+        assert_eq!(heap.allocate(1024, 256), None);
+
+        heap.free(a);
+        heap.free(b);
+        assert_eq!(heap.free, vec![0..1024]);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn placed_and_committed() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let properties = HeapProperties::standard(crate::HeapType::Default);
+        let mut allocator =
+            ResourceAllocator::new(properties, HeapFlags::ALLOW_ONLY_BUFFERS, 1 << 20, 1 << 18)
+                .unwrap();
+        let buffer = |size| CommittedResourceBuilder::default_buffer(size).desc;
+        let allocate = |allocator: &mut ResourceAllocator, size| {
+            let (allocation, hr) =
+                allocator.allocate(&device, &buffer(size), ResourceStates::COMMON, None);
+            assert!(winerror::SUCCEEDED(hr));
+            assert!(!allocation.resource().is_null());
+            allocation
+        };
+
+        let small = allocate(&mut allocator, 1 << 16);
+        let large = allocate(&mut allocator, 1 << 19);
+        assert!(small.is_placed());
+        assert!(!large.is_placed());
+        let range = small.placement.as_ref().unwrap().range.clone();
+
+        // The freed range is handed out again, without a new heap.
+        small.free(&mut allocator);
+        large.free(&mut allocator);
+        let again = allocate(&mut allocator, 1 << 16);
+        assert_eq!(again.placement.as_ref().unwrap().range, range);
+        assert_eq!(allocator.heaps.len(), 1);
+        again.free(&mut allocator);
+    }
+}
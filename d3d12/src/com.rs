@@ -1,11 +1,11 @@
-use crate::D3DResult;
+use crate::{D3DResult, HRESULT};
 use std::{
     fmt,
     hash::{Hash, Hasher},
     ops::Deref,
     ptr,
 };
-use winapi::{ctypes::c_void, um::unknwnbase::IUnknown, Interface};
+use winapi::{ctypes::c_void, shared::winerror, um::unknwnbase::IUnknown, Interface};
 
 #[repr(transparent)]
 pub struct ComPtr<T: Interface>(*mut T);
@@ -41,6 +41,15 @@ impl<T: Interface> ComPtr<T> {
     pub fn mut_self(&mut self) -> *mut *mut T {
         &mut self.0 as *mut *mut _
     }
+
+    /// Clones the pointer, or returns `None` if it's null.
+    pub fn try_clone(&self) -> Option<Self> {
+        if self.is_null() {
+            None
+        } else {
+            Some(self.clone())
+        }
+    }
 }
 
 impl<T: Interface> ComPtr<T> {
@@ -60,13 +69,32 @@ impl<T: Interface> ComPtr<T> {
             .QueryInterface(&U::uuidof(), obj.mut_void());
         (obj, hr)
     }
+
+    /// Queries `U` and releases `self` on success, giving `self` back on failure.
+    pub fn cast_into<U>(self) -> Result<ComPtr<U>, (Self, HRESULT)>
+    where
+        U: Interface,
+    {
+        if self.is_null() {
+            return Err((self, winerror::E_POINTER));
+        }
+        let (obj, hr) = unsafe { self.cast::<U>() };
+        if winerror::SUCCEEDED(hr) {
+            Ok(obj)
+        } else {
+            Err((self, hr))
+        }
+    }
 }
 
 impl<T: Interface> Clone for ComPtr<T> {
     fn clone(&self) -> Self {
         debug_assert!(!self.is_null());
-        unsafe {
-            self.as_unknown().AddRef();
+        // Cloning a null pointer yields another null pointer, there is nothing to AddRef.
+        if !self.is_null() {
+            unsafe {
+                self.as_unknown().AddRef();
+            }
         }
         ComPtr(self.0)
     }
@@ -123,6 +151,10 @@ impl<T: Interface> Hash for ComPtr<T> {
 /// - the as function (`&self -> Option<ComPtr<actual::ComObject1>>`)
 /// - the unwrap function (`&self -> ComPtr<actual::ComObject1>` panicing on failure to cast)
 ///
+/// Prefixing the enum with `@checked` makes debug builds verify the ordering: the first time each
+/// accessor converts to a parent interface, it asserts the object `QueryInterface`s to that parent
+/// at the same address. Release builds are unaffected.
+///
 /// ```rust
 /// # pub use d3d12::weak_com_inheritance_chain;
 /// # mod actual {
@@ -137,20 +169,67 @@ impl<T: Interface> Hash for ComPtr<T> {
 ///         MyComObject2(actual::ComObject2), from_my_com_object2, as_my_com_object2, unwrap_my_com_object2;
 ///     }
 /// }
+///
+/// weak_com_inheritance_chain! {
+///     @checked
+///     pub enum MyCheckedComObject {
+///         MyComObject(actual::ComObject), from_my_com_object, as_my_com_object, my_com_object;
+///         MyComObject1(actual::ComObject1), from_my_com_object1, as_my_com_object1, unwrap_my_com_object1;
+///     }
+/// }
 /// ```
 #[macro_export]
 macro_rules! weak_com_inheritance_chain {
     // We first match a human readable enum style, before going into the recursive section.
     //
     // Internal calls to the macro have either the prefix
+    // - @entry for the expansion of the enum, with the mode being `checked` or `unchecked`
     // - @recursion_logic for the recursion and termination
     // - @render_members for the actual call to fill in the members.
+    // - @check_cast for the debug validation of conversions to parent interfaces.
     (
         $(#[$meta:meta])*
         $vis:vis enum $name:ident {
             $first_variant:ident($first_type:ty), $first_from_name:ident, $first_as_name:ident, $first_unwrap_name:ident $(;)?
             $($variant:ident($type:ty), $from_name:ident, $as_name:ident, $unwrap_name:ident);* $(;)?
         }
+    ) => {
+        $crate::weak_com_inheritance_chain! {
+            @entry,
+            unchecked,
+            $(#[$meta])*
+            $vis enum $name {
+                $first_variant($first_type), $first_from_name, $first_as_name, $first_unwrap_name;
+                $($variant($type), $from_name, $as_name, $unwrap_name);*
+            }
+        }
+    };
+    (
+        @checked
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $first_variant:ident($first_type:ty), $first_from_name:ident, $first_as_name:ident, $first_unwrap_name:ident $(;)?
+            $($variant:ident($type:ty), $from_name:ident, $as_name:ident, $unwrap_name:ident);* $(;)?
+        }
+    ) => {
+        $crate::weak_com_inheritance_chain! {
+            @entry,
+            checked,
+            $(#[$meta])*
+            $vis enum $name {
+                $first_variant($first_type), $first_from_name, $first_as_name, $first_unwrap_name;
+                $($variant($type), $from_name, $as_name, $unwrap_name);*
+            }
+        }
+    };
+    (
+        @entry,
+        $mode:ident,
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $first_variant:ident($first_type:ty), $first_from_name:ident, $first_as_name:ident, $first_unwrap_name:ident $(;)?
+            $($variant:ident($type:ty), $from_name:ident, $as_name:ident, $unwrap_name:ident);* $(;)?
+        }
     ) => {
         $(#[$meta])*
         $vis enum $name {
@@ -162,6 +241,7 @@ macro_rules! weak_com_inheritance_chain {
         impl $name {
             $crate::weak_com_inheritance_chain! {
                 @recursion_logic,
+                $mode,
                 $vis,
                 ;
                 $first_variant($first_type), $first_from_name, $first_as_name, $first_unwrap_name;
@@ -182,6 +262,7 @@ macro_rules! weak_com_inheritance_chain {
     // variant name, not the functions names, as those are not needed.
     (
         @recursion_logic,
+        $mode:ident,
         $vis:vis,
         $(,)? $($prev_variant:ident),* $(,)?;
         $this_variant:ident($this_type:ty), $this_from_name:ident, $this_as_name:ident, $this_unwrap_name:ident $(;)?
@@ -190,6 +271,7 @@ macro_rules! weak_com_inheritance_chain {
         // Actually generate the members for this variant. Needs the previous and future variant names.
         $crate::weak_com_inheritance_chain! {
             @render_members,
+            $mode,
             $vis,
             $this_from_name, $this_as_name, $this_unwrap_name;
             $($prev_variant),*;
@@ -200,6 +282,7 @@ macro_rules! weak_com_inheritance_chain {
         // Recurse on ourselves. If there is no future variants left, we'll hit the base case as the final expansion returns no tokens.
         $crate::weak_com_inheritance_chain! {
             @recursion_logic,
+            $mode,
             $vis,
             $($prev_variant),* , $this_variant;
             $($next_variant($next_type), $next_from_name, $next_as_name, $next_unwrap_name);*
@@ -208,6 +291,7 @@ macro_rules! weak_com_inheritance_chain {
     // Base case for recursion. There are no more variants left
     (
         @recursion_logic,
+        $mode:ident,
         $vis:vis,
         $($prev_variant:ident),*;
     ) => {};
@@ -216,6 +300,7 @@ macro_rules! weak_com_inheritance_chain {
     // This is where we generate the members using the given names.
     (
         @render_members,
+        $mode:ident,
         $vis:vis,
         $from_name:ident, $as_name:ident, $unwrap_name:ident;
         $($prev_variant:ident),*;
@@ -236,6 +321,7 @@ macro_rules! weak_com_inheritance_chain {
                 Self::$variant(ref v) => Some(v),
                 $(
                     Self::$next_variant(ref v) => {
+                        $crate::weak_com_inheritance_chain!(@check_cast, $mode, v, $type);
                         // v is &ComPtr<NextType> and se cast to &ComPtr<Type>
                         Some(unsafe { std::mem::transmute(v) })
                     }
@@ -253,6 +339,7 @@ macro_rules! weak_com_inheritance_chain {
                 Self::$variant(ref v) => &*v,
                 $(
                     Self::$next_variant(ref v) => {
+                        $crate::weak_com_inheritance_chain!(@check_cast, $mode, v, $type);
                         // v is &ComPtr<NextType> and se cast to &ComPtr<Type>
                         unsafe { std::mem::transmute(v) }
                     }
@@ -260,4 +347,223 @@ macro_rules! weak_com_inheritance_chain {
             }
         }
     };
+
+    // Conversions are plain transmutes, only checked in debug builds of `@checked` chains.
+    (@check_cast, unchecked, $value:ident, $type:ty) => {};
+    (@check_cast, checked, $value:ident, $type:ty) => {
+        #[cfg(debug_assertions)]
+        {
+            // Ordering mistakes affect all objects alike, checking the first conversion is enough.
+            // Null pointers can't be queried and must not use up the check.
+            static CHECKED: std::sync::Once = std::sync::Once::new();
+            if !$value.is_null() {
+                CHECKED.call_once(|| {
+                    let (parent, hr) = unsafe { $value.cast::<$type>() };
+                    assert!(
+                        hr >= 0,
+                        concat!("Object doesn't implement its parent interface ", stringify!($type))
+                    );
+                    assert_eq!(
+                        parent.as_ptr() as *const (),
+                        $value.as_ptr() as *const (),
+                        concat!("Object isn't at the same address as ", stringify!($type))
+                    );
+                });
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+// The chains generate accessors not all used here, and the vtable is only read through
+// raw pointers.
+#[allow(dead_code)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use winapi::{
+        shared::{
+            guiddef::{IsEqualGUID, REFIID},
+            minwindef::ULONG,
+        },
+        um::unknwnbase::IUnknownVtbl,
+        RIDL,
+    };
+
+    RIDL! {#[uuid(0x3a1c4f0e, 0x5b7d, 0x4e21, 0x9c, 0x62, 0x0d, 0x8e, 0x4b, 0x31, 0xa7, 0x10)]
+    interface ITestLevel0(ITestLevel0Vtbl): IUnknown(IUnknownVtbl) {}}
+    RIDL! {#[uuid(0x3a1c4f0e, 0x5b7d, 0x4e21, 0x9c, 0x62, 0x0d, 0x8e, 0x4b, 0x31, 0xa7, 0x11)]
+    interface ITestLevel1(ITestLevel1Vtbl): ITestLevel0(ITestLevel0Vtbl) {}}
+    RIDL! {#[uuid(0x3a1c4f0e, 0x5b7d, 0x4e21, 0x9c, 0x62, 0x0d, 0x8e, 0x4b, 0x31, 0xa7, 0x12)]
+    interface ITestLevel2(ITestLevel2Vtbl): ITestLevel1(ITestLevel1Vtbl) {}}
+
+    /// COM object implementing the levels marked in `implemented`.
+    #[repr(C)]
+    struct TestObject {
+        vtbl: *const IUnknownVtbl,
+        refs: AtomicU32,
+        implemented: [bool; 3],
+    }
+
+    unsafe extern "system" fn query_interface(
+        this: *mut IUnknown,
+        riid: REFIID,
+        object: *mut *mut c_void,
+    ) -> HRESULT {
+        let levels = [
+            ITestLevel0::uuidof(),
+            ITestLevel1::uuidof(),
+            ITestLevel2::uuidof(),
+        ];
+        let implemented = &(*(this as *const TestObject)).implemented;
+        let found = IsEqualGUID(&*riid, &IUnknown::uuidof())
+            || levels
+                .iter()
+                .zip(implemented)
+                .any(|(iid, &implemented)| implemented && IsEqualGUID(&*riid, iid));
+        if found {
+            add_ref(this);
+            *object = this as *mut c_void;
+            winerror::S_OK
+        } else {
+            *object = ptr::null_mut();
+            winerror::E_NOINTERFACE
+        }
+    }
+
+    unsafe extern "system" fn add_ref(this: *mut IUnknown) -> ULONG {
+        (*(this as *const TestObject))
+            .refs
+            .fetch_add(1, Ordering::Relaxed)
+            + 1
+    }
+
+    unsafe extern "system" fn release(this: *mut IUnknown) -> ULONG {
+        let refs = (*(this as *const TestObject))
+            .refs
+            .fetch_sub(1, Ordering::Relaxed)
+            - 1;
+        if refs == 0 {
+            drop(Box::from_raw(this as *mut TestObject));
+        }
+        refs
+    }
+
+    static TEST_OBJECT_VTBL: IUnknownVtbl = IUnknownVtbl {
+        QueryInterface: query_interface,
+        AddRef: add_ref,
+        Release: release,
+    };
+
+    fn test_object<T: Interface>(implemented: [bool; 3]) -> ComPtr<T> {
+        let object = Box::new(TestObject {
+            vtbl: &TEST_OBJECT_VTBL,
+            refs: AtomicU32::new(0),
+            implemented,
+        });
+        unsafe { ComPtr::from_raw(Box::into_raw(object) as *mut T) }
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[test]
+    fn clone_null() {
+        let null = ComPtr::<ITestLevel0>::null();
+        assert!(null.clone().is_null());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn clone_null_asserts() {
+        let null = ComPtr::<ITestLevel0>::null();
+        let _ = null.clone();
+    }
+
+    #[test]
+    fn try_clone() {
+        assert!(ComPtr::<ITestLevel0>::null().try_clone().is_none());
+
+        let object = test_object::<ITestLevel0>([true; 3]);
+        let refs = || unsafe {
+            (*(object.as_ptr() as *const TestObject))
+                .refs
+                .load(Ordering::Relaxed)
+        };
+        assert_eq!(refs(), 1);
+        let clone = object.try_clone().unwrap();
+        assert!(clone == object);
+        assert_eq!(refs(), 2);
+        drop(clone);
+        assert_eq!(refs(), 1);
+    }
+
+    crate::weak_com_inheritance_chain! {
+        @checked
+        enum CheckedChain {
+            Level0(ITestLevel0), from_level0, as_level0, level0;
+            Level1(ITestLevel1), from_level1, as_level1, unwrap_level1;
+            Level2(ITestLevel2), from_level2, as_level2, unwrap_level2;
+        }
+    }
+
+    #[test]
+    fn checked_three_level_chain() {
+        let object = test_object::<ITestLevel2>([true; 3]);
+        let raw = object.as_ptr() as *const ();
+        let chain = unsafe { CheckedChain::from_level2(object) };
+        assert_eq!(chain.as_level0().unwrap().as_ptr() as *const (), raw);
+        assert_eq!(chain.as_level1().unwrap().as_ptr() as *const (), raw);
+        assert_eq!(chain.unwrap_level2().as_ptr() as *const (), raw);
+        assert_eq!(chain.level0().as_ptr() as *const (), raw);
+
+        let chain = unsafe { CheckedChain::from_level1(test_object([true, true, false])) };
+        assert!(chain.as_level0().is_some());
+        assert!(chain.as_level2().is_none());
+    }
+
+    crate::weak_com_inheritance_chain! {
+        @checked
+        enum MisorderedChain {
+            Level0(ITestLevel0), from_level0, as_level0, level0;
+            Level1(ITestLevel1), from_level1, as_level1, unwrap_level1;
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Object doesn't implement its parent interface")]
+    fn checked_after_null() {
+        // A null pointer first must not skip the check of the next conversion.
+        let chain = unsafe { MisorderedChain::from_level1(ComPtr::null()) };
+        assert!(chain.as_level0().unwrap().is_null());
+
+        let chain = unsafe { MisorderedChain::from_level1(test_object([false, true, false])) };
+        chain.as_level0();
+    }
+
+    #[test]
+    fn cast_into_refs() {
+        let object = test_object::<ITestLevel0>([true, true, false]);
+        let raw = object.as_ptr();
+        let refs = || unsafe { (*(raw as *const TestObject)).refs.load(Ordering::Relaxed) };
+        assert_eq!(refs(), 1);
+
+        // The failed query hands the same reference back.
+        let (object, hr) = match object.cast_into::<ITestLevel2>() {
+            Ok(_) => panic!("level 2 isn't implemented"),
+            Err(err) => err,
+        };
+        assert_eq!(hr, winerror::E_NOINTERFACE);
+        assert_eq!(object.as_ptr(), raw);
+        assert_eq!(refs(), 1);
+
+        // The upgrade adds a reference to the new interface and releases the old one.
+        let level1 = match object.cast_into::<ITestLevel1>() {
+            Ok(level1) => level1,
+            Err((_, hr)) => panic!("cast failed: {:#x}", hr),
+        };
+        assert_eq!(level1.as_ptr() as *const (), raw as *const ());
+        assert_eq!(refs(), 1);
+        drop(level1);
+    }
 }
@@ -1,7 +1,7 @@
 //! Command Allocator
 
-use crate::com::ComPtr;
-use winapi::um::d3d12;
+use crate::{com::ComPtr, Fence, HRESULT};
+use winapi::{shared::winerror, um::d3d12};
 
 pub type CommandAllocator = ComPtr<d3d12::ID3D12CommandAllocator>;
 
@@ -11,4 +11,65 @@ impl CommandAllocator {
             self.Reset();
         }
     }
+
+    /// Resets the allocator once `fence` reached `value`, i.e. the GPU is done with its lists.
+    ///
+    /// Returns `DXGI_ERROR_WAS_STILL_DRAWING` without resetting if the value isn't reached yet,
+    /// or the removal reason if the device got removed.
+    pub fn reset_after(&self, fence: &Fence, value: u64) -> HRESULT {
+        let (completed, hr) = fence.completed_value();
+        if winerror::FAILED(hr) {
+            return hr;
+        }
+        if completed < value {
+            return winerror::DXGI_ERROR_WAS_STILL_DRAWING;
+        }
+
+        unsafe { self.Reset() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn reset_after_signal() {
+        use crate::{CmdListType, CommandQueueFlags, OwnedEvent, Priority, WaitStatus};
+        use winapi::{shared::winerror, um::winbase};
+
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (allocator, list) = crate::test_command_list(&device);
+        assert!(winerror::SUCCEEDED(list.close()));
+        let (queue, hr) = device.create_command_queue(
+            CmdListType::Direct,
+            Priority::Normal,
+            CommandQueueFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let (fence, hr) = device.create_fence(0);
+        assert!(winerror::SUCCEEDED(hr));
+
+        queue.execute_graphics_command_lists(&[&list]);
+        assert!(winerror::SUCCEEDED(queue.signal(&fence, 1)));
+        // Nothing signals 2, so the allocator can't be reset against it.
+        assert_eq!(
+            allocator.reset_after(&fence, 2),
+            winerror::DXGI_ERROR_WAS_STILL_DRAWING
+        );
+
+        let (event, hr) = OwnedEvent::new(false);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(winerror::SUCCEEDED(
+            fence.set_event_on_completion(&event, 1)
+        ));
+        assert_eq!(event.wait(winbase::INFINITE), WaitStatus::Signaled);
+        assert!(winerror::SUCCEEDED(allocator.reset_after(&fence, 1)));
+        assert!(winerror::SUCCEEDED(
+            list.reset(&allocator, crate::PipelineState::null())
+        ));
+    }
 }
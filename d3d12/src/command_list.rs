@@ -1,15 +1,20 @@
 //! Graphics command list
 
 use crate::{
-    com::ComPtr, resource::DiscardRegion, CommandAllocator, CpuDescriptor, DescriptorHeap, Format,
-    GpuAddress, GpuDescriptor, IndexCount, InstanceCount, PipelineState, Rect, Resource, RootIndex,
-    RootSignature, Subresource, VertexCount, VertexOffset, WorkGroupCount, HRESULT,
+    com::ComPtr, resource::DiscardRegion, types, CommandAllocator, CpuDescriptor, DescriptorHeap,
+    Device, Format, GpuAddress, GpuDescriptor, IndexCount, InstanceCount, PipelineState, QueryHeap,
+    QueryType, Rect, Resource, ResourceStates, RootIndex, RootSignature, RootSignatureLayout,
+    StreamOutputStream, Subresource, VertexCount, VertexOffset, WorkGroupCount, HRESULT,
+};
+use std::{mem, ops::Range, ptr};
+use winapi::{
+    shared::{guiddef::GUID, winerror},
+    um::{d3d12, d3dcommon},
+    Interface,
 };
-use std::{mem, ptr};
-use winapi::um::d3d12;
 
 #[repr(u32)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CmdListType {
     Direct = d3d12::D3D12_COMMAND_LIST_TYPE_DIRECT,
     Bundle = d3d12::D3D12_COMMAND_LIST_TYPE_BUNDLE,
@@ -19,6 +24,84 @@ pub enum CmdListType {
     // VideoProcess = d3d12::D3D12_COMMAND_LIST_TYPE_VIDEO_PROCESS,
 }
 
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PredicationOp {
+    /// Skips the predicated commands if the value is zero.
+    EqualZero = d3d12::D3D12_PREDICATION_OP_EQUAL_ZERO,
+    NotEqualZero = d3d12::D3D12_PREDICATION_OP_NOT_EQUAL_ZERO,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolveMode {
+    /// Decompresses the source in place, `src` and `dst` being the same subresource.
+    Decompress = d3d12::D3D12_RESOLVE_MODE_DECOMPRESS,
+    Min = d3d12::D3D12_RESOLVE_MODE_MIN,
+    Max = d3d12::D3D12_RESOLVE_MODE_MAX,
+    Average = d3d12::D3D12_RESOLVE_MODE_AVERAGE,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrimitiveTopology {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+    LineListAdj,
+    LineStripAdj,
+    TriangleListAdj,
+    TriangleStripAdj,
+    /// Patches of control points, for tessellation.
+    PatchList(ControlPoints),
+}
+
+/// Number of control points of a patch, between 1 and 32.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ControlPoints(u32);
+
+impl ControlPoints {
+    pub const MAX: u32 = 32;
+
+    /// `None` if `count` is 0 or above [`Self::MAX`].
+    pub fn new(count: u32) -> Option<Self> {
+        if (1..=Self::MAX).contains(&count) {
+            Some(ControlPoints(count))
+        } else {
+            None
+        }
+    }
+
+    pub fn count(self) -> u32 {
+        self.0
+    }
+}
+
+impl PrimitiveTopology {
+    /// Patch list of `control_points` points, `None` if it isn't between 1 and 32.
+    pub fn patch_list(control_points: u32) -> Option<Self> {
+        ControlPoints::new(control_points).map(Self::PatchList)
+    }
+
+    fn to_raw(self) -> d3dcommon::D3D_PRIMITIVE_TOPOLOGY {
+        match self {
+            Self::PointList => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_POINTLIST,
+            Self::LineList => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_LINELIST,
+            Self::LineStrip => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_LINESTRIP,
+            Self::TriangleList => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
+            Self::TriangleStrip => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_TRIANGLESTRIP,
+            Self::LineListAdj => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_LINELIST_ADJ,
+            Self::LineStripAdj => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_LINESTRIP_ADJ,
+            Self::TriangleListAdj => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST_ADJ,
+            Self::TriangleStripAdj => d3dcommon::D3D_PRIMITIVE_TOPOLOGY_TRIANGLESTRIP_ADJ,
+            Self::PatchList(control_points) => {
+                d3dcommon::D3D_PRIMITIVE_TOPOLOGY_1_CONTROL_POINT_PATCHLIST + control_points.0 - 1
+            }
+        }
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
     pub struct ClearFlags: u32 {
@@ -27,6 +110,13 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct CommandListFlags: u32 {
+        const NONE = types::D3D12_COMMAND_LIST_FLAG_NONE;
+    }
+}
+
 #[repr(transparent)]
 pub struct IndirectArgument(d3d12::D3D12_INDIRECT_ARGUMENT_DESC);
 
@@ -113,7 +203,7 @@ impl IndirectArgument {
 }
 
 #[repr(transparent)]
-pub struct ResourceBarrier(d3d12::D3D12_RESOURCE_BARRIER);
+pub struct ResourceBarrier(pub(crate) d3d12::D3D12_RESOURCE_BARRIER);
 
 impl ResourceBarrier {
     pub fn transition(
@@ -123,6 +213,8 @@ impl ResourceBarrier {
         state_after: d3d12::D3D12_RESOURCE_STATES,
         flags: d3d12::D3D12_RESOURCE_BARRIER_FLAGS,
     ) -> Self {
+        debug_assert_eq!(ResourceStates::from(state_before).validate(), Ok(()));
+        debug_assert_eq!(ResourceStates::from(state_after).validate(), Ok(()));
         let mut barrier = d3d12::D3D12_RESOURCE_BARRIER {
             Type: d3d12::D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
             Flags: flags,
@@ -138,11 +230,335 @@ impl ResourceBarrier {
         }
         ResourceBarrier(barrier)
     }
+
+    /// Switches usage of a heap range between placed resources, `None` meaning
+    /// any resource that may alias.
+    pub fn aliasing(before: Option<&Resource>, after: Option<&Resource>) -> Self {
+        let mut barrier = d3d12::D3D12_RESOURCE_BARRIER {
+            Type: d3d12::D3D12_RESOURCE_BARRIER_TYPE_ALIASING,
+            Flags: d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            *barrier.u.Aliasing_mut() = d3d12::D3D12_RESOURCE_ALIASING_BARRIER {
+                pResourceBefore: before.map_or(ptr::null_mut(), |r| r.as_mut_ptr()),
+                pResourceAfter: after.map_or(ptr::null_mut(), |r| r.as_mut_ptr()),
+            };
+        }
+        ResourceBarrier(barrier)
+    }
+
+    /// Waits for outstanding UAV accesses to `resource`, or to all resources if `None`.
+    pub fn uav(resource: Option<&Resource>) -> Self {
+        let mut barrier = d3d12::D3D12_RESOURCE_BARRIER {
+            Type: d3d12::D3D12_RESOURCE_BARRIER_TYPE_UAV,
+            Flags: d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            barrier.u.UAV_mut().pResource = resource.map_or(ptr::null_mut(), |r| r.as_mut_ptr());
+        }
+        ResourceBarrier(barrier)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct Viewport(d3d12::D3D12_VIEWPORT);
+
+impl Viewport {
+    pub fn new(x: f32, y: f32, width: f32, height: f32, depth: Range<f32>) -> Self {
+        Viewport(d3d12::D3D12_VIEWPORT {
+            TopLeftX: x,
+            TopLeftY: y,
+            Width: width,
+            Height: height,
+            MinDepth: depth.start,
+            MaxDepth: depth.end,
+        })
+    }
+
+    /// Viewport at the origin over a `width` x `height` target, with the full depth range.
+    pub fn from_size(width: f32, height: f32) -> Self {
+        Self::new(0.0, 0.0, width, height, 0.0..1.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct VertexBufferView(d3d12::D3D12_VERTEX_BUFFER_VIEW);
+
+impl VertexBufferView {
+    /// View of `size` bytes of a buffer, starting `offset` bytes in.
+    pub fn new(resource: &Resource, offset: u64, size: u32, stride: u32) -> Self {
+        VertexBufferView(d3d12::D3D12_VERTEX_BUFFER_VIEW {
+            BufferLocation: resource.gpu_virtual_address().offset(offset).0,
+            SizeInBytes: size,
+            StrideInBytes: stride,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct IndexBufferView(d3d12::D3D12_INDEX_BUFFER_VIEW);
+
+impl IndexBufferView {
+    /// View of `size` bytes of a buffer, starting `offset` bytes in.
+    ///
+    /// `format` is either `DXGI_FORMAT_R16_UINT` or `DXGI_FORMAT_R32_UINT`.
+    pub fn new(resource: &Resource, offset: u64, size: u32, format: Format) -> Self {
+        IndexBufferView(d3d12::D3D12_INDEX_BUFFER_VIEW {
+            BufferLocation: resource.gpu_virtual_address().offset(offset).0,
+            SizeInBytes: size,
+            Format: format,
+        })
+    }
+}
+
+/// Source or destination of a texture copy.
+pub enum TextureCopyLocation {
+    /// Subresource of a texture.
+    Subresource(Resource, Subresource),
+    /// Region of a buffer laid out as a texture subresource.
+    Footprint(Resource, d3d12::D3D12_PLACED_SUBRESOURCE_FOOTPRINT),
+}
+
+/// CPU data of a subresource, see [`GraphicsCommandList::update_subresources`].
+#[derive(Clone, Copy, Debug)]
+pub struct SubresourceData<'a> {
+    pub data: &'a [u8],
+    /// Bytes between rows of texels, or of blocks for block-compressed formats.
+    pub row_pitch: usize,
+    /// Bytes between depth slices.
+    pub slice_pitch: usize,
+}
+
+impl TextureCopyLocation {
+    fn to_raw(&self) -> d3d12::D3D12_TEXTURE_COPY_LOCATION {
+        let mut raw: d3d12::D3D12_TEXTURE_COPY_LOCATION = unsafe { mem::zeroed() };
+        match *self {
+            TextureCopyLocation::Subresource(ref resource, subresource) => {
+                raw.pResource = resource.as_mut_ptr();
+                raw.Type = d3d12::D3D12_TEXTURE_COPY_TYPE_SUBRESOURCE_INDEX;
+                *unsafe { raw.u.SubresourceIndex_mut() } = subresource;
+            }
+            TextureCopyLocation::Footprint(ref resource, footprint) => {
+                raw.pResource = resource.as_mut_ptr();
+                raw.Type = d3d12::D3D12_TEXTURE_COPY_TYPE_PLACED_FOOTPRINT;
+                *unsafe { raw.u.PlacedFootprint_mut() } = footprint;
+            }
+        }
+        raw
+    }
+}
+
+/// Descriptor heaps bound on a command list, to skip redundant `SetDescriptorHeaps` calls.
+///
+/// Bindings don't survive a reset of the list, so neither should the cache.
+#[derive(Debug, Default)]
+pub struct BoundDescriptorHeaps {
+    heaps: Vec<DescriptorHeap>,
+}
+
+impl BoundDescriptorHeaps {
+    /// Binds `heaps` on `list`, unless they are already bound.
+    pub fn set_descriptor_heaps(&mut self, list: &GraphicsCommandList, heaps: &[DescriptorHeap]) {
+        if self.heaps.as_slice() == heaps {
+            return;
+        }
+        list.set_descriptor_heaps(heaps);
+        self.heaps.clear();
+        self.heaps.extend_from_slice(heaps);
+    }
+
+    pub fn reset(&mut self) {
+        self.heaps.clear();
+    }
+
+    /// Whether `descriptor` points into one of the bound shader-visible heaps.
+    pub fn contains_gpu_descriptor(&self, descriptor: GpuDescriptor) -> bool {
+        self.heaps
+            .iter()
+            .any(|heap| heap.contains_gpu_descriptor(descriptor))
+    }
+}
+
+fn check_root_descriptor_table(
+    layout: &RootSignatureLayout,
+    heaps: &BoundDescriptorHeaps,
+    root_index: RootIndex,
+    base_descriptor: GpuDescriptor,
+) {
+    if cfg!(debug_assertions) {
+        assert!(
+            layout.is_descriptor_table(root_index),
+            "Root parameter {} is not a descriptor table ({:?})",
+            root_index,
+            layout.parameter_type(root_index),
+        );
+        assert!(
+            heaps.contains_gpu_descriptor(base_descriptor),
+            "GPU descriptor {:#x} is not part of a bound shader-visible heap",
+            base_descriptor.ptr,
+        );
+    }
 }
 
 pub type CommandSignature = ComPtr<d3d12::ID3D12CommandSignature>;
 pub type CommandList = ComPtr<d3d12::ID3D12CommandList>;
 pub type GraphicsCommandList = ComPtr<d3d12::ID3D12GraphicsCommandList>;
+pub type GraphicsCommandList1 = ComPtr<d3d12::ID3D12GraphicsCommandList1>;
+pub type GraphicsCommandList2 = ComPtr<types::ID3D12GraphicsCommandList2>;
+pub type GraphicsCommandList3 = ComPtr<types::ID3D12GraphicsCommandList3>;
+pub type GraphicsCommandList4 = ComPtr<types::ID3D12GraphicsCommandList4>;
+pub type GraphicsCommandList5 = ComPtr<types::ID3D12GraphicsCommandList5>;
+pub type GraphicsCommandList6 = ComPtr<types::ID3D12GraphicsCommandList6>;
+crate::weak_com_inheritance_chain! {
+    #[derive(Debug, Clone, PartialEq, Hash)]
+    pub enum VersionedGraphicsCommandList {
+        GraphicsCommandList(d3d12::ID3D12GraphicsCommandList), from_command_list, as_command_list, command_list;
+        GraphicsCommandList1(d3d12::ID3D12GraphicsCommandList1), from_command_list1, as_command_list1, unwrap_command_list1;
+        GraphicsCommandList2(types::ID3D12GraphicsCommandList2), from_command_list2, as_command_list2, unwrap_command_list2;
+        GraphicsCommandList3(types::ID3D12GraphicsCommandList3), from_command_list3, as_command_list3, unwrap_command_list3;
+        GraphicsCommandList4(types::ID3D12GraphicsCommandList4), from_command_list4, as_command_list4, unwrap_command_list4;
+        GraphicsCommandList5(types::ID3D12GraphicsCommandList5), from_command_list5, as_command_list5, unwrap_command_list5;
+        GraphicsCommandList6(types::ID3D12GraphicsCommandList6), from_command_list6, as_command_list6, unwrap_command_list6;
+    }
+}
+
+impl VersionedGraphicsCommandList {
+    /// Wraps `list` as the highest version it implements.
+    pub fn new(list: GraphicsCommandList) -> Self {
+        unsafe {
+            let (list6, hr) = list.cast::<types::ID3D12GraphicsCommandList6>();
+            if winerror::SUCCEEDED(hr) {
+                return Self::from_command_list6(list6);
+            }
+            let (list5, hr) = list.cast::<types::ID3D12GraphicsCommandList5>();
+            if winerror::SUCCEEDED(hr) {
+                return Self::from_command_list5(list5);
+            }
+            let (list4, hr) = list.cast::<types::ID3D12GraphicsCommandList4>();
+            if winerror::SUCCEEDED(hr) {
+                return Self::from_command_list4(list4);
+            }
+            let (list3, hr) = list.cast::<types::ID3D12GraphicsCommandList3>();
+            if winerror::SUCCEEDED(hr) {
+                return Self::from_command_list3(list3);
+            }
+            let (list2, hr) = list.cast::<types::ID3D12GraphicsCommandList2>();
+            if winerror::SUCCEEDED(hr) {
+                return Self::from_command_list2(list2);
+            }
+            let (list1, hr) = list.cast::<d3d12::ID3D12GraphicsCommandList1>();
+            if winerror::SUCCEEDED(hr) {
+                return Self::from_command_list1(list1);
+            }
+            Self::from_command_list(list)
+        }
+    }
+
+    /// Dispatches mesh shader thread groups, `E_NOINTERFACE` is returned if the list
+    /// doesn't implement `ID3D12GraphicsCommandList6`.
+    ///
+    /// A mesh shader pipeline state must be bound.
+    pub fn dispatch_mesh(&self, x: u32, y: u32, z: u32) -> HRESULT {
+        match self.as_command_list6() {
+            Some(list) => {
+                list.dispatch_mesh(x, y, z);
+                winerror::S_OK
+            }
+            None => winerror::E_NOINTERFACE,
+        }
+    }
+}
+
+impl GraphicsCommandList1 {
+    /// Resolves `src_rect` of `src`, or all of it, to `dst` at `(dst_x, dst_y)`.
+    ///
+    /// `Min` and `Max` cover depth and integer formats, which can't be averaged.
+    /// `dst` must be in the `RESOLVE_DEST` state and `src` in the `RESOLVE_SOURCE` state.
+    pub fn resolve_subresource_region(
+        &self,
+        dst: &Resource,
+        dst_subresource: Subresource,
+        dst_x: u32,
+        dst_y: u32,
+        src: &Resource,
+        src_subresource: Subresource,
+        src_rect: Option<&Rect>,
+        format: Format,
+        mode: ResolveMode,
+    ) {
+        let src_rect = src_rect.map_or(ptr::null_mut(), |rect| rect as *const _ as *mut _);
+        unsafe {
+            self.ResolveSubresourceRegion(
+                dst.as_mut_ptr(),
+                dst_subresource,
+                dst_x,
+                dst_y,
+                src.as_mut_ptr(),
+                src_subresource,
+                src_rect,
+                format,
+                mode as _,
+            )
+        }
+    }
+
+    /// Overrides the sample positions of the following draws, `num_pixels` being 1, or 4
+    /// for a 2x2 pixel quad.
+    ///
+    /// Needs [`ProgrammableSamplePositionsTier::Tier1`](crate::ProgrammableSamplePositionsTier::Tier1),
+    /// and `Tier2` for quads.
+    /// Zero samples restore the default positions.
+    ///
+    /// Returns `E_INVALIDARG` without recording anything unless `positions` holds exactly
+    /// `num_samples_per_pixel * num_pixels` entries.
+    pub fn set_sample_positions(
+        &self,
+        num_samples_per_pixel: u32,
+        num_pixels: u32,
+        positions: &[d3d12::D3D12_SAMPLE_POSITION],
+    ) -> HRESULT {
+        let expected = (num_samples_per_pixel as usize).checked_mul(num_pixels as usize);
+        if expected != Some(positions.len()) {
+            return winerror::E_INVALIDARG;
+        }
+        unsafe {
+            self.SetSamplePositions(
+                num_samples_per_pixel,
+                num_pixels,
+                positions.as_ptr() as *mut _,
+            )
+        }
+        winerror::S_OK
+    }
+}
+
+impl GraphicsCommandList6 {
+    /// A mesh shader pipeline state must be bound.
+    pub fn dispatch_mesh(&self, x: u32, y: u32, z: u32) {
+        unsafe { self.DispatchMesh(x, y, z) }
+    }
+}
+
+/// Private data of a list, holding a `u32` set while the list is closed.
+const CLOSED_STATE_GUID: GUID = GUID {
+    Data1: 0xd5b5_f7a2,
+    Data2: 0x3c1e,
+    Data3: 0x4f6b,
+    Data4: [0x9a, 0x2d, 0x7e, 0x4c, 0x81, 0xf0, 0xb9, 0x3a],
+};
+
+fn occlusion_query_type(binary: bool) -> QueryType {
+    if binary {
+        QueryType::BinaryOcclusion
+    } else {
+        QueryType::Occlusion
+    }
+}
 
 impl GraphicsCommandList {
     pub fn as_list(&self) -> CommandList {
@@ -150,11 +566,50 @@ impl GraphicsCommandList {
     }
 
     pub fn close(&self) -> HRESULT {
-        unsafe { self.Close() }
+        let hr = unsafe { self.Close() };
+        if cfg!(debug_assertions) && winerror::SUCCEEDED(hr) {
+            self.set_closed(true);
+        }
+        hr
     }
 
     pub fn reset(&self, allocator: &CommandAllocator, initial_pso: PipelineState) -> HRESULT {
-        unsafe { self.Reset(allocator.as_mut_ptr(), initial_pso.as_mut_ptr()) }
+        let hr = unsafe { self.Reset(allocator.as_mut_ptr(), initial_pso.as_mut_ptr()) };
+        if cfg!(debug_assertions) && winerror::SUCCEEDED(hr) {
+            self.set_closed(false);
+        }
+        hr
+    }
+
+    /// Records whether the list is closed, for [`is_closed`](Self::is_closed).
+    pub(crate) fn set_closed(&self, closed: bool) {
+        let closed = closed as u32;
+        unsafe {
+            self.SetPrivateData(
+                &CLOSED_STATE_GUID,
+                mem::size_of::<u32>() as _,
+                &closed as *const u32 as *const _,
+            );
+        }
+    }
+
+    /// Whether the list is closed, `None` if it wasn't created, closed or reset through
+    /// this crate in a debug build.
+    pub(crate) fn is_closed(&self) -> Option<bool> {
+        let mut closed = 0u32;
+        let mut size = mem::size_of::<u32>() as u32;
+        let hr = unsafe {
+            self.GetPrivateData(
+                &CLOSED_STATE_GUID,
+                &mut size,
+                &mut closed as *mut u32 as *mut _,
+            )
+        };
+        if winerror::SUCCEEDED(hr) && size == mem::size_of::<u32>() as u32 {
+            Some(closed != 0)
+        } else {
+            None
+        }
     }
 
     pub fn discard_resource(&self, resource: Resource, region: DiscardRegion) {
@@ -166,12 +621,18 @@ impl GraphicsCommandList {
                     NumRects: region.rects.len() as _,
                     pRects: region.rects.as_ptr(),
                     FirstSubresource: region.subregions.start,
-                    NumSubresources: region.subregions.end - region.subregions.start - 1,
+                    NumSubresources: region.subregions.end - region.subregions.start,
                 },
             );
         }
     }
 
+    /// Discards all subresources of `resource`.
+    pub fn discard_entire_resource(&self, resource: &Resource) {
+        unsafe { self.DiscardResource(resource.as_mut_ptr(), ptr::null()) }
+    }
+
+    /// Clears the depth-stencil view, restricted to `rects` unless empty.
     pub fn clear_depth_stencil_view(
         &self,
         dsv: CpuDescriptor,
@@ -191,6 +652,7 @@ impl GraphicsCommandList {
         }
     }
 
+    /// Clears the render target view, restricted to `rects` unless empty.
     pub fn clear_render_target_view(&self, rtv: CpuDescriptor, color: [f32; 4], rects: &[Rect]) {
         let num_rects = rects.len() as _;
         let rects = if num_rects > 0 {
@@ -203,6 +665,259 @@ impl GraphicsCommandList {
         }
     }
 
+    /// Binds render targets and an optional depth-stencil target.
+    ///
+    /// If `rts_single_handle_to_descriptor_range` is set, the `rtvs` must be contiguous in
+    /// their descriptor heap and only the first handle is passed along with the count.
+    pub fn set_render_targets(
+        &self,
+        rtvs: &[CpuDescriptor],
+        dsv: Option<CpuDescriptor>,
+        rts_single_handle_to_descriptor_range: bool,
+    ) {
+        let num_rtvs = rtvs.len() as _;
+        let rtvs = if num_rtvs > 0 {
+            rtvs.as_ptr()
+        } else {
+            ptr::null()
+        };
+        let dsv = match dsv {
+            Some(ref dsv) => dsv as *const _,
+            None => ptr::null(),
+        };
+        unsafe {
+            self.OMSetRenderTargets(
+                num_rtvs,
+                rtvs,
+                rts_single_handle_to_descriptor_range as _,
+                dsv,
+            );
+        }
+    }
+
+    /// Copies all of `src` to `dst`, which must have the same size, format and layout.
+    ///
+    /// `dst` must be in the `COPY_DEST` state and `src` in the `COPY_SOURCE` state.
+    pub fn copy_resource(&self, dst: &Resource, src: &Resource) {
+        unsafe { self.CopyResource(dst.as_mut_ptr(), src.as_mut_ptr()) }
+    }
+
+    /// Copies `num_bytes` between buffers, in the same states as for [`Self::copy_resource`].
+    pub fn copy_buffer_region(
+        &self,
+        dst: &Resource,
+        dst_offset: u64,
+        src: &Resource,
+        src_offset: u64,
+        num_bytes: u64,
+    ) {
+        unsafe {
+            self.CopyBufferRegion(
+                dst.as_mut_ptr(),
+                dst_offset,
+                src.as_mut_ptr(),
+                src_offset,
+                num_bytes,
+            )
+        }
+    }
+
+    /// Resolves a multisampled subresource into a single-sampled one.
+    ///
+    /// `format` must be compatible with both resources and support resolves.
+    /// `dst` must be in the `RESOLVE_DEST` state and `src` in the `RESOLVE_SOURCE` state.
+    pub fn resolve_subresource(
+        &self,
+        dst: &Resource,
+        dst_subresource: Subresource,
+        src: &Resource,
+        src_subresource: Subresource,
+        format: Format,
+    ) {
+        unsafe {
+            self.ResolveSubresource(
+                dst.as_mut_ptr(),
+                dst_subresource,
+                src.as_mut_ptr(),
+                src_subresource,
+                format,
+            )
+        }
+    }
+
+    /// Copies `src_box` of `src`, or all of it, to `dst` at the given texel coordinates.
+    pub fn copy_texture_region(
+        &self,
+        dst: &TextureCopyLocation,
+        dst_x: u32,
+        dst_y: u32,
+        dst_z: u32,
+        src: &TextureCopyLocation,
+        src_box: Option<&d3d12::D3D12_BOX>,
+    ) {
+        let src_box = match src_box {
+            Some(src_box) => src_box as *const _,
+            None => ptr::null(),
+        };
+        unsafe {
+            self.CopyTextureRegion(&dst.to_raw(), dst_x, dst_y, dst_z, &src.to_raw(), src_box);
+        }
+    }
+
+    /// Writes `subresource_data` into `upload` from `upload_offset` on, and records the copies
+    /// into the subresources of `dest` starting at `first_subresource`.
+    ///
+    /// `upload` must be a buffer in an upload heap, `upload_offset` aligned to
+    /// `D3D12_TEXTURE_DATA_PLACEMENT_ALIGNMENT` and `dest` in the `COPY_DEST` state.
+    /// Returns the bytes written to `upload`, 0 if anything failed, including `upload` being
+    /// too small for the footprints or a source too short for its subresource.
+    pub fn update_subresources(
+        &self,
+        dest: &Resource,
+        upload: &Resource,
+        upload_offset: u64,
+        first_subresource: Subresource,
+        subresource_data: &[SubresourceData],
+    ) -> u64 {
+        debug_assert_eq!(
+            upload_offset % d3d12::D3D12_TEXTURE_DATA_PLACEMENT_ALIGNMENT as u64,
+            0,
+            "misaligned upload offset"
+        );
+        let count = subresource_data.len();
+        if count == 0 {
+            return 0;
+        }
+
+        let mut device = Device::null();
+        let hr = unsafe { dest.GetDevice(&d3d12::ID3D12Device::uuidof(), device.mut_void()) };
+        if winerror::FAILED(hr) {
+            return 0;
+        }
+
+        let desc = dest.desc();
+        let mut layouts =
+            vec![unsafe { mem::zeroed::<d3d12::D3D12_PLACED_SUBRESOURCE_FOOTPRINT>() }; count];
+        let mut num_rows = vec![0u32; count];
+        let mut row_sizes = vec![0u64; count];
+        let mut total_bytes = 0;
+        unsafe {
+            device.GetCopyableFootprints(
+                &desc,
+                first_subresource,
+                count as u32,
+                upload_offset,
+                layouts.as_mut_ptr(),
+                num_rows.as_mut_ptr(),
+                row_sizes.as_mut_ptr(),
+                &mut total_bytes,
+            );
+        }
+
+        // `GetCopyableFootprints` reports `u64::MAX` bytes on invalid arguments.
+        let upload_desc = upload.desc();
+        let end = match upload_offset.checked_add(total_bytes) {
+            Some(end)
+                if upload_desc.Dimension == d3d12::D3D12_RESOURCE_DIMENSION_BUFFER
+                    && end <= upload_desc.Width
+                    && end <= usize::MAX as u64 =>
+            {
+                end
+            }
+            _ => return 0,
+        };
+        for (i, source) in subresource_data.iter().enumerate() {
+            let rows = num_rows[i] as usize;
+            let depth = layouts[i].Footprint.Depth as usize;
+            if rows == 0 || depth == 0 {
+                continue;
+            }
+            // Offset one past the last byte read by the row copies below.
+            let required = (depth - 1)
+                .checked_mul(source.slice_pitch)
+                .and_then(|size| size.checked_add((rows - 1).checked_mul(source.row_pitch)?))
+                .and_then(|size| size.checked_add(row_sizes[i] as usize));
+            match required {
+                Some(required) if required <= source.data.len() => {}
+                _ => return 0,
+            }
+        }
+
+        let (mapped, hr) = upload.map(0, Some(0..0));
+        if winerror::FAILED(hr) {
+            return 0;
+        }
+        let mapped = mapped as *mut u8;
+        for (i, source) in subresource_data.iter().enumerate() {
+            let layout = &layouts[i];
+            let row_pitch = layout.Footprint.RowPitch as usize;
+            let rows = num_rows[i] as usize;
+            let row_size = row_sizes[i] as usize;
+            for z in 0..layout.Footprint.Depth as usize {
+                for y in 0..rows {
+                    let src_start = z * source.slice_pitch + y * source.row_pitch;
+                    let src = &source.data[src_start..src_start + row_size];
+                    let dst_offset = layout.Offset as usize + (z * rows + y) * row_pitch;
+                    unsafe {
+                        ptr::copy_nonoverlapping(src.as_ptr(), mapped.add(dst_offset), row_size)
+                    };
+                }
+            }
+        }
+        upload.unmap(0, Some(upload_offset as usize..end as usize));
+
+        if desc.Dimension == d3d12::D3D12_RESOURCE_DIMENSION_BUFFER {
+            self.copy_buffer_region(
+                dest,
+                0,
+                upload,
+                layouts[0].Offset,
+                layouts[0].Footprint.Width as u64,
+            );
+        } else {
+            for (i, layout) in layouts.iter().enumerate() {
+                self.copy_texture_region(
+                    &TextureCopyLocation::Subresource(dest.clone(), first_subresource + i as u32),
+                    0,
+                    0,
+                    0,
+                    &TextureCopyLocation::Footprint(upload.clone(), *layout),
+                    None,
+                );
+            }
+        }
+
+        total_bytes
+    }
+
+    /// Executes up to `max_command_count` commands laid out by `signature` in `argument_buffer`.
+    ///
+    /// With a `count_buffer`, the number of commands is the `u32` at its offset, clamped
+    /// to `max_command_count`. Both buffers must be in the `INDIRECT_ARGUMENT` state.
+    pub fn execute_indirect(
+        &self,
+        signature: &CommandSignature,
+        max_command_count: u32,
+        argument_buffer: &Resource,
+        argument_buffer_offset: u64,
+        count_buffer: Option<(&Resource, u64)>,
+    ) {
+        let (count_buffer, count_buffer_offset) = match count_buffer {
+            Some((buffer, offset)) => (buffer.as_mut_ptr(), offset),
+            None => (ptr::null_mut(), 0),
+        };
+        unsafe {
+            self.ExecuteIndirect(
+                signature.as_mut_ptr(),
+                max_command_count,
+                argument_buffer.as_mut_ptr(),
+                argument_buffer_offset,
+                count_buffer,
+                count_buffer_offset,
+            );
+        }
+    }
+
     pub fn dispatch(&self, count: WorkGroupCount) {
         unsafe {
             self.Dispatch(count[0], count[1], count[2]);
@@ -242,7 +957,7 @@ impl GraphicsCommandList {
 
     pub fn set_index_buffer(&self, gpu_address: GpuAddress, size: u32, format: Format) {
         let ibv = d3d12::D3D12_INDEX_BUFFER_VIEW {
-            BufferLocation: gpu_address,
+            BufferLocation: gpu_address.0,
             SizeInBytes: size,
             Format: format,
         };
@@ -251,6 +966,58 @@ impl GraphicsCommandList {
         }
     }
 
+    pub fn set_viewports(&self, viewports: &[Viewport]) {
+        debug_assert!(
+            viewports.len()
+                <= d3d12::D3D12_VIEWPORT_AND_SCISSORRECT_OBJECT_COUNT_PER_PIPELINE as usize,
+            "Too many viewports: {}",
+            viewports.len()
+        );
+        unsafe {
+            self.RSSetViewports(viewports.len() as _, viewports.as_ptr() as *const _);
+        }
+    }
+
+    pub fn set_scissor_rects(&self, rects: &[Rect]) {
+        debug_assert!(
+            rects.len() <= d3d12::D3D12_VIEWPORT_AND_SCISSORRECT_OBJECT_COUNT_PER_PIPELINE as usize,
+            "Too many scissor rects: {}",
+            rects.len()
+        );
+        unsafe {
+            self.RSSetScissorRects(rects.len() as _, rects.as_ptr());
+        }
+    }
+
+    /// Binds buffers receiving streamed out vertices, the bound pipeline state needs a
+    /// stream output description to write to them.
+    pub fn set_stream_output_targets(
+        &self,
+        start_slot: u32,
+        views: &[d3d12::D3D12_STREAM_OUTPUT_BUFFER_VIEW],
+    ) {
+        unsafe {
+            self.SOSetTargets(start_slot, views.len() as _, views.as_ptr());
+        }
+    }
+
+    pub fn set_vertex_buffers(&self, start_slot: u32, views: &[VertexBufferView]) {
+        unsafe {
+            self.IASetVertexBuffers(start_slot, views.len() as _, views.as_ptr() as *const _);
+        }
+    }
+
+    /// Binds an index buffer, or unbinds it with `None`.
+    pub fn set_index_buffer_view(&self, view: Option<&IndexBufferView>) {
+        let view = match view {
+            Some(view) => &view.0 as *const _,
+            None => ptr::null(),
+        };
+        unsafe {
+            self.IASetIndexBuffer(view);
+        }
+    }
+
     pub fn set_blend_factor(&self, factor: [f32; 4]) {
         unsafe {
             self.OMSetBlendFactor(&factor);
@@ -269,13 +1036,48 @@ impl GraphicsCommandList {
         }
     }
 
+    /// Has to match the topology type of the pipeline state.
+    pub fn set_primitive_topology(&self, topology: PrimitiveTopology) {
+        unsafe {
+            self.IASetPrimitiveTopology(topology.to_raw());
+        }
+    }
+
+    /// Replays a closed bundle, only valid on direct command lists.
+    ///
+    /// Bundles are created like other lists with [`CmdListType::Bundle`] and a bundle allocator.
+    /// They can't record barriers, render target bindings and clears, copies, queries or other bundles,
+    /// and inherit the descriptor heaps, render targets, viewports and scissors of the calling list.
+    /// [`TypedCommandList<BundleList>`](crate::TypedCommandList) only exposes the allowed commands.
     pub fn execute_bundle(&self, bundle: GraphicsCommandList) {
         unsafe {
             self.ExecuteBundle(bundle.as_mut_ptr());
         }
     }
 
+    /// Binds all shader-visible heaps used by the following commands.
+    ///
+    /// At most one CBV/SRV/UAV and one sampler heap may be bound at a time, which is
+    /// asserted in debug builds. Switching heaps may flush the GPU, so bind them once per list
+    /// where possible, e.g. through [`BoundDescriptorHeaps`].
     pub fn set_descriptor_heaps(&self, heaps: &[DescriptorHeap]) {
+        if cfg!(debug_assertions) {
+            let mut bound_types = Vec::with_capacity(heaps.len());
+            for heap in heaps {
+                let desc = unsafe { heap.GetDesc() };
+                assert!(
+                    desc.Flags & d3d12::D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE != 0,
+                    "Descriptor heap of type {} is not shader visible",
+                    desc.Type
+                );
+                assert!(
+                    !bound_types.contains(&desc.Type),
+                    "Several descriptor heaps of type {} are bound",
+                    desc.Type
+                );
+                bound_types.push(desc.Type);
+            }
+        }
         unsafe {
             self.SetDescriptorHeaps(
                 heaps.len() as _,
@@ -306,13 +1108,26 @@ impl GraphicsCommandList {
         }
     }
 
+    /// Checked variant of [`Self::set_compute_root_descriptor_table`], see
+    /// [`Self::set_graphics_root_descriptor_table_checked`].
+    pub fn set_compute_root_descriptor_table_checked(
+        &self,
+        layout: &RootSignatureLayout,
+        heaps: &BoundDescriptorHeaps,
+        root_index: RootIndex,
+        base_descriptor: GpuDescriptor,
+    ) {
+        check_root_descriptor_table(layout, heaps, root_index, base_descriptor);
+        self.set_compute_root_descriptor_table(root_index, base_descriptor);
+    }
+
     pub fn set_compute_root_constant_buffer_view(
         &self,
         root_index: RootIndex,
         buffer_location: GpuAddress,
     ) {
         unsafe {
-            self.SetComputeRootConstantBufferView(root_index, buffer_location);
+            self.SetComputeRootConstantBufferView(root_index, buffer_location.0);
         }
     }
 
@@ -322,7 +1137,7 @@ impl GraphicsCommandList {
         buffer_location: GpuAddress,
     ) {
         unsafe {
-            self.SetComputeRootShaderResourceView(root_index, buffer_location);
+            self.SetComputeRootShaderResourceView(root_index, buffer_location.0);
         }
     }
 
@@ -332,7 +1147,7 @@ impl GraphicsCommandList {
         buffer_location: GpuAddress,
     ) {
         unsafe {
-            self.SetComputeRootUnorderedAccessView(root_index, buffer_location);
+            self.SetComputeRootUnorderedAccessView(root_index, buffer_location.0);
         }
     }
 
@@ -347,6 +1162,23 @@ impl GraphicsCommandList {
         }
     }
 
+    /// Sets `data.len()` root constants starting at `dest_offset_words`.
+    pub fn set_compute_root_32bit_constants(
+        &self,
+        root_index: RootIndex,
+        data: &[u32],
+        dest_offset_words: u32,
+    ) {
+        unsafe {
+            self.SetComputeRoot32BitConstants(
+                root_index,
+                data.len() as _,
+                data.as_ptr() as *const _,
+                dest_offset_words,
+            );
+        }
+    }
+
     pub fn set_graphics_root_descriptor_table(
         &self,
         root_index: RootIndex,
@@ -357,13 +1189,28 @@ impl GraphicsCommandList {
         }
     }
 
+    /// Checked variant of [`Self::set_graphics_root_descriptor_table`].
+    ///
+    /// In debug builds, panics if `root_index` isn't a descriptor table of `layout`,
+    /// or if `base_descriptor` doesn't point into one of the shader-visible `heaps`.
+    pub fn set_graphics_root_descriptor_table_checked(
+        &self,
+        layout: &RootSignatureLayout,
+        heaps: &BoundDescriptorHeaps,
+        root_index: RootIndex,
+        base_descriptor: GpuDescriptor,
+    ) {
+        check_root_descriptor_table(layout, heaps, root_index, base_descriptor);
+        self.set_graphics_root_descriptor_table(root_index, base_descriptor);
+    }
+
     pub fn set_graphics_root_constant_buffer_view(
         &self,
         root_index: RootIndex,
         buffer_location: GpuAddress,
     ) {
         unsafe {
-            self.SetGraphicsRootConstantBufferView(root_index, buffer_location);
+            self.SetGraphicsRootConstantBufferView(root_index, buffer_location.0);
         }
     }
 
@@ -373,7 +1220,7 @@ impl GraphicsCommandList {
         buffer_location: GpuAddress,
     ) {
         unsafe {
-            self.SetGraphicsRootShaderResourceView(root_index, buffer_location);
+            self.SetGraphicsRootShaderResourceView(root_index, buffer_location.0);
         }
     }
 
@@ -383,7 +1230,7 @@ impl GraphicsCommandList {
         buffer_location: GpuAddress,
     ) {
         unsafe {
-            self.SetGraphicsRootUnorderedAccessView(root_index, buffer_location);
+            self.SetGraphicsRootUnorderedAccessView(root_index, buffer_location.0);
         }
     }
 
@@ -398,9 +1245,1448 @@ impl GraphicsCommandList {
         }
     }
 
+    /// Sets `data.len()` root constants starting at `dest_offset_words`.
+    pub fn set_graphics_root_32bit_constants(
+        &self,
+        root_index: RootIndex,
+        data: &[u32],
+        dest_offset_words: u32,
+    ) {
+        unsafe {
+            self.SetGraphicsRoot32BitConstants(
+                root_index,
+                data.len() as _,
+                data.as_ptr() as *const _,
+                dest_offset_words,
+            );
+        }
+    }
+
+    /// Binds `resource` at `offset` bytes as a root constant buffer view.
+    pub fn set_graphics_root_cbv(&self, root_index: RootIndex, resource: &Resource, offset: u64) {
+        self.set_graphics_root_constant_buffer_view(
+            root_index,
+            resource.gpu_virtual_address().offset(offset),
+        );
+    }
+
+    /// Binds `resource` at `offset` bytes as a root shader resource view.
+    pub fn set_graphics_root_srv(&self, root_index: RootIndex, resource: &Resource, offset: u64) {
+        self.set_graphics_root_shader_resource_view(
+            root_index,
+            resource.gpu_virtual_address().offset(offset),
+        );
+    }
+
+    /// Binds `resource` at `offset` bytes as a root unordered access view.
+    pub fn set_graphics_root_uav(&self, root_index: RootIndex, resource: &Resource, offset: u64) {
+        self.set_graphics_root_unordered_access_view(
+            root_index,
+            resource.gpu_virtual_address().offset(offset),
+        );
+    }
+
+    /// Binds `resource` at `offset` bytes as a root constant buffer view.
+    pub fn set_compute_root_cbv(&self, root_index: RootIndex, resource: &Resource, offset: u64) {
+        self.set_compute_root_constant_buffer_view(
+            root_index,
+            resource.gpu_virtual_address().offset(offset),
+        );
+    }
+
+    /// Binds `resource` at `offset` bytes as a root shader resource view.
+    pub fn set_compute_root_srv(&self, root_index: RootIndex, resource: &Resource, offset: u64) {
+        self.set_compute_root_shader_resource_view(
+            root_index,
+            resource.gpu_virtual_address().offset(offset),
+        );
+    }
+
+    /// Binds `resource` at `offset` bytes as a root unordered access view.
+    pub fn set_compute_root_uav(&self, root_index: RootIndex, resource: &Resource, offset: u64) {
+        self.set_compute_root_unordered_access_view(
+            root_index,
+            resource.gpu_virtual_address().offset(offset),
+        );
+    }
+
+    /// Starts query `index` of `heap`, timestamp queries can only be ended.
+    pub fn begin_query(&self, heap: &QueryHeap, ty: QueryType, index: u32) {
+        unsafe {
+            self.BeginQuery(heap.as_mut_ptr(), ty as _, index);
+        }
+    }
+
+    pub fn end_query(&self, heap: &QueryHeap, ty: QueryType, index: u32) {
+        unsafe {
+            self.EndQuery(heap.as_mut_ptr(), ty as _, index);
+        }
+    }
+
+    /// Starts counting the samples passing depth and stencil tests into query `index`
+    /// of an occlusion `heap`.
+    ///
+    /// Binary queries only report whether any sample passed, which can be cheaper.
+    pub fn begin_occlusion_query(&self, heap: &QueryHeap, index: u32, binary: bool) {
+        self.begin_query(heap, occlusion_query_type(binary), index)
+    }
+
+    /// Ends an occlusion query, `binary` has to match the one it began with.
+    pub fn end_occlusion_query(&self, heap: &QueryHeap, index: u32, binary: bool) {
+        self.end_query(heap, occlusion_query_type(binary), index)
+    }
+
+    /// Starts counting the primitives streamed out to `stream` into query `index`
+    /// of a stream output statistics `heap`.
+    ///
+    /// The results are resolved with [`StreamOutputStream::query_type`] and read
+    /// with [`StreamOutputStatistics::from_bytes`](crate::StreamOutputStatistics::from_bytes).
+    pub fn begin_so_statistics_query(
+        &self,
+        heap: &QueryHeap,
+        index: u32,
+        stream: StreamOutputStream,
+    ) {
+        self.begin_query(heap, stream.query_type(), index)
+    }
+
+    pub fn end_so_statistics_query(
+        &self,
+        heap: &QueryHeap,
+        index: u32,
+        stream: StreamOutputStream,
+    ) {
+        self.end_query(heap, stream.query_type(), index)
+    }
+
+    /// Writes the current GPU timestamp into query `index` of a timestamp `heap`.
+    pub fn end_timestamp_query(&self, heap: &QueryHeap, index: u32) {
+        unsafe {
+            self.EndQuery(heap.as_mut_ptr(), d3d12::D3D12_QUERY_TYPE_TIMESTAMP, index);
+        }
+    }
+
+    /// Writes the results of `count` queries starting at `start` into `destination` at `destination_offset`.
+    pub fn resolve_query_data(
+        &self,
+        heap: &QueryHeap,
+        ty: QueryType,
+        start: u32,
+        count: u32,
+        destination: &Resource,
+        destination_offset: u64,
+    ) {
+        unsafe {
+            self.ResolveQueryData(
+                heap.as_mut_ptr(),
+                ty as _,
+                start,
+                count,
+                destination.as_mut_ptr(),
+                destination_offset,
+            );
+        }
+    }
+
+    /// Predicates the following rendering and dispatches on the 64-bit value at `offset` in `buffer`,
+    /// `None` disabling predication.
+    ///
+    /// `buffer` must be in the `PREDICATION` state.
+    pub fn set_predication(&self, buffer: Option<&Resource>, offset: u64, op: PredicationOp) {
+        let buffer = buffer.map_or(ptr::null_mut(), |buffer| buffer.as_mut_ptr());
+        unsafe { self.SetPredication(buffer, offset, op as _) }
+    }
+
     pub fn resource_barrier(&self, barriers: &[ResourceBarrier]) {
         unsafe {
             self.ResourceBarrier(barriers.len() as _, barriers.as_ptr() as _) // matches representation
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{slot, Mock, MockHeap};
+    use std::sync::Mutex;
+
+    /// Command recorded by a mocked list.
+    #[derive(Debug, PartialEq)]
+    enum Call {
+        SetDescriptorHeaps(Vec<*mut d3d12::ID3D12DescriptorHeap>),
+        GraphicsTable(RootIndex, u64),
+        ComputeTable(RootIndex, u64),
+        /// Origin, size and depth range of each viewport.
+        Viewports(Vec<[f32; 6]>),
+        ScissorRects(Vec<[i32; 4]>),
+        /// Start slot, then address, size and stride of each view.
+        VertexBuffers(u32, Vec<(u64, u32, u32)>),
+        /// Address, size and format of the view, if any.
+        IndexBuffer(Option<(u64, u32, Format)>),
+        /// Root index and GPU virtual address of a root descriptor.
+        GraphicsCbv(RootIndex, u64),
+        GraphicsSrv(RootIndex, u64),
+        GraphicsUav(RootIndex, u64),
+        ComputeCbv(RootIndex, u64),
+        ComputeSrv(RootIndex, u64),
+        ComputeUav(RootIndex, u64),
+        /// Render target handles the runtime reads, whether they start a range, and the
+        /// depth-stencil handle.
+        RenderTargets(Vec<usize>, bool, Option<usize>),
+    }
+
+    #[derive(Default)]
+    struct MockList {
+        calls: Mutex<Vec<Call>>,
+    }
+
+    fn record(this: *mut d3d12::ID3D12GraphicsCommandList, call: Call) {
+        unsafe { Mock::<MockList>::state(this) }
+            .calls
+            .lock()
+            .unwrap()
+            .push(call);
+    }
+
+    fn recorded(list: &GraphicsCommandList) -> Vec<Call> {
+        let state = unsafe { Mock::<MockList>::state(list.as_ptr()) };
+        std::mem::take(&mut *state.calls.lock().unwrap())
+    }
+
+    unsafe extern "system" fn set_descriptor_heaps(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        count: u32,
+        heaps: *const *mut d3d12::ID3D12DescriptorHeap,
+    ) {
+        let heaps = std::slice::from_raw_parts(heaps, count as usize).to_vec();
+        record(this, Call::SetDescriptorHeaps(heaps));
+    }
+
+    unsafe extern "system" fn set_graphics_table(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        root_index: RootIndex,
+        base: GpuDescriptor,
+    ) {
+        record(this, Call::GraphicsTable(root_index, base.ptr));
+    }
+
+    unsafe extern "system" fn set_compute_table(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        root_index: RootIndex,
+        base: GpuDescriptor,
+    ) {
+        record(this, Call::ComputeTable(root_index, base.ptr));
+    }
+
+    unsafe extern "system" fn set_viewports(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        count: u32,
+        viewports: *const d3d12::D3D12_VIEWPORT,
+    ) {
+        let viewports = std::slice::from_raw_parts(viewports, count as usize)
+            .iter()
+            .map(|v| {
+                [
+                    v.TopLeftX, v.TopLeftY, v.Width, v.Height, v.MinDepth, v.MaxDepth,
+                ]
+            })
+            .collect();
+        record(this, Call::Viewports(viewports));
+    }
+
+    unsafe extern "system" fn set_scissor_rects(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        count: u32,
+        rects: *const Rect,
+    ) {
+        let rects = std::slice::from_raw_parts(rects, count as usize)
+            .iter()
+            .map(|r| [r.left, r.top, r.right, r.bottom])
+            .collect();
+        record(this, Call::ScissorRects(rects));
+    }
+
+    unsafe extern "system" fn set_vertex_buffers(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        start_slot: u32,
+        count: u32,
+        views: *const d3d12::D3D12_VERTEX_BUFFER_VIEW,
+    ) {
+        let views = std::slice::from_raw_parts(views, count as usize)
+            .iter()
+            .map(|v| (v.BufferLocation, v.SizeInBytes, v.StrideInBytes))
+            .collect();
+        record(this, Call::VertexBuffers(start_slot, views));
+    }
+
+    unsafe extern "system" fn set_index_buffer(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        view: *const d3d12::D3D12_INDEX_BUFFER_VIEW,
+    ) {
+        let view = view
+            .as_ref()
+            .map(|v| (v.BufferLocation, v.SizeInBytes, v.Format));
+        record(this, Call::IndexBuffer(view));
+    }
+
+    macro_rules! root_view_setter {
+        ($name:ident, $call:ident) => {
+            unsafe extern "system" fn $name(
+                this: *mut d3d12::ID3D12GraphicsCommandList,
+                root_index: RootIndex,
+                address: d3d12::D3D12_GPU_VIRTUAL_ADDRESS,
+            ) {
+                record(this, Call::$call(root_index, address));
+            }
+        };
+    }
+    root_view_setter!(set_graphics_cbv, GraphicsCbv);
+    root_view_setter!(set_graphics_srv, GraphicsSrv);
+    root_view_setter!(set_graphics_uav, GraphicsUav);
+    root_view_setter!(set_compute_cbv, ComputeCbv);
+    root_view_setter!(set_compute_srv, ComputeSrv);
+    root_view_setter!(set_compute_uav, ComputeUav);
+
+    unsafe extern "system" fn set_render_targets(
+        this: *mut d3d12::ID3D12GraphicsCommandList,
+        count: u32,
+        rtvs: *const CpuDescriptor,
+        single_handle_to_range: winapi::shared::minwindef::BOOL,
+        dsv: *const CpuDescriptor,
+    ) {
+        // A descriptor range is passed as its first handle only.
+        let read = match count {
+            0 => 0,
+            _ if single_handle_to_range != 0 => 1,
+            count => count as usize,
+        };
+        let handles = (0..read).map(|i| (*rtvs.add(i)).ptr).collect();
+        let dsv = dsv.as_ref().map(|dsv| dsv.ptr);
+        record(
+            this,
+            Call::RenderTargets(handles, single_handle_to_range != 0, dsv),
+        );
+    }
+
+    fn mock_list() -> GraphicsCommandList {
+        type Vtbl = d3d12::ID3D12GraphicsCommandListVtbl;
+        Mock::new::<d3d12::ID3D12GraphicsCommandList, Vtbl>(
+            MockList::default(),
+            &[
+                (
+                    slot!(Vtbl, SetDescriptorHeaps),
+                    set_descriptor_heaps as usize,
+                ),
+                (
+                    slot!(Vtbl, SetGraphicsRootDescriptorTable),
+                    set_graphics_table as usize,
+                ),
+                (
+                    slot!(Vtbl, SetComputeRootDescriptorTable),
+                    set_compute_table as usize,
+                ),
+                (slot!(Vtbl, RSSetViewports), set_viewports as usize),
+                (slot!(Vtbl, RSSetScissorRects), set_scissor_rects as usize),
+                (slot!(Vtbl, IASetVertexBuffers), set_vertex_buffers as usize),
+                (slot!(Vtbl, IASetIndexBuffer), set_index_buffer as usize),
+                (
+                    slot!(Vtbl, SetGraphicsRootConstantBufferView),
+                    set_graphics_cbv as usize,
+                ),
+                (
+                    slot!(Vtbl, SetGraphicsRootShaderResourceView),
+                    set_graphics_srv as usize,
+                ),
+                (
+                    slot!(Vtbl, SetGraphicsRootUnorderedAccessView),
+                    set_graphics_uav as usize,
+                ),
+                (
+                    slot!(Vtbl, SetComputeRootConstantBufferView),
+                    set_compute_cbv as usize,
+                ),
+                (
+                    slot!(Vtbl, SetComputeRootShaderResourceView),
+                    set_compute_srv as usize,
+                ),
+                (
+                    slot!(Vtbl, SetComputeRootUnorderedAccessView),
+                    set_compute_uav as usize,
+                ),
+                (slot!(Vtbl, OMSetRenderTargets), set_render_targets as usize),
+            ],
+        )
+    }
+
+    unsafe extern "system" fn get_gpu_virtual_address(
+        this: *mut d3d12::ID3D12Resource,
+    ) -> d3d12::D3D12_GPU_VIRTUAL_ADDRESS {
+        *Mock::<u64>::state(this)
+    }
+
+    /// Buffer at the GPU virtual address `address`.
+    fn mock_buffer(address: u64) -> Resource {
+        Mock::new::<d3d12::ID3D12Resource, d3d12::ID3D12ResourceVtbl>(
+            address,
+            &[(
+                slot!(d3d12::ID3D12ResourceVtbl, GetGPUVirtualAddress),
+                get_gpu_virtual_address as usize,
+            )],
+        )
+    }
+
+    /// Shader-visible CBV/SRV/UAV and sampler heaps.
+    fn mock_heaps() -> [DescriptorHeap; 2] {
+        [
+            MockHeap::new(d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 8, true).create(),
+            MockHeap {
+                gpu_start: MockHeap::GPU_START + 0x1000,
+                ..MockHeap::new(d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_SAMPLER, 8, true)
+            }
+            .create(),
+        ]
+    }
+
+    #[test]
+    fn bound_descriptor_heaps() {
+        let list = mock_list();
+        let heaps = mock_heaps();
+        let raw = heaps
+            .iter()
+            .map(|heap| heap.as_mut_ptr())
+            .collect::<Vec<_>>();
+        let mut bound = BoundDescriptorHeaps::default();
+
+        // Binding the same heaps again is skipped.
+        bound.set_descriptor_heaps(&list, &heaps);
+        bound.set_descriptor_heaps(&list, &heaps);
+        assert_eq!(recorded(&list), [Call::SetDescriptorHeaps(raw.clone())]);
+
+        bound.set_descriptor_heaps(&list, &heaps[..1]);
+        assert_eq!(
+            recorded(&list),
+            [Call::SetDescriptorHeaps(raw[..1].to_vec())]
+        );
+
+        // A reset list has nothing bound anymore.
+        bound.reset();
+        bound.set_descriptor_heaps(&list, &heaps[..1]);
+        assert_eq!(
+            recorded(&list),
+            [Call::SetDescriptorHeaps(raw[..1].to_vec())]
+        );
+    }
+
+    #[test]
+    fn patch_list_control_points() {
+        assert_eq!(PrimitiveTopology::patch_list(0), None);
+        assert_eq!(PrimitiveTopology::patch_list(33), None);
+        assert_eq!(
+            PrimitiveTopology::patch_list(1).unwrap().to_raw(),
+            d3dcommon::D3D_PRIMITIVE_TOPOLOGY_1_CONTROL_POINT_PATCHLIST
+        );
+        assert_eq!(
+            PrimitiveTopology::patch_list(32).unwrap().to_raw(),
+            d3dcommon::D3D_PRIMITIVE_TOPOLOGY_32_CONTROL_POINT_PATCHLIST
+        );
+        assert_eq!(
+            PrimitiveTopology::TriangleList.to_raw(),
+            d3dcommon::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn bind_pipeline_sequence() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let blob = crate::RootSignature::serialize(
+            crate::RootSignatureVersion::V1_0,
+            &[],
+            &[],
+            crate::RootSignatureFlags::empty(),
+        )
+        .unwrap();
+        let (signature, hr) = device.create_root_signature(blob, 0);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let code = b"[numthreads(1, 1, 1)] void main() {}";
+        let target = std::ffi::CString::new("cs_5_0").unwrap();
+        let entry = std::ffi::CString::new("main").unwrap();
+        let cs = crate::Shader::compile(code, &target, &entry, crate::ShaderCompileFlags::empty())
+            .unwrap();
+        let (pso, hr) = device.create_compute_pipeline_state(
+            &signature,
+            crate::Shader::from_blob(&cs),
+            0,
+            crate::CachedPSO::null(),
+            crate::PipelineStateFlags::empty(),
+        );
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.set_pipeline_state(&pso);
+        list.set_compute_root_signature(&signature);
+        list.set_graphics_root_signature(&signature);
+        list.set_primitive_topology(PrimitiveTopology::TriangleList);
+        list.set_primitive_topology(PrimitiveTopology::patch_list(3).unwrap());
+        list.dispatch([1, 1, 1]);
+        crate::test_execute(&device, &list);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn sample_positions() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (tier, _) = device.programmable_sample_positions_tier();
+        if tier == crate::ProgrammableSamplePositionsTier::NotSupported {
+            return;
+        }
+        let (_allocator, list) = crate::test_command_list(&device);
+        let (list1, hr) = unsafe { list.cast::<d3d12::ID3D12GraphicsCommandList1>() };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let positions = [
+            d3d12::D3D12_SAMPLE_POSITION { X: -4, Y: -4 },
+            d3d12::D3D12_SAMPLE_POSITION { X: 4, Y: 4 },
+        ];
+        assert_eq!(list1.set_sample_positions(2, 1, &positions), winerror::S_OK);
+        assert_eq!(
+            list1.set_sample_positions(2, 1, &positions[..1]),
+            winerror::E_INVALIDARG
+        );
+        assert_eq!(list1.set_sample_positions(0, 0, &[]), winerror::S_OK);
+        assert_eq!(list.close(), winerror::S_OK);
+    }
+
+    /// Root constants at index 0 and a table at index 1.
+    fn table_layout() -> RootSignatureLayout {
+        let range = crate::DescriptorRange::new(
+            crate::DescriptorRangeType::SRV,
+            1,
+            crate::Binding {
+                space: 0,
+                register: 0,
+            },
+            0,
+        );
+        crate::RootParameterBuilder::new()
+            .root_constants(0, 0, 1, crate::ShaderVisibility::All)
+            .descriptor_table(&[range], crate::ShaderVisibility::All)
+            .layout()
+    }
+
+    /// List with the mocked heaps bound.
+    fn bound_list() -> (GraphicsCommandList, BoundDescriptorHeaps) {
+        let list = mock_list();
+        let mut heaps = BoundDescriptorHeaps::default();
+        heaps.set_descriptor_heaps(&list, &mock_heaps());
+        recorded(&list);
+        (list, heaps)
+    }
+
+    #[test]
+    fn checked_root_descriptor_tables() {
+        let (list, heaps) = bound_list();
+        let layout = table_layout();
+        let base = GpuDescriptor {
+            ptr: MockHeap::GPU_START + 2 * MockHeap::INCREMENT as u64,
+        };
+        list.set_graphics_root_descriptor_table_checked(&layout, &heaps, 1, base);
+        list.set_compute_root_descriptor_table_checked(&layout, &heaps, 1, base);
+        assert_eq!(
+            recorded(&list),
+            [
+                Call::GraphicsTable(1, base.ptr),
+                Call::ComputeTable(1, base.ptr)
+            ]
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Root parameter 5 is not a descriptor table (None)")]
+    fn root_descriptor_table_out_of_range() {
+        let (list, heaps) = bound_list();
+        let base = GpuDescriptor {
+            ptr: MockHeap::GPU_START,
+        };
+        list.set_graphics_root_descriptor_table_checked(&table_layout(), &heaps, 5, base);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Root parameter 0 is not a descriptor table")]
+    fn compute_root_descriptor_table_not_a_table() {
+        let (list, heaps) = bound_list();
+        let base = GpuDescriptor {
+            ptr: MockHeap::GPU_START,
+        };
+        list.set_compute_root_descriptor_table_checked(&table_layout(), &heaps, 0, base);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "is not part of a bound shader-visible heap")]
+    fn root_descriptor_table_not_shader_visible() {
+        let (list, heaps) = bound_list();
+        // The GPU start of a heap which isn't shader visible.
+        let heap = MockHeap::new(d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 8, false).create();
+        let base = heap.start_gpu_descriptor();
+        list.set_graphics_root_descriptor_table_checked(&table_layout(), &heaps, 1, base);
+    }
+
+    #[test]
+    fn set_descriptor_heaps() {
+        let list = mock_list();
+        let heaps = mock_heaps();
+        list.set_descriptor_heaps(&heaps);
+        assert_eq!(
+            recorded(&list),
+            [Call::SetDescriptorHeaps(
+                heaps.iter().map(|heap| heap.as_mut_ptr()).collect()
+            )]
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Several descriptor heaps of type")]
+    fn set_descriptor_heaps_of_same_type() {
+        let list = mock_list();
+        let heap =
+            || MockHeap::new(d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 8, true).create();
+        list.set_descriptor_heaps(&[heap(), heap()]);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "is not shader visible")]
+    fn set_descriptor_heaps_not_shader_visible() {
+        let list = mock_list();
+        let heap = MockHeap::new(d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_SAMPLER, 8, false).create();
+        list.set_descriptor_heaps(&[heap]);
+    }
+
+    /// Copies subresource 0 of a 32-bit `texture` in `state` to the CPU, with packed rows.
+    #[cfg(feature = "implicit-link")]
+    fn read_texture(device: &Device, texture: &Resource, state: ResourceStates) -> Vec<u8> {
+        let desc = texture.desc();
+        let row_size = desc.Width as usize * 4;
+        let alignment = d3d12::D3D12_TEXTURE_DATA_PITCH_ALIGNMENT as usize;
+        let row_pitch = (row_size + alignment - 1) / alignment * alignment;
+        let size = row_pitch * desc.Height as usize;
+        let builder = crate::CommittedResourceBuilder::readback_buffer(size as u64);
+        let (readback, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (_allocator, list) = crate::test_command_list(device);
+        if state != ResourceStates::COPY_SOURCE {
+            list.resource_barrier(&[ResourceBarrier::transition(
+                texture.clone(),
+                0,
+                state.bits(),
+                d3d12::D3D12_RESOURCE_STATE_COPY_SOURCE,
+                d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            )]);
+        }
+        let footprint = d3d12::D3D12_PLACED_SUBRESOURCE_FOOTPRINT {
+            Offset: 0,
+            Footprint: d3d12::D3D12_SUBRESOURCE_FOOTPRINT {
+                Format: desc.Format,
+                Width: desc.Width as u32,
+                Height: desc.Height,
+                Depth: 1,
+                RowPitch: row_pitch as u32,
+            },
+        };
+        list.copy_texture_region(
+            &TextureCopyLocation::Footprint(readback.clone(), footprint),
+            0,
+            0,
+            0,
+            &TextureCopyLocation::Subresource(texture.clone(), 0),
+            None,
+        );
+        crate::test_execute(device, &list);
+
+        let (bytes, hr) = readback.read_to_vec(0, 0..size);
+        assert!(winerror::SUCCEEDED(hr));
+        bytes
+            .chunks(row_pitch)
+            .flat_map(|row| row[..row_size].iter().copied())
+            .collect()
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn update_checkerboard_texture() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let size = 8;
+        let format = winapi::shared::dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let builder = crate::CommittedResourceBuilder::texture2d(
+            format,
+            size as u64,
+            size,
+            1,
+            crate::ResourceFlags::empty(),
+        )
+        .initial_state(ResourceStates::COPY_DEST);
+        let (texture, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        let (upload, hr) =
+            device.create_committed_resource(crate::CommittedResourceBuilder::upload_buffer(4096));
+        assert!(winerror::SUCCEEDED(hr));
+
+        // Opaque black and white texels.
+        let texels: Vec<u8> = (0..size * size * 4)
+            .map(|i| {
+                let texel = i / 4;
+                let white = (texel % size + texel / size) % 2 == 0;
+                if white || i % 4 == 3 {
+                    0xff
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let data = SubresourceData {
+            data: &texels,
+            row_pitch: size as usize * 4,
+            slice_pitch: texels.len(),
+        };
+        let (_allocator, list) = crate::test_command_list(&device);
+        let written = list.update_subresources(&texture, &upload, 0, 0, &[data]);
+        let row_pitch = d3d12::D3D12_TEXTURE_DATA_PITCH_ALIGNMENT as u64;
+        assert_eq!(written, row_pitch * (size as u64 - 1) + size as u64 * 4);
+        crate::test_execute(&device, &list);
+
+        assert_eq!(
+            read_texture(&device, &texture, ResourceStates::COPY_DEST),
+            texels
+        );
+
+        // A source shorter than the subresource is rejected.
+        let (_allocator, list) = crate::test_command_list(&device);
+        let short = SubresourceData {
+            data: &texels[4..],
+            ..data
+        };
+        assert_eq!(
+            list.update_subresources(&texture, &upload, 0, 0, &[short]),
+            0
+        );
+        assert_eq!(list.close(), winerror::S_OK);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn indirect_draw_without_count_buffer() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        // Draw arguments alone need no root signature.
+        let stride = mem::size_of::<d3d12::D3D12_DRAW_ARGUMENTS>() as u32;
+        let (signature, hr) = device.create_command_signature(
+            RootSignature::null(),
+            &[IndirectArgument::draw()],
+            stride,
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let max_command_count = 4;
+        let builder =
+            crate::CommittedResourceBuilder::default_buffer((stride * max_command_count) as u64)
+                .initial_state(ResourceStates::INDIRECT_ARGUMENT);
+        let (arguments, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.execute_indirect(&signature, max_command_count, &arguments, 0, None);
+        list.execute_indirect(&signature, 1, &arguments, stride as u64, None);
+        assert_eq!(list.close(), winerror::S_OK);
+    }
+
+    /// Upload buffer holding `data`.
+    #[cfg(feature = "implicit-link")]
+    fn upload_buffer(device: &Device, data: &[u8]) -> Resource {
+        let builder = crate::CommittedResourceBuilder::upload_buffer(data.len() as u64);
+        let (upload, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        let (mapping, hr) = upload.map_guard(0, 0..data.len());
+        assert!(winerror::SUCCEEDED(hr));
+        mapping.unwrap().as_mut_slice().copy_from_slice(data);
+        upload
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn predicated_copies() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let mut data = 1u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0x11; 8]);
+        let upload = upload_buffer(&device, &data);
+        let builder = crate::CommittedResourceBuilder::default_buffer(8)
+            .initial_state(ResourceStates::COPY_DEST);
+        let (predicate, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        let builder = crate::CommittedResourceBuilder::readback_buffer(16);
+        let (readback, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.copy_buffer_region(&predicate, 0, &upload, 0, 8);
+        list.resource_barrier(&[ResourceBarrier::transition(
+            predicate.clone(),
+            0,
+            d3d12::D3D12_RESOURCE_STATE_COPY_DEST,
+            d3d12::D3D12_RESOURCE_STATE_PREDICATION,
+            d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        )]);
+        // The predicate is 1, so only `NotEqualZero` skips the copy.
+        list.set_predication(Some(&predicate), 0, PredicationOp::NotEqualZero);
+        list.copy_buffer_region(&readback, 0, &upload, 8, 8);
+        list.set_predication(None, 0, PredicationOp::EqualZero);
+        list.copy_buffer_region(&readback, 8, &upload, 8, 8);
+        crate::test_execute(&device, &list);
+
+        let (bytes, hr) = readback.read_to_vec(0, 0..16);
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(bytes[..8], [0; 8]);
+        assert_eq!(bytes[8..], [0x11; 8]);
+    }
+
+    /// `size` 2D texture with `sample_count` samples, created in `state`.
+    #[cfg(feature = "implicit-link")]
+    fn test_texture(
+        device: &Device,
+        format: Format,
+        size: u32,
+        sample_count: u32,
+        flags: crate::ResourceFlags,
+        state: ResourceStates,
+    ) -> Resource {
+        let mut builder =
+            crate::CommittedResourceBuilder::texture2d(format, size as u64, size, 1, flags)
+                .initial_state(state);
+        builder.desc.SampleDesc.Count = sample_count;
+        let (texture, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        texture
+    }
+
+    /// Heap of `count` CPU-only descriptors of type `ty`.
+    #[cfg(feature = "implicit-link")]
+    fn test_views(
+        device: &Device,
+        ty: crate::DescriptorHeapType,
+        count: u32,
+    ) -> crate::IndexedDescriptorHeap {
+        let (heap, hr) = device.create_indexed_descriptor_heap(
+            count,
+            ty,
+            crate::DescriptorHeapFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        heap
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn max_depth_resolve_of_region() {
+        use crate::{DsvFlags, ResourceFlags, TextureViewDesc, TextureViewDimension};
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        // Depth resolves came along with programmable sample positions.
+        let (tier, _) = device.programmable_sample_positions_tier();
+        if tier == crate::ProgrammableSamplePositionsTier::NotSupported {
+            return;
+        }
+        let format = winapi::shared::dxgiformat::DXGI_FORMAT_D32_FLOAT;
+        let depth = |samples| {
+            test_texture(
+                &device,
+                format,
+                8,
+                samples,
+                ResourceFlags::ALLOW_DEPTH_STENCIL,
+                ResourceStates::DEPTH_WRITE,
+            )
+        };
+        let (src, dst) = (depth(4), depth(1));
+        let dsvs = test_views(&device, crate::DescriptorHeapType::Dsv, 2);
+        let views = [
+            (&src, TextureViewDimension::Texture2DMs, 0.75),
+            (&dst, TextureViewDimension::Texture2D, 0.25),
+        ];
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        for (i, &(texture, dimension, value)) in views.iter().enumerate() {
+            let dsv = dsvs.cpu_descriptor_at(i as u32);
+            let desc = TextureViewDesc::new(dimension, format);
+            let hr = device.create_depth_stencil_view(texture, &desc, DsvFlags::empty(), dsv);
+            assert_eq!(hr, winerror::S_OK);
+            list.clear_depth_stencil_view(dsv, ClearFlags::DEPTH, value, 0, &[]);
+        }
+        let transition = |texture: &Resource, state| {
+            ResourceBarrier::transition(
+                texture.clone(),
+                0,
+                d3d12::D3D12_RESOURCE_STATE_DEPTH_WRITE,
+                state,
+                d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            )
+        };
+        list.resource_barrier(&[
+            transition(&src, d3d12::D3D12_RESOURCE_STATE_RESOLVE_SOURCE),
+            transition(&dst, d3d12::D3D12_RESOURCE_STATE_RESOLVE_DEST),
+        ]);
+        let (list1, hr) = unsafe { list.cast::<d3d12::ID3D12GraphicsCommandList1>() };
+        assert!(winerror::SUCCEEDED(hr));
+        let region = Rect {
+            left: 2,
+            top: 2,
+            right: 6,
+            bottom: 6,
+        };
+        list1.resolve_subresource_region(
+            &dst,
+            0,
+            0,
+            0,
+            &src,
+            0,
+            Some(&region),
+            format,
+            ResolveMode::Max,
+        );
+        crate::test_execute(&device, &list);
+
+        // The region lands in the top-left corner, the rest keeps its clear value.
+        let texels = read_texture(&device, &dst, ResourceStates::RESOLVE_DEST);
+        for (i, texel) in texels.chunks(4).enumerate() {
+            let (x, y) = (i % 8, i / 8);
+            let expected: f32 = if x < 4 && y < 4 { 0.75 } else { 0.25 };
+            assert_eq!(texel, expected.to_le_bytes(), "texel ({}, {})", x, y);
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn resolve_msaa_render_target() {
+        use crate::{ResourceFlags, TextureViewDesc, TextureViewDimension};
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let format = winapi::shared::dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let mut levels = d3d12::D3D12_FEATURE_DATA_MULTISAMPLE_QUALITY_LEVELS {
+            Format: format,
+            SampleCount: 4,
+            Flags: d3d12::D3D12_MULTISAMPLE_QUALITY_LEVELS_FLAG_NONE,
+            NumQualityLevels: 0,
+        };
+        let hr = device
+            .check_feature_support(d3d12::D3D12_FEATURE_MULTISAMPLE_QUALITY_LEVELS, &mut levels);
+        if winerror::FAILED(hr) || levels.NumQualityLevels == 0 {
+            return;
+        }
+
+        let src = test_texture(
+            &device,
+            format,
+            8,
+            4,
+            ResourceFlags::ALLOW_RENDER_TARGET,
+            ResourceStates::RENDER_TARGET,
+        );
+        let dst = test_texture(
+            &device,
+            format,
+            8,
+            1,
+            ResourceFlags::empty(),
+            ResourceStates::RESOLVE_DEST,
+        );
+        let rtvs = test_views(&device, crate::DescriptorHeapType::Rtv, 1);
+        let rtv = rtvs.cpu_descriptor_at(0);
+        let desc = TextureViewDesc::new(TextureViewDimension::Texture2DMs, format);
+        device.create_texture_rtv(&src, &desc, rtv);
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.clear_render_target_view(rtv, [1.0, 0.0, 1.0, 1.0], &[]);
+        list.resource_barrier(&[ResourceBarrier::transition(
+            src.clone(),
+            0,
+            d3d12::D3D12_RESOURCE_STATE_RENDER_TARGET,
+            d3d12::D3D12_RESOURCE_STATE_RESOLVE_SOURCE,
+            d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        )]);
+        list.resolve_subresource(&dst, 0, &src, 0, format);
+        crate::test_execute(&device, &list);
+
+        // Every sample holds the clear color, so does their average.
+        let texels = read_texture(&device, &dst, ResourceStates::RESOLVE_DEST);
+        assert_eq!(texels.len(), 8 * 8 * 4);
+        for texel in texels.chunks(4) {
+            assert_eq!(texel, [0xff, 0, 0xff, 0xff]);
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn copy_between_default_buffers() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let data: Vec<u8> = (0..=255).collect();
+        let upload = upload_buffer(&device, &data);
+        let default_buffer = || {
+            let builder = crate::CommittedResourceBuilder::default_buffer(256)
+                .initial_state(ResourceStates::COPY_DEST);
+            let (buffer, hr) = device.create_committed_resource(builder);
+            assert!(winerror::SUCCEEDED(hr));
+            buffer
+        };
+        let (src, full, partial) = (default_buffer(), default_buffer(), default_buffer());
+        let builder = crate::CommittedResourceBuilder::readback_buffer(512);
+        let (readback, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let to_copy_source = |buffer: &Resource| {
+            ResourceBarrier::transition(
+                buffer.clone(),
+                0,
+                d3d12::D3D12_RESOURCE_STATE_COPY_DEST,
+                d3d12::D3D12_RESOURCE_STATE_COPY_SOURCE,
+                d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            )
+        };
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.copy_resource(&src, &upload);
+        list.resource_barrier(&[to_copy_source(&src)]);
+        list.copy_resource(&full, &src);
+        list.copy_buffer_region(&partial, 16, &src, 64, 32);
+        list.resource_barrier(&[to_copy_source(&full), to_copy_source(&partial)]);
+        list.copy_buffer_region(&readback, 0, &full, 0, 256);
+        list.copy_buffer_region(&readback, 256, &partial, 0, 256);
+        crate::test_execute(&device, &list);
+
+        let (bytes, hr) = readback.read_to_vec(0, 0..512);
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(bytes[..256], data[..]);
+        assert_eq!(bytes[256 + 16..256 + 48], data[64..96]);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn discard_render_target() {
+        use crate::{ResourceFlags, TextureViewDesc, TextureViewDimension};
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let format = winapi::shared::dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let target = test_texture(
+            &device,
+            format,
+            8,
+            1,
+            ResourceFlags::ALLOW_RENDER_TARGET,
+            ResourceStates::RENDER_TARGET,
+        );
+        let rtvs = test_views(&device, crate::DescriptorHeapType::Rtv, 1);
+        let rtv = rtvs.cpu_descriptor_at(0);
+        let desc = TextureViewDesc::new(TextureViewDimension::Texture2D, format);
+        device.create_texture_rtv(&target, &desc, rtv);
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.discard_entire_resource(&target);
+        list.resource_barrier(&[ResourceBarrier::aliasing(None, Some(&target))]);
+        let rect = Rect {
+            left: 0,
+            top: 0,
+            right: 4,
+            bottom: 4,
+        };
+        list.discard_resource(
+            target.clone(),
+            DiscardRegion {
+                rects: &[rect],
+                subregions: 0..1,
+            },
+        );
+        // Discarded contents are undefined until the next full write.
+        list.clear_render_target_view(rtv, [0.0, 1.0, 0.0, 1.0], &[]);
+        crate::test_execute(&device, &list);
+
+        let texels = read_texture(&device, &target, ResourceStates::RENDER_TARGET);
+        for texel in texels.chunks(4) {
+            assert_eq!(texel, [0, 0xff, 0, 0xff]);
+        }
+    }
+
+    #[test]
+    fn full_screen_viewport_and_scissor() {
+        let list = mock_list();
+        list.set_viewports(&[Viewport::from_size(1920.0, 1080.0)]);
+        list.set_scissor_rects(&[Rect {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        }]);
+        assert_eq!(
+            recorded(&list),
+            [
+                Call::Viewports(vec![[0.0, 0.0, 1920.0, 1080.0, 0.0, 1.0]]),
+                Call::ScissorRects(vec![[0, 0, 1920, 1080]]),
+            ]
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Too many viewports: 17")]
+    fn too_many_viewports() {
+        mock_list().set_viewports(&[Viewport::from_size(1.0, 1.0); 17]);
+    }
+
+    #[test]
+    fn vertex_and_16_bit_index_buffers() {
+        let list = mock_list();
+        let buffer = mock_buffer(0x10000);
+        let format = winapi::shared::dxgiformat::DXGI_FORMAT_R16_UINT;
+        list.set_vertex_buffers(1, &[VertexBufferView::new(&buffer, 0, 96, 12)]);
+        list.set_index_buffer_view(Some(&IndexBufferView::new(&buffer, 0x100, 12, format)));
+        list.set_index_buffer_view(None);
+        assert_eq!(
+            recorded(&list),
+            [
+                Call::VertexBuffers(1, vec![(0x10000, 96, 12)]),
+                Call::IndexBuffer(Some((0x10100, 12, format))),
+                Call::IndexBuffer(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn root_views_of_resources() {
+        let list = mock_list();
+        let buffer = mock_buffer(0x20000);
+        list.set_graphics_root_cbv(0, &buffer, 0);
+        list.set_graphics_root_srv(1, &buffer, 0x100);
+        list.set_graphics_root_uav(2, &buffer, 0x200);
+        list.set_compute_root_cbv(3, &buffer, 0x300);
+        list.set_compute_root_srv(4, &buffer, 0x400);
+        list.set_compute_root_uav(5, &buffer, 0x500);
+        assert_eq!(
+            recorded(&list),
+            [
+                Call::GraphicsCbv(0, 0x20000),
+                Call::GraphicsSrv(1, 0x20100),
+                Call::GraphicsUav(2, 0x20200),
+                Call::ComputeCbv(3, 0x20300),
+                Call::ComputeSrv(4, 0x20400),
+                Call::ComputeUav(5, 0x20500),
+            ]
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn record_each_root_binding() {
+        use crate::ShaderVisibility::All;
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let range = crate::DescriptorRange::new(
+            crate::DescriptorRangeType::SRV,
+            1,
+            crate::Binding {
+                space: 0,
+                register: 0,
+            },
+            0,
+        );
+        let parameters = crate::RootParameterBuilder::new()
+            .descriptor_table(&[range], All)
+            .root_constants(0, 0, 4, All)
+            .cbv(1, 0, All)
+            .srv(1, 0, All)
+            .uav(0, 0, All);
+        let blob = crate::RootSignature::serialize_versioned(
+            crate::RootSignatureVersion::V1_0,
+            parameters,
+            &[],
+            crate::RootSignatureFlags::empty(),
+        )
+        .unwrap();
+        let (signature, hr) = device.create_root_signature(blob, 0);
+        assert!(winerror::SUCCEEDED(hr));
+        let (heap, hr) = device.create_descriptor_heap(
+            1,
+            crate::DescriptorHeapType::CbvSrvUav,
+            crate::DescriptorHeapFlags::SHADER_VISIBLE,
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let (buffer, hr) =
+            device.create_committed_resource(crate::CommittedResourceBuilder::default_buffer(256));
+        assert!(winerror::SUCCEEDED(hr));
+        let address = buffer.gpu_virtual_address();
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.set_descriptor_heaps(&[heap.clone()]);
+        list.set_graphics_root_signature(&signature);
+        list.set_graphics_root_descriptor_table(0, heap.start_gpu_descriptor());
+        list.set_graphics_root_32bit_constants(1, &[1, 2, 3, 4], 0);
+        list.set_graphics_root_constant_buffer_view(2, address);
+        list.set_graphics_root_shader_resource_view(3, address);
+        list.set_graphics_root_unordered_access_view(4, address);
+        list.set_compute_root_signature(&signature);
+        list.set_compute_root_descriptor_table(0, heap.start_gpu_descriptor());
+        list.set_compute_root_32bit_constants(1, &[5, 6], 2);
+        list.set_compute_root_constant_buffer_view(2, address);
+        list.set_compute_root_shader_resource_view(3, address);
+        list.set_compute_root_unordered_access_view(4, address);
+        crate::test_execute(&device, &list);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn highest_command_list_version() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (_allocator, list) = crate::test_command_list(&device);
+        let implemented = unsafe {
+            [
+                list.cast::<d3d12::ID3D12GraphicsCommandList1>().1,
+                list.cast::<types::ID3D12GraphicsCommandList2>().1,
+                list.cast::<types::ID3D12GraphicsCommandList3>().1,
+                list.cast::<types::ID3D12GraphicsCommandList4>().1,
+                list.cast::<types::ID3D12GraphicsCommandList5>().1,
+                list.cast::<types::ID3D12GraphicsCommandList6>().1,
+            ]
+        };
+        let highest = implemented
+            .iter()
+            .rposition(|&hr| winerror::SUCCEEDED(hr))
+            .map_or(0, |i| i + 1);
+
+        let versioned = VersionedGraphicsCommandList::new(list.clone());
+        let version = match versioned {
+            VersionedGraphicsCommandList::GraphicsCommandList(_) => 0,
+            VersionedGraphicsCommandList::GraphicsCommandList1(_) => 1,
+            VersionedGraphicsCommandList::GraphicsCommandList2(_) => 2,
+            VersionedGraphicsCommandList::GraphicsCommandList3(_) => 3,
+            VersionedGraphicsCommandList::GraphicsCommandList4(_) => 4,
+            VersionedGraphicsCommandList::GraphicsCommandList5(_) => 5,
+            VersionedGraphicsCommandList::GraphicsCommandList6(_) => 6,
+        };
+        assert_eq!(version, highest);
+        // Every version up to the highest one downcasts, none above it.
+        let downcasts = [
+            versioned.as_command_list1().is_some(),
+            versioned.as_command_list2().is_some(),
+            versioned.as_command_list3().is_some(),
+            versioned.as_command_list4().is_some(),
+            versioned.as_command_list5().is_some(),
+            versioned.as_command_list6().is_some(),
+        ];
+        for (i, &downcast) in downcasts.iter().enumerate() {
+            assert_eq!(downcast, i < highest, "version {}", i + 1);
+        }
+        // Methods of the base version go through `Deref`.
+        assert_eq!(versioned.close(), winerror::S_OK);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn dispatch_single_mesh_group() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (_allocator, list) = crate::test_command_list(&device);
+        // Lists pinned to an older version have no mesh dispatch.
+        let base = VersionedGraphicsCommandList::from_command_list(list.clone());
+        assert_eq!(base.dispatch_mesh(1, 1, 1), winerror::E_NOINTERFACE);
+
+        let mut options: types::D3D12_FEATURE_DATA_D3D12_OPTIONS7 = unsafe { mem::zeroed() };
+        let hr = device.check_feature_support(types::D3D12_FEATURE_D3D12_OPTIONS7, &mut options);
+        if winerror::FAILED(hr) || options.MeshShaderTier == 0 {
+            return;
+        }
+        let versioned = VersionedGraphicsCommandList::new(list.clone());
+        assert_eq!(versioned.dispatch_mesh(1, 1, 1), winerror::S_OK);
+        assert_eq!(list.close(), winerror::S_OK);
+    }
+
+    #[test]
+    fn two_render_targets_and_depth() {
+        let list = mock_list();
+        let rtvs = [CpuDescriptor { ptr: 0x100 }, CpuDescriptor { ptr: 0x200 }];
+        let dsv = CpuDescriptor { ptr: 0x300 };
+        list.set_render_targets(&rtvs, Some(dsv), false);
+        list.set_render_targets(&rtvs, None, true);
+        list.set_render_targets(&[], Some(dsv), false);
+        assert_eq!(
+            recorded(&list),
+            [
+                Call::RenderTargets(vec![0x100, 0x200], false, Some(0x300)),
+                Call::RenderTargets(vec![0x100], true, None),
+                Call::RenderTargets(Vec::new(), false, Some(0x300)),
+            ]
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn clear_color_and_depth() {
+        use crate::{DsvFlags, ResourceFlags, TextureViewDesc, TextureViewDimension};
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let color_format = winapi::shared::dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let depth_format = winapi::shared::dxgiformat::DXGI_FORMAT_D32_FLOAT;
+        let color = test_texture(
+            &device,
+            color_format,
+            8,
+            1,
+            ResourceFlags::ALLOW_RENDER_TARGET,
+            ResourceStates::RENDER_TARGET,
+        );
+        let depth = test_texture(
+            &device,
+            depth_format,
+            8,
+            1,
+            ResourceFlags::ALLOW_DEPTH_STENCIL,
+            ResourceStates::DEPTH_WRITE,
+        );
+        let rtvs = test_views(&device, crate::DescriptorHeapType::Rtv, 1);
+        let dsvs = test_views(&device, crate::DescriptorHeapType::Dsv, 1);
+        let (rtv, dsv) = (rtvs.cpu_descriptor_at(0), dsvs.cpu_descriptor_at(0));
+        let desc = TextureViewDesc::new(TextureViewDimension::Texture2D, color_format);
+        device.create_texture_rtv(&color, &desc, rtv);
+        let desc = TextureViewDesc::new(TextureViewDimension::Texture2D, depth_format);
+        let hr = device.create_depth_stencil_view(&depth, &desc, DsvFlags::empty(), dsv);
+        assert_eq!(hr, winerror::S_OK);
+
+        // Whole views first, then the top-left quarter of the color target.
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.clear_render_target_view(rtv, [1.0, 0.0, 0.0, 1.0], &[]);
+        list.clear_depth_stencil_view(dsv, ClearFlags::DEPTH, 0.5, 0, &[]);
+        let quarter = Rect {
+            left: 0,
+            top: 0,
+            right: 4,
+            bottom: 4,
+        };
+        list.clear_render_target_view(rtv, [0.0, 0.0, 1.0, 1.0], &[quarter]);
+        crate::test_execute(&device, &list);
+
+        let texels = read_texture(&device, &color, ResourceStates::RENDER_TARGET);
+        for (i, texel) in texels.chunks(4).enumerate() {
+            let (x, y) = (i % 8, i / 8);
+            let expected = if x < 4 && y < 4 {
+                [0, 0, 0xff, 0xff]
+            } else {
+                [0xff, 0, 0, 0xff]
+            };
+            assert_eq!(texel, expected, "texel ({}, {})", x, y);
+        }
+        let texels = read_texture(&device, &depth, ResourceStates::DEPTH_WRITE);
+        for texel in texels.chunks(4) {
+            assert_eq!(texel, 0.5f32.to_le_bytes());
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn copy_region_from_upload_buffer() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let format = winapi::shared::dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let texture = test_texture(
+            &device,
+            format,
+            8,
+            1,
+            crate::ResourceFlags::empty(),
+            ResourceStates::COPY_DEST,
+        );
+        // 4x4 texels, each row starting at a pitch boundary.
+        let row_pitch = d3d12::D3D12_TEXTURE_DATA_PITCH_ALIGNMENT as usize;
+        let mut data = vec![0; row_pitch * 4];
+        for (y, row) in data.chunks_mut(row_pitch).enumerate() {
+            for (x, texel) in row[..16].chunks_mut(4).enumerate() {
+                texel.copy_from_slice(&[x as u8 + 1, y as u8 + 1, 0, 0xff]);
+            }
+        }
+        let upload = upload_buffer(&device, &data);
+        let footprint = d3d12::D3D12_PLACED_SUBRESOURCE_FOOTPRINT {
+            Offset: 0,
+            Footprint: d3d12::D3D12_SUBRESOURCE_FOOTPRINT {
+                Format: format,
+                Width: 4,
+                Height: 4,
+                Depth: 1,
+                RowPitch: row_pitch as u32,
+            },
+        };
+        let src = TextureCopyLocation::Footprint(upload.clone(), footprint);
+        let dst = TextureCopyLocation::Subresource(texture.clone(), 0);
+        // The bottom-right 2x2 texels of the footprint.
+        let corner = d3d12::D3D12_BOX {
+            left: 2,
+            top: 2,
+            front: 0,
+            right: 4,
+            bottom: 4,
+            back: 1,
+        };
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.copy_texture_region(&dst, 2, 2, 0, &src, None);
+        list.copy_texture_region(&dst, 6, 0, 0, &src, Some(&corner));
+        crate::test_execute(&device, &list);
+
+        let texels = read_texture(&device, &texture, ResourceStates::COPY_DEST);
+        let texel = |x: usize, y: usize| &texels[(y * 8 + x) * 4..][..4];
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(texel(x + 2, y + 2), [x as u8 + 1, y as u8 + 1, 0, 0xff]);
+            }
+        }
+        for y in 0..2 {
+            for x in 0..2 {
+                assert_eq!(texel(x + 6, y), [x as u8 + 3, y as u8 + 3, 0, 0xff]);
+            }
+        }
+    }
+}
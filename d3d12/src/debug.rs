@@ -1,9 +1,12 @@
-use crate::com::ComPtr;
-use winapi::um::d3d12sdklayers;
+use crate::{com::ComPtr, D3DResult, Device, HRESULT};
+use std::{ffi::CStr, mem, ptr, slice};
 #[cfg(any(feature = "libloading", feature = "implicit-link"))]
 use winapi::Interface as _;
+use winapi::{shared::winerror, um::d3d12sdklayers};
 
 pub type Debug = ComPtr<d3d12sdklayers::ID3D12Debug>;
+// `ID3D12Debug1` doesn't inherit from `ID3D12Debug`, so it can't be part of an inheritance chain.
+pub type Debug1 = ComPtr<d3d12sdklayers::ID3D12Debug1>;
 
 #[cfg(feature = "libloading")]
 impl crate::D3D12Lib {
@@ -40,4 +43,270 @@ impl Debug {
     pub fn enable_layer(&self) {
         unsafe { self.EnableDebugLayer() }
     }
+
+    /// Enables GPU-based validation, `E_NOINTERFACE` is returned if `ID3D12Debug1`
+    /// isn't available on this SDK or OS.
+    pub fn enable_gpu_based_validation(&self) -> HRESULT {
+        let (debug1, hr) = unsafe { self.cast::<d3d12sdklayers::ID3D12Debug1>() };
+        if winerror::SUCCEEDED(hr) {
+            debug1.set_enable_gpu_based_validation(true);
+        }
+        hr
+    }
+}
+
+impl Debug1 {
+    pub fn enable_layer(&self) {
+        unsafe { self.EnableDebugLayer() }
+    }
+
+    /// Has to be set before creating the device.
+    pub fn set_enable_gpu_based_validation(&self, enable: bool) {
+        unsafe { self.SetEnableGPUBasedValidation(enable as _) }
+    }
+
+    pub fn set_enable_synchronized_command_queue_validation(&self, enable: bool) {
+        unsafe { self.SetEnableSynchronizedCommandQueueValidation(enable as _) }
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Corruption = d3d12sdklayers::D3D12_MESSAGE_SEVERITY_CORRUPTION,
+    Error = d3d12sdklayers::D3D12_MESSAGE_SEVERITY_ERROR,
+    Warning = d3d12sdklayers::D3D12_MESSAGE_SEVERITY_WARNING,
+    Info = d3d12sdklayers::D3D12_MESSAGE_SEVERITY_INFO,
+    Message = d3d12sdklayers::D3D12_MESSAGE_SEVERITY_MESSAGE,
+}
+
+impl Severity {
+    fn from_raw(raw: d3d12sdklayers::D3D12_MESSAGE_SEVERITY) -> Self {
+        match raw {
+            d3d12sdklayers::D3D12_MESSAGE_SEVERITY_CORRUPTION => Severity::Corruption,
+            d3d12sdklayers::D3D12_MESSAGE_SEVERITY_ERROR => Severity::Error,
+            d3d12sdklayers::D3D12_MESSAGE_SEVERITY_WARNING => Severity::Warning,
+            d3d12sdklayers::D3D12_MESSAGE_SEVERITY_INFO => Severity::Info,
+            _ => Severity::Message,
+        }
+    }
+}
+
+pub type MessageCategory = d3d12sdklayers::D3D12_MESSAGE_CATEGORY;
+pub type MessageId = d3d12sdklayers::D3D12_MESSAGE_ID;
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub severity: Severity,
+    pub category: MessageCategory,
+    pub id: MessageId,
+    pub text: String,
+}
+
+pub type D3D12InfoQueue = ComPtr<d3d12sdklayers::ID3D12InfoQueue>;
+
+pub type DebugDevice = ComPtr<d3d12sdklayers::ID3D12DebugDevice>;
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct RldoFlags: u32 {
+        const SUMMARY = d3d12sdklayers::D3D12_RLDO_SUMMARY;
+        const DETAIL = d3d12sdklayers::D3D12_RLDO_DETAIL;
+        const IGNORE_INTERNAL = d3d12sdklayers::D3D12_RLDO_IGNORE_INTERNAL;
+    }
+}
+
+impl DebugDevice {
+    /// Reports the live objects of the device to the debug output.
+    pub fn report_live_device_objects(&self, flags: RldoFlags) -> HRESULT {
+        unsafe { self.ReportLiveDeviceObjects(flags.bits()) }
+    }
+}
+
+impl Device {
+    /// Debug interface of the device, `None` if the debug layer wasn't enabled before the device was created.
+    pub fn debug_device(&self) -> D3DResult<Option<DebugDevice>> {
+        let (debug, hr) = unsafe { self.cast::<d3d12sdklayers::ID3D12DebugDevice>() };
+        if winerror::SUCCEEDED(hr) {
+            (Some(debug), hr)
+        } else {
+            (None, hr)
+        }
+    }
+
+    /// Info queue of the device, `None` if the debug layer wasn't enabled before the device was created.
+    pub fn info_queue(&self) -> D3DResult<Option<D3D12InfoQueue>> {
+        let (queue, hr) = unsafe { self.cast::<d3d12sdklayers::ID3D12InfoQueue>() };
+        if winerror::SUCCEEDED(hr) {
+            (Some(queue), hr)
+        } else {
+            (None, hr)
+        }
+    }
+}
+
+impl D3D12InfoQueue {
+    /// Drops any new message with one of the given `ids`.
+    pub fn push_deny_filter(&self, ids: &[MessageId]) -> HRESULT {
+        let mut ids = ids.to_vec();
+        let mut filter: d3d12sdklayers::D3D12_INFO_QUEUE_FILTER = unsafe { mem::zeroed() };
+        filter.DenyList.NumIDs = ids.len() as _;
+        filter.DenyList.pIDList = ids.as_mut_ptr();
+
+        unsafe { self.PushStorageFilter(&mut filter) }
+    }
+
+    pub fn set_break_on_severity(&self, severity: Severity, enable: bool) -> HRESULT {
+        unsafe { self.SetBreakOnSeverity(severity as _, enable as _) }
+    }
+
+    /// Reads all stored messages and clears the queue.
+    pub fn drain_messages(&self) -> Vec<Message> {
+        let count = unsafe { self.GetNumStoredMessages() };
+        let mut messages = Vec::with_capacity(count as usize);
+        let mut storage = Vec::<u64>::new();
+
+        for index in 0..count {
+            // First query the size of the message, then fill it.
+            let mut byte_length = 0;
+            let hr = unsafe { self.GetMessage(index, ptr::null_mut(), &mut byte_length) };
+            if !winerror::SUCCEEDED(hr) || byte_length == 0 {
+                continue;
+            }
+
+            // `u64` storage keeps the message suitably aligned.
+            storage.clear();
+            storage.resize((byte_length + 7) / 8, 0);
+            let raw = storage.as_mut_ptr() as *mut d3d12sdklayers::D3D12_MESSAGE;
+            let hr = unsafe { self.GetMessage(index, raw, &mut byte_length) };
+            if !winerror::SUCCEEDED(hr) {
+                continue;
+            }
+
+            let raw = unsafe { &*raw };
+            let text = if raw.pDescription.is_null() || raw.DescriptionByteLength == 0 {
+                String::new()
+            } else {
+                let bytes = unsafe {
+                    slice::from_raw_parts(
+                        raw.pDescription as *const u8,
+                        raw.DescriptionByteLength as usize,
+                    )
+                };
+                match CStr::from_bytes_with_nul(bytes) {
+                    Ok(text) => text.to_string_lossy().into_owned(),
+                    Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+                }
+            };
+
+            messages.push(Message {
+                severity: Severity::from_raw(raw.Severity),
+                category: raw.Category,
+                id: raw.ID,
+                text,
+            });
+        }
+
+        unsafe { self.ClearStoredMessages() };
+        messages
+    }
+}
+
+#[cfg(all(test, feature = "implicit-link"))]
+mod tests {
+    use super::*;
+
+    /// `None` if the debug layer isn't installed.
+    fn debug_interface() -> Option<Debug> {
+        let (debug, hr) = Debug::get_interface();
+        if winerror::FAILED(hr) {
+            return None;
+        }
+        Some(debug)
+    }
+
+    #[test]
+    fn toggle_gpu_based_validation() {
+        let debug = match debug_interface() {
+            Some(debug) => debug,
+            None => return,
+        };
+        let hr = debug.enable_gpu_based_validation();
+        if hr == winerror::E_NOINTERFACE {
+            return;
+        }
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (debug1, hr) = unsafe { debug.cast::<d3d12sdklayers::ID3D12Debug1>() };
+        assert!(winerror::SUCCEEDED(hr));
+        debug1.set_enable_synchronized_command_queue_validation(true);
+        // Devices of the other tests are created without it.
+        debug1.set_enable_gpu_based_validation(false);
+    }
+
+    #[test]
+    fn drain_mismatching_clear_warning() {
+        use crate::{CommittedResourceBuilder, DescriptorHeapFlags, DescriptorHeapType};
+        use winapi::{shared::dxgiformat, um::d3d12};
+
+        // The layer stays enabled for the process, it has to be before the device is created.
+        match debug_interface() {
+            Some(debug) => debug.enable_layer(),
+            None => return,
+        }
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (queue, hr) = device.info_queue();
+        assert!(winerror::SUCCEEDED(hr));
+        let queue = queue.unwrap();
+        queue.drain_messages();
+
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let mut clear_value: d3d12::D3D12_CLEAR_VALUE = unsafe { mem::zeroed() };
+        clear_value.Format = format;
+        *unsafe { clear_value.u.Color_mut() } = [0.0, 0.0, 0.0, 1.0];
+        let builder = CommittedResourceBuilder::texture2d(
+            format,
+            4,
+            4,
+            1,
+            crate::ResourceFlags::ALLOW_RENDER_TARGET,
+        )
+        .initial_state(crate::ResourceStates::RENDER_TARGET)
+        .clear_value(clear_value);
+        let (target, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        let (rtv_heap, hr) = device.create_descriptor_heap(
+            1,
+            DescriptorHeapType::Rtv,
+            DescriptorHeapFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let rtv = rtv_heap.start_cpu_descriptor();
+        device.create_render_target_view(
+            target,
+            &crate::RenderTargetViewDesc::texture_2d(format, 0, 0),
+            rtv,
+        );
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.clear_render_target_view(rtv, [1.0, 0.0, 0.0, 1.0], &[]);
+        assert!(winerror::SUCCEEDED(list.close()));
+
+        let messages = queue.drain_messages();
+        let warning = messages
+            .iter()
+            .find(|message| {
+                message.id
+                    == d3d12sdklayers::D3D12_MESSAGE_ID_CLEARRENDERTARGETVIEW_MISMATCHINGCLEARVALUE
+            })
+            .unwrap_or_else(|| panic!("No mismatching clear value warning in {:?}", messages));
+        assert_eq!(warning.severity, Severity::Warning);
+        assert!(!warning.text.is_empty());
+        // Draining cleared the queue.
+        assert!(queue.drain_messages().is_empty());
+    }
 }
@@ -1,10 +1,108 @@
-use crate::{com::ComPtr, Blob, D3DResult, Error, TextureAddressMode};
-use std::{fmt, mem, ops::Range};
-use winapi::{shared::dxgiformat, um::d3d12};
+use crate::{
+    blob_result, com::ComPtr, Blob, BlobError, D3DResult, Device, Error, NodeMask, RootIndex,
+    TextureAddressMode,
+};
+use std::{cmp, convert::TryFrom, fmt, hash, mem, ops::Range, ptr, slice};
+use winapi::{
+    shared::{dxgiformat, winerror},
+    um::d3d12,
+    Interface,
+};
 
 pub type CpuDescriptor = d3d12::D3D12_CPU_DESCRIPTOR_HANDLE;
 pub type GpuDescriptor = d3d12::D3D12_GPU_DESCRIPTOR_HANDLE;
 
+/// Offsetting of descriptor handles within a descriptor heap.
+pub trait DescriptorHandle: Copy {
+    /// Returns the handle `count` descriptors further into the heap.
+    ///
+    /// `increment` is the descriptor increment size of the heap type,
+    /// see [`Device::get_descriptor_increment_size`](crate::Device::get_descriptor_increment_size).
+    fn advance(self, count: u32, increment: u32) -> Self;
+
+    /// Address of the descriptor, widened to 64 bits for CPU handles.
+    fn raw_ptr(self) -> u64;
+}
+
+impl DescriptorHandle for CpuDescriptor {
+    fn advance(self, count: u32, increment: u32) -> Self {
+        let offset = count as usize * increment as usize;
+        debug_assert!(
+            self.ptr.checked_add(offset).is_some(),
+            "CPU descriptor handle overflow"
+        );
+        CpuDescriptor {
+            ptr: self.ptr.wrapping_add(offset),
+        }
+    }
+
+    fn raw_ptr(self) -> u64 {
+        self.ptr as u64
+    }
+}
+
+impl DescriptorHandle for GpuDescriptor {
+    fn advance(self, count: u32, increment: u32) -> Self {
+        let offset = count as u64 * increment as u64;
+        debug_assert!(
+            self.ptr.checked_add(offset).is_some(),
+            "GPU descriptor handle overflow"
+        );
+        GpuDescriptor {
+            ptr: self.ptr.wrapping_add(offset),
+        }
+    }
+
+    fn raw_ptr(self) -> u64 {
+        self.ptr
+    }
+}
+
+/// Descriptor handle comparing, hashing and ordering by its address.
+///
+/// The handles themselves are winapi types without these impls, wrap them to store
+/// them in sets and maps, e.g. a `BTreeMap` of free ranges.
+#[derive(Clone, Copy)]
+pub struct DescriptorKey<D: DescriptorHandle>(pub D);
+
+impl<D: DescriptorHandle> fmt::Debug for DescriptorKey<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DescriptorKey({:#x})", self.0.raw_ptr())
+    }
+}
+
+impl<D: DescriptorHandle> PartialEq for DescriptorKey<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.raw_ptr() == other.0.raw_ptr()
+    }
+}
+
+impl<D: DescriptorHandle> Eq for DescriptorKey<D> {}
+
+impl<D: DescriptorHandle> PartialOrd for DescriptorKey<D> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<D: DescriptorHandle> Ord for DescriptorKey<D> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.0.raw_ptr().cmp(&other.0.raw_ptr())
+    }
+}
+
+impl<D: DescriptorHandle> hash::Hash for DescriptorKey<D> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.raw_ptr().hash(state)
+    }
+}
+
+impl<D: DescriptorHandle> From<D> for DescriptorKey<D> {
+    fn from(handle: D) -> Self {
+        DescriptorKey(handle)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Binding {
     pub space: u32,
@@ -20,6 +118,26 @@ pub enum DescriptorHeapType {
     Dsv = d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_DSV,
 }
 
+/// Descriptor increment sizes of all heap types, see [`Device::descriptor_increment_sizes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DescriptorIncrements {
+    pub cbv_srv_uav: u32,
+    pub sampler: u32,
+    pub rtv: u32,
+    pub dsv: u32,
+}
+
+impl DescriptorIncrements {
+    pub fn get(&self, heap_type: DescriptorHeapType) -> u32 {
+        match heap_type {
+            DescriptorHeapType::CbvSrvUav => self.cbv_srv_uav,
+            DescriptorHeapType::Sampler => self.sampler,
+            DescriptorHeapType::Rtv => self.rtv,
+            DescriptorHeapType::Dsv => self.dsv,
+        }
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
     pub struct DescriptorHeapFlags: u32 {
@@ -37,6 +155,249 @@ impl DescriptorHeap {
     pub fn start_gpu_descriptor(&self) -> GpuDescriptor {
         unsafe { self.GetGPUDescriptorHandleForHeapStart() }
     }
+
+    /// GPU handle of the first descriptor, `None` for heaps which aren't shader visible.
+    pub fn gpu_descriptor_start(&self) -> Option<GpuDescriptor> {
+        if self.is_shader_visible() {
+            Some(self.start_gpu_descriptor())
+        } else {
+            None
+        }
+    }
+
+    pub fn is_shader_visible(&self) -> bool {
+        let desc = unsafe { self.GetDesc() };
+        desc.Flags & d3d12::D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE != 0
+    }
+
+    /// Whether `descriptor` points into this heap, always `false` for heaps which aren't shader visible.
+    pub fn contains_gpu_descriptor(&self, descriptor: GpuDescriptor) -> bool {
+        let desc = unsafe { self.GetDesc() };
+        if desc.Flags & d3d12::D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE == 0 {
+            return false;
+        }
+
+        let mut device = Device::null();
+        let hr = unsafe { self.GetDevice(&d3d12::ID3D12Device::uuidof(), device.mut_void()) };
+        if !winerror::SUCCEEDED(hr) {
+            return false;
+        }
+        let increment = unsafe { device.GetDescriptorHandleIncrementSize(desc.Type) };
+
+        let start = self.start_gpu_descriptor().ptr;
+        let end = start + desc.NumDescriptors as u64 * increment as u64;
+        (start..end).contains(&descriptor.ptr)
+    }
+}
+
+/// Descriptor heap together with the increment size of its type, to index into it.
+///
+/// Created by [`Device::create_indexed_descriptor_heap`](crate::Device::create_indexed_descriptor_heap).
+#[derive(Clone, Debug)]
+pub struct IndexedDescriptorHeap {
+    heap: DescriptorHeap,
+    increment: u32,
+}
+
+impl IndexedDescriptorHeap {
+    pub(crate) fn new(heap: DescriptorHeap, increment: u32) -> Self {
+        IndexedDescriptorHeap { heap, increment }
+    }
+
+    pub fn heap(&self) -> &DescriptorHeap {
+        &self.heap
+    }
+
+    pub fn increment(&self) -> u32 {
+        self.increment
+    }
+
+    /// CPU handle of the descriptor at `index`.
+    pub fn cpu_descriptor_at(&self, index: u32) -> CpuDescriptor {
+        self.heap
+            .start_cpu_descriptor()
+            .advance(index, self.increment)
+    }
+
+    /// GPU handle of the descriptor at `index`.
+    ///
+    /// The heap must be shader visible.
+    pub fn gpu_descriptor_at(&self, index: u32) -> GpuDescriptor {
+        self.heap
+            .start_gpu_descriptor()
+            .advance(index, self.increment)
+    }
+}
+
+/// Linear allocator of contiguous descriptor ranges in a shader-visible CBV/SRV/UAV heap.
+///
+/// Allocations are never freed individually, the whole heap is recycled with [`Self::reset`],
+/// typically once per frame after the GPU is done with it.
+#[derive(Debug)]
+pub struct GpuDescriptorAllocator {
+    heap: IndexedDescriptorHeap,
+    capacity: u32,
+    offset: u32,
+}
+
+impl GpuDescriptorAllocator {
+    pub fn new(device: &Device, capacity: u32, node_mask: NodeMask) -> D3DResult<Self> {
+        let (heap, hr) = device.create_indexed_descriptor_heap(
+            capacity,
+            DescriptorHeapType::CbvSrvUav,
+            DescriptorHeapFlags::SHADER_VISIBLE,
+            node_mask,
+        );
+        // A failed heap has no room, so that `allocate` never hands out null handles.
+        let allocator = GpuDescriptorAllocator {
+            heap,
+            capacity: if winerror::SUCCEEDED(hr) { capacity } else { 0 },
+            offset: 0,
+        };
+
+        (allocator, hr)
+    }
+
+    pub fn heap(&self) -> &DescriptorHeap {
+        self.heap.heap()
+    }
+
+    /// Allocates `count` contiguous descriptors.
+    ///
+    /// Returns the CPU handle of the first descriptor, to copy descriptors into,
+    /// and the matching GPU handle, to bind the range as a descriptor table.
+    pub fn allocate(&mut self, count: u32) -> Option<(CpuDescriptor, GpuDescriptor)> {
+        if count > self.capacity - self.offset {
+            return None;
+        }
+        let index = self.offset;
+        self.offset += count;
+
+        Some((
+            self.heap.cpu_descriptor_at(index),
+            self.heap.gpu_descriptor_at(index),
+        ))
+    }
+
+    /// Makes the whole heap available again.
+    pub fn reset(&mut self) {
+        self.offset = 0;
+    }
+}
+
+/// Contiguous descriptors handed out by a [`DescriptorAllocator`].
+pub struct DescriptorAllocation {
+    index: u32,
+    count: u32,
+    cpu: CpuDescriptor,
+    gpu: Option<GpuDescriptor>,
+    increment: u32,
+}
+
+impl DescriptorAllocation {
+    /// Index of the first descriptor in the heap.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn cpu_descriptor(&self, index: u32) -> CpuDescriptor {
+        debug_assert!(index < self.count);
+        self.cpu.advance(index, self.increment)
+    }
+
+    /// `None` unless the heap is shader visible.
+    pub fn gpu_descriptor(&self, index: u32) -> Option<GpuDescriptor> {
+        debug_assert!(index < self.count);
+        self.gpu.map(|gpu| gpu.advance(index, self.increment))
+    }
+}
+
+/// Allocator of contiguous descriptor ranges in a heap of any type, freed individually.
+#[derive(Debug)]
+pub struct DescriptorAllocator {
+    heap: IndexedDescriptorHeap,
+    /// Free ranges of descriptor indices, sorted and never adjacent.
+    free: Vec<Range<u32>>,
+}
+
+impl DescriptorAllocator {
+    pub fn new(
+        device: &Device,
+        heap_type: DescriptorHeapType,
+        capacity: u32,
+        flags: DescriptorHeapFlags,
+        node_mask: NodeMask,
+    ) -> D3DResult<Self> {
+        let (heap, hr) =
+            device.create_indexed_descriptor_heap(capacity, heap_type, flags, node_mask);
+        let free = if winerror::SUCCEEDED(hr) && capacity > 0 {
+            vec![0..capacity]
+        } else {
+            Vec::new()
+        };
+        let allocator = DescriptorAllocator { heap, free };
+
+        (allocator, hr)
+    }
+
+    pub fn heap(&self) -> &DescriptorHeap {
+        self.heap.heap()
+    }
+
+    /// Allocates `count` contiguous descriptors, `None` if no free range is large enough.
+    pub fn allocate(&mut self, count: u32) -> Option<DescriptorAllocation> {
+        debug_assert!(count > 0);
+        let index = self
+            .free
+            .iter()
+            .position(|range| range.end - range.start >= count)?;
+        let start = self.free[index].start;
+        if self.free[index].end - start == count {
+            self.free.remove(index);
+        } else {
+            self.free[index].start += count;
+        }
+
+        Some(DescriptorAllocation {
+            index: start,
+            count,
+            cpu: self.heap.cpu_descriptor_at(start),
+            gpu: self
+                .heap
+                .heap()
+                .gpu_descriptor_start()
+                .map(|gpu| gpu.advance(start, self.heap.increment())),
+            increment: self.heap.increment(),
+        })
+    }
+
+    /// Returns the descriptors to the allocator, merging them with adjacent free ranges.
+    ///
+    /// The GPU must be done with them.
+    pub fn free(&mut self, allocation: DescriptorAllocation) {
+        let mut range = allocation.index..allocation.index + allocation.count;
+        let index = self
+            .free
+            .iter()
+            .position(|other| other.start > range.start)
+            .unwrap_or(self.free.len());
+        debug_assert!(
+            index == 0 || self.free[index - 1].end <= range.start,
+            "double free"
+        );
+        if index < self.free.len() && self.free[index].start == range.end {
+            range.end = self.free.remove(index).end;
+        }
+        if index > 0 && self.free[index - 1].end == range.start {
+            self.free[index - 1].end = range.end;
+        } else {
+            self.free.insert(index, range);
+        }
+    }
 }
 
 #[repr(u32)]
@@ -48,10 +409,54 @@ pub enum ShaderVisibility {
     DS = d3d12::D3D12_SHADER_VISIBILITY_DOMAIN,
     GS = d3d12::D3D12_SHADER_VISIBILITY_GEOMETRY,
     PS = d3d12::D3D12_SHADER_VISIBILITY_PIXEL,
+    AS = crate::types::D3D12_SHADER_VISIBILITY_AMPLIFICATION,
+    MS = crate::types::D3D12_SHADER_VISIBILITY_MESH,
+}
+
+impl ShaderVisibility {
+    fn from_raw(raw: d3d12::D3D12_SHADER_VISIBILITY) -> Option<Self> {
+        Some(match raw {
+            d3d12::D3D12_SHADER_VISIBILITY_ALL => Self::All,
+            d3d12::D3D12_SHADER_VISIBILITY_VERTEX => Self::VS,
+            d3d12::D3D12_SHADER_VISIBILITY_HULL => Self::HS,
+            d3d12::D3D12_SHADER_VISIBILITY_DOMAIN => Self::DS,
+            d3d12::D3D12_SHADER_VISIBILITY_GEOMETRY => Self::GS,
+            d3d12::D3D12_SHADER_VISIBILITY_PIXEL => Self::PS,
+            crate::types::D3D12_SHADER_VISIBILITY_AMPLIFICATION => Self::AS,
+            crate::types::D3D12_SHADER_VISIBILITY_MESH => Self::MS,
+            _ => return None,
+        })
+    }
 }
 
 #[repr(u32)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SrvDimension {
+    Buffer = d3d12::D3D12_SRV_DIMENSION_BUFFER,
+    Texture1D = d3d12::D3D12_SRV_DIMENSION_TEXTURE1D,
+    Texture1DArray = d3d12::D3D12_SRV_DIMENSION_TEXTURE1DARRAY,
+    Texture2D = d3d12::D3D12_SRV_DIMENSION_TEXTURE2D,
+    Texture2DArray = d3d12::D3D12_SRV_DIMENSION_TEXTURE2DARRAY,
+    Texture2DMs = d3d12::D3D12_SRV_DIMENSION_TEXTURE2DMS,
+    Texture2DMsArray = d3d12::D3D12_SRV_DIMENSION_TEXTURE2DMSARRAY,
+    Texture3D = d3d12::D3D12_SRV_DIMENSION_TEXTURE3D,
+    TextureCube = d3d12::D3D12_SRV_DIMENSION_TEXTURECUBE,
+    TextureCubeArray = d3d12::D3D12_SRV_DIMENSION_TEXTURECUBEARRAY,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UavDimension {
+    Buffer = d3d12::D3D12_UAV_DIMENSION_BUFFER,
+    Texture1D = d3d12::D3D12_UAV_DIMENSION_TEXTURE1D,
+    Texture1DArray = d3d12::D3D12_UAV_DIMENSION_TEXTURE1DARRAY,
+    Texture2D = d3d12::D3D12_UAV_DIMENSION_TEXTURE2D,
+    Texture2DArray = d3d12::D3D12_UAV_DIMENSION_TEXTURE2DARRAY,
+    Texture3D = d3d12::D3D12_UAV_DIMENSION_TEXTURE3D,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DescriptorRangeType {
     SRV = d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
     UAV = d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_UAV,
@@ -59,6 +464,18 @@ pub enum DescriptorRangeType {
     Sampler = d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_SAMPLER,
 }
 
+impl DescriptorRangeType {
+    fn from_raw(raw: d3d12::D3D12_DESCRIPTOR_RANGE_TYPE) -> Option<Self> {
+        Some(match raw {
+            d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_SRV => Self::SRV,
+            d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_UAV => Self::UAV,
+            d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_CBV => Self::CBV,
+            d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_SAMPLER => Self::Sampler,
+            _ => return None,
+        })
+    }
+}
+
 #[repr(transparent)]
 pub struct DescriptorRange(d3d12::D3D12_DESCRIPTOR_RANGE);
 impl DescriptorRange {
@@ -86,6 +503,52 @@ impl fmt::Debug for DescriptorRange {
     }
 }
 
+bitflags::bitflags! {
+    /// Root signature 1.1 hints on how descriptors and their data change.
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct DescriptorRangeFlags: u32 {
+        const NONE = d3d12::D3D12_DESCRIPTOR_RANGE_FLAG_NONE;
+        const DESCRIPTORS_VOLATILE = d3d12::D3D12_DESCRIPTOR_RANGE_FLAG_DESCRIPTORS_VOLATILE;
+        const DATA_VOLATILE = d3d12::D3D12_DESCRIPTOR_RANGE_FLAG_DATA_VOLATILE;
+        const DATA_STATIC_WHILE_SET_AT_EXECUTE = d3d12::D3D12_DESCRIPTOR_RANGE_FLAG_DATA_STATIC_WHILE_SET_AT_EXECUTE;
+        const DATA_STATIC = d3d12::D3D12_DESCRIPTOR_RANGE_FLAG_DATA_STATIC;
+    }
+}
+
+impl DescriptorRangeFlags {
+    /// Flags matching the behavior of version 1.0 root signatures.
+    fn v1_0(ty: d3d12::D3D12_DESCRIPTOR_RANGE_TYPE) -> Self {
+        if ty == d3d12::D3D12_DESCRIPTOR_RANGE_TYPE_SAMPLER {
+            Self::DESCRIPTORS_VOLATILE
+        } else {
+            Self::DESCRIPTORS_VOLATILE | Self::DATA_VOLATILE
+        }
+    }
+}
+
+/// Descriptor range carrying root signature 1.1 flags, see
+/// [`RootParameterBuilder::descriptor_table1`].
+#[derive(Debug)]
+pub struct DescriptorRange1 {
+    range: DescriptorRange,
+    flags: DescriptorRangeFlags,
+}
+
+impl DescriptorRange1 {
+    /// Creates a range with no flags, i.e. the static defaults of version 1.1.
+    pub fn new(ty: DescriptorRangeType, count: u32, base_binding: Binding, offset: u32) -> Self {
+        DescriptorRange1 {
+            range: DescriptorRange::new(ty, count, base_binding, offset),
+            flags: DescriptorRangeFlags::NONE,
+        }
+    }
+
+    pub fn flags(mut self, flags: DescriptorRangeFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+}
+
 #[repr(transparent)]
 pub struct RootParameter(d3d12::D3D12_ROOT_PARAMETER);
 impl RootParameter {
@@ -206,6 +669,276 @@ impl fmt::Debug for RootParameter {
     }
 }
 
+/// Root parameters of a root signature, owning the descriptor ranges of its tables
+/// so they stay alive until serialization.
+#[derive(Debug, Default)]
+pub struct RootParameterBuilder {
+    parameters: Vec<RootParameter>,
+    // Boxed so they don't move when more tables are added.
+    ranges: Vec<Box<[DescriptorRange]>>,
+    /// Version 1.1 flags of the ranges, the 1.0 behavior for tables without flags.
+    range_flags: Vec<Box<[DescriptorRangeFlags]>>,
+}
+
+/// Version 1.1 copy of the parameters, alive until serialization.
+#[derive(Default)]
+struct RootParameters1 {
+    parameters: Vec<d3d12::D3D12_ROOT_PARAMETER1>,
+    ranges: Vec<Vec<d3d12::D3D12_DESCRIPTOR_RANGE1>>,
+}
+
+impl RootParameterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn descriptor_table(
+        mut self,
+        ranges: &[DescriptorRange],
+        visibility: ShaderVisibility,
+    ) -> Self {
+        let ranges = ranges
+            .iter()
+            .map(|range| DescriptorRange(range.0))
+            .collect::<Box<[_]>>();
+        self.parameters
+            .push(RootParameter::descriptor_table(visibility, &ranges));
+        self.range_flags.push(
+            ranges
+                .iter()
+                .map(|range| DescriptorRangeFlags::v1_0(range.0.RangeType))
+                .collect(),
+        );
+        self.ranges.push(ranges);
+        self
+    }
+
+    /// Adds a table of ranges with version 1.1 flags.
+    ///
+    /// Flags differing from the 1.0 behavior make the root signature require version 1.1,
+    /// see [`requires_v1_1`](Self::requires_v1_1).
+    pub fn descriptor_table1(
+        mut self,
+        ranges: &[DescriptorRange1],
+        visibility: ShaderVisibility,
+    ) -> Self {
+        let (ranges, flags): (Vec<_>, Vec<_>) = ranges
+            .iter()
+            .map(|range| (DescriptorRange(range.range.0), range.flags))
+            .unzip();
+        let ranges = ranges.into_boxed_slice();
+        self.parameters
+            .push(RootParameter::descriptor_table(visibility, &ranges));
+        self.range_flags.push(flags.into_boxed_slice());
+        self.ranges.push(ranges);
+        self
+    }
+
+    pub fn root_constants(
+        mut self,
+        register: u32,
+        space: u32,
+        num_32bit_values: u32,
+        visibility: ShaderVisibility,
+    ) -> Self {
+        let binding = Binding { space, register };
+        self.parameters.push(RootParameter::constants(
+            visibility,
+            binding,
+            num_32bit_values,
+        ));
+        self
+    }
+
+    pub fn cbv(mut self, register: u32, space: u32, visibility: ShaderVisibility) -> Self {
+        let binding = Binding { space, register };
+        self.parameters
+            .push(RootParameter::cbv_descriptor(visibility, binding));
+        self
+    }
+
+    pub fn srv(mut self, register: u32, space: u32, visibility: ShaderVisibility) -> Self {
+        let binding = Binding { space, register };
+        self.parameters
+            .push(RootParameter::srv_descriptor(visibility, binding));
+        self
+    }
+
+    pub fn uav(mut self, register: u32, space: u32, visibility: ShaderVisibility) -> Self {
+        let binding = Binding { space, register };
+        self.parameters
+            .push(RootParameter::uav_descriptor(visibility, binding));
+        self
+    }
+
+    pub fn parameters(&self) -> &[RootParameter] {
+        &self.parameters
+    }
+
+    pub fn layout(&self) -> RootSignatureLayout {
+        RootSignatureLayout::new(&self.parameters)
+    }
+
+    /// Whether some range flags have no version 1.0 equivalent.
+    pub fn requires_v1_1(&self) -> bool {
+        self.first_v1_1_range().is_some()
+    }
+
+    /// Root index, range index and flags of the first range whose flags have no
+    /// version 1.0 equivalent.
+    fn first_v1_1_range(&self) -> Option<(RootIndex, usize, DescriptorRangeFlags)> {
+        let tables = self.parameters.iter().enumerate().filter(|(_, param)| {
+            param.0.ParameterType == d3d12::D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE
+        });
+        // Tables were added in the same order as their ranges.
+        tables
+            .zip(self.ranges.iter().zip(&self.range_flags))
+            .find_map(|((root_index, _), (ranges, flags))| {
+                ranges
+                    .iter()
+                    .zip(flags.iter())
+                    .position(|(range, &flags)| {
+                        flags != DescriptorRangeFlags::v1_0(range.0.RangeType)
+                    })
+                    .map(|index| (root_index as RootIndex, index, flags[index]))
+            })
+    }
+
+    fn parameters1(&self) -> RootParameters1 {
+        let mut raw = RootParameters1::default();
+        for (ranges, flags) in self.ranges.iter().zip(&self.range_flags) {
+            raw.ranges.push(
+                ranges
+                    .iter()
+                    .zip(flags.iter())
+                    .map(|(range, flags)| d3d12::D3D12_DESCRIPTOR_RANGE1 {
+                        RangeType: range.0.RangeType,
+                        NumDescriptors: range.0.NumDescriptors,
+                        BaseShaderRegister: range.0.BaseShaderRegister,
+                        RegisterSpace: range.0.RegisterSpace,
+                        Flags: flags.bits(),
+                        OffsetInDescriptorsFromTableStart: range
+                            .0
+                            .OffsetInDescriptorsFromTableStart,
+                    })
+                    .collect(),
+            );
+        }
+
+        // Tables were added in the same order as their ranges.
+        let mut tables = raw.ranges.iter();
+        for param in &self.parameters {
+            let mut param1 = d3d12::D3D12_ROOT_PARAMETER1 {
+                ParameterType: param.0.ParameterType,
+                ShaderVisibility: param.0.ShaderVisibility,
+                ..unsafe { mem::zeroed() }
+            };
+            unsafe {
+                match param.0.ParameterType {
+                    d3d12::D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE => {
+                        let ranges = tables.next().unwrap();
+                        *param1.u.DescriptorTable_mut() = d3d12::D3D12_ROOT_DESCRIPTOR_TABLE1 {
+                            NumDescriptorRanges: ranges.len() as _,
+                            pDescriptorRanges: ranges.as_ptr(),
+                        };
+                    }
+                    d3d12::D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS => {
+                        *param1.u.Constants_mut() = *param.0.u.Constants();
+                    }
+                    _ => {
+                        let raw = param.0.u.Descriptor();
+                        *param1.u.Descriptor_mut() = d3d12::D3D12_ROOT_DESCRIPTOR1 {
+                            ShaderRegister: raw.ShaderRegister,
+                            RegisterSpace: raw.RegisterSpace,
+                            // Behavior of version 1.0.
+                            Flags: d3d12::D3D12_ROOT_DESCRIPTOR_FLAG_DATA_VOLATILE,
+                        };
+                    }
+                }
+            }
+            raw.parameters.push(param1);
+        }
+        raw
+    }
+
+    /// Fails with a message naming the offending range if version 1.0 is requested
+    /// but [`requires_v1_1`](Self::requires_v1_1).
+    ///
+    /// For version 1.1 the description points into `parameters1`.
+    fn versioned_desc(
+        &self,
+        version: RootSignatureVersion,
+        static_samplers: &[StaticSampler],
+        flags: RootSignatureFlags,
+        parameters1: &mut RootParameters1,
+    ) -> Result<d3d12::D3D12_VERSIONED_ROOT_SIGNATURE_DESC, BlobError> {
+        let mut desc = d3d12::D3D12_VERSIONED_ROOT_SIGNATURE_DESC {
+            Version: version as _,
+            ..unsafe { mem::zeroed() }
+        };
+        match version {
+            RootSignatureVersion::V1_0 => {
+                if let Some((root_index, range, range_flags)) = self.first_v1_1_range() {
+                    return Err(BlobError {
+                        hr: winerror::E_INVALIDARG,
+                        message: Some(format!(
+                            "Range {} of the table at root index {} has flags {:?}, \
+                             which require root signature version 1.1",
+                            range, root_index, range_flags
+                        )),
+                    });
+                }
+                *unsafe { desc.u.Desc_1_0_mut() } = d3d12::D3D12_ROOT_SIGNATURE_DESC {
+                    NumParameters: self.parameters.len() as _,
+                    pParameters: self.parameters.as_ptr() as *const _,
+                    NumStaticSamplers: static_samplers.len() as _,
+                    pStaticSamplers: static_samplers.as_ptr() as _,
+                    Flags: flags.bits(),
+                };
+            }
+            RootSignatureVersion::V1_1 => {
+                *parameters1 = self.parameters1();
+                *unsafe { desc.u.Desc_1_1_mut() } = d3d12::D3D12_ROOT_SIGNATURE_DESC1 {
+                    NumParameters: parameters1.parameters.len() as _,
+                    pParameters: parameters1.parameters.as_ptr(),
+                    NumStaticSamplers: static_samplers.len() as _,
+                    pStaticSamplers: static_samplers.as_ptr() as _,
+                    Flags: flags.bits(),
+                };
+            }
+        }
+        Ok(desc)
+    }
+}
+
+/// Parameter types of a root signature, recorded to validate root argument bindings.
+#[derive(Clone, Debug, Default)]
+pub struct RootSignatureLayout {
+    parameter_types: Vec<d3d12::D3D12_ROOT_PARAMETER_TYPE>,
+}
+
+impl RootSignatureLayout {
+    pub fn new(parameters: &[RootParameter]) -> Self {
+        RootSignatureLayout {
+            parameter_types: parameters
+                .iter()
+                .map(|param| param.0.ParameterType)
+                .collect(),
+        }
+    }
+
+    pub fn parameter_type(
+        &self,
+        root_index: RootIndex,
+    ) -> Option<d3d12::D3D12_ROOT_PARAMETER_TYPE> {
+        self.parameter_types.get(root_index as usize).copied()
+    }
+
+    pub fn is_descriptor_table(&self, root_index: RootIndex) -> bool {
+        self.parameter_type(root_index) == Some(d3d12::D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE)
+    }
+}
+
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
 pub enum StaticBorderColor {
@@ -246,6 +979,150 @@ impl StaticSampler {
     }
 }
 
+/// Linear filtering and clamp addressing on `s0` in space 0, visible to all stages.
+impl Default for StaticSampler {
+    fn default() -> Self {
+        StaticSampler::new(
+            ShaderVisibility::All,
+            Binding {
+                space: 0,
+                register: 0,
+            },
+            d3d12::D3D12_FILTER_MIN_MAG_MIP_LINEAR,
+            [d3d12::D3D12_TEXTURE_ADDRESS_MODE_CLAMP; 3],
+            0.0,
+            1,
+            d3d12::D3D12_COMPARISON_FUNC_NEVER,
+            StaticBorderColor::TransparentBlack,
+            0.0..d3d12::D3D12_FLOAT32_MAX,
+        )
+    }
+}
+
+impl StaticSampler {
+    /// Comparison samplers, e.g. for shadow mapping, also need a `D3D12_FILTER_COMPARISON_*` filter.
+    pub fn filter(mut self, filter: d3d12::D3D12_FILTER) -> Self {
+        self.0.Filter = filter;
+        self
+    }
+
+    pub fn address_mode(mut self, address_mode: TextureAddressMode) -> Self {
+        self.0.AddressU = address_mode[0];
+        self.0.AddressV = address_mode[1];
+        self.0.AddressW = address_mode[2];
+        self
+    }
+
+    pub fn comparison_func(mut self, func: d3d12::D3D12_COMPARISON_FUNC) -> Self {
+        self.0.ComparisonFunc = func;
+        self
+    }
+
+    /// Only used with `D3D12_TEXTURE_ADDRESS_MODE_BORDER`.
+    pub fn border_color(mut self, color: StaticBorderColor) -> Self {
+        self.0.BorderColor = color as _;
+        self
+    }
+
+    pub fn max_anisotropy(mut self, max_anisotropy: u32) -> Self {
+        self.0.MaxAnisotropy = max_anisotropy;
+        self
+    }
+
+    pub fn mip_lod_bias(mut self, bias: f32) -> Self {
+        self.0.MipLODBias = bias;
+        self
+    }
+
+    pub fn lod_clamp(mut self, lod: Range<f32>) -> Self {
+        self.0.MinLOD = lod.start;
+        self.0.MaxLOD = lod.end;
+        self
+    }
+
+    pub fn register(mut self, register: u32, space: u32) -> Self {
+        self.0.ShaderRegister = register;
+        self.0.RegisterSpace = space;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: ShaderVisibility) -> Self {
+        self.0.ShaderVisibility = visibility as _;
+        self
+    }
+}
+
+/// Sampler written into a sampler descriptor heap.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct SamplerDesc(pub(crate) d3d12::D3D12_SAMPLER_DESC);
+
+/// Linear filtering and clamp addressing.
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        SamplerDesc(d3d12::D3D12_SAMPLER_DESC {
+            Filter: d3d12::D3D12_FILTER_MIN_MAG_MIP_LINEAR,
+            AddressU: d3d12::D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+            AddressV: d3d12::D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+            AddressW: d3d12::D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+            MipLODBias: 0.0,
+            MaxAnisotropy: 1,
+            ComparisonFunc: d3d12::D3D12_COMPARISON_FUNC_NEVER,
+            BorderColor: [0.0; 4],
+            MinLOD: 0.0,
+            MaxLOD: d3d12::D3D12_FLOAT32_MAX,
+        })
+    }
+}
+
+impl SamplerDesc {
+    /// Comparison samplers, e.g. for shadow mapping, also need a `D3D12_FILTER_COMPARISON_*` filter.
+    pub fn filter(mut self, filter: d3d12::D3D12_FILTER) -> Self {
+        self.0.Filter = filter;
+        self
+    }
+
+    pub fn address_mode(mut self, address_mode: TextureAddressMode) -> Self {
+        self.0.AddressU = address_mode[0];
+        self.0.AddressV = address_mode[1];
+        self.0.AddressW = address_mode[2];
+        self
+    }
+
+    pub fn comparison_func(mut self, func: d3d12::D3D12_COMPARISON_FUNC) -> Self {
+        self.0.ComparisonFunc = func;
+        self
+    }
+
+    /// Only used with `D3D12_TEXTURE_ADDRESS_MODE_BORDER`.
+    pub fn border_color(mut self, color: [f32; 4]) -> Self {
+        self.0.BorderColor = color;
+        self
+    }
+
+    /// Anisotropy above 1 replaces the filter by the anisotropic one, keeping its reduction type.
+    pub fn max_anisotropy(mut self, max_anisotropy: u32) -> Self {
+        self.0.MaxAnisotropy = max_anisotropy;
+        if max_anisotropy > 1 {
+            let reduction_mask =
+                d3d12::D3D12_FILTER_REDUCTION_TYPE_MASK << d3d12::D3D12_FILTER_REDUCTION_TYPE_SHIFT;
+            self.0.Filter = d3d12::D3D12_FILTER_ANISOTROPIC | (self.0.Filter & reduction_mask);
+        }
+        self
+    }
+
+    pub fn mip_lod_bias(mut self, bias: f32) -> Self {
+        self.0.MipLODBias = bias;
+        self
+    }
+
+    pub fn lod_clamp(mut self, lod: Range<f32>) -> Self {
+        self.0.MinLOD = lod.start;
+        self.0.MaxLOD = lod.end;
+        self
+    }
+}
+
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
 pub enum RootSignatureVersion {
@@ -262,13 +1139,27 @@ bitflags::bitflags! {
         const DENY_DS_ROOT_ACCESS = d3d12::D3D12_ROOT_SIGNATURE_FLAG_DENY_DOMAIN_SHADER_ROOT_ACCESS;
         const DENY_GS_ROOT_ACCESS = d3d12::D3D12_ROOT_SIGNATURE_FLAG_DENY_GEOMETRY_SHADER_ROOT_ACCESS;
         const DENY_PS_ROOT_ACCESS = d3d12::D3D12_ROOT_SIGNATURE_FLAG_DENY_PIXEL_SHADER_ROOT_ACCESS;
+        const ALLOW_STREAM_OUTPUT = d3d12::D3D12_ROOT_SIGNATURE_FLAG_ALLOW_STREAM_OUTPUT;
+        const LOCAL_ROOT_SIGNATURE = crate::types::D3D12_ROOT_SIGNATURE_FLAG_LOCAL_ROOT_SIGNATURE;
     }
 }
 
-pub type RootSignature = ComPtr<d3d12::ID3D12RootSignature>;
-pub type BlobResult = D3DResult<(Blob, Error)>;
+impl From<d3d12::D3D12_ROOT_SIGNATURE_FLAGS> for RootSignatureFlags {
+    fn from(raw: d3d12::D3D12_ROOT_SIGNATURE_FLAGS) -> Self {
+        Self::from_bits_retain(raw)
+    }
+}
 
-#[cfg(feature = "libloading")]
+impl From<RootSignatureFlags> for d3d12::D3D12_ROOT_SIGNATURE_FLAGS {
+    fn from(flags: RootSignatureFlags) -> Self {
+        flags.bits()
+    }
+}
+
+pub type RootSignature = ComPtr<d3d12::ID3D12RootSignature>;
+pub type BlobResult = D3DResult<(Blob, Error)>;
+
+#[cfg(feature = "libloading")]
 impl crate::D3D12Lib {
     pub fn serialize_root_signature(
         &self,
@@ -276,7 +1167,7 @@ impl crate::D3D12Lib {
         parameters: &[RootParameter],
         static_samplers: &[StaticSampler],
         flags: RootSignatureFlags,
-    ) -> Result<BlobResult, libloading::Error> {
+    ) -> Result<Result<Blob, BlobError>, libloading::Error> {
         use winapi::um::d3dcommon::ID3DBlob;
         type Fun = extern "system" fn(
             *const d3d12::D3D12_ROOT_SIGNATURE_DESC,
@@ -305,7 +1196,46 @@ impl crate::D3D12Lib {
             )
         };
 
-        Ok(((blob, error), hr))
+        Ok(blob_result(((blob, error), hr)))
+    }
+
+    /// Serializes a root signature through `D3D12SerializeVersionedRootSignature`.
+    ///
+    /// Fails with `E_INVALIDARG` and a message naming the range when asking for version 1.0
+    /// if range flags [require 1.1](RootParameterBuilder::requires_v1_1).
+    pub fn serialize_versioned(
+        &self,
+        version: RootSignatureVersion,
+        parameters: RootParameterBuilder,
+        static_samplers: &[StaticSampler],
+        flags: RootSignatureFlags,
+    ) -> Result<Result<Blob, BlobError>, libloading::Error> {
+        use winapi::um::d3dcommon::ID3DBlob;
+        type Fun = extern "system" fn(
+            *const d3d12::D3D12_VERSIONED_ROOT_SIGNATURE_DESC,
+            *mut *mut ID3DBlob,
+            *mut *mut ID3DBlob,
+        ) -> crate::HRESULT;
+
+        let mut blob = Blob::null();
+        let mut error = Error::null();
+        let mut parameters1 = RootParameters1::default();
+        let desc =
+            match parameters.versioned_desc(version, static_samplers, flags, &mut parameters1) {
+                Ok(desc) => desc,
+                Err(error) => return Ok(Err(error)),
+            };
+        let hr = unsafe {
+            let func: libloading::Symbol<Fun> =
+                self.lib.get(b"D3D12SerializeVersionedRootSignature")?;
+            func(
+                &desc,
+                blob.mut_void() as *mut *mut _,
+                error.mut_void() as *mut *mut _,
+            )
+        };
+
+        Ok(blob_result(((blob, error), hr)))
     }
 }
 
@@ -316,7 +1246,7 @@ impl RootSignature {
         parameters: &[RootParameter],
         static_samplers: &[StaticSampler],
         flags: RootSignatureFlags,
-    ) -> BlobResult {
+    ) -> Result<Blob, BlobError> {
         let mut blob = Blob::null();
         let mut error = Error::null();
 
@@ -337,7 +1267,246 @@ impl RootSignature {
             )
         };
 
-        ((blob, error), hr)
+        blob_result(((blob, error), hr))
+    }
+
+    /// Serializes a root signature through `D3D12SerializeVersionedRootSignature`.
+    ///
+    /// Fails with `E_INVALIDARG` and a message naming the range when asking for version 1.0
+    /// if range flags [require 1.1](RootParameterBuilder::requires_v1_1).
+    #[cfg(feature = "implicit-link")]
+    pub fn serialize_versioned(
+        version: RootSignatureVersion,
+        parameters: RootParameterBuilder,
+        static_samplers: &[StaticSampler],
+        flags: RootSignatureFlags,
+    ) -> Result<Blob, BlobError> {
+        let mut blob = Blob::null();
+        let mut error = Error::null();
+        let mut parameters1 = RootParameters1::default();
+        let desc = parameters.versioned_desc(version, static_samplers, flags, &mut parameters1)?;
+        let hr = unsafe {
+            d3d12::D3D12SerializeVersionedRootSignature(
+                &desc,
+                blob.mut_void() as *mut *mut _,
+                error.mut_void() as *mut *mut _,
+            )
+        };
+
+        blob_result(((blob, error), hr))
+    }
+}
+
+pub type VersionedRootSignatureDeserializer =
+    ComPtr<d3d12::ID3D12VersionedRootSignatureDeserializer>;
+
+/// Descriptor range of a deserialized root signature.
+#[derive(Clone, Copy, Debug)]
+pub struct ReflectedDescriptorRange {
+    pub ty: DescriptorRangeType,
+    pub count: u32,
+    pub base_binding: Binding,
+    /// Offset in descriptors from the table start, may be `D3D12_DESCRIPTOR_RANGE_OFFSET_APPEND`.
+    pub offset: u32,
+    /// Bits of `D3D12_DESCRIPTOR_RANGE_FLAGS`.
+    pub flags: u32,
+}
+
+#[derive(Clone, Debug)]
+pub enum ReflectedParameterKind {
+    DescriptorTable(Vec<ReflectedDescriptorRange>),
+    Constants {
+        binding: Binding,
+        num_values: u32,
+    },
+    /// Root descriptor, `flags` holding bits of `D3D12_ROOT_DESCRIPTOR_FLAGS`.
+    Cbv {
+        binding: Binding,
+        flags: u32,
+    },
+    Srv {
+        binding: Binding,
+        flags: u32,
+    },
+    Uav {
+        binding: Binding,
+        flags: u32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct ReflectedRootParameter {
+    pub visibility: ShaderVisibility,
+    pub kind: ReflectedParameterKind,
+}
+
+/// Contents of a serialized root signature, as seen in version 1.1.
+///
+/// Version 1.0 blobs are converted, using the 1.0 defaults for the descriptor and range flags.
+#[derive(Clone)]
+pub struct RootSignatureReflection {
+    pub parameters: Vec<ReflectedRootParameter>,
+    pub static_samplers: Vec<d3d12::D3D12_STATIC_SAMPLER_DESC>,
+    pub flags: RootSignatureFlags,
+}
+
+impl RootSignatureReflection {
+    fn empty() -> Self {
+        RootSignatureReflection {
+            parameters: Vec::new(),
+            static_samplers: Vec::new(),
+            flags: RootSignatureFlags::empty(),
+        }
+    }
+
+    fn from_deserializer(deserializer: &VersionedRootSignatureDeserializer) -> D3DResult<Self> {
+        let mut desc = ptr::null();
+        let hr = unsafe {
+            deserializer
+                .GetRootSignatureDescAtVersion(d3d12::D3D_ROOT_SIGNATURE_VERSION_1_1, &mut desc)
+        };
+        if winerror::FAILED(hr) {
+            return (Self::empty(), hr);
+        }
+
+        // The description is owned by the deserializer.
+        let desc = unsafe { (*desc).u.Desc_1_1() };
+        let raw_parameters = match desc.NumParameters {
+            0 => &[],
+            n => unsafe { slice::from_raw_parts(desc.pParameters, n as usize) },
+        };
+        let static_samplers = match desc.NumStaticSamplers {
+            0 => Vec::new(),
+            n => unsafe { slice::from_raw_parts(desc.pStaticSamplers, n as usize) }.to_vec(),
+        };
+
+        let mut parameters = Vec::with_capacity(raw_parameters.len());
+        for raw in raw_parameters {
+            let visibility = match ShaderVisibility::from_raw(raw.ShaderVisibility) {
+                Some(visibility) => visibility,
+                None => return (Self::empty(), winerror::E_UNEXPECTED),
+            };
+            let kind = match raw.ParameterType {
+                d3d12::D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE => {
+                    let table = unsafe { raw.u.DescriptorTable() };
+                    let raw_ranges = match table.NumDescriptorRanges {
+                        0 => &[],
+                        n => unsafe { slice::from_raw_parts(table.pDescriptorRanges, n as usize) },
+                    };
+                    let mut ranges = Vec::with_capacity(raw_ranges.len());
+                    for range in raw_ranges {
+                        let ty = match DescriptorRangeType::from_raw(range.RangeType) {
+                            Some(ty) => ty,
+                            None => return (Self::empty(), winerror::E_UNEXPECTED),
+                        };
+                        ranges.push(ReflectedDescriptorRange {
+                            ty,
+                            count: range.NumDescriptors,
+                            base_binding: Binding {
+                                space: range.RegisterSpace,
+                                register: range.BaseShaderRegister,
+                            },
+                            offset: range.OffsetInDescriptorsFromTableStart,
+                            flags: range.Flags,
+                        });
+                    }
+                    ReflectedParameterKind::DescriptorTable(ranges)
+                }
+                d3d12::D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS => {
+                    let constants = unsafe { raw.u.Constants() };
+                    ReflectedParameterKind::Constants {
+                        binding: Binding {
+                            space: constants.RegisterSpace,
+                            register: constants.ShaderRegister,
+                        },
+                        num_values: constants.Num32BitValues,
+                    }
+                }
+                ty => {
+                    let descriptor = unsafe { raw.u.Descriptor() };
+                    let binding = Binding {
+                        space: descriptor.RegisterSpace,
+                        register: descriptor.ShaderRegister,
+                    };
+                    let flags = descriptor.Flags;
+                    match ty {
+                        d3d12::D3D12_ROOT_PARAMETER_TYPE_CBV => {
+                            ReflectedParameterKind::Cbv { binding, flags }
+                        }
+                        d3d12::D3D12_ROOT_PARAMETER_TYPE_SRV => {
+                            ReflectedParameterKind::Srv { binding, flags }
+                        }
+                        d3d12::D3D12_ROOT_PARAMETER_TYPE_UAV => {
+                            ReflectedParameterKind::Uav { binding, flags }
+                        }
+                        _ => return (Self::empty(), winerror::E_UNEXPECTED),
+                    }
+                }
+            };
+            parameters.push(ReflectedRootParameter { visibility, kind });
+        }
+
+        let reflection = RootSignatureReflection {
+            parameters,
+            static_samplers,
+            flags: RootSignatureFlags::from_bits_retain(desc.Flags),
+        };
+        (reflection, hr)
+    }
+}
+
+#[cfg(feature = "libloading")]
+impl crate::D3D12Lib {
+    /// See [`RootSignature::deserialize`].
+    pub fn deserialize_root_signature(
+        &self,
+        blob: &[u8],
+    ) -> Result<D3DResult<RootSignatureReflection>, libloading::Error> {
+        type Fun = extern "system" fn(
+            *const winapi::ctypes::c_void,
+            winapi::shared::basetsd::SIZE_T,
+            winapi::shared::guiddef::REFIID,
+            *mut *mut winapi::ctypes::c_void,
+        ) -> crate::HRESULT;
+
+        let mut deserializer = VersionedRootSignatureDeserializer::null();
+        let hr = unsafe {
+            let func: libloading::Symbol<Fun> = self
+                .lib
+                .get(b"D3D12CreateVersionedRootSignatureDeserializer")?;
+            func(
+                blob.as_ptr() as *const _,
+                blob.len(),
+                &d3d12::ID3D12VersionedRootSignatureDeserializer::uuidof(),
+                deserializer.mut_void(),
+            )
+        };
+        if winerror::FAILED(hr) {
+            return Ok((RootSignatureReflection::empty(), hr));
+        }
+
+        Ok(RootSignatureReflection::from_deserializer(&deserializer))
+    }
+}
+
+impl RootSignature {
+    /// Reads back the parameters and static samplers of a serialized root signature of any version.
+    #[cfg(feature = "implicit-link")]
+    pub fn deserialize(blob: &[u8]) -> D3DResult<RootSignatureReflection> {
+        let mut deserializer = VersionedRootSignatureDeserializer::null();
+        let hr = unsafe {
+            d3d12::D3D12CreateVersionedRootSignatureDeserializer(
+                blob.as_ptr() as *const _,
+                blob.len(),
+                &d3d12::ID3D12VersionedRootSignatureDeserializer::uuidof(),
+                deserializer.mut_void(),
+            )
+        };
+        if winerror::FAILED(hr) {
+            return (RootSignatureReflection::empty(), hr);
+        }
+
+        RootSignatureReflection::from_deserializer(&deserializer)
     }
 }
 
@@ -360,3 +1529,844 @@ impl RenderTargetViewDesc {
         RenderTargetViewDesc(desc)
     }
 }
+
+/// Constant buffer view, with its size rounded up to the required alignment.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct ConstantBufferView(pub(crate) d3d12::D3D12_CONSTANT_BUFFER_VIEW_DESC);
+
+impl ConstantBufferView {
+    const ALIGNMENT: u32 = d3d12::D3D12_CONSTANT_BUFFER_DATA_PLACEMENT_ALIGNMENT;
+
+    /// `gpu_address` has to be 256 bytes aligned, `size_in_bytes` is rounded up to 256 bytes.
+    ///
+    /// `None` if the rounded size doesn't fit a view.
+    pub fn new(gpu_address: crate::GpuAddress, size_in_bytes: u32) -> Option<Self> {
+        debug_assert!(
+            gpu_address.is_aligned(Self::ALIGNMENT as u64),
+            "misaligned constant buffer address"
+        );
+        let size = Self::required_buffer_size(size_in_bytes as u64)?;
+        Some(ConstantBufferView(d3d12::D3D12_CONSTANT_BUFFER_VIEW_DESC {
+            BufferLocation: gpu_address.0,
+            SizeInBytes: u32::try_from(size).ok()?,
+        }))
+    }
+
+    /// Size a buffer needs to back a view of `logical_size` bytes, `None` on overflow.
+    pub fn required_buffer_size(logical_size: u64) -> Option<u64> {
+        let alignment = Self::ALIGNMENT as u64;
+        let padded = logical_size.checked_add(alignment - 1)?;
+        Some(padded & !(alignment - 1))
+    }
+
+    pub fn gpu_address(&self) -> crate::GpuAddress {
+        crate::GpuAddress(self.0.BufferLocation)
+    }
+
+    pub fn size_in_bytes(&self) -> u32 {
+        self.0.SizeInBytes
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureViewDimension {
+    Texture1D,
+    Texture1DArray,
+    Texture2D,
+    Texture2DArray,
+    Texture2DMs,
+    Texture2DMsArray,
+    Texture3D,
+    Cube,
+    CubeArray,
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct DsvFlags: u32 {
+        const NONE = d3d12::D3D12_DSV_FLAG_NONE;
+        const READ_ONLY_DEPTH = d3d12::D3D12_DSV_FLAG_READ_ONLY_DEPTH;
+        const READ_ONLY_STENCIL = d3d12::D3D12_DSV_FLAG_READ_ONLY_STENCIL;
+    }
+}
+
+/// Subresources seen by a texture SRV, RTV or DSV.
+///
+/// Array layers count faces for cube views and depth slices for 3D render targets.
+/// Render target and depth-stencil views only use the base mip level.
+#[derive(Clone, Copy, Debug)]
+pub struct TextureViewDesc {
+    dimension: TextureViewDimension,
+    format: dxgiformat::DXGI_FORMAT,
+    base_mip: u32,
+    mip_count: u32,
+    first_layer: u32,
+    layer_count: u32,
+    plane_slice: u32,
+}
+
+impl TextureViewDesc {
+    /// View of all mip levels and the first array layer, or the first cube for cube views.
+    pub fn new(dimension: TextureViewDimension, format: dxgiformat::DXGI_FORMAT) -> Self {
+        let layer_count = match dimension {
+            TextureViewDimension::Cube | TextureViewDimension::CubeArray => 6,
+            _ => 1,
+        };
+        TextureViewDesc {
+            dimension,
+            format,
+            base_mip: 0,
+            mip_count: !0,
+            first_layer: 0,
+            layer_count,
+            plane_slice: 0,
+        }
+    }
+
+    /// `count` of `!0` selects all levels from `base` on.
+    pub fn mips(mut self, base: u32, count: u32) -> Self {
+        self.base_mip = base;
+        self.mip_count = count;
+        self
+    }
+
+    pub fn array_layers(mut self, first: u32, count: u32) -> Self {
+        self.first_layer = first;
+        self.layer_count = count;
+        self
+    }
+
+    pub fn plane_slice(mut self, plane_slice: u32) -> Self {
+        self.plane_slice = plane_slice;
+        self
+    }
+
+    pub(crate) fn srv_desc(&self) -> d3d12::D3D12_SHADER_RESOURCE_VIEW_DESC {
+        let mut desc = d3d12::D3D12_SHADER_RESOURCE_VIEW_DESC {
+            Format: self.format,
+            Shader4ComponentMapping: crate::types::D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+            ..unsafe { mem::zeroed() }
+        };
+
+        unsafe {
+            match self.dimension {
+                TextureViewDimension::Texture1D => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURE1D;
+                    *desc.u.Texture1D_mut() = d3d12::D3D12_TEX1D_SRV {
+                        MostDetailedMip: self.base_mip,
+                        MipLevels: self.mip_count,
+                        ResourceMinLODClamp: 0.0,
+                    };
+                }
+                TextureViewDimension::Texture1DArray => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURE1DARRAY;
+                    *desc.u.Texture1DArray_mut() = d3d12::D3D12_TEX1D_ARRAY_SRV {
+                        MostDetailedMip: self.base_mip,
+                        MipLevels: self.mip_count,
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                        ResourceMinLODClamp: 0.0,
+                    };
+                }
+                TextureViewDimension::Texture2D => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURE2D;
+                    *desc.u.Texture2D_mut() = d3d12::D3D12_TEX2D_SRV {
+                        MostDetailedMip: self.base_mip,
+                        MipLevels: self.mip_count,
+                        PlaneSlice: self.plane_slice,
+                        ResourceMinLODClamp: 0.0,
+                    };
+                }
+                TextureViewDimension::Texture2DArray => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURE2DARRAY;
+                    *desc.u.Texture2DArray_mut() = d3d12::D3D12_TEX2D_ARRAY_SRV {
+                        MostDetailedMip: self.base_mip,
+                        MipLevels: self.mip_count,
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                        PlaneSlice: self.plane_slice,
+                        ResourceMinLODClamp: 0.0,
+                    };
+                }
+                TextureViewDimension::Texture2DMs => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURE2DMS;
+                }
+                TextureViewDimension::Texture2DMsArray => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURE2DMSARRAY;
+                    *desc.u.Texture2DMSArray_mut() = d3d12::D3D12_TEX2DMS_ARRAY_SRV {
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                    };
+                }
+                TextureViewDimension::Texture3D => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURE3D;
+                    *desc.u.Texture3D_mut() = d3d12::D3D12_TEX3D_SRV {
+                        MostDetailedMip: self.base_mip,
+                        MipLevels: self.mip_count,
+                        ResourceMinLODClamp: 0.0,
+                    };
+                }
+                TextureViewDimension::Cube => {
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURECUBE;
+                    *desc.u.TextureCube_mut() = d3d12::D3D12_TEXCUBE_SRV {
+                        MostDetailedMip: self.base_mip,
+                        MipLevels: self.mip_count,
+                        ResourceMinLODClamp: 0.0,
+                    };
+                }
+                TextureViewDimension::CubeArray => {
+                    debug_assert_eq!(self.layer_count % 6, 0, "partial cube in a cube array view");
+                    desc.ViewDimension = d3d12::D3D12_SRV_DIMENSION_TEXTURECUBEARRAY;
+                    *desc.u.TextureCubeArray_mut() = d3d12::D3D12_TEXCUBE_ARRAY_SRV {
+                        MostDetailedMip: self.base_mip,
+                        MipLevels: self.mip_count,
+                        First2DArrayFace: self.first_layer,
+                        NumCubes: self.layer_count / 6,
+                        ResourceMinLODClamp: 0.0,
+                    };
+                }
+            }
+        }
+
+        desc
+    }
+
+    /// Cube views are rendered to as 2D arrays of faces.
+    pub(crate) fn rtv_desc(&self) -> d3d12::D3D12_RENDER_TARGET_VIEW_DESC {
+        let mut desc = d3d12::D3D12_RENDER_TARGET_VIEW_DESC {
+            Format: self.format,
+            ..unsafe { mem::zeroed() }
+        };
+
+        unsafe {
+            match self.dimension {
+                TextureViewDimension::Texture1D => {
+                    desc.ViewDimension = d3d12::D3D12_RTV_DIMENSION_TEXTURE1D;
+                    desc.u.Texture1D_mut().MipSlice = self.base_mip;
+                }
+                TextureViewDimension::Texture1DArray => {
+                    desc.ViewDimension = d3d12::D3D12_RTV_DIMENSION_TEXTURE1DARRAY;
+                    *desc.u.Texture1DArray_mut() = d3d12::D3D12_TEX1D_ARRAY_RTV {
+                        MipSlice: self.base_mip,
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                    };
+                }
+                TextureViewDimension::Texture2D => {
+                    desc.ViewDimension = d3d12::D3D12_RTV_DIMENSION_TEXTURE2D;
+                    *desc.u.Texture2D_mut() = d3d12::D3D12_TEX2D_RTV {
+                        MipSlice: self.base_mip,
+                        PlaneSlice: self.plane_slice,
+                    };
+                }
+                TextureViewDimension::Texture2DArray
+                | TextureViewDimension::Cube
+                | TextureViewDimension::CubeArray => {
+                    desc.ViewDimension = d3d12::D3D12_RTV_DIMENSION_TEXTURE2DARRAY;
+                    *desc.u.Texture2DArray_mut() = d3d12::D3D12_TEX2D_ARRAY_RTV {
+                        MipSlice: self.base_mip,
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                        PlaneSlice: self.plane_slice,
+                    };
+                }
+                TextureViewDimension::Texture2DMs => {
+                    desc.ViewDimension = d3d12::D3D12_RTV_DIMENSION_TEXTURE2DMS;
+                }
+                TextureViewDimension::Texture2DMsArray => {
+                    desc.ViewDimension = d3d12::D3D12_RTV_DIMENSION_TEXTURE2DMSARRAY;
+                    *desc.u.Texture2DMSArray_mut() = d3d12::D3D12_TEX2DMS_ARRAY_RTV {
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                    };
+                }
+                TextureViewDimension::Texture3D => {
+                    desc.ViewDimension = d3d12::D3D12_RTV_DIMENSION_TEXTURE3D;
+                    *desc.u.Texture3D_mut() = d3d12::D3D12_TEX3D_RTV {
+                        MipSlice: self.base_mip,
+                        FirstWSlice: self.first_layer,
+                        WSize: self.layer_count,
+                    };
+                }
+            }
+        }
+
+        desc
+    }
+
+    /// Cube views are written as 2D arrays of faces, 3D textures have no depth-stencil views
+    /// and give `None`.
+    pub(crate) fn dsv_desc(&self, flags: DsvFlags) -> Option<d3d12::D3D12_DEPTH_STENCIL_VIEW_DESC> {
+        let mut desc = d3d12::D3D12_DEPTH_STENCIL_VIEW_DESC {
+            Format: self.format,
+            Flags: flags.bits(),
+            ..unsafe { mem::zeroed() }
+        };
+
+        unsafe {
+            match self.dimension {
+                TextureViewDimension::Texture1D => {
+                    desc.ViewDimension = d3d12::D3D12_DSV_DIMENSION_TEXTURE1D;
+                    desc.u.Texture1D_mut().MipSlice = self.base_mip;
+                }
+                TextureViewDimension::Texture1DArray => {
+                    desc.ViewDimension = d3d12::D3D12_DSV_DIMENSION_TEXTURE1DARRAY;
+                    *desc.u.Texture1DArray_mut() = d3d12::D3D12_TEX1D_ARRAY_DSV {
+                        MipSlice: self.base_mip,
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                    };
+                }
+                TextureViewDimension::Texture2D => {
+                    desc.ViewDimension = d3d12::D3D12_DSV_DIMENSION_TEXTURE2D;
+                    desc.u.Texture2D_mut().MipSlice = self.base_mip;
+                }
+                TextureViewDimension::Texture2DArray
+                | TextureViewDimension::Cube
+                | TextureViewDimension::CubeArray => {
+                    desc.ViewDimension = d3d12::D3D12_DSV_DIMENSION_TEXTURE2DARRAY;
+                    *desc.u.Texture2DArray_mut() = d3d12::D3D12_TEX2D_ARRAY_DSV {
+                        MipSlice: self.base_mip,
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                    };
+                }
+                TextureViewDimension::Texture2DMs => {
+                    desc.ViewDimension = d3d12::D3D12_DSV_DIMENSION_TEXTURE2DMS;
+                }
+                TextureViewDimension::Texture2DMsArray => {
+                    desc.ViewDimension = d3d12::D3D12_DSV_DIMENSION_TEXTURE2DMSARRAY;
+                    *desc.u.Texture2DMSArray_mut() = d3d12::D3D12_TEX2DMS_ARRAY_DSV {
+                        FirstArraySlice: self.first_layer,
+                        ArraySize: self.layer_count,
+                    };
+                }
+                TextureViewDimension::Texture3D => return None,
+            }
+        }
+
+        Some(desc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{slot, Mock, MockHeap};
+
+    #[test]
+    fn constant_buffer_sizes() {
+        assert_eq!(ConstantBufferView::required_buffer_size(0), Some(0));
+        assert_eq!(ConstantBufferView::required_buffer_size(100), Some(256));
+        assert_eq!(ConstantBufferView::required_buffer_size(256), Some(256));
+        assert_eq!(ConstantBufferView::required_buffer_size(257), Some(512));
+        assert_eq!(
+            ConstantBufferView::required_buffer_size(u32::MAX as u64),
+            Some(1 << 32)
+        );
+        assert_eq!(ConstantBufferView::required_buffer_size(u64::MAX), None);
+
+        let view = ConstantBufferView::new(crate::GpuAddress(0x1_0000), 100).unwrap();
+        assert_eq!(view.size_in_bytes(), 256);
+        assert_eq!(view.gpu_address(), crate::GpuAddress(0x1_0000));
+        assert!(ConstantBufferView::new(crate::GpuAddress(0x1_0000), u32::MAX).is_none());
+    }
+
+    #[test]
+    fn advance_handles() {
+        let cpu = CpuDescriptor { ptr: 0x1000 };
+        assert_eq!(cpu.advance(3, 32).ptr, 0x1000 + 96);
+        let gpu = GpuDescriptor { ptr: 1 << 40 };
+        assert_eq!(gpu.advance(2, 64).ptr, (1 << 40) + 128);
+        assert_eq!(
+            DescriptorKey(cpu.advance(0, 32)),
+            DescriptorKey(CpuDescriptor { ptr: 0x1000 })
+        );
+    }
+
+    const CPU_START: usize = MockHeap::CPU_START;
+    const GPU_START: u64 = MockHeap::GPU_START;
+    const INCREMENT: u32 = MockHeap::INCREMENT;
+
+    /// Mocked CBV/SRV/UAV heap of 8 descriptors.
+    fn mock_heap(shader_visible: bool) -> DescriptorHeap {
+        MockHeap::new(
+            d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+            8,
+            shader_visible,
+        )
+        .create()
+    }
+
+    /// Allocator over a mocked heap of 8 descriptors.
+    fn allocator(shader_visible: bool) -> DescriptorAllocator {
+        DescriptorAllocator {
+            heap: IndexedDescriptorHeap::new(mock_heap(shader_visible), INCREMENT),
+            free: vec![0..8],
+        }
+    }
+
+    #[test]
+    fn gpu_descriptor_allocation() {
+        let mut allocator = GpuDescriptorAllocator {
+            heap: IndexedDescriptorHeap::new(mock_heap(true), INCREMENT),
+            capacity: 8,
+            offset: 0,
+        };
+        let (cpu, gpu) = allocator.allocate(3).unwrap();
+        assert_eq!((cpu.ptr, gpu.ptr), (CPU_START, GPU_START));
+
+        // The next range starts right after, at the same slot on both sides.
+        let (cpu, gpu) = allocator.allocate(2).unwrap();
+        assert_eq!(cpu.ptr, CPU_START + 3 * INCREMENT as usize);
+        assert_eq!(gpu.ptr, GPU_START + 3 * INCREMENT as u64);
+        assert!(allocator.allocate(4).is_none());
+
+        allocator.reset();
+        let (cpu, gpu) = allocator.allocate(8).unwrap();
+        assert_eq!((cpu.ptr, gpu.ptr), (CPU_START, GPU_START));
+    }
+
+    unsafe extern "system" fn create_descriptor_heap_fails(
+        _this: *mut d3d12::ID3D12Device,
+        _desc: *const d3d12::D3D12_DESCRIPTOR_HEAP_DESC,
+        _riid: winapi::shared::guiddef::REFIID,
+        _heap: *mut *mut winapi::ctypes::c_void,
+    ) -> winerror::HRESULT {
+        winerror::E_OUTOFMEMORY
+    }
+
+    unsafe extern "system" fn get_increment(
+        _this: *mut d3d12::ID3D12Device,
+        _ty: d3d12::D3D12_DESCRIPTOR_HEAP_TYPE,
+    ) -> u32 {
+        INCREMENT
+    }
+
+    #[test]
+    fn gpu_descriptor_allocator_without_heap() {
+        let device = Mock::new::<d3d12::ID3D12Device, d3d12::ID3D12DeviceVtbl>(
+            (),
+            &[
+                (
+                    slot!(d3d12::ID3D12DeviceVtbl, CreateDescriptorHeap),
+                    create_descriptor_heap_fails as usize,
+                ),
+                (
+                    slot!(d3d12::ID3D12DeviceVtbl, GetDescriptorHandleIncrementSize),
+                    get_increment as usize,
+                ),
+            ],
+        );
+        let (mut allocator, hr) = GpuDescriptorAllocator::new(&device, 8, 0);
+        assert_eq!(hr, winerror::E_OUTOFMEMORY);
+        assert!(allocator.heap().is_null());
+        assert!(allocator.allocate(1).is_none());
+    }
+
+    #[test]
+    fn descriptor_allocation() {
+        let mut allocator = allocator(true);
+        let a = allocator.allocate(3).unwrap();
+        let b = allocator.allocate(2).unwrap();
+        assert_eq!((a.index(), a.count()), (0, 3));
+        assert_eq!((b.index(), b.count()), (3, 2));
+        assert_eq!(b.cpu_descriptor(1).ptr, CPU_START + 4 * INCREMENT as usize);
+        assert_eq!(
+            b.gpu_descriptor(1).unwrap().ptr,
+            GPU_START + 4 * INCREMENT as u64
+        );
+
+        let mut allocator = self::allocator(false);
+        let a = allocator.allocate(1).unwrap();
+        assert_eq!(a.cpu_descriptor(0).ptr, CPU_START);
+        assert!(a.gpu_descriptor(0).is_none());
+    }
+
+    #[test]
+    fn descriptor_coalescing() {
+        let mut allocator = allocator(false);
+        let a = allocator.allocate(2).unwrap();
+        let b = allocator.allocate(2).unwrap();
+        let c = allocator.allocate(2).unwrap();
+        let d = allocator.allocate(2).unwrap();
+
+        // Fragmented: 0..2 and 4..6 are free but not contiguous.
+        allocator.free(a);
+        allocator.free(c);
+        assert_eq!(allocator.free, [0..2, 4..6]);
+        assert!(allocator.allocate(4).is_none());
+
+        // Freeing the range in between merges all three.
+        allocator.free(b);
+        assert_eq!(allocator.free, [0..6]);
+        let e = allocator.allocate(6).unwrap();
+        assert_eq!(e.index(), 0);
+
+        allocator.free(d);
+        allocator.free(e);
+        assert_eq!(allocator.free, [0..8]);
+    }
+
+    #[test]
+    fn descriptor_exhaustion() {
+        let mut allocator = allocator(false);
+        let all = allocator.allocate(8).unwrap();
+        assert!(allocator.free.is_empty());
+        assert!(allocator.allocate(1).is_none());
+
+        allocator.free(all);
+        assert!(allocator.allocate(9).is_none());
+        assert!(allocator.allocate(8).is_some());
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn indexed_rtv_heap() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (heap, hr) = device.create_indexed_descriptor_heap(
+            4,
+            DescriptorHeapType::Rtv,
+            DescriptorHeapFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let increment = device.get_descriptor_increment_size(DescriptorHeapType::Rtv);
+        assert_eq!(heap.increment(), increment);
+        let base = heap.heap().start_cpu_descriptor().ptr;
+        assert_eq!(heap.cpu_descriptor_at(2).ptr, base + 2 * increment as usize);
+    }
+
+    #[test]
+    fn dsv_dimensions() {
+        let format = dxgiformat::DXGI_FORMAT_D32_FLOAT;
+        let desc = TextureViewDesc::new(TextureViewDimension::Cube, format)
+            .dsv_desc(DsvFlags::empty())
+            .unwrap();
+        assert_eq!(
+            desc.ViewDimension,
+            d3d12::D3D12_DSV_DIMENSION_TEXTURE2DARRAY
+        );
+        assert_eq!(unsafe { desc.u.Texture2DArray().ArraySize }, 6);
+
+        let desc = TextureViewDesc::new(TextureViewDimension::Texture3D, format);
+        assert!(desc.dsv_desc(DsvFlags::empty()).is_none());
+    }
+
+    fn data_static_table() -> RootParameterBuilder {
+        let range = DescriptorRange1::new(
+            DescriptorRangeType::SRV,
+            1,
+            Binding {
+                space: 0,
+                register: 0,
+            },
+            0,
+        )
+        .flags(DescriptorRangeFlags::DATA_STATIC);
+        RootParameterBuilder::new()
+            .root_constants(0, 0, 1, ShaderVisibility::All)
+            .descriptor_table1(&[range], ShaderVisibility::All)
+    }
+
+    #[test]
+    fn v1_1_flags_at_v1_0() {
+        let parameters = data_static_table();
+        assert!(parameters.requires_v1_1());
+        let error = match parameters.versioned_desc(
+            RootSignatureVersion::V1_0,
+            &[],
+            RootSignatureFlags::empty(),
+            &mut RootParameters1::default(),
+        ) {
+            Ok(_) => panic!("version 1.0 accepted DATA_STATIC"),
+            Err(error) => error,
+        };
+        assert_eq!(error.hr, winerror::E_INVALIDARG);
+        let message = error.message.unwrap();
+        assert!(message.contains("DATA_STATIC"), "{}", message);
+        assert!(message.contains("root index 1"), "{}", message);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn data_static_round_trip() {
+        let error = RootSignature::serialize_versioned(
+            RootSignatureVersion::V1_0,
+            data_static_table(),
+            &[],
+            RootSignatureFlags::empty(),
+        )
+        .unwrap_err();
+        assert!(error.message.unwrap().contains("DATA_STATIC"));
+
+        let blob = RootSignature::serialize_versioned(
+            RootSignatureVersion::V1_1,
+            data_static_table(),
+            &[],
+            RootSignatureFlags::empty(),
+        )
+        .unwrap();
+        let (reflection, hr) = RootSignature::deserialize(crate::blob_bytes(&blob));
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(reflection.parameters.len(), 2);
+        match reflection.parameters[1].kind {
+            ReflectedParameterKind::DescriptorTable(ref ranges) => {
+                assert_eq!(ranges.len(), 1);
+                assert_eq!(ranges[0].flags, DescriptorRangeFlags::DATA_STATIC.bits());
+            }
+            ref kind => panic!("unexpected parameter {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn descriptor_key_order() {
+        let ptrs = [0x3000, 0x1000, 0x2040, 0x2000];
+        let mut keys = ptrs
+            .iter()
+            .map(|&ptr| DescriptorKey(CpuDescriptor { ptr }))
+            .collect::<Vec<_>>();
+        keys.sort();
+        let sorted = keys.iter().map(|key| key.0.ptr).collect::<Vec<_>>();
+        assert_eq!(sorted, [0x1000, 0x2000, 0x2040, 0x3000]);
+        assert_eq!(format!("{:?}", keys[0]), "DescriptorKey(0x1000)");
+
+        let gpu = |ptr| DescriptorKey(GpuDescriptor { ptr });
+        assert!(gpu(0x10) < gpu(0x20));
+        assert_eq!(gpu(0x10), gpu(0x10));
+        let set = [gpu(0x10), gpu(0x10), gpu(0x20)]
+            .iter()
+            .copied()
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(set.len(), 2);
+    }
+
+    /// Table of a CBV and two SRVs at index 0, 4 root constants at index 1.
+    fn table_and_constants() -> RootParameterBuilder {
+        let binding = |register| Binding { space: 0, register };
+        RootParameterBuilder::new()
+            .descriptor_table(
+                &[
+                    DescriptorRange::new(DescriptorRangeType::CBV, 1, binding(0), 0),
+                    DescriptorRange::new(
+                        DescriptorRangeType::SRV,
+                        2,
+                        binding(0),
+                        d3d12::D3D12_DESCRIPTOR_RANGE_OFFSET_APPEND,
+                    ),
+                ],
+                ShaderVisibility::All,
+            )
+            .root_constants(1, 0, 4, ShaderVisibility::PS)
+    }
+
+    #[test]
+    fn table_and_constants_desc() {
+        let parameters = table_and_constants();
+        let desc = match parameters.versioned_desc(
+            RootSignatureVersion::V1_0,
+            &[],
+            RootSignatureFlags::ALLOW_IA_INPUT_LAYOUT,
+            &mut RootParameters1::default(),
+        ) {
+            Ok(desc) => desc,
+            Err(error) => panic!("{:?}", error),
+        };
+        assert_eq!(desc.Version, d3d12::D3D_ROOT_SIGNATURE_VERSION_1_0);
+        let desc = unsafe { desc.u.Desc_1_0() };
+        assert_eq!(desc.NumParameters, 2);
+        let params = unsafe { slice::from_raw_parts(desc.pParameters, 2) };
+        assert_eq!(
+            params[0].ParameterType,
+            d3d12::D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE
+        );
+        // The ranges are owned by the builder, still alive here.
+        let table = unsafe { params[0].u.DescriptorTable() };
+        assert_eq!(table.NumDescriptorRanges, 2);
+        let ranges = unsafe { slice::from_raw_parts(table.pDescriptorRanges, 2) };
+        assert_eq!(ranges[1].NumDescriptors, 2);
+        assert_eq!(
+            params[1].ParameterType,
+            d3d12::D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS
+        );
+        assert_eq!(unsafe { params[1].u.Constants() }.Num32BitValues, 4);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn serialize_table_and_constants() {
+        for &version in &[RootSignatureVersion::V1_0, RootSignatureVersion::V1_1] {
+            let blob = RootSignature::serialize_versioned(
+                version,
+                table_and_constants(),
+                &[],
+                RootSignatureFlags::ALLOW_IA_INPUT_LAYOUT,
+            );
+            assert!(blob.is_ok(), "{:?}", blob.err());
+        }
+    }
+
+    /// Shadow map sampler on `s1`, comparing against white borders.
+    fn comparison_sampler() -> StaticSampler {
+        StaticSampler::default()
+            .filter(d3d12::D3D12_FILTER_COMPARISON_MIN_MAG_LINEAR_MIP_POINT)
+            .comparison_func(d3d12::D3D12_COMPARISON_FUNC_LESS_EQUAL)
+            .address_mode([d3d12::D3D12_TEXTURE_ADDRESS_MODE_BORDER; 3])
+            .border_color(StaticBorderColor::OpaqueWhite)
+            .register(1, 0)
+            .visibility(ShaderVisibility::PS)
+    }
+
+    #[test]
+    fn comparison_static_sampler() {
+        let sampler = comparison_sampler().0;
+        assert_eq!(
+            sampler.Filter,
+            d3d12::D3D12_FILTER_COMPARISON_MIN_MAG_LINEAR_MIP_POINT
+        );
+        assert_eq!(
+            sampler.ComparisonFunc,
+            d3d12::D3D12_COMPARISON_FUNC_LESS_EQUAL
+        );
+        assert_eq!(sampler.AddressV, d3d12::D3D12_TEXTURE_ADDRESS_MODE_BORDER);
+        assert_eq!(
+            sampler.BorderColor,
+            d3d12::D3D12_STATIC_BORDER_COLOR_OPAQUE_WHITE
+        );
+        assert_eq!((sampler.ShaderRegister, sampler.RegisterSpace), (1, 0));
+        assert_eq!(
+            sampler.ShaderVisibility,
+            d3d12::D3D12_SHADER_VISIBILITY_PIXEL
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn serialize_comparison_sampler() {
+        let blob = RootSignature::serialize_versioned(
+            RootSignatureVersion::V1_0,
+            table_and_constants(),
+            &[StaticSampler::default(), comparison_sampler()],
+            RootSignatureFlags::empty(),
+        )
+        .unwrap();
+        let (reflection, hr) = RootSignature::deserialize(crate::blob_bytes(&blob));
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(reflection.static_samplers.len(), 2);
+        let sampler = &reflection.static_samplers[1];
+        assert_eq!(
+            sampler.ComparisonFunc,
+            d3d12::D3D12_COMPARISON_FUNC_LESS_EQUAL
+        );
+        assert_eq!(
+            sampler.BorderColor,
+            d3d12::D3D12_STATIC_BORDER_COLOR_OPAQUE_WHITE
+        );
+        assert_eq!(sampler.ShaderRegister, 1);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn deserialize_parameters() {
+        let parameters = || {
+            table_and_constants()
+                .cbv(2, 0, ShaderVisibility::VS)
+                .srv(3, 1, ShaderVisibility::All)
+                .uav(0, 0, ShaderVisibility::All)
+        };
+        for &version in &[RootSignatureVersion::V1_0, RootSignatureVersion::V1_1] {
+            let blob = RootSignature::serialize_versioned(
+                version,
+                parameters(),
+                &[],
+                RootSignatureFlags::ALLOW_IA_INPUT_LAYOUT,
+            )
+            .unwrap();
+            let (reflection, hr) = RootSignature::deserialize(crate::blob_bytes(&blob));
+            assert!(winerror::SUCCEEDED(hr));
+            assert_eq!(reflection.flags, RootSignatureFlags::ALLOW_IA_INPUT_LAYOUT);
+
+            let params = &reflection.parameters;
+            assert_eq!(params.len(), 5);
+            match params[0].kind {
+                ReflectedParameterKind::DescriptorTable(ref ranges) => {
+                    let types = ranges.iter().map(|r| r.ty as u32).collect::<Vec<_>>();
+                    assert_eq!(
+                        types,
+                        [
+                            DescriptorRangeType::CBV as u32,
+                            DescriptorRangeType::SRV as u32
+                        ]
+                    );
+                    assert_eq!(ranges[1].count, 2);
+                }
+                ref kind => panic!("unexpected parameter {:?}", kind),
+            }
+            match params[1].kind {
+                ReflectedParameterKind::Constants {
+                    binding,
+                    num_values,
+                } => {
+                    assert_eq!((binding.register, num_values), (1, 4));
+                }
+                ref kind => panic!("unexpected parameter {:?}", kind),
+            }
+            assert_eq!(params[1].visibility as u32, ShaderVisibility::PS as u32);
+            match params[2].kind {
+                ReflectedParameterKind::Cbv { binding, .. } => assert_eq!(binding.register, 2),
+                ref kind => panic!("unexpected parameter {:?}", kind),
+            }
+            match params[3].kind {
+                ReflectedParameterKind::Srv { binding, .. } => {
+                    assert_eq!((binding.register, binding.space), (3, 1))
+                }
+                ref kind => panic!("unexpected parameter {:?}", kind),
+            }
+            assert!(matches!(params[4].kind, ReflectedParameterKind::Uav { .. }));
+        }
+    }
+
+    #[test]
+    fn heap_starts() {
+        let heap = mock_heap(true);
+        assert_eq!(heap.start_cpu_descriptor().ptr, CPU_START);
+        assert_eq!(
+            heap.gpu_descriptor_start().map(|gpu| gpu.ptr),
+            Some(GPU_START)
+        );
+        assert!(mock_heap(false).gpu_descriptor_start().is_none());
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn shader_visible_heap_starts() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (heap, hr) = device.create_descriptor_heap(
+            4,
+            DescriptorHeapType::CbvSrvUav,
+            DescriptorHeapFlags::SHADER_VISIBLE,
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let gpu = heap.gpu_descriptor_start().unwrap();
+        assert_ne!(gpu.ptr, 0);
+        assert_ne!(heap.start_cpu_descriptor().ptr, 0);
+
+        let (heap, hr) = device.create_descriptor_heap(
+            4,
+            DescriptorHeapType::Rtv,
+            DescriptorHeapFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(heap.gpu_descriptor_start().is_none());
+        assert_ne!(heap.start_cpu_descriptor().ptr, 0);
+    }
+}
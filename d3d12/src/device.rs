@@ -2,17 +2,99 @@
 
 use crate::{
     com::ComPtr,
-    command_list::{CmdListType, CommandSignature, IndirectArgument},
-    descriptor::{CpuDescriptor, DescriptorHeapFlags, DescriptorHeapType, RenderTargetViewDesc},
-    heap::{Heap, HeapFlags, HeapProperties},
-    pso, query, queue, Blob, CachedPSO, CommandAllocator, CommandQueue, D3DResult, DescriptorHeap,
-    Fence, GraphicsCommandList, NodeMask, PipelineState, QueryHeap, Resource, RootSignature,
-    Shader, TextureAddressMode,
+    command_list::{CmdListType, CommandListFlags, CommandSignature, IndirectArgument},
+    descriptor::{
+        ConstantBufferView, CpuDescriptor, DescriptorHeapFlags, DescriptorHeapType,
+        DescriptorIncrements, DsvFlags, IndexedDescriptorHeap, RenderTargetViewDesc, SamplerDesc,
+        SrvDimension, TextureViewDesc, UavDimension,
+    },
+    heap::{Heap, HeapFlags, HeapProperties, TrackedHeap},
+    pso, query, queue, Adapter1, Blob, CachedPSO, CommandAllocator, CommandQueue,
+    CommittedResourceBuilder, D3DResult, DescriptorHeap, Factory4, FeatureLevel, Fence, Format,
+    GpuAddress, GraphicsCommandList, NodeMask, PipelineState, QueryHeap, Resource, ResourceStates,
+    ResourceTilingInfo, RootSignature, Shader, TextureAddressMode, HRESULT,
+};
+use std::{convert::TryFrom, mem, ops::Range, ptr};
+use winapi::{
+    ctypes::c_void,
+    shared::{dxgiformat, winerror},
+    um::{
+        d3d12, d3dcommon,
+        winnt::{self, HANDLE},
+    },
+    Interface,
 };
-use std::ops::Range;
-use winapi::{um::d3d12, Interface};
 
 pub type Device = ComPtr<d3d12::ID3D12Device>;
+pub type Device3 = ComPtr<crate::types::ID3D12Device3>;
+pub type Device4 = ComPtr<crate::types::ID3D12Device4>;
+pub type Device5 = ComPtr<crate::types::ID3D12Device5>;
+
+/// Objects whose residency can be managed, see [`Device::make_resident`].
+pub trait Pageable {
+    fn as_pageable(&self) -> *mut d3d12::ID3D12Pageable;
+}
+
+impl Pageable for Resource {
+    fn as_pageable(&self) -> *mut d3d12::ID3D12Pageable {
+        self.as_mut_ptr() as *mut _
+    }
+}
+
+impl Pageable for Heap {
+    fn as_pageable(&self) -> *mut d3d12::ID3D12Pageable {
+        self.as_mut_ptr() as *mut _
+    }
+}
+
+fn pageables(objects: &[&dyn Pageable]) -> Vec<*mut d3d12::ID3D12Pageable> {
+    objects.iter().map(|object| object.as_pageable()).collect()
+}
+
+/// Looks for the root signature part (`RTS0`) in a DXBC or DXIL container.
+fn has_embedded_root_signature(bytecode: &[u8]) -> bool {
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes = bytecode.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+
+    // Header: magic, 16 bytes digest, version, total size, part count, then the part offsets.
+    if bytecode.get(..4) != Some(&b"DXBC"[..]) {
+        return false;
+    }
+    // The count comes from the blob, only as many offsets as it holds are read.
+    let part_count = match read_u32(28) {
+        Some(count) => (count as usize).min((bytecode.len() - 32) / 4),
+        None => return false,
+    };
+    (0..part_count).any(|index| {
+        read_u32(32 + 4 * index).and_then(|offset| {
+            let offset = offset as usize;
+            bytecode.get(offset..offset.checked_add(4)?)
+        }) == Some(&b"RTS0"[..])
+    })
+}
+
+/// Whether `hr` reports the loss of the device, which has to be recreated then.
+///
+/// [`Device::device_removed_reason`] tells the cause apart.
+pub fn is_device_removed(hr: HRESULT) -> bool {
+    matches!(
+        hr,
+        winerror::DXGI_ERROR_DEVICE_REMOVED
+            | winerror::DXGI_ERROR_DEVICE_RESET
+            | winerror::DXGI_ERROR_DEVICE_HUNG
+            | winerror::DXGI_ERROR_DRIVER_INTERNAL_ERROR
+    )
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct ResidencyFlags: u32 {
+        /// Fails instead of exceeding the memory budget.
+        const DENY_OVERBUDGET = crate::types::D3D12_RESIDENCY_FLAG_DENY_OVERBUDGET;
+    }
+}
 
 #[cfg(feature = "libloading")]
 impl crate::D3D12Lib {
@@ -41,6 +123,53 @@ impl crate::D3D12Lib {
 
         Ok((device, hr))
     }
+
+    /// See [`Device::create_with_fallback`].
+    pub fn create_device_with_fallback(
+        &self,
+        factory: &Factory4,
+        preferred: &[crate::FeatureLevel],
+    ) -> Result<D3DResult<Option<(Device, crate::FeatureLevel)>>, libloading::Error> {
+        create_with_fallback(factory, preferred, |adapter, level| {
+            self.create_device(adapter, level)
+        })
+    }
+}
+
+/// Tries the feature levels in order on each adapter of `factory`, and then on WARP.
+///
+/// On failure, the `HRESULT` of the last attempt is returned.
+#[cfg(any(feature = "libloading", feature = "implicit-link"))]
+fn create_with_fallback<E>(
+    factory: &Factory4,
+    preferred: &[FeatureLevel],
+    mut create: impl FnMut(&Adapter1, FeatureLevel) -> Result<D3DResult<Device>, E>,
+) -> Result<D3DResult<Option<(Device, FeatureLevel)>>, E> {
+    let mut adapters = Vec::new();
+    for id in 0.. {
+        let (adapter, hr) = factory.enumerate_adapters(id);
+        if winerror::FAILED(hr) {
+            break;
+        }
+        adapters.push(adapter);
+    }
+    let (warp, hr) = factory.warp_adapter();
+    if winerror::SUCCEEDED(hr) {
+        adapters.push(warp);
+    }
+
+    let mut last_hr = winerror::DXGI_ERROR_NOT_FOUND;
+    for adapter in &adapters {
+        for &level in preferred {
+            let (device, hr) = create(adapter, level)?;
+            if winerror::SUCCEEDED(hr) {
+                return Ok((Some((device, level)), hr));
+            }
+            last_hr = hr;
+        }
+    }
+
+    Ok((None, last_hr))
 }
 
 impl Device {
@@ -62,6 +191,25 @@ impl Device {
         (device, hr)
     }
 
+    /// Creates a device on the first adapter supporting one of the `preferred` feature levels,
+    /// falling back to the WARP software adapter.
+    ///
+    /// Returns the feature level the device was created with, or the `HRESULT` of
+    /// the last attempt if all failed.
+    #[cfg(feature = "implicit-link")]
+    pub fn create_with_fallback(
+        factory: &Factory4,
+        preferred: &[FeatureLevel],
+    ) -> D3DResult<Option<(Self, FeatureLevel)>> {
+        let result = create_with_fallback(factory, preferred, |adapter, level| {
+            Ok::<_, std::convert::Infallible>(Device::create(adapter.clone(), level))
+        });
+        match result {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
     pub fn create_heap(
         &self,
         size_in_bytes: u64,
@@ -83,6 +231,211 @@ impl Device {
         (heap, hr)
     }
 
+    /// Opens a heap over existing CPU memory, e.g. a memory-mapped file.
+    ///
+    /// Requires `ID3D12Device3`, `E_NOINTERFACE` is returned otherwise.
+    /// See [`Device3::open_heap_from_address`] for the requirements on `address`.
+    pub fn open_heap_from_address(&self, address: *const c_void) -> D3DResult<Heap> {
+        let (device3, hr) = unsafe { self.cast::<crate::types::ID3D12Device3>() };
+        if winerror::FAILED(hr) {
+            return (Heap::null(), hr);
+        }
+
+        device3.open_heap_from_address(address)
+    }
+
+    /// Creates a resource together with an implicit heap backing it.
+    ///
+    /// Returns `E_INVALIDARG` if the builder is inconsistent, e.g. buffers not
+    /// using the row-major layout or display-capable textures using a format
+    /// that can't be scanned out.
+    pub fn create_committed_resource(
+        &self,
+        builder: CommittedResourceBuilder,
+    ) -> D3DResult<Resource> {
+        let mut resource = Resource::null();
+        if !builder.is_valid() {
+            return (resource, winerror::E_INVALIDARG);
+        }
+        debug_assert_eq!(builder.initial_state.validate(), Ok(()));
+
+        let clear_value = match builder.clear_value {
+            Some(ref value) => value as *const _,
+            None => ptr::null(),
+        };
+        let hr = unsafe {
+            self.CreateCommittedResource(
+                &builder.heap_properties.0,
+                builder.heap_flags.bits(),
+                &builder.desc,
+                builder.initial_state.bits(),
+                clear_value,
+                &d3d12::ID3D12Resource::uuidof(),
+                resource.mut_void(),
+            )
+        };
+
+        (resource, hr)
+    }
+
+    /// Creates a resource with virtual memory only, tiles are mapped to heaps
+    /// through [`CommandQueue::update_tile_mappings`](crate::CommandQueue::update_tile_mappings).
+    ///
+    /// Textures must use `D3D12_TEXTURE_LAYOUT_64KB_UNDEFINED_SWIZZLE`.
+    pub fn create_reserved_resource(
+        &self,
+        desc: &d3d12::D3D12_RESOURCE_DESC,
+        initial_state: ResourceStates,
+    ) -> D3DResult<Resource> {
+        debug_assert_eq!(initial_state.validate(), Ok(()));
+        let mut resource = Resource::null();
+        let hr = unsafe {
+            self.CreateReservedResource(
+                desc,
+                initial_state.bits(),
+                ptr::null(),
+                &d3d12::ID3D12Resource::uuidof(),
+                resource.mut_void(),
+            )
+        };
+
+        (resource, hr)
+    }
+
+    pub fn resource_tiling(&self, resource: &Resource) -> ResourceTilingInfo {
+        let desc = unsafe { resource.GetDesc() };
+        let mut num_subresources = match desc.Dimension {
+            d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE3D => desc.MipLevels as u32,
+            _ => desc.MipLevels as u32 * desc.DepthOrArraySize as u32,
+        };
+
+        let mut num_tiles = 0;
+        let mut packed_mips: d3d12::D3D12_PACKED_MIP_INFO = unsafe { mem::zeroed() };
+        let mut tile_shape: d3d12::D3D12_TILE_SHAPE = unsafe { mem::zeroed() };
+        let mut subresource_tilings =
+            vec![
+                unsafe { mem::zeroed::<d3d12::D3D12_SUBRESOURCE_TILING>() };
+                num_subresources as usize
+            ];
+        unsafe {
+            self.GetResourceTiling(
+                resource.as_mut_ptr(),
+                &mut num_tiles,
+                &mut packed_mips,
+                &mut tile_shape,
+                &mut num_subresources,
+                0,
+                subresource_tilings.as_mut_ptr(),
+            );
+        }
+        subresource_tilings.truncate(num_subresources as usize);
+
+        ResourceTilingInfo {
+            num_tiles,
+            tile_shape: [
+                tile_shape.WidthInTexels,
+                tile_shape.HeightInTexels,
+                tile_shape.DepthInTexels,
+            ],
+            num_standard_mips: packed_mips.NumStandardMips,
+            num_packed_mips: packed_mips.NumPackedMips,
+            num_tiles_for_packed_mips: packed_mips.NumTilesForPackedMips,
+            start_tile_index_in_overall_resource: packed_mips.StartTileIndexInOverallResource,
+            subresource_tilings,
+        }
+    }
+
+    /// Creates a resource at `heap_offset` bytes into `heap`.
+    ///
+    /// The offset must be aligned to the resource alignment, 64 KiB unless the
+    /// description asks for a different one.
+    pub fn create_placed_resource(
+        &self,
+        heap: &Heap,
+        heap_offset: u64,
+        desc: &d3d12::D3D12_RESOURCE_DESC,
+        initial_state: ResourceStates,
+        clear_value: Option<&d3d12::D3D12_CLEAR_VALUE>,
+    ) -> D3DResult<Resource> {
+        let alignment = match desc.Alignment {
+            0 => d3d12::D3D12_DEFAULT_RESOURCE_PLACEMENT_ALIGNMENT as u64,
+            alignment => alignment,
+        };
+        debug_assert_eq!(heap_offset % alignment, 0, "misaligned heap offset");
+        debug_assert!(
+            crate::resource::is_layout_valid(desc),
+            "invalid layout for the resource dimension"
+        );
+        debug_assert_eq!(initial_state.validate(), Ok(()));
+
+        let mut resource = Resource::null();
+        let clear_value = match clear_value {
+            Some(value) => value as *const _,
+            None => ptr::null(),
+        };
+        let hr = unsafe {
+            self.CreatePlacedResource(
+                heap.as_mut_ptr(),
+                heap_offset,
+                desc,
+                initial_state.bits(),
+                clear_value,
+                &d3d12::ID3D12Resource::uuidof(),
+                resource.mut_void(),
+            )
+        };
+
+        (resource, hr)
+    }
+
+    /// Size and alignment each of `descs` requires when placed in a heap.
+    ///
+    /// A size of `!0` reports an invalid description.
+    pub fn resource_allocation_info(
+        &self,
+        descs: &[d3d12::D3D12_RESOURCE_DESC],
+        visible_mask: NodeMask,
+    ) -> Vec<d3d12::D3D12_RESOURCE_ALLOCATION_INFO> {
+        descs
+            .iter()
+            .map(|desc| unsafe { self.GetResourceAllocationInfo(visible_mask, 1, desc) })
+            .collect()
+    }
+
+    /// Size and alignment of a heap range fitting all of `descs`, placed one after the other
+    /// in order with their own alignment.
+    pub fn combined_resource_allocation_info(
+        &self,
+        descs: &[d3d12::D3D12_RESOURCE_DESC],
+        visible_mask: NodeMask,
+    ) -> d3d12::D3D12_RESOURCE_ALLOCATION_INFO {
+        unsafe { self.GetResourceAllocationInfo(visible_mask, descs.len() as _, descs.as_ptr()) }
+    }
+
+    /// Creates a placed resource, recording its range in `heap`.
+    ///
+    /// Overlapping another placement asserts in debug builds unless `aliasing` is set.
+    pub fn create_tracked_placed_resource(
+        &self,
+        heap: &mut TrackedHeap,
+        heap_offset: u64,
+        desc: &d3d12::D3D12_RESOURCE_DESC,
+        initial_state: ResourceStates,
+        clear_value: Option<&d3d12::D3D12_CLEAR_VALUE>,
+        aliasing: bool,
+    ) -> D3DResult<Resource> {
+        let info = unsafe { self.GetResourceAllocationInfo(0, 1, desc) };
+        heap.place(heap_offset..heap_offset + info.SizeInBytes, aliasing);
+
+        let (resource, hr) =
+            self.create_placed_resource(heap.heap(), heap_offset, desc, initial_state, clear_value);
+        if winerror::FAILED(hr) {
+            heap.release(heap_offset);
+        }
+
+        (resource, hr)
+    }
+
     pub fn create_command_allocator(&self, list_type: CmdListType) -> D3DResult<CommandAllocator> {
         let mut allocator = CommandAllocator::null();
         let hr = unsafe {
@@ -102,12 +455,25 @@ impl Device {
         priority: queue::Priority,
         flags: queue::CommandQueueFlags,
         node_mask: NodeMask,
+    ) -> D3DResult<CommandQueue> {
+        let desc = queue::CommandQueueDesc::new(list_type)
+            .priority(priority)
+            .flags(flags)
+            .node_mask(node_mask);
+        self.create_command_queue_with_desc(&desc)
+    }
+
+    /// Fails rather than falling back if the priority isn't available,
+    /// e.g. `GlobalRealtime` without realtime privileges.
+    pub fn create_command_queue_with_desc(
+        &self,
+        desc: &queue::CommandQueueDesc,
     ) -> D3DResult<CommandQueue> {
         let desc = d3d12::D3D12_COMMAND_QUEUE_DESC {
-            Type: list_type as _,
-            Priority: priority as _,
-            Flags: flags.bits(),
-            NodeMask: node_mask,
+            Type: desc.list_type as _,
+            Priority: desc.priority as _,
+            Flags: desc.flags.bits(),
+            NodeMask: desc.node_mask,
         };
 
         let mut queue = CommandQueue::null();
@@ -148,10 +514,66 @@ impl Device {
         (heap, hr)
     }
 
+    /// Creates a descriptor heap which remembers the increment size of `heap_type`,
+    /// to get handles by index.
+    pub fn create_indexed_descriptor_heap(
+        &self,
+        num_descriptors: u32,
+        heap_type: DescriptorHeapType,
+        flags: DescriptorHeapFlags,
+        node_mask: NodeMask,
+    ) -> D3DResult<IndexedDescriptorHeap> {
+        let (heap, hr) = self.create_descriptor_heap(num_descriptors, heap_type, flags, node_mask);
+        let increment = self.get_descriptor_increment_size(heap_type);
+
+        (IndexedDescriptorHeap::new(heap, increment), hr)
+    }
+
+    /// Fixes the GPU clock to its base frequency, making timings reproducible for profiling.
+    ///
+    /// Peak performance drops while enabled, so this is meant for benchmarking only.
+    /// Fails when Windows developer mode is off, the `HRESULT` is returned unchanged
+    /// so callers can report it.
+    pub fn set_stable_power_state(&self, enable: bool) -> HRESULT {
+        unsafe { self.SetStablePowerState(enable as _) }
+    }
+
+    /// Why the device was removed, `S_OK` while it's still usable.
+    pub fn device_removed_reason(&self) -> HRESULT {
+        unsafe { self.GetDeviceRemovedReason() }
+    }
+
+    /// Blocks until `objects` are resident again, after they were evicted.
+    pub fn make_resident(&self, objects: &[&dyn Pageable]) -> HRESULT {
+        let objects = pageables(objects);
+        unsafe { self.MakeResident(objects.len() as _, objects.as_ptr()) }
+    }
+
+    /// Allows the memory of `objects` to be paged out, they can't be used by the GPU until made resident.
+    pub fn evict(&self, objects: &[&dyn Pageable]) -> HRESULT {
+        let objects = pageables(objects);
+        unsafe { self.Evict(objects.len() as _, objects.as_ptr()) }
+    }
+
+    /// Number of physical adapters linked into this device, node masks have one bit per node.
+    pub fn node_count(&self) -> u32 {
+        unsafe { self.GetNodeCount() }
+    }
+
     pub fn get_descriptor_increment_size(&self, heap_type: DescriptorHeapType) -> u32 {
         unsafe { self.GetDescriptorHandleIncrementSize(heap_type as _) }
     }
 
+    /// Increment sizes of all heap types at once, they never change for a given device.
+    pub fn descriptor_increment_sizes(&self) -> DescriptorIncrements {
+        DescriptorIncrements {
+            cbv_srv_uav: self.get_descriptor_increment_size(DescriptorHeapType::CbvSrvUav),
+            sampler: self.get_descriptor_increment_size(DescriptorHeapType::Sampler),
+            rtv: self.get_descriptor_increment_size(DescriptorHeapType::Rtv),
+            dsv: self.get_descriptor_increment_size(DescriptorHeapType::Dsv),
+        }
+    }
+
     pub fn create_graphics_command_list(
         &self,
         list_type: CmdListType,
@@ -170,16 +592,32 @@ impl Device {
                 command_list.mut_void(),
             )
         };
+        if cfg!(debug_assertions) && winerror::SUCCEEDED(hr) {
+            command_list.set_closed(false);
+        }
 
         (command_list, hr)
     }
 
+    /// `E_INVALIDARG` is returned for copy queue timestamps if they aren't supported.
     pub fn create_query_heap(
         &self,
         heap_ty: query::QueryHeapType,
         count: u32,
         node_mask: NodeMask,
     ) -> D3DResult<QueryHeap> {
+        if let query::QueryHeapType::CopyQueueTimestamp = heap_ty {
+            let (supported, hr) = self.copy_queue_timestamp_queries_supported();
+            if !supported {
+                let hr = if winerror::FAILED(hr) {
+                    hr
+                } else {
+                    winerror::E_INVALIDARG
+                };
+                return (QueryHeap::null(), hr);
+            }
+        }
+
         let desc = d3d12::D3D12_QUERY_HEAP_DESC {
             Type: heap_ty as _,
             Count: count,
@@ -213,6 +651,22 @@ impl Device {
         unimplemented!()
     }
 
+    pub fn create_graphics_pipeline(
+        &self,
+        builder: &pso::GraphicsPipelineBuilder,
+    ) -> D3DResult<PipelineState> {
+        let mut pipeline = PipelineState::null();
+        let hr = builder.with_raw(|desc| unsafe {
+            self.CreateGraphicsPipelineState(
+                desc,
+                &d3d12::ID3D12PipelineState::uuidof(),
+                pipeline.mut_void(),
+            )
+        });
+
+        (pipeline, hr)
+    }
+
     pub fn create_compute_pipeline_state(
         &self,
         root_signature: &RootSignature,
@@ -270,6 +724,10 @@ impl Device {
         }
     }
 
+    pub fn create_sampler_with_desc(&self, desc: &SamplerDesc, handle: CpuDescriptor) {
+        unsafe { self.CreateSampler(&desc.0, handle) }
+    }
+
     pub fn create_root_signature(
         &self,
         blob: Blob,
@@ -289,6 +747,33 @@ impl Device {
         (signature, hr)
     }
 
+    /// Creates the root signature embedded into compiled `shader` bytecode, e.g. through a
+    /// `[RootSignature(...)]` attribute.
+    ///
+    /// Returns `E_INVALIDARG` without calling into the runtime if the bytecode has no root signature part.
+    pub fn create_root_signature_from_shader(
+        &self,
+        shader: &[u8],
+        node_mask: NodeMask,
+    ) -> D3DResult<RootSignature> {
+        let mut signature = RootSignature::null();
+        if !has_embedded_root_signature(shader) {
+            return (signature, winerror::E_INVALIDARG);
+        }
+
+        let hr = unsafe {
+            self.CreateRootSignature(
+                node_mask,
+                shader.as_ptr() as *const _,
+                shader.len(),
+                &d3d12::ID3D12RootSignature::uuidof(),
+                signature.mut_void(),
+            )
+        };
+
+        (signature, hr)
+    }
+
     pub fn create_command_signature(
         &self,
         root_signature: RootSignature,
@@ -327,6 +812,380 @@ impl Device {
         }
     }
 
+    pub fn create_texture_srv(
+        &self,
+        resource: &Resource,
+        desc: &TextureViewDesc,
+        descriptor: CpuDescriptor,
+    ) {
+        let desc = desc.srv_desc();
+        unsafe { self.CreateShaderResourceView(resource.as_mut_ptr(), &desc, descriptor) }
+    }
+
+    pub fn create_texture_rtv(
+        &self,
+        resource: &Resource,
+        desc: &TextureViewDesc,
+        descriptor: CpuDescriptor,
+    ) {
+        let desc = desc.rtv_desc();
+        unsafe { self.CreateRenderTargetView(resource.as_mut_ptr(), &desc, descriptor) }
+    }
+
+    /// Returns `E_INVALIDARG` without writing the descriptor for 3D texture views.
+    pub fn create_depth_stencil_view(
+        &self,
+        resource: &Resource,
+        desc: &TextureViewDesc,
+        flags: DsvFlags,
+        descriptor: CpuDescriptor,
+    ) -> HRESULT {
+        let desc = match desc.dsv_desc(flags) {
+            Some(desc) => desc,
+            None => return winerror::E_INVALIDARG,
+        };
+        unsafe { self.CreateDepthStencilView(resource.as_mut_ptr(), &desc, descriptor) }
+        winerror::S_OK
+    }
+
+    /// Creates a buffer SRV, raw if `stride` is 0 and structured otherwise.
+    ///
+    /// `first_element` and `num_elements` count 4 byte words for raw views.
+    pub fn create_buffer_srv(
+        &self,
+        resource: &Resource,
+        first_element: u64,
+        num_elements: u32,
+        stride: u32,
+        handle: CpuDescriptor,
+    ) {
+        let (format, flags) = match stride {
+            0 => (
+                dxgiformat::DXGI_FORMAT_R32_TYPELESS,
+                d3d12::D3D12_BUFFER_SRV_FLAG_RAW,
+            ),
+            _ => (
+                dxgiformat::DXGI_FORMAT_UNKNOWN,
+                d3d12::D3D12_BUFFER_SRV_FLAG_NONE,
+            ),
+        };
+        let mut desc = d3d12::D3D12_SHADER_RESOURCE_VIEW_DESC {
+            Format: format,
+            ViewDimension: d3d12::D3D12_SRV_DIMENSION_BUFFER,
+            Shader4ComponentMapping: crate::types::D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+            ..unsafe { mem::zeroed() }
+        };
+        *unsafe { desc.u.Buffer_mut() } = d3d12::D3D12_BUFFER_SRV {
+            FirstElement: first_element,
+            NumElements: num_elements,
+            StructureByteStride: stride,
+            Flags: flags,
+        };
+
+        unsafe { self.CreateShaderResourceView(resource.as_mut_ptr(), &desc, handle) }
+    }
+
+    /// Creates a buffer UAV, raw if `stride` is 0 and structured otherwise.
+    ///
+    /// `counter` is the resource and byte offset of an append/consume counter,
+    /// only supported by structured views.
+    pub fn create_buffer_uav(
+        &self,
+        resource: &Resource,
+        counter: Option<(&Resource, u64)>,
+        first_element: u64,
+        num_elements: u32,
+        stride: u32,
+        handle: CpuDescriptor,
+    ) {
+        debug_assert!(
+            counter.is_none() || stride != 0,
+            "raw buffer views can't have a counter"
+        );
+        let (format, flags) = match stride {
+            0 => (
+                dxgiformat::DXGI_FORMAT_R32_TYPELESS,
+                d3d12::D3D12_BUFFER_UAV_FLAG_RAW,
+            ),
+            _ => (
+                dxgiformat::DXGI_FORMAT_UNKNOWN,
+                d3d12::D3D12_BUFFER_UAV_FLAG_NONE,
+            ),
+        };
+        let (counter, counter_offset) = match counter {
+            Some((counter, offset)) => (counter.as_mut_ptr(), offset),
+            None => (ptr::null_mut(), 0),
+        };
+        let mut desc = d3d12::D3D12_UNORDERED_ACCESS_VIEW_DESC {
+            Format: format,
+            ViewDimension: d3d12::D3D12_UAV_DIMENSION_BUFFER,
+            ..unsafe { mem::zeroed() }
+        };
+        *unsafe { desc.u.Buffer_mut() } = d3d12::D3D12_BUFFER_UAV {
+            FirstElement: first_element,
+            NumElements: num_elements,
+            StructureByteStride: stride,
+            CounterOffsetInBytes: counter_offset,
+            Flags: flags,
+        };
+
+        unsafe { self.CreateUnorderedAccessView(resource.as_mut_ptr(), counter, &desc, handle) }
+    }
+
+    /// Creates a CBV, `size_in_bytes` is rounded up to the required 256 bytes alignment.
+    ///
+    /// `gpu_address` itself has to be 256 bytes aligned. Fails with `E_INVALIDARG`
+    /// if the rounded size overflows.
+    pub fn create_constant_buffer_view(
+        &self,
+        gpu_address: GpuAddress,
+        size_in_bytes: u32,
+        handle: CpuDescriptor,
+    ) -> HRESULT {
+        match ConstantBufferView::new(gpu_address, size_in_bytes) {
+            Some(view) => {
+                self.create_cbv(&view, handle);
+                winerror::S_OK
+            }
+            None => winerror::E_INVALIDARG,
+        }
+    }
+
+    pub fn create_cbv(&self, view: &ConstantBufferView, handle: CpuDescriptor) {
+        unsafe { self.CreateConstantBufferView(&view.0, handle) }
+    }
+
+    /// Copies `count` contiguous descriptors from `src` to `dst`.
+    pub fn copy_descriptors_simple(
+        &self,
+        count: u32,
+        dst: CpuDescriptor,
+        src: CpuDescriptor,
+        heap_type: DescriptorHeapType,
+    ) {
+        unsafe { self.CopyDescriptorsSimple(count, dst, src, heap_type as _) }
+    }
+
+    /// Copies descriptors between ranges given as start handle and length.
+    ///
+    /// Source and destination ranges may be split differently but must hold as many descriptors.
+    pub fn copy_descriptors(
+        &self,
+        dst_ranges: &[(CpuDescriptor, u32)],
+        src_ranges: &[(CpuDescriptor, u32)],
+        heap_type: DescriptorHeapType,
+    ) {
+        debug_assert_eq!(
+            dst_ranges.iter().map(|&(_, count)| count).sum::<u32>(),
+            src_ranges.iter().map(|&(_, count)| count).sum::<u32>(),
+            "mismatched descriptor counts"
+        );
+        let (dst_starts, dst_sizes): (Vec<_>, Vec<_>) = dst_ranges.iter().cloned().unzip();
+        let (src_starts, src_sizes): (Vec<_>, Vec<_>) = src_ranges.iter().cloned().unzip();
+
+        unsafe {
+            self.CopyDescriptors(
+                dst_starts.len() as _,
+                dst_starts.as_ptr(),
+                dst_sizes.as_ptr(),
+                src_starts.len() as _,
+                src_starts.as_ptr(),
+                src_sizes.as_ptr(),
+                heap_type as _,
+            )
+        }
+    }
+
+    /// Queries `feature`, `data` being the matching `D3D12_FEATURE_DATA_*` structure.
+    ///
+    /// Some queries also read input fields of `data`.
+    pub fn check_feature_support<T>(&self, feature: d3d12::D3D12_FEATURE, data: &mut T) -> HRESULT {
+        unsafe {
+            self.CheckFeatureSupport(feature, data as *mut T as *mut _, mem::size_of::<T>() as _)
+        }
+    }
+
+    pub fn resource_binding_tier(&self) -> D3DResult<ResourceBindingTier> {
+        let mut options: d3d12::D3D12_FEATURE_DATA_D3D12_OPTIONS = unsafe { mem::zeroed() };
+        let hr = self.check_feature_support(d3d12::D3D12_FEATURE_D3D12_OPTIONS, &mut options);
+        let tier = if winerror::SUCCEEDED(hr) {
+            ResourceBindingTier::from_raw(options.ResourceBindingTier)
+        } else {
+            ResourceBindingTier::Tier1
+        };
+
+        (tier, hr)
+    }
+
+    pub fn resource_heap_tier(&self) -> D3DResult<ResourceHeapTier> {
+        let mut options: d3d12::D3D12_FEATURE_DATA_D3D12_OPTIONS = unsafe { mem::zeroed() };
+        let hr = self.check_feature_support(d3d12::D3D12_FEATURE_D3D12_OPTIONS, &mut options);
+        let tier = if winerror::SUCCEEDED(hr) {
+            ResourceHeapTier::from_raw(options.ResourceHeapTier)
+        } else {
+            ResourceHeapTier::Tier1
+        };
+
+        (tier, hr)
+    }
+
+    /// Whether [`QueryHeapType::CopyQueueTimestamp`](query::QueryHeapType::CopyQueueTimestamp)
+    /// heaps can be created.
+    pub fn copy_queue_timestamp_queries_supported(&self) -> D3DResult<bool> {
+        let mut options: crate::types::D3D12_FEATURE_DATA_D3D12_OPTIONS3 = unsafe { mem::zeroed() };
+        let hr =
+            self.check_feature_support(crate::types::D3D12_FEATURE_D3D12_OPTIONS3, &mut options);
+
+        (options.CopyQueueTimestampQueriesSupported != 0, hr)
+    }
+
+    pub fn programmable_sample_positions_tier(&self) -> D3DResult<ProgrammableSamplePositionsTier> {
+        let mut options: d3d12::D3D12_FEATURE_DATA_D3D12_OPTIONS2 = unsafe { mem::zeroed() };
+        let hr = self.check_feature_support(d3d12::D3D12_FEATURE_D3D12_OPTIONS2, &mut options);
+        let tier = match options.ProgrammableSamplePositionsTier {
+            d3d12::D3D12_PROGRAMMABLE_SAMPLE_POSITIONS_TIER_1 => {
+                ProgrammableSamplePositionsTier::Tier1
+            }
+            d3d12::D3D12_PROGRAMMABLE_SAMPLE_POSITIONS_TIER_2 => {
+                ProgrammableSamplePositionsTier::Tier2
+            }
+            // Also reported by runtimes not knowing the query.
+            _ => ProgrammableSamplePositionsTier::NotSupported,
+        };
+
+        (tier, hr)
+    }
+
+    /// Highest shader model supported by both the device and the runtime.
+    ///
+    /// The query clamps the requested model, but runtimes reject models newer than
+    /// they know of, so lower ones are tried in turn. Falls back to 5.1, which every
+    /// device supports, returning the last error.
+    pub fn highest_shader_model(&self) -> D3DResult<ShaderModel> {
+        let mut hr = winerror::E_INVALIDARG;
+        for &model in ShaderModel::ALL.iter().rev() {
+            let mut data = d3d12::D3D12_FEATURE_DATA_SHADER_MODEL {
+                HighestShaderModel: model as _,
+            };
+            hr = self.check_feature_support(d3d12::D3D12_FEATURE_SHADER_MODEL, &mut data);
+            if winerror::SUCCEEDED(hr) {
+                let model = ShaderModel::from_raw(data.HighestShaderModel).unwrap_or(model);
+                return (model, hr);
+            }
+        }
+
+        (ShaderModel::V5_1, hr)
+    }
+
+    pub fn wave_info(&self) -> D3DResult<WaveInfo> {
+        let mut options: d3d12::D3D12_FEATURE_DATA_D3D12_OPTIONS1 = unsafe { mem::zeroed() };
+        let hr = self.check_feature_support(d3d12::D3D12_FEATURE_D3D12_OPTIONS1, &mut options);
+        if winerror::FAILED(hr) {
+            return (WaveInfo::default(), hr);
+        }
+
+        let info = WaveInfo {
+            supported: options.WaveOps != 0,
+            lane_count_min: options.WaveLaneCountMin,
+            lane_count_max: options.WaveLaneCountMax,
+            total_lane_count: options.TotalLaneCount,
+        };
+        (info, hr)
+    }
+
+    /// Highest of the `candidates` feature levels supported by the device, `None` on failure.
+    pub fn max_supported_feature_level(
+        &self,
+        candidates: &[FeatureLevel],
+    ) -> D3DResult<Option<FeatureLevel>> {
+        let levels = candidates
+            .iter()
+            .map(|&level| level as d3dcommon::D3D_FEATURE_LEVEL)
+            .collect::<Vec<_>>();
+        let mut data = d3d12::D3D12_FEATURE_DATA_FEATURE_LEVELS {
+            NumFeatureLevels: levels.len() as _,
+            pFeatureLevelsRequested: levels.as_ptr(),
+            MaxSupportedFeatureLevel: 0,
+        };
+        let hr = self.check_feature_support(d3d12::D3D12_FEATURE_FEATURE_LEVELS, &mut data);
+        if winerror::FAILED(hr) {
+            return (None, hr);
+        }
+
+        (
+            FeatureLevel::try_from(data.MaxSupportedFeatureLevel).ok(),
+            hr,
+        )
+    }
+
+    /// Writes a null SRV, reads return zeros.
+    ///
+    /// `dimension` and `format` have to match what the shader declares at the slot.
+    pub fn create_null_srv(&self, dimension: SrvDimension, format: Format, handle: CpuDescriptor) {
+        let mut desc = d3d12::D3D12_SHADER_RESOURCE_VIEW_DESC {
+            Format: format,
+            ViewDimension: dimension as _,
+            Shader4ComponentMapping: crate::types::D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+            ..unsafe { mem::zeroed() }
+        };
+        // The runtime validates the view even without a resource, so counts can't stay 0.
+        unsafe {
+            match dimension {
+                SrvDimension::Buffer | SrvDimension::Texture2DMs => {}
+                SrvDimension::Texture1D => desc.u.Texture1D_mut().MipLevels = 1,
+                SrvDimension::Texture1DArray => {
+                    let raw = desc.u.Texture1DArray_mut();
+                    raw.MipLevels = 1;
+                    raw.ArraySize = 1;
+                }
+                SrvDimension::Texture2D => desc.u.Texture2D_mut().MipLevels = 1,
+                SrvDimension::Texture2DArray => {
+                    let raw = desc.u.Texture2DArray_mut();
+                    raw.MipLevels = 1;
+                    raw.ArraySize = 1;
+                }
+                SrvDimension::Texture2DMsArray => desc.u.Texture2DMSArray_mut().ArraySize = 1,
+                SrvDimension::Texture3D => desc.u.Texture3D_mut().MipLevels = 1,
+                SrvDimension::TextureCube => desc.u.TextureCube_mut().MipLevels = 1,
+                SrvDimension::TextureCubeArray => {
+                    let raw = desc.u.TextureCubeArray_mut();
+                    raw.MipLevels = 1;
+                    raw.NumCubes = 1;
+                }
+            }
+            self.CreateShaderResourceView(ptr::null_mut(), &desc, handle);
+        }
+    }
+
+    /// Writes a null UAV, reads return zeros and writes are discarded.
+    ///
+    /// `dimension` and `format` have to match what the shader declares at the slot.
+    pub fn create_null_uav(&self, dimension: UavDimension, format: Format, handle: CpuDescriptor) {
+        let mut desc = d3d12::D3D12_UNORDERED_ACCESS_VIEW_DESC {
+            Format: format,
+            ViewDimension: dimension as _,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            match dimension {
+                UavDimension::Buffer | UavDimension::Texture1D | UavDimension::Texture2D => {}
+                UavDimension::Texture1DArray => desc.u.Texture1DArray_mut().ArraySize = 1,
+                UavDimension::Texture2DArray => desc.u.Texture2DArray_mut().ArraySize = 1,
+                UavDimension::Texture3D => desc.u.Texture3D_mut().WSize = 1,
+            }
+            self.CreateUnorderedAccessView(ptr::null_mut(), ptr::null_mut(), &desc, handle);
+        }
+    }
+
+    /// Writes a null CBV, reads return zeros.
+    pub fn create_null_cbv(&self, handle: CpuDescriptor) {
+        let desc = d3d12::D3D12_CONSTANT_BUFFER_VIEW_DESC {
+            BufferLocation: 0,
+            SizeInBytes: 0,
+        };
+        unsafe { self.CreateConstantBufferView(&desc, handle) }
+    }
+
     // TODO: interface not complete
     pub fn create_fence(&self, initial: u64) -> D3DResult<Fence> {
         let mut fence = Fence::null();
@@ -341,4 +1200,1037 @@ impl Device {
 
         (fence, hr)
     }
+
+    /// Creates an NT handle to share `resource` with another API or process.
+    ///
+    /// The resource must live in a heap created with `HeapFlags::SHARED`.
+    /// The handle is owned by the caller and has to be closed with `CloseHandle`,
+    /// opened objects keep the resource alive on their own.
+    pub fn create_shared_resource_handle(
+        &self,
+        resource: &Resource,
+        access: SharedHandleAccess,
+        name: Option<&str>,
+    ) -> D3DResult<HANDLE> {
+        let name = name.map(|name| name.encode_utf16().chain(Some(0)).collect::<Vec<u16>>());
+        let name = match name {
+            Some(ref name) => name.as_ptr(),
+            None => ptr::null(),
+        };
+
+        let mut handle = ptr::null_mut();
+        let hr = unsafe {
+            self.CreateSharedHandle(
+                resource.as_mut_ptr() as *mut d3d12::ID3D12DeviceChild,
+                ptr::null(),
+                access.bits(),
+                name,
+                &mut handle,
+            )
+        };
+
+        (handle, hr)
+    }
+
+    /// Opens a shared `handle`, e.g. created by [`Self::create_shared_resource_handle`].
+    ///
+    /// The handle isn't consumed and still has to be closed by the caller.
+    pub fn open_shared_handle<T: Interface>(&self, handle: HANDLE) -> D3DResult<ComPtr<T>> {
+        let mut object = ComPtr::<T>::null();
+        let hr = unsafe { self.OpenSharedHandle(handle, &T::uuidof(), object.mut_void()) };
+
+        (object, hr)
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResourceBindingTier {
+    Tier1 = d3d12::D3D12_RESOURCE_BINDING_TIER_1,
+    Tier2 = d3d12::D3D12_RESOURCE_BINDING_TIER_2,
+    Tier3 = d3d12::D3D12_RESOURCE_BINDING_TIER_3,
+}
+
+impl ResourceBindingTier {
+    /// Unknown tiers above the highest one are supersets of it, anything below
+    /// is the lowest tier.
+    fn from_raw(raw: d3d12::D3D12_RESOURCE_BINDING_TIER) -> Self {
+        match raw {
+            d3d12::D3D12_RESOURCE_BINDING_TIER_2 => Self::Tier2,
+            raw if raw >= d3d12::D3D12_RESOURCE_BINDING_TIER_3 => Self::Tier3,
+            _ => Self::Tier1,
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShaderModel {
+    V5_1 = d3d12::D3D_SHADER_MODEL_5_1,
+    V6_0 = d3d12::D3D_SHADER_MODEL_6_0,
+    V6_1 = crate::types::D3D_SHADER_MODEL_6_1,
+    V6_2 = crate::types::D3D_SHADER_MODEL_6_2,
+    V6_3 = crate::types::D3D_SHADER_MODEL_6_3,
+    V6_4 = crate::types::D3D_SHADER_MODEL_6_4,
+    V6_5 = crate::types::D3D_SHADER_MODEL_6_5,
+    V6_6 = crate::types::D3D_SHADER_MODEL_6_6,
+    V6_7 = crate::types::D3D_SHADER_MODEL_6_7,
+}
+
+impl ShaderModel {
+    const ALL: [Self; 9] = [
+        Self::V5_1,
+        Self::V6_0,
+        Self::V6_1,
+        Self::V6_2,
+        Self::V6_3,
+        Self::V6_4,
+        Self::V6_5,
+        Self::V6_6,
+        Self::V6_7,
+    ];
+
+    fn from_raw(raw: d3d12::D3D_SHADER_MODEL) -> Option<Self> {
+        Self::ALL.iter().cloned().find(|&model| model as u32 == raw)
+    }
+}
+
+/// Wave intrinsics support, from `D3D12_FEATURE_DATA_D3D12_OPTIONS1`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WaveInfo {
+    pub supported: bool,
+    pub lane_count_min: u32,
+    pub lane_count_max: u32,
+    /// Lanes across the whole GPU.
+    pub total_lane_count: u32,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProgrammableSamplePositionsTier {
+    NotSupported = d3d12::D3D12_PROGRAMMABLE_SAMPLE_POSITIONS_TIER_NOT_SUPPORTED,
+    /// Positions shared by all pixels.
+    Tier1 = d3d12::D3D12_PROGRAMMABLE_SAMPLE_POSITIONS_TIER_1,
+    /// Positions varying across a 2x2 pixel quad.
+    Tier2 = d3d12::D3D12_PROGRAMMABLE_SAMPLE_POSITIONS_TIER_2,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResourceHeapTier {
+    Tier1 = d3d12::D3D12_RESOURCE_HEAP_TIER_1,
+    Tier2 = d3d12::D3D12_RESOURCE_HEAP_TIER_2,
+}
+
+impl ResourceHeapTier {
+    /// Same mapping as [`ResourceBindingTier`].
+    fn from_raw(raw: d3d12::D3D12_RESOURCE_HEAP_TIER) -> Self {
+        if raw >= d3d12::D3D12_RESOURCE_HEAP_TIER_2 {
+            Self::Tier2
+        } else {
+            Self::Tier1
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Access rights of shared handles, D3D12 only supports `ALL`.
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct SharedHandleAccess: u32 {
+        const ALL = winnt::GENERIC_ALL;
+    }
+}
+
+impl Device3 {
+    /// Opens a heap over existing CPU memory, e.g. a memory-mapped file.
+    ///
+    /// `address` must be page aligned and stay valid for the lifetime of the heap,
+    /// `E_INVALIDARG` is returned for null or misaligned addresses.
+    pub fn open_heap_from_address(&self, address: *const c_void) -> D3DResult<Heap> {
+        let mut heap = Heap::null();
+        if address.is_null() || address as usize % 0x1000 != 0 {
+            return (heap, winerror::E_INVALIDARG);
+        }
+
+        let hr = unsafe {
+            self.OpenExistingHeapFromAddress(address, &d3d12::ID3D12Heap::uuidof(), heap.mut_void())
+        };
+
+        (heap, hr)
+    }
+
+    /// Makes `objects` resident without blocking, `fence` being signaled with `value` once done.
+    pub fn enqueue_make_resident(
+        &self,
+        objects: &[&dyn Pageable],
+        fence: &Fence,
+        value: u64,
+        flags: ResidencyFlags,
+    ) -> HRESULT {
+        let objects = pageables(objects);
+        unsafe {
+            self.EnqueueMakeResident(
+                flags.bits(),
+                objects.len() as _,
+                objects.as_ptr(),
+                fence.as_mut_ptr(),
+                value,
+            )
+        }
+    }
+}
+
+impl Device4 {
+    /// Creates a closed command list without an allocator, cheaper to keep in a pool.
+    ///
+    /// The list has to be [`reset`](GraphicsCommandList::reset) with an allocator
+    /// before recording.
+    pub fn create_command_list1(
+        &self,
+        list_type: CmdListType,
+        flags: CommandListFlags,
+        node_mask: NodeMask,
+    ) -> D3DResult<GraphicsCommandList> {
+        let mut command_list = GraphicsCommandList::null();
+        let hr = unsafe {
+            self.CreateCommandList1(
+                node_mask,
+                list_type as _,
+                flags.bits(),
+                &d3d12::ID3D12GraphicsCommandList::uuidof(),
+                command_list.mut_void(),
+            )
+        };
+        if cfg!(debug_assertions) && winerror::SUCCEEDED(hr) {
+            command_list.set_closed(true);
+        }
+
+        (command_list, hr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{slot, Mock};
+    use std::sync::Mutex;
+    use winapi::shared::guiddef::{IsEqualGUID, REFIID};
+
+    struct HeapOpener {
+        heap: Heap,
+        /// Address and whether the heap interface was requested, for each call.
+        calls: Mutex<Vec<(usize, bool)>>,
+    }
+
+    unsafe extern "system" fn open_existing_heap_from_address(
+        this: *mut crate::types::ID3D12Device3,
+        address: *const c_void,
+        riid: REFIID,
+        heap: *mut *mut c_void,
+    ) -> HRESULT {
+        let state = Mock::<HeapOpener>::state(this);
+        let heap_iid = IsEqualGUID(&*riid, &d3d12::ID3D12Heap::uuidof());
+        state
+            .calls
+            .lock()
+            .unwrap()
+            .push((address as usize, heap_iid));
+        let opened = state.heap.clone();
+        *heap = opened.as_mut_ptr() as *mut c_void;
+        mem::forget(opened);
+        winerror::S_OK
+    }
+
+    fn heap_opener() -> (Device, Heap) {
+        let heap = Mock::new::<d3d12::ID3D12Heap, d3d12::ID3D12HeapVtbl>((), &[]);
+        let device = Mock::new::<d3d12::ID3D12Device, crate::types::ID3D12Device3Vtbl>(
+            HeapOpener {
+                heap: heap.clone(),
+                calls: Mutex::new(Vec::new()),
+            },
+            &[(
+                slot!(crate::types::ID3D12Device3Vtbl, OpenExistingHeapFromAddress),
+                open_existing_heap_from_address as usize,
+            )],
+        );
+        (device, heap)
+    }
+
+    #[test]
+    fn open_heap_from_address_forwarding() {
+        let (device, heap) = heap_opener();
+        let (opened, hr) = device.open_heap_from_address(0x2_0000 as *const c_void);
+        assert_eq!(hr, winerror::S_OK);
+        assert!(opened == heap);
+        // Held by the mock, `heap` and `opened`.
+        assert_eq!(unsafe { Mock::<()>::refs(heap.as_ptr()) }, 3);
+
+        let state = unsafe { Mock::<HeapOpener>::state(device.as_ptr()) };
+        assert_eq!(*state.calls.lock().unwrap(), [(0x2_0000, true)]);
+    }
+
+    #[test]
+    fn open_heap_from_address_alignment() {
+        let (device, _heap) = heap_opener();
+        for &address in [0, 0x2_0010, 0x2_0800].iter() {
+            let (opened, hr) = device.open_heap_from_address(address as *const c_void);
+            assert_eq!(hr, winerror::E_INVALIDARG);
+            assert!(opened.is_null());
+        }
+
+        let state = unsafe { Mock::<HeapOpener>::state(device.as_ptr()) };
+        assert!(state.calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_tiers() {
+        assert_eq!(ResourceBindingTier::from_raw(0), ResourceBindingTier::Tier1);
+        assert_eq!(
+            ResourceBindingTier::from_raw(d3d12::D3D12_RESOURCE_BINDING_TIER_2),
+            ResourceBindingTier::Tier2
+        );
+        assert_eq!(
+            ResourceBindingTier::from_raw(d3d12::D3D12_RESOURCE_BINDING_TIER_3 + 1),
+            ResourceBindingTier::Tier3
+        );
+        assert_eq!(ResourceHeapTier::from_raw(0), ResourceHeapTier::Tier1);
+        assert_eq!(
+            ResourceHeapTier::from_raw(d3d12::D3D12_RESOURCE_HEAP_TIER_2 + 1),
+            ResourceHeapTier::Tier2
+        );
+    }
+
+    /// DXBC container header followed by parts with the given tags.
+    fn container(tags: &[&[u8; 4]]) -> Vec<u8> {
+        let mut bytes = b"DXBC".to_vec();
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(tags.len() as u32).to_le_bytes());
+        let parts_start = 32 + 4 * tags.len();
+        for index in 0..tags.len() {
+            bytes.extend_from_slice(&((parts_start + 8 * index) as u32).to_le_bytes());
+        }
+        for tag in tags {
+            // Tag and an empty size.
+            bytes.extend_from_slice(&tag[..]);
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn embedded_root_signature_part() {
+        assert!(has_embedded_root_signature(&container(&[b"SHEX", b"RTS0"])));
+        assert!(!has_embedded_root_signature(&container(&[
+            b"SHEX", b"ISG1"
+        ])));
+        assert!(!has_embedded_root_signature(&container(&[])));
+        assert!(!has_embedded_root_signature(b"DXBC"));
+        assert!(!has_embedded_root_signature(b"not a shader"));
+
+        // The part count is bounded by the blob, not trusted.
+        let mut bytes = container(&[b"SHEX"]);
+        bytes[28..32].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(!has_embedded_root_signature(&bytes));
+        // Offsets pointing past the end are skipped.
+        let mut bytes = container(&[b"RTS0"]);
+        bytes[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(!has_embedded_root_signature(&bytes));
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn root_signature_from_shader() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let target = std::ffi::CString::new("cs_5_1").unwrap();
+        let entry = std::ffi::CString::new("main").unwrap();
+        let compile = |code: &[u8]| {
+            crate::Shader::compile(code, &target, &entry, crate::ShaderCompileFlags::empty())
+                .unwrap()
+        };
+        let embedded = compile(
+            b"[RootSignature(\"RootConstants(num32BitConstants = 1, b0)\")]
+            [numthreads(1, 1, 1)] void main() {}",
+        );
+        let plain = compile(b"[numthreads(1, 1, 1)] void main() {}");
+
+        let (signature, hr) =
+            device.create_root_signature_from_shader(crate::blob_bytes(&embedded), 0);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(!signature.is_null());
+        let (signature, hr) =
+            device.create_root_signature_from_shader(crate::blob_bytes(&plain), 0);
+        assert_eq!(hr, winerror::E_INVALIDARG);
+        assert!(signature.is_null());
+    }
+
+    #[test]
+    fn device_removed_codes() {
+        for &(hr, removed) in &[
+            (winerror::DXGI_ERROR_DEVICE_REMOVED, true),
+            (winerror::DXGI_ERROR_DEVICE_HUNG, true),
+            (winerror::DXGI_ERROR_DEVICE_RESET, true),
+            (winerror::DXGI_ERROR_DRIVER_INTERNAL_ERROR, true),
+            (winerror::S_OK, false),
+            (winerror::E_FAIL, false),
+            (winerror::E_OUTOFMEMORY, false),
+        ] {
+            assert_eq!(is_device_removed(hr), removed, "{:#x}", hr);
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn descriptor_increments() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let increments = device.descriptor_increment_sizes();
+        for &ty in &[
+            DescriptorHeapType::CbvSrvUav,
+            DescriptorHeapType::Sampler,
+            DescriptorHeapType::Rtv,
+            DescriptorHeapType::Dsv,
+        ] {
+            assert_ne!(increments.get(ty), 0, "{:?}", ty);
+            assert_eq!(increments.get(ty), device.get_descriptor_increment_size(ty));
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn texture_views() {
+        use crate::{ResourceFlags, TextureViewDimension};
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let texture = |format, layers, flags| {
+            let mut builder = CommittedResourceBuilder::texture2d(format, 64, 64, 1, flags);
+            builder.desc.DepthOrArraySize = layers;
+            let (resource, hr) = device.create_committed_resource(builder);
+            assert!(winerror::SUCCEEDED(hr));
+            resource
+        };
+        let heap = |ty, count| {
+            let (heap, hr) =
+                device.create_indexed_descriptor_heap(count, ty, DescriptorHeapFlags::empty(), 0);
+            assert!(winerror::SUCCEEDED(hr));
+            heap
+        };
+
+        let color_format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let color = texture(color_format, 1, ResourceFlags::ALLOW_RENDER_TARGET);
+        let rtvs = heap(DescriptorHeapType::Rtv, 1);
+        device.create_texture_rtv(
+            &color,
+            &TextureViewDesc::new(TextureViewDimension::Texture2D, color_format),
+            rtvs.cpu_descriptor_at(0),
+        );
+
+        let depth_format = dxgiformat::DXGI_FORMAT_D32_FLOAT;
+        let depth = texture(depth_format, 1, ResourceFlags::ALLOW_DEPTH_STENCIL);
+        let dsvs = heap(DescriptorHeapType::Dsv, 2);
+        let desc = TextureViewDesc::new(TextureViewDimension::Texture2D, depth_format);
+        for (i, &flags) in [DsvFlags::empty(), DsvFlags::READ_ONLY_DEPTH]
+            .iter()
+            .enumerate()
+        {
+            let hr = device.create_depth_stencil_view(
+                &depth,
+                &desc,
+                flags,
+                dsvs.cpu_descriptor_at(i as u32),
+            );
+            assert_eq!(hr, winerror::S_OK);
+        }
+
+        let array = texture(color_format, 4, ResourceFlags::empty());
+        let desc = TextureViewDesc::new(TextureViewDimension::Texture2DArray, color_format)
+            .array_layers(1, 3);
+        let srv = desc.srv_desc();
+        assert_eq!(srv.ViewDimension, d3d12::D3D12_SRV_DIMENSION_TEXTURE2DARRAY);
+        let layers = unsafe { srv.u.Texture2DArray() };
+        assert_eq!((layers.FirstArraySlice, layers.ArraySize), (1, 3));
+        let srvs = heap(DescriptorHeapType::CbvSrvUav, 1);
+        device.create_texture_srv(&array, &desc, srvs.cpu_descriptor_at(0));
+    }
+
+    #[test]
+    fn anisotropic_sampler_filters() {
+        let sampler = SamplerDesc::default().max_anisotropy(16);
+        assert_eq!(sampler.0.Filter, d3d12::D3D12_FILTER_ANISOTROPIC);
+        assert_eq!(sampler.0.MaxAnisotropy, 16);
+
+        // The comparison reduction survives the switch to anisotropic filtering.
+        let sampler = SamplerDesc::default()
+            .filter(d3d12::D3D12_FILTER_COMPARISON_MIN_MAG_MIP_LINEAR)
+            .max_anisotropy(4);
+        assert_eq!(sampler.0.Filter, d3d12::D3D12_FILTER_COMPARISON_ANISOTROPIC);
+
+        let sampler = SamplerDesc::default().max_anisotropy(1);
+        assert_eq!(sampler.0.Filter, d3d12::D3D12_FILTER_MIN_MAG_MIP_LINEAR);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn samplers_into_heap() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (heap, hr) = device.create_indexed_descriptor_heap(
+            2,
+            DescriptorHeapType::Sampler,
+            DescriptorHeapFlags::SHADER_VISIBLE,
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+
+        let anisotropic = SamplerDesc::default()
+            .address_mode([d3d12::D3D12_TEXTURE_ADDRESS_MODE_WRAP; 3])
+            .max_anisotropy(16);
+        let shadow = SamplerDesc::default()
+            .filter(d3d12::D3D12_FILTER_COMPARISON_MIN_MAG_LINEAR_MIP_POINT)
+            .comparison_func(d3d12::D3D12_COMPARISON_FUNC_LESS_EQUAL)
+            .address_mode([d3d12::D3D12_TEXTURE_ADDRESS_MODE_BORDER; 3])
+            .border_color([1.0; 4]);
+        device.create_sampler_with_desc(&anisotropic, heap.cpu_descriptor_at(0));
+        device.create_sampler_with_desc(&shadow, heap.cpu_descriptor_at(1));
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn evict_and_make_resident() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (buffer, hr) = device
+            .create_committed_resource(crate::CommittedResourceBuilder::default_buffer(65536));
+        assert!(winerror::SUCCEEDED(hr));
+        let (heap, hr) = device.create_heap(
+            65536,
+            crate::HeapProperties::standard(crate::HeapType::Default),
+            0,
+            crate::HeapFlags::ALLOW_ONLY_BUFFERS,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+
+        let objects: [&dyn Pageable; 2] = [&buffer, &heap];
+        assert!(winerror::SUCCEEDED(device.evict(&objects)));
+        assert!(winerror::SUCCEEDED(device.make_resident(&objects)));
+
+        // Evicted again, then made resident asynchronously.
+        assert!(winerror::SUCCEEDED(device.evict(&objects)));
+        let (device3, hr) = unsafe { device.cast::<crate::types::ID3D12Device3>() };
+        if winerror::FAILED(hr) {
+            return;
+        }
+        let (fence, hr) = device.create_fence(0);
+        assert!(winerror::SUCCEEDED(hr));
+        let hr = device3.enqueue_make_resident(&objects, &fence, 1, ResidencyFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        let (event, hr) = crate::OwnedEvent::new(false);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(winerror::SUCCEEDED(
+            fence.set_event_on_completion(&event, 1)
+        ));
+        assert_eq!(event.wait(1000), crate::WaitStatus::Signaled);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn texture_allocation_info() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let texture = |width, height| {
+            crate::CommittedResourceBuilder::texture2d(
+                dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM,
+                width,
+                height,
+                1,
+                crate::ResourceFlags::empty(),
+            )
+            .desc
+        };
+        let descs = [texture(256, 256), texture(100, 30)];
+
+        let infos = device.resource_allocation_info(&descs, 0);
+        assert_eq!(infos.len(), 2);
+        for info in &infos {
+            assert_ne!(info.SizeInBytes, !0);
+            assert_eq!(info.SizeInBytes % 65536, 0, "{} bytes", info.SizeInBytes);
+            assert_eq!(info.Alignment, 65536);
+        }
+        // 256 * 256 texels of 4 bytes.
+        assert!(infos[0].SizeInBytes >= 256 * 256 * 4);
+
+        let combined = device.combined_resource_allocation_info(&descs, 0);
+        assert!(combined.SizeInBytes >= infos[0].SizeInBytes + infos[1].SizeInBytes);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn shader_model_and_waves() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (model, hr) = device.highest_shader_model();
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(model >= ShaderModel::V5_1);
+
+        let (waves, hr) = device.wave_info();
+        assert!(winerror::SUCCEEDED(hr));
+        if waves.supported {
+            assert!(model >= ShaderModel::V6_0);
+            assert!(waves.lane_count_min <= waves.lane_count_max, "{:?}", waves);
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn null_texture_srv() {
+        use crate::DescriptorHandle as _;
+
+        // Validates the views if the layer is installed, it has to be before the device is created.
+        let (debug, hr) = crate::Debug::get_interface();
+        if winerror::SUCCEEDED(hr) {
+            debug.enable_layer();
+        }
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (info_queue, _) = device.info_queue();
+        if let Some(ref queue) = info_queue {
+            queue.drain_messages();
+        }
+        let (heap, hr) = device.create_descriptor_heap(
+            3,
+            DescriptorHeapType::CbvSrvUav,
+            DescriptorHeapFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let increment = device.get_descriptor_increment_size(DescriptorHeapType::CbvSrvUav);
+        let start = heap.start_cpu_descriptor();
+
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        device.create_null_srv(SrvDimension::Texture2D, format, start);
+        device.create_null_uav(UavDimension::Texture2D, format, start.advance(1, increment));
+        device.create_null_cbv(start.advance(2, increment));
+
+        if let Some(queue) = info_queue {
+            let errors = queue
+                .drain_messages()
+                .into_iter()
+                .filter(|message| message.severity <= crate::Severity::Error)
+                .collect::<Vec<_>>();
+            assert!(errors.is_empty(), "{:?}", errors);
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn shared_texture_handle() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let builder = crate::CommittedResourceBuilder::texture2d(
+            dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM,
+            32,
+            16,
+            1,
+            crate::ResourceFlags::empty(),
+        )
+        .heap_flags(HeapFlags::SHARED);
+        let (texture, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (handle, hr) =
+            device.create_shared_resource_handle(&texture, SharedHandleAccess::ALL, None);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(!handle.is_null());
+        let (opened, hr) = device.open_shared_handle::<d3d12::ID3D12Resource>(handle);
+        unsafe { winapi::um::handleapi::CloseHandle(handle) };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let desc = opened.desc();
+        assert_eq!((desc.Width, desc.Height), (32, 16));
+        assert_eq!(desc.Format, dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn closed_command_list1() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (device4, hr) = unsafe { device.cast::<crate::types::ID3D12Device4>() };
+        if winerror::FAILED(hr) {
+            return;
+        }
+        let (list, hr) =
+            device4.create_command_list1(CmdListType::Direct, CommandListFlags::empty(), 0);
+        assert!(winerror::SUCCEEDED(hr));
+        if cfg!(debug_assertions) {
+            assert_eq!(list.is_closed(), Some(true));
+        }
+
+        let (allocator, hr) = device.create_command_allocator(CmdListType::Direct);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(winerror::SUCCEEDED(
+            list.reset(&allocator, PipelineState::null())
+        ));
+        if cfg!(debug_assertions) {
+            assert_eq!(list.is_closed(), Some(false));
+        }
+        list.set_viewports(&[crate::Viewport::from_size(4.0, 4.0)]);
+        crate::test_execute(&device, &list);
+    }
+
+    /// Destination and source ranges of each mocked `CopyDescriptors` call.
+    type CopyCalls = Mutex<Vec<(Vec<(usize, u32)>, Vec<(usize, u32)>)>>;
+
+    unsafe extern "system" fn copy_descriptors(
+        this: *mut d3d12::ID3D12Device,
+        num_dst_ranges: u32,
+        dst_starts: *const CpuDescriptor,
+        dst_sizes: *const u32,
+        num_src_ranges: u32,
+        src_starts: *const CpuDescriptor,
+        src_sizes: *const u32,
+        _heap_type: d3d12::D3D12_DESCRIPTOR_HEAP_TYPE,
+    ) {
+        let ranges = |count, starts, sizes| {
+            let starts = std::slice::from_raw_parts(starts, count as usize);
+            let sizes = std::slice::from_raw_parts(sizes, count as usize);
+            starts
+                .iter()
+                .zip(sizes)
+                .map(|(start, &size): (&CpuDescriptor, _)| (start.ptr, size))
+                .collect::<Vec<_>>()
+        };
+        Mock::<CopyCalls>::state(this).lock().unwrap().push((
+            ranges(num_dst_ranges, dst_starts, dst_sizes),
+            ranges(num_src_ranges, src_starts, src_sizes),
+        ));
+    }
+
+    #[test]
+    fn copy_descriptor_ranges() {
+        let device = Mock::new::<d3d12::ID3D12Device, d3d12::ID3D12DeviceVtbl>(
+            CopyCalls::default(),
+            &[(
+                slot!(d3d12::ID3D12DeviceVtbl, CopyDescriptors),
+                copy_descriptors as usize,
+            )],
+        );
+        let handle = |ptr| CpuDescriptor { ptr };
+        device.copy_descriptors(
+            &[(handle(0x2000), 2), (handle(0x3000), 1)],
+            &[(handle(0x1000), 3)],
+            DescriptorHeapType::CbvSrvUav,
+        );
+
+        let calls = unsafe { Mock::<CopyCalls>::state(device.as_ptr()) }
+            .lock()
+            .unwrap();
+        assert_eq!(
+            *calls,
+            [(vec![(0x2000, 2), (0x3000, 1)], vec![(0x1000, 3)])]
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn copy_three_descriptors() {
+        use crate::DescriptorHandle as _;
+
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let heap = || {
+            let (heap, hr) = device.create_descriptor_heap(
+                3,
+                DescriptorHeapType::CbvSrvUav,
+                DescriptorHeapFlags::empty(),
+                0,
+            );
+            assert!(winerror::SUCCEEDED(hr));
+            heap
+        };
+        let (src, dst, back) = (heap(), heap(), heap());
+        let increment = device.get_descriptor_increment_size(DescriptorHeapType::CbvSrvUav);
+        let (buffer, hr) =
+            device.create_committed_resource(CommittedResourceBuilder::upload_buffer(3 * 256));
+        assert!(winerror::SUCCEEDED(hr));
+        for index in 0..3 {
+            let address = buffer.gpu_virtual_address().offset(index as u64 * 256);
+            let hr = device.create_constant_buffer_view(
+                address,
+                256,
+                src.start_cpu_descriptor().advance(index, increment),
+            );
+            assert!(winerror::SUCCEEDED(hr));
+        }
+
+        // Split differently on both sides, then copied back as one range.
+        let dst_start = dst.start_cpu_descriptor();
+        device.copy_descriptors(
+            &[(dst_start, 2), (dst_start.advance(2, increment), 1)],
+            &[(src.start_cpu_descriptor(), 3)],
+            DescriptorHeapType::CbvSrvUav,
+        );
+        device.copy_descriptors_simple(
+            3,
+            back.start_cpu_descriptor(),
+            dst_start,
+            DescriptorHeapType::CbvSrvUav,
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn buffer_views_into_shader_visible_heap() {
+        use crate::DescriptorHandle as _;
+
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (heap, hr) = device.create_descriptor_heap(
+            5,
+            DescriptorHeapType::CbvSrvUav,
+            DescriptorHeapFlags::SHADER_VISIBLE,
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let increment = device.get_descriptor_increment_size(DescriptorHeapType::CbvSrvUav);
+        let handle = |index| heap.start_cpu_descriptor().advance(index, increment);
+
+        let uav_buffer = || {
+            let mut builder = CommittedResourceBuilder::default_buffer(4096);
+            builder.desc.Flags = d3d12::D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS;
+            let (buffer, hr) = device.create_committed_resource(builder);
+            assert!(winerror::SUCCEEDED(hr));
+            buffer
+        };
+        let (buffer, counter) = (uav_buffer(), uav_buffer());
+        let (constants, hr) =
+            device.create_committed_resource(CommittedResourceBuilder::upload_buffer(512));
+        assert!(winerror::SUCCEEDED(hr));
+
+        // Raw views count words, structured ones elements of 16 bytes.
+        device.create_buffer_srv(&buffer, 0, 1024, 0, handle(0));
+        device.create_buffer_srv(&buffer, 16, 128, 16, handle(1));
+        device.create_buffer_uav(&buffer, None, 0, 1024, 0, handle(2));
+        device.create_buffer_uav(&buffer, Some((&counter, 0)), 0, 256, 16, handle(3));
+        // Rounded up to 256 bytes.
+        let hr =
+            device.create_constant_buffer_view(constants.gpu_virtual_address(), 100, handle(4));
+        assert!(winerror::SUCCEEDED(hr));
+        let hr = device.create_constant_buffer_view(constants.gpu_virtual_address(), !0, handle(4));
+        assert_eq!(hr, winerror::E_INVALIDARG);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn two_placed_buffers() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (heap, hr) = device.create_heap(
+            2 * 65536,
+            HeapProperties::standard(crate::HeapType::Default),
+            0,
+            HeapFlags::ALLOW_ONLY_BUFFERS,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let desc = CommittedResourceBuilder::default_buffer(65536).desc;
+
+        let buffers = [0, 65536]
+            .iter()
+            .map(|&offset| {
+                let (buffer, hr) = device.create_placed_resource(
+                    &heap,
+                    offset,
+                    &desc,
+                    ResourceStates::COMMON,
+                    None,
+                );
+                assert!(winerror::SUCCEEDED(hr));
+                buffer
+            })
+            .collect::<Vec<_>>();
+        let (a, b) = (
+            buffers[0].gpu_virtual_address(),
+            buffers[1].gpu_virtual_address(),
+        );
+        assert_ne!(a, GpuAddress(0));
+        assert_eq!(a.offset(65536), b);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "misaligned heap offset")]
+    fn misaligned_placement() {
+        let desc = CommittedResourceBuilder::default_buffer(65536).desc;
+        Device::null().create_placed_resource(
+            &Heap::null(),
+            4096,
+            &desc,
+            ResourceStates::COMMON,
+            None,
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn single_node_masks() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        if device.node_count() != 1 {
+            return;
+        }
+        // The first and only node, same as the 0 default.
+        let node = 0x1;
+        let (_queue, hr) = device.create_command_queue(
+            CmdListType::Direct,
+            queue::Priority::Normal,
+            queue::CommandQueueFlags::empty(),
+            node,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let (_heap, hr) = device.create_descriptor_heap(
+            1,
+            DescriptorHeapType::CbvSrvUav,
+            DescriptorHeapFlags::SHADER_VISIBLE,
+            node,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let builder = CommittedResourceBuilder::default_buffer(256).node_masks(node, node);
+        let (_buffer, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        // There's no second node.
+        let (_queue, hr) = device.create_command_queue(
+            CmdListType::Direct,
+            queue::Priority::Normal,
+            queue::CommandQueueFlags::empty(),
+            0x2,
+        );
+        assert!(winerror::FAILED(hr));
+    }
+
+    /// Opt-in with `D3D12_TEST_STABLE_POWER_STATE`: outside of developer mode the call
+    /// removes the device, which is shared with every other test of the process.
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn toggle_stable_power_state() {
+        if std::env::var_os("D3D12_TEST_STABLE_POWER_STATE").is_none() {
+            return;
+        }
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let hr = device.set_stable_power_state(true);
+        if winerror::FAILED(hr) {
+            println!("Stable power state unavailable: {:#x}", hr);
+            return;
+        }
+        assert!(winerror::SUCCEEDED(device.set_stable_power_state(false)));
+        assert_eq!(device.device_removed_reason(), winerror::S_OK);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn create_with_fallback_at_11_0() {
+        use winapi::shared::dxgi;
+
+        let (factory, hr) = Factory4::create(crate::FactoryCreationFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        match Device::create_with_fallback(&factory, &[FeatureLevel::L11_0]) {
+            (Some((device, level)), hr) => {
+                assert!(winerror::SUCCEEDED(hr));
+                assert!(!device.is_null());
+                assert_eq!(level, FeatureLevel::L11_0);
+            }
+            // Neither a GPU nor WARP, e.g. without the Graphics Tools.
+            (None, hr) => assert!(winerror::FAILED(hr)),
+        }
+
+        // Refusing the hardware adapters leaves the software ones, WARP last.
+        let result = create_with_fallback(&factory, &[FeatureLevel::L11_0], |adapter, level| {
+            let (desc, _) = adapter.description();
+            if desc.flags & dxgi::DXGI_ADAPTER_FLAG_SOFTWARE == 0 {
+                return Ok::<_, ()>((Device::null(), winerror::DXGI_ERROR_UNSUPPORTED));
+            }
+            Ok(Device::create(adapter.clone(), level))
+        });
+        match result.unwrap() {
+            (Some((device, level)), hr) => {
+                assert!(winerror::SUCCEEDED(hr));
+                assert!(!device.is_null());
+                assert_eq!(level, FeatureLevel::L11_0);
+            }
+            (None, last_hr) => assert!(winerror::FAILED(last_hr)),
+        }
+
+        // Nothing to try.
+        match Device::create_with_fallback(&factory, &[]) {
+            (None, hr) => assert_eq!(hr, winerror::DXGI_ERROR_NOT_FOUND),
+            (Some(_), _) => panic!("created a device without feature levels"),
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn reserved_texture_tiling() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let mut options: d3d12::D3D12_FEATURE_DATA_D3D12_OPTIONS = unsafe { mem::zeroed() };
+        let hr = device.check_feature_support(d3d12::D3D12_FEATURE_D3D12_OPTIONS, &mut options);
+        assert!(winerror::SUCCEEDED(hr));
+        if options.TiledResourcesTier == d3d12::D3D12_TILED_RESOURCES_TIER_NOT_SUPPORTED {
+            return;
+        }
+
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let mut desc =
+            CommittedResourceBuilder::texture2d(format, 512, 512, 1, crate::ResourceFlags::empty())
+                .desc;
+        desc.Layout = d3d12::D3D12_TEXTURE_LAYOUT_64KB_UNDEFINED_SWIZZLE;
+        let (texture, hr) = device.create_reserved_resource(&desc, ResourceStates::COMMON);
+        assert!(winerror::SUCCEEDED(hr));
+
+        // 64KiB tiles of 32-bit texels are 128x128.
+        let tiling = device.resource_tiling(&texture);
+        assert_eq!(tiling.tile_shape, [128, 128, 1]);
+        assert_eq!(tiling.num_tiles, 16);
+        assert_eq!(tiling.num_standard_mips, 1);
+        assert_eq!(tiling.num_packed_mips, 0);
+        assert_eq!(tiling.subresource_tilings.len(), 1);
+        let tiling = &tiling.subresource_tilings[0];
+        assert_eq!((tiling.WidthInTiles, tiling.HeightInTiles), (4, 4));
+        assert_eq!(tiling.DepthInTiles, 1);
+    }
 }
@@ -0,0 +1,204 @@
+//! Device removed extended data (DRED)
+
+use crate::{com::ComPtr, types, D3DResult, Device, GpuAddress, HRESULT};
+use std::{ffi::CStr, marker::PhantomData, mem, slice};
+use winapi::ctypes::{c_char, wchar_t};
+#[cfg(any(feature = "libloading", feature = "implicit-link"))]
+use winapi::Interface as _;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug)]
+pub enum DredEnablement {
+    SystemControlled = types::D3D12_DRED_ENABLEMENT_SYSTEM_CONTROLLED,
+    ForcedOff = types::D3D12_DRED_ENABLEMENT_FORCED_OFF,
+    ForcedOn = types::D3D12_DRED_ENABLEMENT_FORCED_ON,
+}
+
+pub type AutoBreadcrumbOp = types::D3D12_AUTO_BREADCRUMB_OP;
+
+pub type DredSettings = ComPtr<types::ID3D12DeviceRemovedExtendedDataSettings>;
+
+#[cfg(feature = "libloading")]
+impl crate::D3D12Lib {
+    pub fn get_dred_settings(&self) -> Result<D3DResult<DredSettings>, libloading::Error> {
+        type Fun = extern "system" fn(
+            winapi::shared::guiddef::REFIID,
+            *mut *mut winapi::ctypes::c_void,
+        ) -> HRESULT;
+
+        let mut settings = DredSettings::null();
+        let hr = unsafe {
+            let func: libloading::Symbol<Fun> = self.lib.get(b"D3D12GetDebugInterface")?;
+            func(
+                &types::ID3D12DeviceRemovedExtendedDataSettings::uuidof(),
+                settings.mut_void(),
+            )
+        };
+
+        Ok((settings, hr))
+    }
+}
+
+impl DredSettings {
+    #[cfg(feature = "implicit-link")]
+    pub fn get_interface() -> D3DResult<Self> {
+        let mut settings = DredSettings::null();
+        let hr = unsafe {
+            winapi::um::d3d12::D3D12GetDebugInterface(
+                &types::ID3D12DeviceRemovedExtendedDataSettings::uuidof(),
+                settings.mut_void(),
+            )
+        };
+
+        (settings, hr)
+    }
+
+    /// Settings only apply to devices created afterwards.
+    pub fn set_auto_breadcrumbs_enablement(&self, enablement: DredEnablement) {
+        unsafe { self.SetAutoBreadcrumbsEnablement(enablement as _) }
+    }
+
+    pub fn set_page_fault_enablement(&self, enablement: DredEnablement) {
+        unsafe { self.SetPageFaultEnablement(enablement as _) }
+    }
+}
+
+pub type Dred = ComPtr<types::ID3D12DeviceRemovedExtendedData>;
+
+impl Device {
+    /// DRED output of a removed device, requires DRED to be enabled before the device was created.
+    pub fn dred(&self) -> D3DResult<Dred> {
+        unsafe { self.cast::<types::ID3D12DeviceRemovedExtendedData>() }
+    }
+}
+
+impl Dred {
+    pub fn auto_breadcrumbs(&self) -> D3DResult<AutoBreadcrumbs> {
+        let mut output: types::D3D12_DRED_AUTO_BREADCRUMBS_OUTPUT = unsafe { mem::zeroed() };
+        let hr = unsafe { self.GetAutoBreadcrumbsOutput(&mut output) };
+        let breadcrumbs = AutoBreadcrumbs {
+            node: output.pHeadAutoBreadcrumbNode,
+            marker: PhantomData,
+        };
+
+        (breadcrumbs, hr)
+    }
+
+    pub fn page_fault(&self) -> D3DResult<PageFault> {
+        let mut output: types::D3D12_DRED_PAGE_FAULT_OUTPUT = unsafe { mem::zeroed() };
+        let hr = unsafe { self.GetPageFaultAllocationOutput(&mut output) };
+        let page_fault = unsafe {
+            PageFault {
+                address: GpuAddress(output.PageFaultVA),
+                existing_allocations: allocation_names(output.pHeadExistingAllocationNode),
+                recently_freed_allocations: allocation_names(output.pHeadRecentFreedAllocationNode),
+            }
+        };
+
+        (page_fault, hr)
+    }
+}
+
+/// Breadcrumbs of a command list which was submitted before the device got removed.
+#[derive(Debug)]
+pub struct AutoBreadcrumb {
+    pub command_list_name: Option<String>,
+    pub command_queue_name: Option<String>,
+    /// Last operation the GPU completed on the command list, if any.
+    pub last_completed_op: Option<AutoBreadcrumbOp>,
+}
+
+/// Iterator over the breadcrumb nodes, only valid while the DRED interface is alive.
+pub struct AutoBreadcrumbs<'a> {
+    node: *const types::D3D12_AUTO_BREADCRUMB_NODE,
+    marker: PhantomData<&'a Dred>,
+}
+
+impl<'a> Iterator for AutoBreadcrumbs<'a> {
+    type Item = AutoBreadcrumb;
+
+    fn next(&mut self) -> Option<AutoBreadcrumb> {
+        let node = unsafe { self.node.as_ref()? };
+        self.node = node.pNext;
+
+        let last_completed_op = unsafe {
+            let history = if node.pCommandHistory.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(node.pCommandHistory, node.BreadcrumbCount as usize)
+            };
+            match node.pLastBreadcrumbValue.as_ref() {
+                Some(&completed) if completed > 0 => history.get(completed as usize - 1).copied(),
+                _ => None,
+            }
+        };
+
+        Some(unsafe {
+            AutoBreadcrumb {
+                command_list_name: object_name(
+                    node.pCommandListDebugNameA,
+                    node.pCommandListDebugNameW,
+                ),
+                command_queue_name: object_name(
+                    node.pCommandQueueDebugNameA,
+                    node.pCommandQueueDebugNameW,
+                ),
+                last_completed_op,
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PageFault {
+    pub address: GpuAddress,
+    pub existing_allocations: Vec<Option<String>>,
+    pub recently_freed_allocations: Vec<Option<String>>,
+}
+
+unsafe fn allocation_names(
+    mut node: *const types::D3D12_DRED_ALLOCATION_NODE,
+) -> Vec<Option<String>> {
+    let mut names = Vec::new();
+    while let Some(allocation) = node.as_ref() {
+        names.push(object_name(allocation.ObjectNameA, allocation.ObjectNameW));
+        node = allocation.pNext;
+    }
+    names
+}
+
+unsafe fn object_name(narrow: *const c_char, wide: *const wchar_t) -> Option<String> {
+    if !wide.is_null() {
+        let len = (0..).take_while(|&i| *wide.add(i) != 0).count();
+        Some(String::from_utf16_lossy(slice::from_raw_parts(wide, len)))
+    } else if !narrow.is_null() {
+        Some(CStr::from_ptr(narrow).to_string_lossy().into_owned())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn enable_settings() {
+        use winapi::shared::winerror;
+
+        let (settings, hr) = DredSettings::get_interface();
+        // Windows 10 before 1903, or a runtime without DRED.
+        if hr == winerror::E_NOINTERFACE {
+            return;
+        }
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(!settings.is_null());
+
+        settings.set_auto_breadcrumbs_enablement(DredEnablement::ForcedOn);
+        settings.set_page_fault_enablement(DredEnablement::ForcedOn);
+        // Devices of the other tests keep the defaults.
+        settings.set_auto_breadcrumbs_enablement(DredEnablement::SystemControlled);
+        settings.set_page_fault_enablement(DredEnablement::SystemControlled);
+    }
+}
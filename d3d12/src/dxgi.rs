@@ -1,9 +1,14 @@
-use crate::{com::ComPtr, D3DResult, Resource, SampleDesc, HRESULT};
-use std::ptr;
+use crate::{
+    com::ComPtr, CommandQueue, CpuDescriptor, D3DResult, DescriptorHandle, DescriptorHeap,
+    DescriptorHeapType, Device, OwnedEvent, Resource, SampleDesc, HRESULT,
+};
+use std::{mem, ptr};
 use winapi::{
     shared::{
-        dxgi, dxgi1_2, dxgi1_3, dxgi1_4, dxgi1_5, dxgi1_6, dxgiformat, dxgitype, minwindef::TRUE,
-        windef::HWND,
+        dxgi, dxgi1_2, dxgi1_3, dxgi1_4, dxgi1_5, dxgi1_6, dxgiformat, dxgitype,
+        minwindef::TRUE,
+        windef::{HMONITOR, HWND, RECT},
+        winerror,
     },
     um::{d3d12, dxgidebug, unknwnbase::IUnknown, winnt::HANDLE},
     Interface,
@@ -45,6 +50,158 @@ pub enum AlphaMode {
 
 pub type InfoQueue = ComPtr<dxgidebug::IDXGIInfoQueue>;
 
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct RloFlags: u32 {
+        const SUMMARY = dxgidebug::DXGI_DEBUG_RLO_SUMMARY;
+        const DETAIL = dxgidebug::DXGI_DEBUG_RLO_DETAIL;
+        const IGNORE_INTERNAL = dxgidebug::DXGI_DEBUG_RLO_IGNORE_INTERNAL;
+    }
+}
+
+pub type DxgiDebug = ComPtr<dxgidebug::IDXGIDebug1>;
+
+impl DxgiDebug {
+    /// Fails if the debug layer isn't installed.
+    #[cfg(feature = "implicit-link")]
+    pub fn get_interface() -> D3DResult<Self> {
+        let mut debug = DxgiDebug::null();
+        let hr = unsafe {
+            dxgi1_3::DXGIGetDebugInterface1(0, &dxgidebug::IDXGIDebug1::uuidof(), debug.mut_void())
+        };
+
+        (debug, hr)
+    }
+
+    /// Reports all live DXGI and D3D objects to the debug output.
+    pub fn report_live_objects(&self, flags: RloFlags) -> HRESULT {
+        unsafe { self.ReportLiveObjects(dxgidebug::DXGI_DEBUG_ALL, flags.bits()) }
+    }
+}
+
+/// Reports the live objects once dropped, e.g. at the end of `main` after everything else is released.
+pub struct LiveObjectsReport {
+    pub debug: DxgiDebug,
+    pub flags: RloFlags,
+}
+
+impl Drop for LiveObjectsReport {
+    fn drop(&mut self) {
+        self.debug.report_live_objects(self.flags);
+    }
+}
+
+pub type Output = ComPtr<dxgi::IDXGIOutput>;
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct EnumModesFlags: u32 {
+        const INTERLACED = dxgi::DXGI_ENUM_MODES_INTERLACED;
+        const SCALING = dxgi::DXGI_ENUM_MODES_SCALING;
+        const STEREO = dxgi1_2::DXGI_ENUM_MODES_STEREO;
+        const DISABLED_STEREO = dxgi1_2::DXGI_ENUM_MODES_DISABLED_STEREO;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OutputDesc {
+    /// Name of the display device, e.g. `\\.\DISPLAY1`.
+    pub device_name: String,
+    pub desktop_coordinates: RECT,
+    pub attached_to_desktop: bool,
+    pub rotation: dxgitype::DXGI_MODE_ROTATION,
+    pub monitor: HMONITOR,
+}
+
+impl Output {
+    pub fn desc(&self) -> D3DResult<OutputDesc> {
+        let mut raw: dxgi::DXGI_OUTPUT_DESC = unsafe { mem::zeroed() };
+        let hr = unsafe { self.GetDesc(&mut raw) };
+
+        let name_len = raw
+            .DeviceName
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(raw.DeviceName.len());
+        let desc = OutputDesc {
+            device_name: String::from_utf16_lossy(&raw.DeviceName[..name_len]),
+            desktop_coordinates: raw.DesktopCoordinates,
+            attached_to_desktop: raw.AttachedToDesktop != 0,
+            rotation: raw.Rotation,
+            monitor: raw.Monitor,
+        };
+
+        (desc, hr)
+    }
+
+    /// See [`Output6::hdr_metadata`], returns `E_NOINTERFACE` on runtimes without `IDXGIOutput6`.
+    pub fn hdr_metadata(&self) -> D3DResult<OutputHdrDesc> {
+        let (output6, hr) = unsafe { self.cast::<dxgi1_6::IDXGIOutput6>() };
+        if winerror::FAILED(hr) {
+            return (OutputHdrDesc::default(), hr);
+        }
+        output6.hdr_metadata()
+    }
+
+    /// Display modes supported for `format`.
+    ///
+    /// Returns `DXGI_ERROR_MORE_DATA` if the mode list changed while querying it,
+    /// e.g. by a display being reconfigured, in which case the query can be retried.
+    pub fn display_modes(
+        &self,
+        format: dxgiformat::DXGI_FORMAT,
+        flags: EnumModesFlags,
+    ) -> D3DResult<Vec<dxgitype::DXGI_MODE_DESC>> {
+        let mut count = 0;
+        let hr =
+            unsafe { self.GetDisplayModeList(format, flags.bits(), &mut count, ptr::null_mut()) };
+        if winerror::FAILED(hr) || count == 0 {
+            return (Vec::new(), hr);
+        }
+
+        let mut modes = Vec::with_capacity(count as usize);
+        let hr = unsafe {
+            self.GetDisplayModeList(format, flags.bits(), &mut count, modes.as_mut_ptr())
+        };
+        if winerror::SUCCEEDED(hr) {
+            unsafe { modes.set_len(count as usize) };
+        }
+
+        (modes, hr)
+    }
+}
+
+pub type Output6 = ComPtr<dxgi1_6::IDXGIOutput6>;
+
+/// Color space and luminance range of an output, all luminances in nits.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OutputHdrDesc {
+    pub color_space: dxgitype::DXGI_COLOR_SPACE_TYPE,
+    pub bits_per_color: u32,
+    pub min_luminance: f32,
+    pub max_luminance: f32,
+    pub max_full_frame_luminance: f32,
+    /// Whether the output expects HDR10, i.e. the ST 2084 curve with BT.2020 primaries.
+    pub is_hdr10: bool,
+}
+
+impl Output6 {
+    pub fn hdr_metadata(&self) -> D3DResult<OutputHdrDesc> {
+        let mut raw: dxgi1_6::DXGI_OUTPUT_DESC1 = unsafe { mem::zeroed() };
+        let hr = unsafe { self.GetDesc1(&mut raw) };
+        let desc = OutputHdrDesc {
+            color_space: raw.ColorSpace,
+            bits_per_color: raw.BitsPerColor,
+            min_luminance: raw.MinLuminance,
+            max_luminance: raw.MaxLuminance,
+            max_full_frame_luminance: raw.MaxFullFrameLuminance,
+            is_hdr10: raw.ColorSpace == dxgitype::DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+        };
+
+        (desc, hr)
+    }
+}
+
 pub type Adapter1 = ComPtr<dxgi::IDXGIAdapter1>;
 pub type Adapter2 = ComPtr<dxgi1_2::IDXGIAdapter2>;
 pub type Adapter3 = ComPtr<dxgi1_4::IDXGIAdapter3>;
@@ -59,6 +216,98 @@ crate::weak_com_inheritance_chain! {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct AdapterDesc {
+    pub name: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub sub_sys_id: u32,
+    pub revision: u32,
+    pub dedicated_video_memory: usize,
+    pub dedicated_system_memory: usize,
+    pub shared_system_memory: usize,
+    pub luid: u64,
+    /// Bits of `DXGI_ADAPTER_FLAG`.
+    pub flags: u32,
+}
+
+impl Adapter1 {
+    /// Outputs, i.e. monitors, connected to the adapter.
+    pub fn enumerate_outputs(&self) -> Vec<Output> {
+        let mut outputs = Vec::new();
+        for index in 0.. {
+            let mut output = Output::null();
+            let hr = unsafe { self.EnumOutputs(index, output.mut_self()) };
+            if winerror::FAILED(hr) {
+                // `DXGI_ERROR_NOT_FOUND` past the last output.
+                break;
+            }
+            outputs.push(output);
+        }
+        outputs
+    }
+
+    pub fn description(&self) -> D3DResult<AdapterDesc> {
+        let mut raw: dxgi::DXGI_ADAPTER_DESC1 = unsafe { mem::zeroed() };
+        let hr = unsafe { self.GetDesc1(&mut raw) };
+
+        let name_len = raw
+            .Description
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(raw.Description.len());
+        let desc = AdapterDesc {
+            name: String::from_utf16_lossy(&raw.Description[..name_len]),
+            vendor_id: raw.VendorId,
+            device_id: raw.DeviceId,
+            sub_sys_id: raw.SubSysId,
+            revision: raw.Revision,
+            dedicated_video_memory: raw.DedicatedVideoMemory,
+            dedicated_system_memory: raw.DedicatedSystemMemory,
+            shared_system_memory: raw.SharedSystemMemory,
+            luid: (raw.AdapterLuid.HighPart as u32 as u64) << 32 | raw.AdapterLuid.LowPart as u64,
+            flags: raw.Flags,
+        };
+
+        (desc, hr)
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemorySegmentGroup {
+    Local = dxgi1_4::DXGI_MEMORY_SEGMENT_GROUP_LOCAL,
+    NonLocal = dxgi1_4::DXGI_MEMORY_SEGMENT_GROUP_NON_LOCAL,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VideoMemoryInfo {
+    /// Amount of memory the application should stay within.
+    pub budget: u64,
+    pub current_usage: u64,
+    pub available_for_reservation: u64,
+    pub current_reservation: u64,
+}
+
+impl Adapter3 {
+    pub fn query_video_memory_info(
+        &self,
+        node: u32,
+        segment: MemorySegmentGroup,
+    ) -> D3DResult<VideoMemoryInfo> {
+        let mut raw: dxgi1_4::DXGI_QUERY_VIDEO_MEMORY_INFO = unsafe { mem::zeroed() };
+        let hr = unsafe { self.QueryVideoMemoryInfo(node, segment as _, &mut raw) };
+        let info = VideoMemoryInfo {
+            budget: raw.Budget,
+            current_usage: raw.CurrentUsage,
+            available_for_reservation: raw.AvailableForReservation,
+            current_reservation: raw.CurrentReservation,
+        };
+
+        (info, hr)
+    }
+}
+
 pub type Factory1 = ComPtr<dxgi::IDXGIFactory1>;
 pub type Factory2 = ComPtr<dxgi1_2::IDXGIFactory2>;
 pub type Factory3 = ComPtr<dxgi1_3::IDXGIFactory3>;
@@ -83,6 +332,7 @@ pub type SwapChain = ComPtr<dxgi::IDXGISwapChain>;
 pub type SwapChain1 = ComPtr<dxgi1_2::IDXGISwapChain1>;
 pub type SwapChain2 = ComPtr<dxgi1_3::IDXGISwapChain2>;
 pub type SwapChain3 = ComPtr<dxgi1_4::IDXGISwapChain3>;
+pub type SwapChain4 = ComPtr<dxgi1_5::IDXGISwapChain4>;
 crate::weak_com_inheritance_chain! {
     #[derive(Debug, Clone, PartialEq, Hash)]
     pub enum DxgiSwapchain {
@@ -173,6 +423,32 @@ impl DxgiLib {
         };
         Ok((queue, hr))
     }
+
+    pub fn get_dxgi_debug(&self) -> Result<D3DResult<DxgiDebug>, libloading::Error> {
+        type Fun = extern "system" fn(
+            winapi::shared::minwindef::UINT,
+            winapi::shared::guiddef::REFIID,
+            *mut *mut winapi::ctypes::c_void,
+        ) -> HRESULT;
+
+        let mut debug = DxgiDebug::null();
+        let hr = unsafe {
+            let func: libloading::Symbol<Fun> = self.lib.get(b"DXGIGetDebugInterface1")?;
+            func(0, &dxgidebug::IDXGIDebug1::uuidof(), debug.mut_void())
+        };
+        Ok((debug, hr))
+    }
+
+    /// Reports all live DXGI and D3D objects to the debug output, typically at shutdown.
+    ///
+    /// Fails if the debug layer isn't installed.
+    pub fn report_live_dxgi_objects(&self) -> Result<HRESULT, libloading::Error> {
+        let (debug, hr) = self.get_dxgi_debug()?;
+        if winapi::shared::winerror::FAILED(hr) {
+            return Ok(hr);
+        }
+        Ok(debug.report_live_objects(RloFlags::DETAIL | RloFlags::IGNORE_INTERNAL))
+    }
 }
 
 // TODO: strong types
@@ -187,6 +463,7 @@ pub struct SwapchainDesc {
     pub scaling: Scaling,
     pub swap_effect: SwapEffect,
     pub alpha_mode: AlphaMode,
+    /// Bits of [`SwapChainFlags`].
     pub flags: u32,
 }
 impl SwapchainDesc {
@@ -210,6 +487,99 @@ impl SwapchainDesc {
     }
 }
 
+/// Flip model swapchain description, see [`Factory4::create_swapchain_for_hwnd`].
+///
+/// Defaults to two buffers, flip-discard, no scaling and no MSAA.
+#[derive(Clone, Copy, Debug)]
+pub struct SwapChainDesc1 {
+    width: u32,
+    height: u32,
+    format: dxgiformat::DXGI_FORMAT,
+    buffer_count: u32,
+    sample_count: u32,
+    swap_effect: SwapEffect,
+    scaling: Scaling,
+    alpha_mode: AlphaMode,
+    flags: SwapChainFlags,
+}
+
+impl SwapChainDesc1 {
+    pub fn new(width: u32, height: u32, format: dxgiformat::DXGI_FORMAT) -> Self {
+        SwapChainDesc1 {
+            width,
+            height,
+            format,
+            buffer_count: 2,
+            sample_count: 1,
+            swap_effect: SwapEffect::FlipDiscard,
+            scaling: Scaling::Identity,
+            alpha_mode: AlphaMode::Unspecified,
+            flags: SwapChainFlags::empty(),
+        }
+    }
+
+    /// At least 2 for the flip model.
+    pub fn buffer_count(mut self, count: u32) -> Self {
+        self.buffer_count = count;
+        self
+    }
+
+    /// The flip model doesn't support MSAA, creation fails with anything above 1.
+    /// Render into a multisampled texture and resolve into the back buffer instead.
+    pub fn sample_count(mut self, count: u32) -> Self {
+        self.sample_count = count;
+        self
+    }
+
+    /// D3D12 only supports [`SwapEffect::FlipDiscard`] and [`SwapEffect::FlipSequential`].
+    pub fn swap_effect(mut self, swap_effect: SwapEffect) -> Self {
+        self.swap_effect = swap_effect;
+        self
+    }
+
+    pub fn scaling(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    pub fn alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    pub fn flags(mut self, flags: SwapChainFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    fn is_valid(&self) -> bool {
+        let flip = matches!(
+            self.swap_effect,
+            SwapEffect::FlipDiscard | SwapEffect::FlipSequential
+        );
+        flip && self.sample_count == 1 && self.buffer_count >= 2
+    }
+
+    fn to_raw(&self) -> dxgi1_2::DXGI_SWAP_CHAIN_DESC1 {
+        dxgi1_2::DXGI_SWAP_CHAIN_DESC1 {
+            Width: self.width,
+            Height: self.height,
+            Format: self.format,
+            Stereo: 0,
+            SampleDesc: dxgitype::DXGI_SAMPLE_DESC {
+                Count: self.sample_count,
+                Quality: 0,
+            },
+            BufferUsage: dxgitype::DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            BufferCount: self.buffer_count,
+            Scaling: self.scaling as _,
+            SwapEffect: self.swap_effect as _,
+            AlphaMode: self.alpha_mode as _,
+            Flags: self.flags.bits(),
+        }
+    }
+}
+
 impl Factory1 {
     pub fn create_swapchain(
         &self,
@@ -292,6 +662,66 @@ impl Factory2 {
 }
 
 impl Factory4 {
+    /// Creates a flip model swapchain presenting to `hwnd`.
+    ///
+    /// Returns `E_INVALIDARG` without calling into DXGI if `desc` isn't valid for the flip model,
+    /// i.e. with MSAA, fewer than 2 buffers or a bitblt swap effect.
+    pub fn create_swapchain_for_hwnd(
+        &self,
+        queue: &CommandQueue,
+        hwnd: HWND,
+        desc: &SwapChainDesc1,
+    ) -> D3DResult<SwapChain1> {
+        let mut swap_chain = SwapChain1::null();
+        if !desc.is_valid() {
+            return (swap_chain, winerror::E_INVALIDARG);
+        }
+
+        let hr = unsafe {
+            self.CreateSwapChainForHwnd(
+                queue.as_mut_ptr() as *mut IUnknown,
+                hwnd,
+                &desc.to_raw(),
+                ptr::null(),
+                ptr::null_mut(),
+                swap_chain.mut_void() as *mut *mut _,
+            )
+        };
+
+        (swap_chain, hr)
+    }
+
+    /// Creates a flip model swapchain for a DirectComposition visual.
+    ///
+    /// Besides the flip model requirements, `desc` needs [`Scaling::Stretch`] and an alpha mode
+    /// other than [`AlphaMode::Unspecified`], `E_INVALIDARG` is returned otherwise.
+    pub fn create_swapchain_for_composition(
+        &self,
+        queue: &CommandQueue,
+        desc: &SwapChainDesc1,
+    ) -> D3DResult<SwapChain1> {
+        let mut swap_chain = SwapChain1::null();
+        let composable = matches!(desc.scaling, Scaling::Stretch)
+            && matches!(
+                desc.alpha_mode,
+                AlphaMode::Premultiplied | AlphaMode::Straight | AlphaMode::Ignore
+            );
+        if !desc.is_valid() || !composable {
+            return (swap_chain, winerror::E_INVALIDARG);
+        }
+
+        let hr = unsafe {
+            self.CreateSwapChainForComposition(
+                queue.as_mut_ptr() as *mut IUnknown,
+                &desc.to_raw(),
+                ptr::null_mut(),
+                swap_chain.mut_void() as *mut *mut _,
+            )
+        };
+
+        (swap_chain, hr)
+    }
+
     #[cfg(feature = "implicit-link")]
     pub fn create(flags: FactoryCreationFlags) -> D3DResult<Self> {
         let mut factory = Factory4::null();
@@ -312,6 +742,18 @@ impl Factory4 {
 
         (adapter, hr)
     }
+
+    /// Software rasterizer adapter, available without a GPU.
+    ///
+    /// On some Windows editions WARP is part of the Graphics Tools optional feature,
+    /// which has to be installed first.
+    pub fn warp_adapter(&self) -> D3DResult<Adapter1> {
+        let mut adapter = Adapter1::null();
+        let hr =
+            unsafe { self.EnumWarpAdapter(&dxgi::IDXGIAdapter1::uuidof(), adapter.mut_void()) };
+
+        (adapter, hr)
+    }
 }
 
 impl FactoryMedia {
@@ -351,8 +793,20 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// Creation flags of a swapchain, see [`SwapchainDesc::flags`].
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct SwapChainFlags: u32 {
+        const ALLOW_MODE_SWITCH = dxgi::DXGI_SWAP_CHAIN_FLAG_ALLOW_MODE_SWITCH;
+        const FRAME_LATENCY_WAITABLE_OBJECT = dxgi::DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT;
+        const ALLOW_TEARING = dxgi::DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING;
+    }
+}
+
 impl SwapChain {
+    /// Back buffer `id` of the swapchain.
     pub fn get_buffer(&self, id: u32) -> D3DResult<Resource> {
+        // The returned buffer is already referenced, ownership moves to the `ComPtr`.
         let mut resource = Resource::null();
         let hr =
             unsafe { self.GetBuffer(id, &d3d12::ID3D12Resource::uuidof(), resource.mut_void()) };
@@ -360,6 +814,46 @@ impl SwapChain {
         (resource, hr)
     }
 
+    /// Fetches every back buffer and creates a default RTV for each, at successive
+    /// descriptors of `heap` from `start` on.
+    pub fn create_rtvs(
+        &self,
+        device: &Device,
+        heap: &DescriptorHeap,
+        start: CpuDescriptor,
+    ) -> D3DResult<Vec<(Resource, CpuDescriptor)>> {
+        let mut desc: dxgi::DXGI_SWAP_CHAIN_DESC = unsafe { mem::zeroed() };
+        let hr = unsafe { self.GetDesc(&mut desc) };
+        if winerror::FAILED(hr) {
+            return (Vec::new(), hr);
+        }
+
+        let increment = device.get_descriptor_increment_size(DescriptorHeapType::Rtv);
+        if cfg!(debug_assertions) {
+            let heap_desc = unsafe { heap.GetDesc() };
+            debug_assert_eq!(heap_desc.Type, d3d12::D3D12_DESCRIPTOR_HEAP_TYPE_RTV);
+            let offset = start.ptr.wrapping_sub(heap.start_cpu_descriptor().ptr);
+            debug_assert!(
+                offset / increment as usize + desc.BufferCount as usize
+                    <= heap_desc.NumDescriptors as usize,
+                "RTVs don't fit in the heap"
+            );
+        }
+
+        let mut buffers = Vec::with_capacity(desc.BufferCount as usize);
+        for index in 0..desc.BufferCount {
+            let (resource, hr) = self.get_buffer(index);
+            if winerror::FAILED(hr) {
+                return (Vec::new(), hr);
+            }
+            let handle = start.advance(index, increment);
+            unsafe { device.CreateRenderTargetView(resource.as_mut_ptr(), ptr::null(), handle) };
+            buffers.push((resource, handle));
+        }
+
+        (buffers, hr)
+    }
+
     //TODO: replace by present_flags
     pub fn present(&self, interval: u32, flags: u32) -> HRESULT {
         unsafe { self.Present(interval, flags) }
@@ -368,10 +862,579 @@ impl SwapChain {
     pub fn present_flags(&self, interval: u32, flags: SwapChainPresentFlags) -> HRESULT {
         unsafe { self.Present(interval, flags.bits()) }
     }
+
+    /// Switches between windowed and exclusive fullscreen mode, on `output` or the one containing the window.
+    ///
+    /// `ResizeBuffers` has to be called after a transition.
+    pub fn set_fullscreen_state(&self, fullscreen: bool, output: Option<&Output>) -> HRESULT {
+        let output = match output {
+            Some(output) => output.as_mut_ptr(),
+            None => ptr::null_mut(),
+        };
+        unsafe { self.SetFullscreenState(fullscreen as _, output) }
+    }
+
+    /// Timing of the last vblank and present, for frame pacing.
+    ///
+    /// `DXGI_ERROR_FRAME_STATISTICS_DISJOINT` is returned along with the statistics when
+    /// the timing sequence broke, e.g. on a mode change, older values shouldn't be
+    /// compared against them then.
+    pub fn frame_statistics(&self) -> D3DResult<dxgi::DXGI_FRAME_STATISTICS> {
+        let mut stats: dxgi::DXGI_FRAME_STATISTICS = unsafe { mem::zeroed() };
+        let hr = unsafe { self.GetFrameStatistics(&mut stats) };
+
+        (stats, hr)
+    }
+
+    /// Number of presents so far.
+    pub fn last_present_count(&self) -> D3DResult<u32> {
+        let mut count = 0;
+        let hr = unsafe { self.GetLastPresentCount(&mut count) };
+
+        (count, hr)
+    }
+
+    /// Whether the swapchain is in exclusive fullscreen mode, and on which output.
+    pub fn get_fullscreen_state(&self) -> D3DResult<(bool, Option<Output>)> {
+        let mut fullscreen = 0;
+        // The returned output is already referenced, ownership moves to the `ComPtr`.
+        let mut output = Output::null();
+        let hr = unsafe { self.GetFullscreenState(&mut fullscreen, output.mut_self()) };
+        let output = if output.is_null() { None } else { Some(output) };
+
+        ((fullscreen != 0, output), hr)
+    }
+}
+
+impl SwapChain2 {
+    /// Event signaled when the swapchain is ready for a new frame, wait on it before rendering.
+    ///
+    /// Only available if the swapchain was created with
+    /// [`SwapChainFlags::FRAME_LATENCY_WAITABLE_OBJECT`], `None` is returned otherwise.
+    /// Every call returns a new handle, closed once the event is dropped.
+    pub fn frame_latency_waitable_object(&self) -> Option<OwnedEvent> {
+        let handle = unsafe { self.GetFrameLatencyWaitableObject() };
+        if handle.is_null() {
+            None
+        } else {
+            Some(unsafe { OwnedEvent::from_raw(handle) })
+        }
+    }
+
+    /// Requires the swapchain to be created with [`SwapChainFlags::FRAME_LATENCY_WAITABLE_OBJECT`].
+    pub fn set_maximum_frame_latency(&self, max_latency: u32) -> HRESULT {
+        unsafe { self.SetMaximumFrameLatency(max_latency) }
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// sRGB.
+    RgbFullG22NoneP709 = dxgitype::DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+    /// Linear scRGB, for FP16 back buffers.
+    RgbFullG10NoneP709 = dxgitype::DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709,
+    /// HDR10.
+    RgbFullG2084NoneP2020 = dxgitype::DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+    RgbFullG22NoneP2020 = dxgitype::DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P2020,
 }
 
 impl SwapChain3 {
     pub fn get_current_back_buffer_index(&self) -> u32 {
         unsafe { self.GetCurrentBackBufferIndex() }
     }
+
+    /// Whether the swap chain can present in `space`, in the current format and on the current output.
+    pub fn supports_color_space(&self, space: ColorSpace) -> D3DResult<bool> {
+        let mut support = 0;
+        let hr = unsafe { self.CheckColorSpaceSupport(space as _, &mut support) };
+        (
+            support & dxgi1_4::DXGI_SWAP_CHAIN_COLOR_SPACE_SUPPORT_FLAG_PRESENT != 0,
+            hr,
+        )
+    }
+
+    pub fn set_color_space(&self, space: ColorSpace) -> HRESULT {
+        unsafe { self.SetColorSpace1(space as _) }
+    }
+}
+
+/// ST 2086 mastering display and content light levels.
+///
+/// Chromaticity coordinates are in units of 0.00002, mastering luminance in nits
+/// for the maximum and in 0.0001 nits for the minimum, and light levels in nits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hdr10Metadata {
+    pub red_primary: [u16; 2],
+    pub green_primary: [u16; 2],
+    pub blue_primary: [u16; 2],
+    pub white_point: [u16; 2],
+    pub max_mastering_luminance: u32,
+    pub min_mastering_luminance: u32,
+    pub max_content_light_level: u16,
+    pub max_frame_average_light_level: u16,
+}
+
+impl SwapChain4 {
+    pub fn set_hdr_metadata(&self, metadata: &Hdr10Metadata) -> HRESULT {
+        let mut raw = dxgi1_5::DXGI_HDR_METADATA_HDR10 {
+            RedPrimary: metadata.red_primary,
+            GreenPrimary: metadata.green_primary,
+            BluePrimary: metadata.blue_primary,
+            WhitePoint: metadata.white_point,
+            MaxMasteringLuminance: metadata.max_mastering_luminance,
+            MinMasteringLuminance: metadata.min_mastering_luminance,
+            MaxContentLightLevel: metadata.max_content_light_level,
+            MaxFrameAverageLightLevel: metadata.max_frame_average_light_level,
+        };
+        unsafe {
+            self.SetHDRMetaData(
+                dxgi1_5::DXGI_HDR_METADATA_TYPE_HDR10,
+                mem::size_of_val(&raw) as _,
+                &mut raw as *mut _ as *mut _,
+            )
+        }
+    }
+
+    /// Tells the display the content is no longer HDR10.
+    pub fn clear_hdr_metadata(&self) -> HRESULT {
+        unsafe { self.SetHDRMetaData(dxgi1_5::DXGI_HDR_METADATA_TYPE_NONE, 0, ptr::null_mut()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Device, direct queue and a windowless swapchain made composable from `desc`,
+    /// `None` without hardware or composition support.
+    #[cfg(feature = "implicit-link")]
+    fn composition_swapchain(desc: SwapChainDesc1) -> Option<(Device, CommandQueue, SwapChain1)> {
+        let device = crate::test_device()?;
+        let (factory, hr) = Factory4::create(FactoryCreationFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        let (queue, hr) = device.create_command_queue(
+            crate::CmdListType::Direct,
+            crate::Priority::Normal,
+            crate::CommandQueueFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+
+        let desc = desc
+            .scaling(Scaling::Stretch)
+            .alpha_mode(AlphaMode::Premultiplied);
+        let (swapchain, hr) = factory.create_swapchain_for_composition(&queue, &desc);
+        if winerror::FAILED(hr) {
+            return None;
+        }
+        Some((device, queue, swapchain))
+    }
+
+    /// Window which is never shown, destroyed on drop.
+    #[cfg(feature = "implicit-link")]
+    struct HiddenWindow(HWND);
+
+    #[cfg(feature = "implicit-link")]
+    impl HiddenWindow {
+        fn new() -> Self {
+            use winapi::um::winuser;
+            // The predefined class needs no registration.
+            let class = "STATIC\0".encode_utf16().collect::<Vec<_>>();
+            let hwnd = unsafe {
+                winuser::CreateWindowExW(
+                    0,
+                    class.as_ptr(),
+                    ptr::null(),
+                    winuser::WS_OVERLAPPEDWINDOW,
+                    0,
+                    0,
+                    64,
+                    64,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                )
+            };
+            assert!(!hwnd.is_null());
+            HiddenWindow(hwnd)
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    impl Drop for HiddenWindow {
+        fn drop(&mut self) {
+            unsafe { winapi::um::winuser::DestroyWindow(self.0) };
+        }
+    }
+
+    /// Device, direct queue and a swapchain of `window` made from `desc`, `None` without hardware.
+    #[cfg(feature = "implicit-link")]
+    fn window_swapchain(
+        window: &HiddenWindow,
+        desc: SwapChainDesc1,
+    ) -> Option<(Device, CommandQueue, SwapChain1)> {
+        let device = crate::test_device()?;
+        let (factory, hr) = Factory4::create(FactoryCreationFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        let (queue, hr) = device.create_command_queue(
+            crate::CmdListType::Direct,
+            crate::Priority::Normal,
+            crate::CommandQueueFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (swapchain, hr) = factory.create_swapchain_for_hwnd(&queue, window.0, &desc);
+        assert!(winerror::SUCCEEDED(hr));
+        Some((device, queue, swapchain))
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn present_count() {
+        let desc = SwapChainDesc1::new(64, 64, dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM);
+        let (_device, _queue, swapchain1) = match composition_swapchain(desc) {
+            Some(swapchain) => swapchain,
+            None => return,
+        };
+        let (swapchain, hr) = unsafe { swapchain1.cast::<dxgi::IDXGISwapChain>() };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (mut last, hr) = swapchain.last_present_count();
+        assert!(winerror::SUCCEEDED(hr));
+        for _ in 0..3 {
+            assert!(winerror::SUCCEEDED(swapchain.present(0, 0)));
+            let (count, hr) = swapchain.last_present_count();
+            assert!(winerror::SUCCEEDED(hr));
+            assert!(count > last, "present count {} after {}", count, last);
+            last = count;
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn frame_latency_waitable_object() {
+        let desc = SwapChainDesc1::new(64, 64, dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM)
+            .flags(SwapChainFlags::FRAME_LATENCY_WAITABLE_OBJECT);
+        let (_device, _queue, swapchain1) = match composition_swapchain(desc) {
+            Some(swapchain) => swapchain,
+            None => return,
+        };
+        let (swapchain, hr) = unsafe { swapchain1.cast::<dxgi1_3::IDXGISwapChain2>() };
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(winerror::SUCCEEDED(swapchain.set_maximum_frame_latency(1)));
+
+        // Nothing was presented yet, the swapchain is ready for a frame.
+        let event = swapchain.frame_latency_waitable_object().unwrap();
+        assert_eq!(event.wait(1000), crate::WaitStatus::Signaled);
+    }
+
+    /// Filters of the mocked `ReportLiveObjects` calls, in order.
+    type MockDebug = std::sync::Mutex<Vec<(bool, dxgidebug::DXGI_DEBUG_RLO_FLAGS)>>;
+
+    unsafe extern "system" fn report_live_objects(
+        this: *mut dxgidebug::IDXGIDebug,
+        apiid: winapi::shared::guiddef::GUID,
+        flags: dxgidebug::DXGI_DEBUG_RLO_FLAGS,
+    ) -> HRESULT {
+        let all = winapi::shared::guiddef::IsEqualGUID(&apiid, &dxgidebug::DXGI_DEBUG_ALL);
+        crate::mock::Mock::<MockDebug>::state(this)
+            .lock()
+            .unwrap()
+            .push((all, flags));
+        winerror::S_OK
+    }
+
+    #[test]
+    fn live_objects_report_on_drop() {
+        use crate::mock::{slot, Mock};
+
+        let debug = Mock::new::<dxgidebug::IDXGIDebug1, dxgidebug::IDXGIDebug1Vtbl>(
+            MockDebug::default(),
+            &[(
+                slot!(dxgidebug::IDXGIDebug1Vtbl, parent.ReportLiveObjects),
+                report_live_objects as usize,
+            )],
+        );
+        let calls = || unsafe {
+            Mock::<MockDebug>::state(debug.as_ptr())
+                .lock()
+                .unwrap()
+                .clone()
+        };
+
+        let report = LiveObjectsReport {
+            debug: debug.clone(),
+            flags: RloFlags::SUMMARY | RloFlags::IGNORE_INTERNAL,
+        };
+        assert!(calls().is_empty());
+        drop(report);
+        assert_eq!(
+            calls(),
+            [(
+                true,
+                dxgidebug::DXGI_DEBUG_RLO_SUMMARY | dxgidebug::DXGI_DEBUG_RLO_IGNORE_INTERNAL
+            )]
+        );
+        // The guard released its reference.
+        assert_eq!(unsafe { Mock::<MockDebug>::refs(debug.as_ptr()) }, 1);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn report_live_objects_after_device_drop() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        // Only there if another test enabled the debug layer first.
+        if let (Some(debug_device), _) = device.debug_device() {
+            let hr = debug_device.report_live_device_objects(crate::RldoFlags::SUMMARY);
+            assert!(winerror::SUCCEEDED(hr));
+        }
+        drop(device);
+
+        let (debug, hr) = DxgiDebug::get_interface();
+        if winerror::FAILED(hr) {
+            return;
+        }
+        let hr = debug.report_live_objects(RloFlags::SUMMARY | RloFlags::IGNORE_INTERNAL);
+        assert!(winerror::SUCCEEDED(hr));
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn create_rtvs_of_three_buffers() {
+        let desc = SwapChainDesc1::new(64, 64, dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM)
+            .buffer_count(3)
+            .swap_effect(SwapEffect::FlipDiscard);
+        let (device, _queue, swapchain1) = match composition_swapchain(desc) {
+            Some(swapchain) => swapchain,
+            None => return,
+        };
+        let (swapchain, hr) = unsafe { swapchain1.cast::<dxgi::IDXGISwapChain>() };
+        assert!(winerror::SUCCEEDED(hr));
+        let (heap, hr) = device.create_descriptor_heap(
+            3,
+            DescriptorHeapType::Rtv,
+            crate::DescriptorHeapFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (buffers, hr) = swapchain.create_rtvs(&device, &heap, heap.start_cpu_descriptor());
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(buffers.len(), 3);
+        let increment = device.get_descriptor_increment_size(DescriptorHeapType::Rtv) as usize;
+        let start = heap.start_cpu_descriptor().ptr;
+        for (index, (buffer, handle)) in buffers.iter().enumerate() {
+            assert!(!buffer.is_null());
+            assert_eq!(handle.ptr, start + index * increment);
+        }
+        assert!(buffers[0].0 != buffers[1].0 && buffers[1].0 != buffers[2].0);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn windowed_fullscreen_state() {
+        let window = HiddenWindow::new();
+        let desc = SwapChainDesc1::new(64, 64, dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM);
+        let (_device, _queue, swapchain1) = match window_swapchain(&window, desc) {
+            Some(swapchain) => swapchain,
+            None => return,
+        };
+        let (swapchain, hr) = unsafe { swapchain1.cast::<dxgi::IDXGISwapChain>() };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let ((fullscreen, output), hr) = swapchain.get_fullscreen_state();
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(!fullscreen);
+        assert!(output.is_none());
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn first_adapter_description_and_budget() {
+        let (factory, hr) = Factory4::create(FactoryCreationFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        let (adapter, hr) = factory.enumerate_adapters(0);
+        if hr == winerror::DXGI_ERROR_NOT_FOUND {
+            return;
+        }
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (desc, hr) = adapter.description();
+        assert!(winerror::SUCCEEDED(hr));
+        println!("{:?}", desc);
+        assert!(!desc.name.is_empty());
+
+        let (adapter3, hr) = unsafe { adapter.cast::<dxgi1_4::IDXGIAdapter3>() };
+        assert!(winerror::SUCCEEDED(hr));
+        let (local, hr) = adapter3.query_video_memory_info(0, MemorySegmentGroup::Local);
+        assert!(winerror::SUCCEEDED(hr));
+        let (non_local, hr) = adapter3.query_video_memory_info(0, MemorySegmentGroup::NonLocal);
+        assert!(winerror::SUCCEEDED(hr));
+        println!("local {:?}, non-local {:?}", local, non_local);
+        // Integrated and software adapters may only have system memory.
+        assert_ne!(local.budget + non_local.budget, 0);
+    }
+
+    /// Outputs of the first adapter, usually empty without a monitor.
+    #[cfg(feature = "implicit-link")]
+    fn primary_outputs() -> Vec<Output> {
+        let (factory, hr) = Factory4::create(FactoryCreationFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        let (adapter, hr) = factory.enumerate_adapters(0);
+        if winerror::FAILED(hr) {
+            return Vec::new();
+        }
+        adapter.enumerate_outputs()
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn outputs_and_modes() {
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        for output in primary_outputs() {
+            let (desc, hr) = output.desc();
+            assert!(winerror::SUCCEEDED(hr));
+            assert!(!desc.device_name.is_empty());
+
+            let (modes, hr) = output.display_modes(format, EnumModesFlags::empty());
+            // E.g. in a remote session.
+            if hr == winerror::DXGI_ERROR_NOT_CURRENTLY_AVAILABLE {
+                continue;
+            }
+            assert!(winerror::SUCCEEDED(hr));
+            for mode in &modes {
+                assert_eq!(mode.Format, format);
+                assert!(mode.Width > 0 && mode.Height > 0);
+            }
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn output_luminance() {
+        for output in primary_outputs() {
+            let (hdr, hr) = output.hdr_metadata();
+            if hr == winerror::E_NOINTERFACE {
+                continue;
+            }
+            assert!(winerror::SUCCEEDED(hr));
+            assert!(hdr.min_luminance >= 0.0);
+            assert!(hdr.min_luminance <= hdr.max_luminance, "{:?}", hdr);
+            assert_eq!(
+                hdr.is_hdr10,
+                hdr.color_space == dxgitype::DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020
+            );
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn srgb_color_space_and_hdr10_support() {
+        let window = HiddenWindow::new();
+        let desc = SwapChainDesc1::new(64, 64, dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM);
+        let (_device, _queue, swapchain1) = match window_swapchain(&window, desc) {
+            Some(swapchain) => swapchain,
+            None => return,
+        };
+        let (swapchain, hr) = unsafe { swapchain1.cast::<dxgi1_4::IDXGISwapChain3>() };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (supported, hr) = swapchain.supports_color_space(ColorSpace::RgbFullG22NoneP709);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(supported);
+        assert!(winerror::SUCCEEDED(
+            swapchain.set_color_space(ColorSpace::RgbFullG22NoneP709)
+        ));
+
+        // Depends on the display, only the query itself has to work.
+        let (hdr10, hr) = swapchain.supports_color_space(ColorSpace::RgbFullG2084NoneP2020);
+        assert!(winerror::SUCCEEDED(hr));
+        println!("HDR10 supported: {}", hdr10);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn two_back_buffers() {
+        let window = HiddenWindow::new();
+        let desc =
+            SwapChainDesc1::new(64, 32, dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM).buffer_count(2);
+        let (_device, _queue, swapchain1) = match window_swapchain(&window, desc) {
+            Some(swapchain) => swapchain,
+            None => return,
+        };
+        let (swapchain3, hr) = unsafe { swapchain1.cast::<dxgi1_4::IDXGISwapChain3>() };
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(swapchain3.get_current_back_buffer_index(), 0);
+        let (swapchain, hr) = unsafe { swapchain1.cast::<dxgi::IDXGISwapChain>() };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let buffers = (0..2)
+            .map(|index| {
+                let (buffer, hr) = swapchain.get_buffer(index);
+                assert!(winerror::SUCCEEDED(hr));
+                buffer
+            })
+            .collect::<Vec<_>>();
+        assert!(buffers[0] != buffers[1]);
+        for buffer in &buffers {
+            let desc = buffer.desc();
+            assert_eq!((desc.Width, desc.Height), (64, 32));
+            assert_eq!(desc.Format, dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM);
+        }
+        // Fetching a buffer again gives the same resource.
+        assert!(swapchain.get_buffer(1).0 == buffers[1]);
+        assert!(winerror::FAILED(swapchain.get_buffer(2).1));
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn flip_model_window_swapchain() {
+        let window = HiddenWindow::new();
+        let desc = SwapChainDesc1::new(64, 64, dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM)
+            .buffer_count(3)
+            .swap_effect(SwapEffect::FlipSequential);
+        let (_device, queue, swapchain) = match window_swapchain(&window, desc) {
+            Some(swapchain) => swapchain,
+            None => return,
+        };
+        let mut raw: dxgi1_2::DXGI_SWAP_CHAIN_DESC1 = unsafe { mem::zeroed() };
+        assert!(winerror::SUCCEEDED(unsafe { swapchain.GetDesc1(&mut raw) }));
+        assert_eq!(raw.SwapEffect, dxgi::DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL);
+        assert_eq!(raw.Scaling, dxgi1_2::DXGI_SCALING_NONE);
+        assert_eq!((raw.BufferCount, raw.SampleDesc.Count), (3, 1));
+
+        // Rejected before reaching DXGI, the flip model has no MSAA back buffers.
+        let (factory, hr) = Factory4::create(FactoryCreationFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        let msaa =
+            SwapChainDesc1::new(64, 64, dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM).sample_count(4);
+        let (swapchain, hr) = factory.create_swapchain_for_hwnd(&queue, window.0, &msaa);
+        assert_eq!(hr, winerror::E_INVALIDARG);
+        assert!(swapchain.is_null());
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn device_on_warp() {
+        let (factory, hr) = Factory4::create(FactoryCreationFlags::empty());
+        assert!(winerror::SUCCEEDED(hr));
+        let (warp, hr) = factory.warp_adapter();
+        // The Graphics Tools aren't installed.
+        if winerror::FAILED(hr) {
+            return;
+        }
+        let (desc, hr) = warp.description();
+        assert!(winerror::SUCCEEDED(hr));
+        assert_ne!(desc.flags & dxgi::DXGI_ADAPTER_FLAG_SOFTWARE, 0);
+
+        let (device, hr) = Device::create(warp, crate::FeatureLevel::L11_0);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(!device.is_null());
+    }
 }
@@ -0,0 +1,351 @@
+//! DXGI format properties
+
+use crate::Format;
+use winapi::shared::dxgiformat;
+
+/// Properties of a [`Format`], which is a plain `DXGI_FORMAT`.
+///
+/// Only the color, depth and block-compressed formats usable for textures are covered,
+/// video and palettized formats are treated as unknown.
+pub trait FormatExt: Sized {
+    /// Size of a texel, or of a 4x4 block for block-compressed formats.
+    fn bytes_per_pixel(self) -> Option<u32>;
+    fn is_block_compressed(self) -> bool;
+    fn is_depth_stencil(self) -> bool;
+    fn is_srgb(self) -> bool;
+    /// Typeless format of the same family, or the format itself if there is none.
+    fn to_typeless(self) -> Self;
+    /// sRGB variant, or the format itself if there is none.
+    fn to_srgb(self) -> Self;
+    /// Linear variant of an sRGB format, or the format itself.
+    fn to_linear(self) -> Self;
+    /// Number of planes, 2 for formats with both depth and stencil.
+    fn aspect_count(self) -> u32;
+}
+
+impl FormatExt for Format {
+    fn bytes_per_pixel(self) -> Option<u32> {
+        Some(match self {
+            dxgiformat::DXGI_FORMAT_R32G32B32A32_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R32G32B32A32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32G32B32A32_UINT
+            | dxgiformat::DXGI_FORMAT_R32G32B32A32_SINT
+            | dxgiformat::DXGI_FORMAT_BC2_TYPELESS
+            | dxgiformat::DXGI_FORMAT_BC2_UNORM
+            | dxgiformat::DXGI_FORMAT_BC2_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_BC3_TYPELESS
+            | dxgiformat::DXGI_FORMAT_BC3_UNORM
+            | dxgiformat::DXGI_FORMAT_BC3_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_BC5_TYPELESS
+            | dxgiformat::DXGI_FORMAT_BC5_UNORM
+            | dxgiformat::DXGI_FORMAT_BC5_SNORM
+            | dxgiformat::DXGI_FORMAT_BC6H_TYPELESS
+            | dxgiformat::DXGI_FORMAT_BC6H_UF16
+            | dxgiformat::DXGI_FORMAT_BC6H_SF16
+            | dxgiformat::DXGI_FORMAT_BC7_TYPELESS
+            | dxgiformat::DXGI_FORMAT_BC7_UNORM
+            | dxgiformat::DXGI_FORMAT_BC7_UNORM_SRGB => 16,
+            dxgiformat::DXGI_FORMAT_R32G32B32_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R32G32B32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32G32B32_UINT
+            | dxgiformat::DXGI_FORMAT_R32G32B32_SINT => 12,
+            dxgiformat::DXGI_FORMAT_R16G16B16A16_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_FLOAT
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_UINT
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_SNORM
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_SINT
+            | dxgiformat::DXGI_FORMAT_R32G32_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R32G32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32G32_UINT
+            | dxgiformat::DXGI_FORMAT_R32G32_SINT
+            | dxgiformat::DXGI_FORMAT_R32G8X24_TYPELESS
+            | dxgiformat::DXGI_FORMAT_D32_FLOAT_S8X24_UINT
+            | dxgiformat::DXGI_FORMAT_R32_FLOAT_X8X24_TYPELESS
+            | dxgiformat::DXGI_FORMAT_X32_TYPELESS_G8X24_UINT
+            | dxgiformat::DXGI_FORMAT_BC1_TYPELESS
+            | dxgiformat::DXGI_FORMAT_BC1_UNORM
+            | dxgiformat::DXGI_FORMAT_BC1_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_BC4_TYPELESS
+            | dxgiformat::DXGI_FORMAT_BC4_UNORM
+            | dxgiformat::DXGI_FORMAT_BC4_SNORM => 8,
+            dxgiformat::DXGI_FORMAT_R10G10B10A2_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R10G10B10A2_UNORM
+            | dxgiformat::DXGI_FORMAT_R10G10B10A2_UINT
+            | dxgiformat::DXGI_FORMAT_R11G11B10_FLOAT
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_UINT
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_SNORM
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_SINT
+            | dxgiformat::DXGI_FORMAT_R16G16_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R16G16_FLOAT
+            | dxgiformat::DXGI_FORMAT_R16G16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16G16_UINT
+            | dxgiformat::DXGI_FORMAT_R16G16_SNORM
+            | dxgiformat::DXGI_FORMAT_R16G16_SINT
+            | dxgiformat::DXGI_FORMAT_R32_TYPELESS
+            | dxgiformat::DXGI_FORMAT_D32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32_UINT
+            | dxgiformat::DXGI_FORMAT_R32_SINT
+            | dxgiformat::DXGI_FORMAT_R24G8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_D24_UNORM_S8_UINT
+            | dxgiformat::DXGI_FORMAT_R24_UNORM_X8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_X24_TYPELESS_G8_UINT
+            | dxgiformat::DXGI_FORMAT_R9G9B9E5_SHAREDEXP
+            | dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM
+            | dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM
+            | dxgiformat::DXGI_FORMAT_B8G8R8A8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_B8G8R8X8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB => 4,
+            dxgiformat::DXGI_FORMAT_R8G8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R8G8_UNORM
+            | dxgiformat::DXGI_FORMAT_R8G8_UINT
+            | dxgiformat::DXGI_FORMAT_R8G8_SNORM
+            | dxgiformat::DXGI_FORMAT_R8G8_SINT
+            | dxgiformat::DXGI_FORMAT_R16_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R16_FLOAT
+            | dxgiformat::DXGI_FORMAT_D16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16_UINT
+            | dxgiformat::DXGI_FORMAT_R16_SNORM
+            | dxgiformat::DXGI_FORMAT_R16_SINT
+            | dxgiformat::DXGI_FORMAT_B5G6R5_UNORM
+            | dxgiformat::DXGI_FORMAT_B5G5R5A1_UNORM
+            | dxgiformat::DXGI_FORMAT_B4G4R4A4_UNORM => 2,
+            dxgiformat::DXGI_FORMAT_R8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_R8_UNORM
+            | dxgiformat::DXGI_FORMAT_R8_UINT
+            | dxgiformat::DXGI_FORMAT_R8_SNORM
+            | dxgiformat::DXGI_FORMAT_R8_SINT
+            | dxgiformat::DXGI_FORMAT_A8_UNORM => 1,
+            _ => return None,
+        })
+    }
+
+    fn is_block_compressed(self) -> bool {
+        (dxgiformat::DXGI_FORMAT_BC1_TYPELESS..=dxgiformat::DXGI_FORMAT_BC5_SNORM).contains(&self)
+            || (dxgiformat::DXGI_FORMAT_BC6H_TYPELESS..=dxgiformat::DXGI_FORMAT_BC7_UNORM_SRGB)
+                .contains(&self)
+    }
+
+    fn is_depth_stencil(self) -> bool {
+        matches!(
+            self,
+            dxgiformat::DXGI_FORMAT_D32_FLOAT_S8X24_UINT
+                | dxgiformat::DXGI_FORMAT_D32_FLOAT
+                | dxgiformat::DXGI_FORMAT_D24_UNORM_S8_UINT
+                | dxgiformat::DXGI_FORMAT_D16_UNORM
+        )
+    }
+
+    fn is_srgb(self) -> bool {
+        matches!(
+            self,
+            dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+                | dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+                | dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB
+                | dxgiformat::DXGI_FORMAT_BC1_UNORM_SRGB
+                | dxgiformat::DXGI_FORMAT_BC2_UNORM_SRGB
+                | dxgiformat::DXGI_FORMAT_BC3_UNORM_SRGB
+                | dxgiformat::DXGI_FORMAT_BC7_UNORM_SRGB
+        )
+    }
+
+    fn to_typeless(self) -> Self {
+        match self {
+            dxgiformat::DXGI_FORMAT_R32G32B32A32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32G32B32A32_UINT
+            | dxgiformat::DXGI_FORMAT_R32G32B32A32_SINT => {
+                dxgiformat::DXGI_FORMAT_R32G32B32A32_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_R32G32B32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32G32B32_UINT
+            | dxgiformat::DXGI_FORMAT_R32G32B32_SINT => dxgiformat::DXGI_FORMAT_R32G32B32_TYPELESS,
+            dxgiformat::DXGI_FORMAT_R16G16B16A16_FLOAT
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_UINT
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_SNORM
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_SINT => {
+                dxgiformat::DXGI_FORMAT_R16G16B16A16_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_R32G32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32G32_UINT
+            | dxgiformat::DXGI_FORMAT_R32G32_SINT => dxgiformat::DXGI_FORMAT_R32G32_TYPELESS,
+            dxgiformat::DXGI_FORMAT_D32_FLOAT_S8X24_UINT
+            | dxgiformat::DXGI_FORMAT_R32_FLOAT_X8X24_TYPELESS
+            | dxgiformat::DXGI_FORMAT_X32_TYPELESS_G8X24_UINT => {
+                dxgiformat::DXGI_FORMAT_R32G8X24_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_R10G10B10A2_UNORM
+            | dxgiformat::DXGI_FORMAT_R10G10B10A2_UINT => {
+                dxgiformat::DXGI_FORMAT_R10G10B10A2_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_UINT
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_SNORM
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_SINT => dxgiformat::DXGI_FORMAT_R8G8B8A8_TYPELESS,
+            dxgiformat::DXGI_FORMAT_R16G16_FLOAT
+            | dxgiformat::DXGI_FORMAT_R16G16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16G16_UINT
+            | dxgiformat::DXGI_FORMAT_R16G16_SNORM
+            | dxgiformat::DXGI_FORMAT_R16G16_SINT => dxgiformat::DXGI_FORMAT_R16G16_TYPELESS,
+            dxgiformat::DXGI_FORMAT_D32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32_FLOAT
+            | dxgiformat::DXGI_FORMAT_R32_UINT
+            | dxgiformat::DXGI_FORMAT_R32_SINT => dxgiformat::DXGI_FORMAT_R32_TYPELESS,
+            dxgiformat::DXGI_FORMAT_D24_UNORM_S8_UINT
+            | dxgiformat::DXGI_FORMAT_R24_UNORM_X8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_X24_TYPELESS_G8_UINT => {
+                dxgiformat::DXGI_FORMAT_R24G8_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_R8G8_UNORM
+            | dxgiformat::DXGI_FORMAT_R8G8_UINT
+            | dxgiformat::DXGI_FORMAT_R8G8_SNORM
+            | dxgiformat::DXGI_FORMAT_R8G8_SINT => dxgiformat::DXGI_FORMAT_R8G8_TYPELESS,
+            dxgiformat::DXGI_FORMAT_R16_FLOAT
+            | dxgiformat::DXGI_FORMAT_D16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16_UNORM
+            | dxgiformat::DXGI_FORMAT_R16_UINT
+            | dxgiformat::DXGI_FORMAT_R16_SNORM
+            | dxgiformat::DXGI_FORMAT_R16_SINT => dxgiformat::DXGI_FORMAT_R16_TYPELESS,
+            dxgiformat::DXGI_FORMAT_R8_UNORM
+            | dxgiformat::DXGI_FORMAT_R8_UINT
+            | dxgiformat::DXGI_FORMAT_R8_SNORM
+            | dxgiformat::DXGI_FORMAT_R8_SINT => dxgiformat::DXGI_FORMAT_R8_TYPELESS,
+            dxgiformat::DXGI_FORMAT_BC1_UNORM | dxgiformat::DXGI_FORMAT_BC1_UNORM_SRGB => {
+                dxgiformat::DXGI_FORMAT_BC1_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_BC2_UNORM | dxgiformat::DXGI_FORMAT_BC2_UNORM_SRGB => {
+                dxgiformat::DXGI_FORMAT_BC2_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_BC3_UNORM | dxgiformat::DXGI_FORMAT_BC3_UNORM_SRGB => {
+                dxgiformat::DXGI_FORMAT_BC3_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_BC4_UNORM | dxgiformat::DXGI_FORMAT_BC4_SNORM => {
+                dxgiformat::DXGI_FORMAT_BC4_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_BC5_UNORM | dxgiformat::DXGI_FORMAT_BC5_SNORM => {
+                dxgiformat::DXGI_FORMAT_BC5_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_BC6H_UF16 | dxgiformat::DXGI_FORMAT_BC6H_SF16 => {
+                dxgiformat::DXGI_FORMAT_BC6H_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_BC7_UNORM | dxgiformat::DXGI_FORMAT_BC7_UNORM_SRGB => {
+                dxgiformat::DXGI_FORMAT_BC7_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM
+            | dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => {
+                dxgiformat::DXGI_FORMAT_B8G8R8A8_TYPELESS
+            }
+            dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM
+            | dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB => {
+                dxgiformat::DXGI_FORMAT_B8G8R8X8_TYPELESS
+            }
+            other => other,
+        }
+    }
+
+    fn to_srgb(self) -> Self {
+        match self {
+            dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM => dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+            dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM => dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB,
+            dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM => dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB,
+            dxgiformat::DXGI_FORMAT_BC1_UNORM => dxgiformat::DXGI_FORMAT_BC1_UNORM_SRGB,
+            dxgiformat::DXGI_FORMAT_BC2_UNORM => dxgiformat::DXGI_FORMAT_BC2_UNORM_SRGB,
+            dxgiformat::DXGI_FORMAT_BC3_UNORM => dxgiformat::DXGI_FORMAT_BC3_UNORM_SRGB,
+            dxgiformat::DXGI_FORMAT_BC7_UNORM => dxgiformat::DXGI_FORMAT_BC7_UNORM_SRGB,
+            other => other,
+        }
+    }
+
+    fn to_linear(self) -> Self {
+        match self {
+            dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB => dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM,
+            dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM,
+            dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB => dxgiformat::DXGI_FORMAT_B8G8R8X8_UNORM,
+            dxgiformat::DXGI_FORMAT_BC1_UNORM_SRGB => dxgiformat::DXGI_FORMAT_BC1_UNORM,
+            dxgiformat::DXGI_FORMAT_BC2_UNORM_SRGB => dxgiformat::DXGI_FORMAT_BC2_UNORM,
+            dxgiformat::DXGI_FORMAT_BC3_UNORM_SRGB => dxgiformat::DXGI_FORMAT_BC3_UNORM,
+            dxgiformat::DXGI_FORMAT_BC7_UNORM_SRGB => dxgiformat::DXGI_FORMAT_BC7_UNORM,
+            other => other,
+        }
+    }
+
+    fn aspect_count(self) -> u32 {
+        match self {
+            dxgiformat::DXGI_FORMAT_R32G8X24_TYPELESS
+            | dxgiformat::DXGI_FORMAT_D32_FLOAT_S8X24_UINT
+            | dxgiformat::DXGI_FORMAT_R24G8_TYPELESS
+            | dxgiformat::DXGI_FORMAT_D24_UNORM_S8_UINT => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_properties() {
+        // (format, bytes per texel or block, block-compressed, depth-stencil, typeless)
+        for &(format, bytes, compressed, depth, typeless) in &[
+            (
+                dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM,
+                4,
+                false,
+                false,
+                dxgiformat::DXGI_FORMAT_R8G8B8A8_TYPELESS,
+            ),
+            (
+                dxgiformat::DXGI_FORMAT_R16G16_FLOAT,
+                4,
+                false,
+                false,
+                dxgiformat::DXGI_FORMAT_R16G16_TYPELESS,
+            ),
+            (
+                dxgiformat::DXGI_FORMAT_D32_FLOAT,
+                4,
+                false,
+                true,
+                dxgiformat::DXGI_FORMAT_R32_TYPELESS,
+            ),
+            (
+                dxgiformat::DXGI_FORMAT_BC7_UNORM,
+                16,
+                true,
+                false,
+                dxgiformat::DXGI_FORMAT_BC7_TYPELESS,
+            ),
+        ] {
+            assert_eq!(format.bytes_per_pixel(), Some(bytes), "{}", format);
+            assert_eq!(format.is_block_compressed(), compressed, "{}", format);
+            assert_eq!(format.is_depth_stencil(), depth, "{}", format);
+            assert_eq!(format.to_typeless(), typeless, "{}", format);
+            assert_eq!(format.aspect_count(), 1, "{}", format);
+        }
+
+        assert_eq!(dxgiformat::DXGI_FORMAT_D24_UNORM_S8_UINT.aspect_count(), 2);
+        assert_eq!(dxgiformat::DXGI_FORMAT_UNKNOWN.bytes_per_pixel(), None);
+    }
+
+    #[test]
+    fn srgb_variants() {
+        let linear = dxgiformat::DXGI_FORMAT_BC7_UNORM;
+        let srgb = dxgiformat::DXGI_FORMAT_BC7_UNORM_SRGB;
+        assert!(!linear.is_srgb());
+        assert!(srgb.is_srgb());
+        assert_eq!(linear.to_srgb(), srgb);
+        assert_eq!(srgb.to_linear(), linear);
+        assert_eq!(
+            dxgiformat::DXGI_FORMAT_R16G16_FLOAT.to_srgb(),
+            dxgiformat::DXGI_FORMAT_R16G16_FLOAT
+        );
+    }
+}
@@ -1,8 +1,59 @@
 use crate::com::ComPtr;
+use std::ops::Range;
 use winapi::um::d3d12;
 
 pub type Heap = ComPtr<d3d12::ID3D12Heap>;
 
+/// Heap recording the ranges of its placed resources in debug builds,
+/// to catch placements that overlap by accident.
+///
+/// See [`Device::create_tracked_placed_resource`](crate::Device::create_tracked_placed_resource).
+#[derive(Debug)]
+pub struct TrackedHeap {
+    heap: Heap,
+    placements: Vec<Range<u64>>,
+}
+
+impl TrackedHeap {
+    pub fn new(heap: Heap) -> Self {
+        TrackedHeap {
+            heap,
+            placements: Vec::new(),
+        }
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    /// Records a placement, asserting it doesn't overlap another one unless `aliasing` is set.
+    pub fn place(&mut self, range: Range<u64>, aliasing: bool) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        if !aliasing {
+            if let Some(other) = self
+                .placements
+                .iter()
+                .find(|other| other.start < range.end && range.start < other.end)
+            {
+                panic!(
+                    "Placement {:?} overlaps placement {:?} without aliasing",
+                    range, other
+                );
+            }
+        }
+        self.placements.push(range);
+    }
+
+    /// Forgets the placement starting at `offset`, once its resource is released.
+    pub fn release(&mut self, offset: u64) {
+        if let Some(index) = self.placements.iter().position(|p| p.start == offset) {
+            self.placements.swap_remove(index);
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Clone, Copy)]
 pub enum HeapType {
@@ -24,7 +75,7 @@ pub enum CpuPageProperty {
 #[repr(u32)]
 #[derive(Clone, Copy)]
 pub enum MemoryPool {
-    Unknown = d3d12::D3D12_CPU_PAGE_PROPERTY_UNKNOWN,
+    Unknown = d3d12::D3D12_MEMORY_POOL_UNKNOWN,
     L0 = d3d12::D3D12_MEMORY_POOL_L0,
     L1 = d3d12::D3D12_MEMORY_POOL_L1,
 }
@@ -37,7 +88,7 @@ bitflags::bitflags! {
         const DENY_BUFFERS = d3d12::D3D12_HEAP_FLAG_DENY_BUFFERS;
         const ALLOW_DISPLAY = d3d12::D3D12_HEAP_FLAG_ALLOW_DISPLAY;
         const SHARED_CROSS_ADAPTER = d3d12::D3D12_HEAP_FLAG_SHARED_CROSS_ADAPTER;
-        const DENT_RT_DS_TEXTURES = d3d12::D3D12_HEAP_FLAG_DENY_RT_DS_TEXTURES;
+        const DENY_RT_DS_TEXTURES = d3d12::D3D12_HEAP_FLAG_DENY_RT_DS_TEXTURES;
         const DENY_NON_RT_DS_TEXTURES = d3d12::D3D12_HEAP_FLAG_DENY_NON_RT_DS_TEXTURES;
         const HARDWARE_PROTECTED = d3d12::D3D12_HEAP_FLAG_HARDWARE_PROTECTED;
         const ALLOW_WRITE_WATCH = d3d12::D3D12_HEAP_FLAG_ALLOW_WRITE_WATCH;
@@ -48,6 +99,24 @@ bitflags::bitflags! {
     }
 }
 
+impl HeapFlags {
+    #[deprecated(note = "misspelled, use `DENY_RT_DS_TEXTURES`")]
+    pub const DENT_RT_DS_TEXTURES: Self = Self::DENY_RT_DS_TEXTURES;
+}
+
+impl From<d3d12::D3D12_HEAP_FLAGS> for HeapFlags {
+    fn from(raw: d3d12::D3D12_HEAP_FLAGS) -> Self {
+        Self::from_bits_retain(raw)
+    }
+}
+
+impl From<HeapFlags> for d3d12::D3D12_HEAP_FLAGS {
+    fn from(flags: HeapFlags) -> Self {
+        flags.bits()
+    }
+}
+
+#[derive(Clone, Copy)]
 #[repr(transparent)]
 pub struct HeapProperties(pub d3d12::D3D12_HEAP_PROPERTIES);
 impl HeapProperties {
@@ -66,6 +135,35 @@ impl HeapProperties {
             VisibleNodeMask: visible_node_mask,
         })
     }
+
+    /// Properties of a standard heap type on the single node.
+    pub fn standard(heap_type: HeapType) -> Self {
+        Self::new(
+            heap_type,
+            CpuPageProperty::Unknown,
+            MemoryPool::Unknown,
+            0,
+            0,
+        )
+    }
+
+    /// Properties of a custom heap, e.g. a write-combined upload heap in `L0`.
+    ///
+    /// UMA adapters, see `D3D12_FEATURE_DATA_ARCHITECTURE::UMA`, only have `L0`.
+    pub fn custom(
+        cpu_page_property: CpuPageProperty,
+        memory_pool_preference: MemoryPool,
+        creation_node_mask: u32,
+        visible_node_mask: u32,
+    ) -> Self {
+        Self::new(
+            HeapType::Custom,
+            cpu_page_property,
+            memory_pool_preference,
+            creation_node_mask,
+            visible_node_mask,
+        )
+    }
 }
 
 #[repr(transparent)]
@@ -85,3 +183,79 @@ impl HeapDesc {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composite_heap_flags() {
+        assert_eq!(HeapFlags::ALLOW_ALL_BUFFERS_AND_TEXTURES.bits(), 0);
+        assert_eq!(HeapFlags::ALLOW_ONLY_BUFFERS.bits(), 0xC0);
+        assert_eq!(HeapFlags::ALLOW_ONLY_NON_RT_DS_TEXTURES.bits(), 0x44);
+        assert_eq!(HeapFlags::ALLOW_ONLY_RT_DS_TEXTURES.bits(), 0x84);
+        assert_eq!(
+            HeapFlags::ALLOW_ONLY_BUFFERS,
+            HeapFlags::DENY_RT_DS_TEXTURES | HeapFlags::DENY_NON_RT_DS_TEXTURES
+        );
+        #[allow(deprecated)]
+        let misspelled = HeapFlags::DENT_RT_DS_TEXTURES;
+        assert_eq!(misspelled, HeapFlags::DENY_RT_DS_TEXTURES);
+    }
+
+    #[test]
+    fn standard_properties() {
+        let properties = HeapProperties::standard(HeapType::Upload).0;
+        assert_eq!(properties.Type, d3d12::D3D12_HEAP_TYPE_UPLOAD);
+        assert_eq!(
+            properties.CPUPageProperty,
+            d3d12::D3D12_CPU_PAGE_PROPERTY_UNKNOWN
+        );
+        assert_eq!(
+            properties.MemoryPoolPreference,
+            d3d12::D3D12_MEMORY_POOL_UNKNOWN
+        );
+    }
+
+    #[test]
+    fn custom_properties() {
+        let properties =
+            HeapProperties::custom(CpuPageProperty::WriteCombine, MemoryPool::L0, 1, 1).0;
+        assert_eq!(properties.Type, d3d12::D3D12_HEAP_TYPE_CUSTOM);
+        assert_eq!(
+            properties.CPUPageProperty,
+            d3d12::D3D12_CPU_PAGE_PROPERTY_WRITE_COMBINE
+        );
+        assert_eq!(properties.MemoryPoolPreference, d3d12::D3D12_MEMORY_POOL_L0);
+        assert_eq!(properties.CreationNodeMask, 1);
+        assert_eq!(properties.VisibleNodeMask, 1);
+    }
+
+    #[test]
+    fn adjacent_placements() {
+        let mut heap = TrackedHeap::new(Heap::null());
+        heap.place(0..65536, false);
+        heap.place(65536..131072, false);
+
+        // A released range can be placed again.
+        heap.release(65536);
+        heap.place(65536..98304, false);
+        if cfg!(debug_assertions) {
+            assert_eq!(heap.placements, [0..65536, 65536..98304]);
+        }
+
+        // Intended aliasing is allowed to overlap.
+        heap.place(0..131072, true);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(
+        expected = "Placement 32768..98304 overlaps placement 0..65536 without aliasing"
+    )]
+    fn overlapping_placement() {
+        let mut heap = TrackedHeap::new(Heap::null());
+        heap.place(0..65536, false);
+        heap.place(32768..98304, false);
+    }
+}
@@ -5,44 +5,116 @@
     clippy::not_unsafe_ptr_arg_deref
 )]
 
-use std::{convert::TryFrom, ffi::CStr};
+use std::{convert::TryFrom, ffi::CStr, fmt};
 use winapi::{
-    shared::dxgiformat,
+    shared::{dxgiformat, winerror},
     um::{d3d12, d3dcommon},
 };
 
+mod allocator;
 mod com;
 mod command_allocator;
 mod command_list;
 mod debug;
 mod descriptor;
 mod device;
+mod dred;
 mod dxgi;
+mod format;
 mod heap;
+mod lifetime;
+#[cfg(test)]
+mod mock;
+#[cfg(feature = "libloading")]
+mod pix;
 mod pso;
 mod query;
 mod queue;
+mod raytracing;
+mod reflection;
+mod region;
 mod resource;
+mod state_tracker;
 mod sync;
+mod typed_command_list;
+mod types;
+mod upload_ring;
 
+pub use crate::allocator::*;
 pub use crate::com::*;
 pub use crate::command_allocator::*;
 pub use crate::command_list::*;
 pub use crate::debug::*;
 pub use crate::descriptor::*;
 pub use crate::device::*;
+pub use crate::dred::*;
 pub use crate::dxgi::*;
+pub use crate::format::*;
 pub use crate::heap::*;
+pub use crate::lifetime::*;
+#[cfg(feature = "libloading")]
+pub use crate::pix::*;
 pub use crate::pso::*;
 pub use crate::query::*;
 pub use crate::queue::*;
+pub use crate::raytracing::*;
+pub use crate::reflection::*;
+pub use crate::region::*;
 pub use crate::resource::*;
+pub use crate::state_tracker::*;
 pub use crate::sync::*;
+pub use crate::typed_command_list::*;
+pub use crate::upload_ring::*;
 
 pub use winapi::shared::winerror::HRESULT;
 
 pub type D3DResult<T> = (T, HRESULT);
-pub type GpuAddress = d3d12::D3D12_GPU_VIRTUAL_ADDRESS;
+
+/// GPU virtual address, as returned by [`Resource::gpu_virtual_address`].
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct GpuAddress(pub d3d12::D3D12_GPU_VIRTUAL_ADDRESS);
+
+impl GpuAddress {
+    pub const NULL: Self = GpuAddress(0);
+
+    pub fn raw(self) -> d3d12::D3D12_GPU_VIRTUAL_ADDRESS {
+        self.0
+    }
+
+    pub fn is_null(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Address `offset` bytes further, `None` on overflow.
+    pub fn checked_add(self, offset: u64) -> Option<Self> {
+        self.0.checked_add(offset).map(GpuAddress)
+    }
+
+    /// Address `offset` bytes further.
+    ///
+    /// Overflowing is a bug, use [`Self::checked_add`] for untrusted offsets.
+    pub fn offset(self, offset: u64) -> Self {
+        GpuAddress(self.0 + offset)
+    }
+
+    pub fn is_aligned(self, alignment: u64) -> bool {
+        debug_assert!(alignment.is_power_of_two());
+        self.0 & (alignment - 1) == 0
+    }
+}
+
+impl From<d3d12::D3D12_GPU_VIRTUAL_ADDRESS> for GpuAddress {
+    fn from(address: d3d12::D3D12_GPU_VIRTUAL_ADDRESS) -> Self {
+        GpuAddress(address)
+    }
+}
+
+impl From<GpuAddress> for d3d12::D3D12_GPU_VIRTUAL_ADDRESS {
+    fn from(address: GpuAddress) -> Self {
+        address.0
+    }
+}
 pub type Format = dxgiformat::DXGI_FORMAT;
 pub type Rect = d3d12::D3D12_RECT;
 pub type NodeMask = u32;
@@ -69,6 +141,7 @@ pub struct SampleDesc {
 
 #[repr(u32)]
 #[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FeatureLevel {
     L9_1 = d3dcommon::D3D_FEATURE_LEVEL_9_1,
     L9_2 = d3dcommon::D3D_FEATURE_LEVEL_9_2,
@@ -109,6 +182,54 @@ impl Error {
         let data = self.GetBufferPointer();
         CStr::from_ptr(data as *const _ as *const _)
     }
+
+    /// Text of the error blob, `None` if there is no blob.
+    pub fn message(&self) -> Option<String> {
+        if self.is_null() {
+            return None;
+        }
+        let bytes = unsafe {
+            std::slice::from_raw_parts(self.GetBufferPointer() as *const u8, self.GetBufferSize())
+        };
+        let bytes = bytes.split(|&b| b == 0).next().unwrap_or_default();
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// Failure of a call also producing an error blob, like shader compilation
+/// or root signature serialization.
+#[derive(Clone, Debug)]
+pub struct BlobError {
+    pub hr: HRESULT,
+    /// Diagnostics from the error blob.
+    pub message: Option<String>,
+}
+
+impl fmt::Display for BlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HRESULT {:#x}", self.hr)?;
+        if let Some(ref message) = self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BlobError {}
+
+/// Turns the result of a call producing a blob and an error blob into a `Result`,
+/// keeping the error blob's diagnostics on failure.
+///
+/// The error blob may hold warnings on success, these are dropped.
+pub fn blob_result(((blob, error), hr): D3DResult<(Blob, Error)>) -> Result<Blob, BlobError> {
+    if winerror::SUCCEEDED(hr) {
+        Ok(blob)
+    } else {
+        Err(BlobError {
+            hr,
+            message: error.message(),
+        })
+    }
 }
 
 #[cfg(feature = "libloading")]
@@ -123,3 +244,86 @@ impl D3D12Lib {
         unsafe { libloading::Library::new("d3d12.dll").map(|lib| D3D12Lib { lib }) }
     }
 }
+
+/// Device for tests needing hardware, `None` if there is neither a D3D12 adapter nor WARP.
+#[cfg(test)]
+pub(crate) fn blob_bytes(blob: &Blob) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(blob.GetBufferPointer() as *const u8, blob.GetBufferSize())
+    }
+}
+
+#[cfg(all(test, feature = "implicit-link"))]
+pub(crate) fn test_device() -> Option<Device> {
+    let (factory, hr) = Factory4::create(FactoryCreationFlags::empty());
+    if winerror::FAILED(hr) {
+        return None;
+    }
+    match Device::create_with_fallback(&factory, &[FeatureLevel::L11_0]) {
+        (Some((device, _)), _) => Some(device),
+        (None, _) => None,
+    }
+}
+
+/// Direct command list open for recording, with its allocator.
+#[cfg(all(test, feature = "implicit-link"))]
+pub(crate) fn test_command_list(device: &Device) -> (CommandAllocator, GraphicsCommandList) {
+    let (allocator, hr) = device.create_command_allocator(CmdListType::Direct);
+    assert!(winerror::SUCCEEDED(hr));
+    let (list, hr) = device.create_graphics_command_list(
+        CmdListType::Direct,
+        &allocator,
+        PipelineState::null(),
+        0,
+    );
+    assert!(winerror::SUCCEEDED(hr));
+    (allocator, list)
+}
+
+/// Closes and executes `list` on a new direct queue, waiting for the GPU to finish it.
+#[cfg(all(test, feature = "implicit-link"))]
+pub(crate) fn test_execute(device: &Device, list: &GraphicsCommandList) {
+    assert!(winerror::SUCCEEDED(list.close()));
+    let (queue, hr) = device.create_command_queue(
+        CmdListType::Direct,
+        Priority::Normal,
+        CommandQueueFlags::empty(),
+        0,
+    );
+    assert!(winerror::SUCCEEDED(hr));
+    queue.execute_graphics_command_lists(&[list]);
+
+    let (fence, hr) = device.create_fence(0);
+    assert!(winerror::SUCCEEDED(hr));
+    assert!(winerror::SUCCEEDED(queue.signal(&fence, 1)));
+    let (event, hr) = OwnedEvent::new(false);
+    assert!(winerror::SUCCEEDED(hr));
+    assert!(winerror::SUCCEEDED(
+        fence.set_event_on_completion(&event, 1)
+    ));
+    assert_eq!(
+        event.wait(winapi::um::winbase::INFINITE),
+        WaitStatus::Signaled
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpu_address_offsets() {
+        let base = GpuAddress(0x1_0000);
+        assert_eq!(base.offset(0x100), GpuAddress(0x1_0100));
+        assert_eq!(base.checked_add(0x100), Some(GpuAddress(0x1_0100)));
+        assert_eq!(
+            GpuAddress(u64::MAX - 1).checked_add(1),
+            Some(GpuAddress(u64::MAX))
+        );
+        assert_eq!(GpuAddress(u64::MAX).checked_add(1), None);
+        assert!(base.is_aligned(256));
+        assert!(!base.offset(4).is_aligned(256));
+        assert!(GpuAddress::NULL.is_null());
+        assert_eq!(u64::from(base), 0x1_0000);
+    }
+}
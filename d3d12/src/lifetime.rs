@@ -0,0 +1,229 @@
+//! Lifetime tracking of device objects
+
+use crate::{com::ComPtr, types, D3DResult, Device5, HRESULT};
+use std::{
+    panic, ptr,
+    sync::atomic::{self, AtomicU32, Ordering},
+};
+use winapi::{
+    ctypes::c_void,
+    shared::{guiddef, minwindef::ULONG, winerror},
+    um::{d3d12, unknwnbase::IUnknown},
+    Interface,
+};
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LifetimeState {
+    InUse = types::D3D12_LIFETIME_STATE_IN_USE,
+    NotInUse = types::D3D12_LIFETIME_STATE_NOT_IN_USE,
+}
+
+pub type LifetimeTracker = ComPtr<types::ID3D12LifetimeTracker>;
+
+impl LifetimeTracker {
+    /// Destroys `object`, which has to be a device child tracked by this tracker.
+    pub fn destroy_owned_object<I: Interface>(&self, object: &ComPtr<I>) -> HRESULT {
+        unsafe { self.DestroyOwnedObject(object.as_mut_ptr() as *mut d3d12::ID3D12DeviceChild) }
+    }
+}
+
+impl Device5 {
+    /// Creates a lifetime tracker, `callback` is invoked by the runtime whenever
+    /// the state of the tracked objects changes.
+    pub fn create_lifetime_tracker<F>(&self, callback: F) -> D3DResult<LifetimeTracker>
+    where
+        F: Fn(LifetimeState) + Send + Sync + 'static,
+    {
+        let owner = LifetimeOwner::new(Box::new(callback));
+
+        let mut tracker = LifetimeTracker::null();
+        let hr = unsafe {
+            let hr = self.CreateLifetimeTracker(
+                owner as *mut _,
+                &types::ID3D12LifetimeTracker::uuidof(),
+                tracker.mut_void(),
+            );
+            // The tracker holds its own reference to the owner.
+            owner_release(owner as *mut IUnknown);
+            hr
+        };
+
+        (tracker, hr)
+    }
+}
+
+/// `ID3D12LifetimeOwner` implementation forwarding notifications to a closure.
+#[repr(C)]
+struct LifetimeOwner {
+    vtbl: *const types::ID3D12LifetimeOwnerVtbl,
+    ref_count: AtomicU32,
+    callback: Box<dyn Fn(LifetimeState) + Send + Sync>,
+}
+
+static LIFETIME_OWNER_VTBL: types::ID3D12LifetimeOwnerVtbl = types::ID3D12LifetimeOwnerVtbl {
+    parent: winapi::um::unknwnbase::IUnknownVtbl {
+        QueryInterface: owner_query_interface,
+        AddRef: owner_add_ref,
+        Release: owner_release,
+    },
+    LifetimeStateUpdated: owner_lifetime_state_updated,
+};
+
+impl LifetimeOwner {
+    /// Allocates an owner with a reference count of one.
+    fn new(callback: Box<dyn Fn(LifetimeState) + Send + Sync>) -> *mut LifetimeOwner {
+        Box::into_raw(Box::new(LifetimeOwner {
+            vtbl: &LIFETIME_OWNER_VTBL,
+            ref_count: AtomicU32::new(1),
+            callback,
+        }))
+    }
+}
+
+unsafe extern "system" fn owner_query_interface(
+    this: *mut IUnknown,
+    riid: guiddef::REFIID,
+    object: *mut *mut c_void,
+) -> HRESULT {
+    let riid = &*riid;
+    if guiddef::IsEqualGUID(riid, &IUnknown::uuidof())
+        || guiddef::IsEqualGUID(riid, &types::ID3D12LifetimeOwner::uuidof())
+    {
+        owner_add_ref(this);
+        *object = this as *mut _;
+        winerror::S_OK
+    } else {
+        *object = ptr::null_mut();
+        winerror::E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn owner_add_ref(this: *mut IUnknown) -> ULONG {
+    let owner = &*(this as *const LifetimeOwner);
+    owner.ref_count.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+unsafe extern "system" fn owner_release(this: *mut IUnknown) -> ULONG {
+    let owner = &*(this as *const LifetimeOwner);
+    let count = owner.ref_count.fetch_sub(1, Ordering::Release) - 1;
+    if count == 0 {
+        atomic::fence(Ordering::Acquire);
+        drop(Box::from_raw(this as *mut LifetimeOwner));
+    }
+    count
+}
+
+unsafe extern "system" fn owner_lifetime_state_updated(
+    this: *mut types::ID3D12LifetimeOwner,
+    new_state: types::D3D12_LIFETIME_STATE,
+) {
+    let owner = &*(this as *const LifetimeOwner);
+    let state = match new_state {
+        types::D3D12_LIFETIME_STATE_IN_USE => LifetimeState::InUse,
+        _ => LifetimeState::NotInUse,
+    };
+    // Unwinding into the runtime is undefined behavior.
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| (owner.callback)(state)));
+    if result.is_err() {
+        log::error!("Lifetime tracker callback panicked on {:?}", state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{slot, Mock};
+    use std::sync::{Arc, Mutex};
+
+    /// Owner handed to the mocked `CreateLifetimeTracker`.
+    struct Runtime {
+        owner: Mutex<usize>,
+    }
+
+    unsafe extern "system" fn create_lifetime_tracker(
+        this: *mut types::ID3D12Device5,
+        owner: *mut types::ID3D12LifetimeOwner,
+        _riid: guiddef::REFIID,
+        tracker: *mut *mut c_void,
+    ) -> HRESULT {
+        (*(owner as *mut IUnknown)).AddRef();
+        *Mock::<Runtime>::state(this).owner.lock().unwrap() = owner as usize;
+
+        let created =
+            Mock::new::<types::ID3D12LifetimeTracker, types::ID3D12LifetimeTrackerVtbl>((), &[]);
+        *tracker = created.as_mut_ptr() as *mut c_void;
+        std::mem::forget(created);
+        winerror::S_OK
+    }
+
+    fn mock_device() -> Device5 {
+        Mock::new::<types::ID3D12Device5, types::ID3D12Device5Vtbl>(
+            Runtime {
+                owner: Mutex::new(0),
+            },
+            &[(
+                slot!(types::ID3D12Device5Vtbl, CreateLifetimeTracker),
+                create_lifetime_tracker as usize,
+            )],
+        )
+    }
+
+    /// Owner handed to the mocked device by the last tracker creation.
+    fn registered_owner(device: &Device5) -> *mut types::ID3D12LifetimeOwner {
+        let runtime = unsafe { Mock::<Runtime>::state(device.as_ptr()) };
+        *runtime.owner.lock().unwrap() as *mut types::ID3D12LifetimeOwner
+    }
+
+    /// Notifies `owner` the way the runtime does, through its vtable.
+    unsafe fn notify(owner: *mut types::ID3D12LifetimeOwner, state: types::D3D12_LIFETIME_STATE) {
+        ((*(*owner).lpVtbl).LifetimeStateUpdated)(owner, state);
+    }
+
+    #[test]
+    fn lifetime_state_callback() {
+        let device = mock_device();
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let callback_states = Arc::clone(&states);
+        let (tracker, hr) = device
+            .create_lifetime_tracker(move |state| callback_states.lock().unwrap().push(state));
+        assert_eq!(hr, winerror::S_OK);
+        assert!(!tracker.is_null());
+
+        let owner = registered_owner(&device);
+        unsafe {
+            notify(owner, types::D3D12_LIFETIME_STATE_NOT_IN_USE);
+            notify(owner, types::D3D12_LIFETIME_STATE_IN_USE);
+        }
+        assert_eq!(
+            *states.lock().unwrap(),
+            [LifetimeState::NotInUse, LifetimeState::InUse]
+        );
+
+        // The runtime holds the last reference, releasing it drops the callback.
+        assert_eq!(Arc::strong_count(&states), 2);
+        assert_eq!(unsafe { (*(owner as *mut IUnknown)).Release() }, 0);
+        assert_eq!(Arc::strong_count(&states), 1);
+    }
+
+    #[test]
+    fn panicking_callback() {
+        let device = mock_device();
+        let calls = Arc::new(AtomicU32::new(0));
+        let callback_calls = Arc::clone(&calls);
+        let (_tracker, hr) = device.create_lifetime_tracker(move |_| {
+            callback_calls.fetch_add(1, Ordering::Relaxed);
+            panic!("callback panic");
+        });
+        assert_eq!(hr, winerror::S_OK);
+
+        // The panic stays on the Rust side of the callback, later ones still run.
+        let owner = registered_owner(&device);
+        unsafe {
+            notify(owner, types::D3D12_LIFETIME_STATE_NOT_IN_USE);
+            notify(owner, types::D3D12_LIFETIME_STATE_IN_USE);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        unsafe { (*(owner as *mut IUnknown)).Release() };
+    }
+}
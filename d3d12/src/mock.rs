@@ -0,0 +1,209 @@
+//! COM objects standing in for the runtime in tests
+
+use crate::{com::ComPtr, CpuDescriptor, DescriptorHeap, GpuDescriptor};
+use std::{
+    mem,
+    sync::atomic::{self, AtomicU32, Ordering},
+};
+use winapi::{
+    ctypes::c_void,
+    shared::{guiddef::REFIID, minwindef::ULONG, winerror},
+    um::{d3d12, unknwnbase::IUnknown},
+    Interface,
+};
+
+/// Index of `method` in the vtable type `vtbl`, for [`Mock::new`].
+macro_rules! slot {
+    ($vtbl:ty, $($method:ident).+) => {{
+        let vtbl = std::mem::MaybeUninit::<$vtbl>::uninit();
+        let base = vtbl.as_ptr();
+        let method = unsafe { std::ptr::addr_of!((*base).$($method).+) };
+        (method as usize - base as usize) / std::mem::size_of::<usize>()
+    }};
+}
+pub(crate) use slot;
+
+/// Object answering every `QueryInterface` with itself.
+///
+/// Methods which aren't given to [`Mock::new`] abort the process when called.
+#[repr(C)]
+pub(crate) struct Mock<S> {
+    vtbl: *const usize,
+    refs: AtomicU32,
+    _slots: Box<[usize]>,
+    state: S,
+}
+
+impl<S> Mock<S> {
+    /// Creates a mock with the vtable layout `V`, `methods` being pairs of slot
+    /// index and function pointer.
+    pub(crate) fn new<T: Interface, V>(state: S, methods: &[(usize, usize)]) -> ComPtr<T> {
+        let count = mem::size_of::<V>() / mem::size_of::<usize>();
+        let mut slots = vec![unexpected_call as usize; count].into_boxed_slice();
+        slots[0] = query_interface::<S> as usize;
+        slots[1] = add_ref::<S> as usize;
+        slots[2] = release::<S> as usize;
+        for &(slot, method) in methods {
+            slots[slot] = method;
+        }
+
+        let mock = Box::new(Mock {
+            vtbl: slots.as_ptr(),
+            refs: AtomicU32::new(0),
+            _slots: slots,
+            state,
+        });
+        unsafe { ComPtr::from_raw(Box::into_raw(mock) as *mut T) }
+    }
+
+    /// State of the mock `this` points to.
+    pub(crate) unsafe fn state<'a, T>(this: *const T) -> &'a S {
+        &(*(this as *const Mock<S>)).state
+    }
+
+    /// Reference count of the mock `this` points to.
+    pub(crate) unsafe fn refs<T>(this: *const T) -> u32 {
+        (*(this as *const Mock<S>)).refs.load(Ordering::Acquire)
+    }
+}
+
+unsafe extern "system" fn unexpected_call() {
+    eprintln!("Unexpected call of a mocked COM method");
+    std::process::abort();
+}
+
+unsafe extern "system" fn query_interface<S>(
+    this: *mut IUnknown,
+    _riid: REFIID,
+    object: *mut *mut c_void,
+) -> winerror::HRESULT {
+    add_ref::<S>(this);
+    *object = this as *mut c_void;
+    winerror::S_OK
+}
+
+unsafe extern "system" fn add_ref<S>(this: *mut IUnknown) -> ULONG {
+    let mock = &*(this as *const Mock<S>);
+    mock.refs.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+unsafe extern "system" fn release<S>(this: *mut IUnknown) -> ULONG {
+    let mock = &*(this as *const Mock<S>);
+    let refs = mock.refs.fetch_sub(1, Ordering::Release) - 1;
+    if refs == 0 {
+        atomic::fence(Ordering::Acquire);
+        drop(Box::from_raw(this as *mut Mock<S>));
+    }
+    refs
+}
+
+/// State of a mocked descriptor heap, created by [`MockHeap::create`].
+pub(crate) struct MockHeap {
+    pub(crate) desc: d3d12::D3D12_DESCRIPTOR_HEAP_DESC,
+    pub(crate) cpu_start: usize,
+    pub(crate) gpu_start: u64,
+    /// Handle increment reported by the device of the heap.
+    pub(crate) increment: u32,
+}
+
+impl MockHeap {
+    pub(crate) const CPU_START: usize = 0x1000;
+    pub(crate) const GPU_START: u64 = 0x10_0000;
+    pub(crate) const INCREMENT: u32 = 32;
+
+    /// Heap of `count` descriptors starting at [`CPU_START`](Self::CPU_START)
+    /// and, when shader visible, [`GPU_START`](Self::GPU_START).
+    pub(crate) fn new(
+        ty: d3d12::D3D12_DESCRIPTOR_HEAP_TYPE,
+        count: u32,
+        shader_visible: bool,
+    ) -> Self {
+        MockHeap {
+            desc: d3d12::D3D12_DESCRIPTOR_HEAP_DESC {
+                Type: ty,
+                NumDescriptors: count,
+                Flags: if shader_visible {
+                    d3d12::D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE
+                } else {
+                    d3d12::D3D12_DESCRIPTOR_HEAP_FLAG_NONE
+                },
+                NodeMask: 0,
+            },
+            cpu_start: Self::CPU_START,
+            gpu_start: if shader_visible { Self::GPU_START } else { 0 },
+            increment: Self::INCREMENT,
+        }
+    }
+
+    pub(crate) fn create(self) -> DescriptorHeap {
+        type Vtbl = d3d12::ID3D12DescriptorHeapVtbl;
+        Mock::new::<d3d12::ID3D12DescriptorHeap, Vtbl>(
+            self,
+            &[
+                (slot!(Vtbl, GetDesc), heap_desc as usize),
+                (
+                    slot!(Vtbl, GetCPUDescriptorHandleForHeapStart),
+                    heap_cpu_start as usize,
+                ),
+                (
+                    slot!(Vtbl, GetGPUDescriptorHandleForHeapStart),
+                    heap_gpu_start as usize,
+                ),
+                (slot!(Vtbl, parent.parent.GetDevice), heap_device as usize),
+            ],
+        )
+    }
+}
+
+// The by-value getters use the hidden return pointer ABI winapi declares them with.
+unsafe extern "system" fn heap_desc(
+    this: *mut d3d12::ID3D12DescriptorHeap,
+    ret: *mut d3d12::D3D12_DESCRIPTOR_HEAP_DESC,
+) -> *mut d3d12::D3D12_DESCRIPTOR_HEAP_DESC {
+    *ret = Mock::<MockHeap>::state(this).desc;
+    ret
+}
+
+unsafe extern "system" fn heap_cpu_start(
+    this: *mut d3d12::ID3D12DescriptorHeap,
+    ret: *mut CpuDescriptor,
+) -> *mut CpuDescriptor {
+    *ret = CpuDescriptor {
+        ptr: Mock::<MockHeap>::state(this).cpu_start,
+    };
+    ret
+}
+
+unsafe extern "system" fn heap_gpu_start(
+    this: *mut d3d12::ID3D12DescriptorHeap,
+    ret: *mut GpuDescriptor,
+) -> *mut GpuDescriptor {
+    *ret = GpuDescriptor {
+        ptr: Mock::<MockHeap>::state(this).gpu_start,
+    };
+    ret
+}
+
+unsafe extern "system" fn heap_device(
+    this: *mut d3d12::ID3D12DescriptorHeap,
+    _riid: REFIID,
+    device: *mut *mut c_void,
+) -> winerror::HRESULT {
+    let mock = Mock::new::<d3d12::ID3D12Device, d3d12::ID3D12DeviceVtbl>(
+        Mock::<MockHeap>::state(this).increment,
+        &[(
+            slot!(d3d12::ID3D12DeviceVtbl, GetDescriptorHandleIncrementSize),
+            device_increment as usize,
+        )],
+    );
+    *device = mock.as_mut_ptr() as *mut c_void;
+    mem::forget(mock);
+    winerror::S_OK
+}
+
+unsafe extern "system" fn device_increment(
+    this: *mut d3d12::ID3D12Device,
+    _ty: d3d12::D3D12_DESCRIPTOR_HEAP_TYPE,
+) -> u32 {
+    *Mock::<u32>::state(this)
+}
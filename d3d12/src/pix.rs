@@ -0,0 +1,168 @@
+//! Programmatic PIX GPU captures
+
+use crate::HRESULT;
+use std::{mem, os::windows::ffi::OsStrExt, path::Path};
+use winapi::{
+    shared::{minwindef::BOOL, winerror},
+    um::winnt::PCWSTR,
+};
+
+/// `PIX_CAPTURE_GPU` from `pix3.h`.
+const PIX_CAPTURE_GPU: u32 = 1 << 1;
+
+/// `PIXCaptureParameters` from `pix3.h`.
+///
+/// Only GPU captures are used, but the runtime may read the size of the largest member.
+#[repr(C)]
+union PixCaptureParameters {
+    gpu: PixGpuCaptureParameters,
+    #[allow(dead_code)]
+    timing: PixTimingCaptureParameters,
+}
+
+/// `PIXCaptureParameters::GpuCaptureParameters`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PixGpuCaptureParameters {
+    file_name: PCWSTR,
+}
+
+/// `PIXCaptureParameters::TimingCaptureParameters`, sizing the union.
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+struct PixTimingCaptureParameters {
+    file_name: PCWSTR,
+    maximum_tooling_memory_size_mb: u32,
+    /// `PIXCaptureStorage`.
+    capture_storage: u32,
+    capture_gpu_timing: BOOL,
+    capture_callstacks: BOOL,
+    capture_cpu_samples: BOOL,
+    cpu_samples_per_second: u32,
+    capture_file_io: BOOL,
+    capture_virtual_alloc_events: BOOL,
+    capture_heap_alloc_events: BOOL,
+    capture_xmem_events: BOOL,
+    capture_pix_mem_events: BOOL,
+    capture_page_fault_events: BOOL,
+    capture_video_memory: BOOL,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixCaptureError {
+    /// The PIX GPU capturer isn't loaded into the process.
+    NotAttached,
+    Failed(HRESULT),
+}
+
+/// GPU captures driven by the application through the PIX event runtime.
+///
+/// Captures need `WinPixGpuCapturer.dll`, which is loaded on creation when found on the
+/// library search path, or injected by PIX when launching or attaching to the process.
+#[derive(Debug)]
+pub struct PixCapture {
+    runtime: libloading::Library,
+    capturer: Option<libloading::Library>,
+}
+
+impl PixCapture {
+    /// Loads `WinPixEventRuntime.dll`, failing if it can't be found.
+    pub fn new() -> Result<Self, libloading::Error> {
+        let runtime = unsafe { libloading::Library::new("WinPixEventRuntime.dll") }?;
+        // Has to be loaded before the device is created to capture it.
+        let capturer = unsafe { libloading::Library::new("WinPixGpuCapturer.dll") }.ok();
+        Ok(PixCapture { runtime, capturer })
+    }
+
+    /// Whether the GPU capturer is loaded, captures are no-ops otherwise.
+    pub fn is_attached(&self) -> bool {
+        self.capturer.is_some()
+    }
+
+    /// Starts capturing all GPU work into a `.wpix` file at `path`.
+    pub fn begin_gpu_capture(&self, path: &Path) -> Result<(), PixCaptureError> {
+        type Fun = extern "system" fn(u32, *const PixCaptureParameters) -> HRESULT;
+
+        if !self.is_attached() {
+            return Err(PixCaptureError::NotAttached);
+        }
+
+        let path = path
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect::<Vec<u16>>();
+        // Zeroed past the GPU member, which is smaller than the union.
+        let mut params: PixCaptureParameters = unsafe { mem::zeroed() };
+        params.gpu = PixGpuCaptureParameters {
+            file_name: path.as_ptr(),
+        };
+        let hr = unsafe {
+            let func: libloading::Symbol<Fun> = self
+                .runtime
+                .get(b"PIXBeginCapture2")
+                .map_err(|_| PixCaptureError::Failed(winerror::E_NOTIMPL))?;
+            func(PIX_CAPTURE_GPU, &params)
+        };
+        if winerror::SUCCEEDED(hr) {
+            Ok(())
+        } else {
+            Err(PixCaptureError::Failed(hr))
+        }
+    }
+
+    /// Ends the capture, dropping it if `discard` is set.
+    pub fn end_gpu_capture(&self, discard: bool) -> Result<(), PixCaptureError> {
+        type Fun = extern "system" fn(i32) -> HRESULT;
+
+        if !self.is_attached() {
+            return Err(PixCaptureError::NotAttached);
+        }
+
+        let hr = unsafe {
+            let func: libloading::Symbol<Fun> = self
+                .runtime
+                .get(b"PIXEndCapture")
+                .map_err(|_| PixCaptureError::Failed(winerror::E_NOTIMPL))?;
+            func(discard as i32)
+        };
+        if winerror::SUCCEEDED(hr) {
+            Ok(())
+        } else {
+            Err(PixCaptureError::Failed(hr))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_parameters_layout() {
+        assert_eq!(
+            mem::size_of::<PixCaptureParameters>(),
+            mem::size_of::<PixTimingCaptureParameters>()
+        );
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(mem::size_of::<PixCaptureParameters>(), 64);
+    }
+
+    #[test]
+    fn begin_end_capture() {
+        let pix = match PixCapture::new() {
+            Ok(pix) => pix,
+            Err(_) => return,
+        };
+        let path = std::env::temp_dir().join("d3d12-pix-test.wpix");
+        match pix.begin_gpu_capture(&path) {
+            Ok(()) => assert_eq!(pix.end_gpu_capture(true), Ok(())),
+            Err(PixCaptureError::NotAttached) => {
+                assert!(!pix.is_attached());
+                assert_eq!(pix.end_gpu_capture(true), Err(PixCaptureError::NotAttached));
+            }
+            Err(error) => panic!("Capture failed: {:?}", error),
+        }
+    }
+}
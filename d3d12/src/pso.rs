@@ -1,13 +1,30 @@
 //! Pipeline state
 
-use crate::{com::ComPtr, Blob, D3DResult, Error};
+use crate::{
+    blob_result, com::ComPtr, Blob, BlobError, Error, Format, NodeMask, RootSignature, SampleDesc,
+};
 use std::{
     ffi::{self, c_void},
+    future::Future,
     marker::PhantomData,
+    mem,
     ops::Deref,
+    os::windows::ffi::OsStrExt,
+    path::Path,
+    pin::Pin,
     ptr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+use winapi::{
+    shared::{
+        dxgiformat, dxgitype,
+        minwindef::{FALSE, TRUE},
+        winerror,
+    },
+    um::{d3d12, d3dcommon, d3dcompiler},
 };
-use winapi::um::{d3d12, d3dcompiler};
 
 bitflags::bitflags! {
     #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -25,7 +42,19 @@ bitflags::bitflags! {
         const PACK_MATRIX_ROW_MAJOR = d3dcompiler::D3DCOMPILE_PACK_MATRIX_ROW_MAJOR;
         const PACK_MATRIX_COLUMN_MAJOR = d3dcompiler::D3DCOMPILE_PACK_MATRIX_COLUMN_MAJOR;
         const PARTIAL_PRECISION = d3dcompiler::D3DCOMPILE_PARTIAL_PRECISION;
-        // TODO: add missing flags
+        const AVOID_FLOW_CONTROL = d3dcompiler::D3DCOMPILE_AVOID_FLOW_CONTROL;
+        const PREFER_FLOW_CONTROL = d3dcompiler::D3DCOMPILE_PREFER_FLOW_CONTROL;
+        const ENABLE_STRICTNESS = d3dcompiler::D3DCOMPILE_ENABLE_STRICTNESS;
+        const ENABLE_BACKWARDS_COMPATIBILITY = d3dcompiler::D3DCOMPILE_ENABLE_BACKWARDS_COMPATIBILITY;
+        const IEEE_STRICTNESS = d3dcompiler::D3DCOMPILE_IEEE_STRICTNESS;
+        const OPTIMIZATION_LEVEL0 = d3dcompiler::D3DCOMPILE_OPTIMIZATION_LEVEL0;
+        const OPTIMIZATION_LEVEL1 = d3dcompiler::D3DCOMPILE_OPTIMIZATION_LEVEL1;
+        const OPTIMIZATION_LEVEL2 = d3dcompiler::D3DCOMPILE_OPTIMIZATION_LEVEL2;
+        const OPTIMIZATION_LEVEL3 = d3dcompiler::D3DCOMPILE_OPTIMIZATION_LEVEL3;
+        const WARNINGS_ARE_ERRORS = d3dcompiler::D3DCOMPILE_WARNINGS_ARE_ERRORS;
+        const RESOURCES_MAY_ALIAS = d3dcompiler::D3DCOMPILE_RESOURCES_MAY_ALIAS;
+        const ENABLE_UNBOUNDED_DESCRIPTOR_TABLES = d3dcompiler::D3DCOMPILE_ENABLE_UNBOUNDED_DESCRIPTOR_TABLES;
+        const ALL_RESOURCES_BOUND = d3dcompiler::D3DCOMPILE_ALL_RESOURCES_BOUND;
     }
 }
 
@@ -66,12 +95,14 @@ impl<'a> Shader<'a> {
     /// Compile a shader from raw HLSL.
     ///
     /// * `target`: example format: `ps_5_1`.
+    ///
+    /// On failure, the compiler messages are in the returned [`BlobError`].
     pub fn compile(
         code: &[u8],
         target: &ffi::CStr,
         entry: &ffi::CStr,
         flags: ShaderCompileFlags,
-    ) -> D3DResult<(Blob, Error)> {
+    ) -> Result<Blob, BlobError> {
         let mut shader = Blob::null();
         let mut error = Error::null();
 
@@ -91,10 +122,165 @@ impl<'a> Shader<'a> {
             )
         };
 
-        ((shader, error), hr)
+        blob_result(((shader, error), hr))
+    }
+
+    /// Compile a shader from an HLSL file.
+    ///
+    /// `#include "..."` directives are resolved relative to the including file.
+    /// On failure, the compiler messages are in the returned [`BlobError`].
+    /// Defines containing nul bytes fail with `E_INVALIDARG` before compiling.
+    pub fn compile_from_file(
+        path: &Path,
+        target: &ffi::CStr,
+        entry: &ffi::CStr,
+        defines: &[(String, String)],
+        flags: ShaderCompileFlags,
+    ) -> Result<Blob, BlobError> {
+        let mut shader = Blob::null();
+        let mut error = Error::null();
+
+        let path = path
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect::<Vec<u16>>();
+        let defines = defines
+            .iter()
+            .map(|(name, value)| {
+                Ok((
+                    ffi::CString::new(name.as_str())?,
+                    ffi::CString::new(value.as_str())?,
+                ))
+            })
+            .collect::<Result<Vec<_>, ffi::NulError>>();
+        let defines = match defines {
+            Ok(defines) => defines,
+            Err(_) => {
+                return Err(BlobError {
+                    hr: winerror::E_INVALIDARG,
+                    message: None,
+                })
+            }
+        };
+        let macros = defines
+            .iter()
+            .map(|(name, value)| d3dcommon::D3D_SHADER_MACRO {
+                Name: name.as_ptr(),
+                Definition: value.as_ptr(),
+            })
+            .chain(Some(d3dcommon::D3D_SHADER_MACRO {
+                Name: ptr::null(),
+                Definition: ptr::null(),
+            }))
+            .collect::<Vec<_>>();
+
+        let hr = unsafe {
+            d3dcompiler::D3DCompileFromFile(
+                path.as_ptr(),
+                macros.as_ptr(),
+                D3D_COMPILE_STANDARD_FILE_INCLUDE,
+                entry.as_ptr() as *const _,
+                target.as_ptr() as *const _,
+                flags.bits(),
+                0,
+                shader.mut_void() as *mut *mut _,
+                error.mut_void() as *mut *mut _,
+            )
+        };
+
+        blob_result(((shader, error), hr))
+    }
+
+    /// Compiles on a new thread, as [`compile`](Self::compile) would.
+    ///
+    /// The blobs aren't `Send`, they are only moved out of the compiling thread once it's done
+    /// and then stay on the thread polling the future.
+    pub fn compile_async(
+        code: Vec<u8>,
+        target: ffi::CString,
+        entry: ffi::CString,
+        flags: ShaderCompileFlags,
+    ) -> CompileFuture {
+        let shared = Arc::new(Mutex::new(CompileState {
+            result: None,
+            waker: None,
+        }));
+        let mut completion = CompileCompletion {
+            shared: Arc::clone(&shared),
+            result: None,
+        };
+        thread::spawn(move || {
+            let result = Shader::compile(&code, &target, &entry, flags);
+            completion.result = Some(CompiledBlobs(result));
+        });
+
+        CompileFuture { shared }
+    }
+}
+
+struct CompiledBlobs(Result<Blob, BlobError>);
+
+// Only created and moved out of the compiling thread once, and blobs are free-threaded.
+unsafe impl Send for CompiledBlobs {}
+
+struct CompileState {
+    result: Option<CompiledBlobs>,
+    waker: Option<Waker>,
+}
+
+/// Hands the result over to the future once dropped, failing it with `E_FAIL`
+/// if the compiling thread panicked before producing one.
+struct CompileCompletion {
+    shared: Arc<Mutex<CompileState>>,
+    result: Option<CompiledBlobs>,
+}
+
+impl Drop for CompileCompletion {
+    fn drop(&mut self) {
+        let result = self.result.take().unwrap_or_else(|| {
+            CompiledBlobs(Err(BlobError {
+                hr: winerror::E_FAIL,
+                message: Some("Shader compilation thread panicked".to_string()),
+            }))
+        });
+        let mut state = self
+            .shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
     }
 }
 
+/// Pending compilation started by [`Shader::compile_async`].
+pub struct CompileFuture {
+    shared: Arc<Mutex<CompileState>>,
+}
+
+impl Future for CompileFuture {
+    type Output = Result<Blob, BlobError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self
+            .shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match state.result.take() {
+            Some(CompiledBlobs(result)) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Default include handler of the compiler, opening includes relative to the current file.
+const D3D_COMPILE_STANDARD_FILE_INCLUDE: *mut d3dcommon::ID3DInclude = 1 as *mut _;
+
 impl<'a> Deref for Shader<'a> {
     type Target = d3d12::D3D12_SHADER_BYTECODE;
     fn deref(&self) -> &Self::Target {
@@ -180,3 +366,926 @@ impl<T> PipelineStateSubobject<T> {
         }
     }
 }
+
+/// Vertex attribute of an input layout, owning its semantic name.
+#[derive(Clone, Debug)]
+pub struct InputElement {
+    semantic_name: ffi::CString,
+    semantic_index: u32,
+    format: Format,
+    input_slot: u32,
+    aligned_byte_offset: u32,
+    input_slot_class: d3d12::D3D12_INPUT_CLASSIFICATION,
+    instance_data_step_rate: u32,
+}
+
+impl InputElement {
+    /// Per-vertex attribute, `aligned_byte_offset` may be `D3D12_APPEND_ALIGNED_ELEMENT`.
+    ///
+    /// Panics if `semantic_name` contains a nul byte.
+    pub fn new(
+        semantic_name: &str,
+        semantic_index: u32,
+        format: Format,
+        input_slot: u32,
+        aligned_byte_offset: u32,
+    ) -> Self {
+        InputElement {
+            semantic_name: ffi::CString::new(semantic_name)
+                .expect("Semantic name contains a nul byte"),
+            semantic_index,
+            format,
+            input_slot,
+            aligned_byte_offset,
+            input_slot_class: d3d12::D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+            instance_data_step_rate: 0,
+        }
+    }
+
+    /// Advances the attribute once per `step_rate` instances instead of per vertex.
+    pub fn per_instance(mut self, step_rate: u32) -> Self {
+        self.input_slot_class = d3d12::D3D12_INPUT_CLASSIFICATION_PER_INSTANCE_DATA;
+        self.instance_data_step_rate = step_rate;
+        self
+    }
+
+    /// Borrows the semantic name, which must outlive the returned description.
+    fn to_raw(&self) -> d3d12::D3D12_INPUT_ELEMENT_DESC {
+        d3d12::D3D12_INPUT_ELEMENT_DESC {
+            SemanticName: self.semantic_name.as_ptr(),
+            SemanticIndex: self.semantic_index,
+            Format: self.format,
+            InputSlot: self.input_slot,
+            AlignedByteOffset: self.aligned_byte_offset,
+            InputSlotClass: self.input_slot_class,
+            InstanceDataStepRate: self.instance_data_step_rate,
+        }
+    }
+}
+
+/// Blending of a single render target, see [`BlendDesc`].
+///
+/// Defaults to blending disabled and all channels written.
+#[derive(Clone, Copy)]
+pub struct RenderTargetBlend(d3d12::D3D12_RENDER_TARGET_BLEND_DESC);
+
+impl Default for RenderTargetBlend {
+    fn default() -> Self {
+        RenderTargetBlend(d3d12::D3D12_RENDER_TARGET_BLEND_DESC {
+            BlendEnable: FALSE,
+            LogicOpEnable: FALSE,
+            SrcBlend: d3d12::D3D12_BLEND_ONE,
+            DestBlend: d3d12::D3D12_BLEND_ZERO,
+            BlendOp: d3d12::D3D12_BLEND_OP_ADD,
+            SrcBlendAlpha: d3d12::D3D12_BLEND_ONE,
+            DestBlendAlpha: d3d12::D3D12_BLEND_ZERO,
+            BlendOpAlpha: d3d12::D3D12_BLEND_OP_ADD,
+            LogicOp: d3d12::D3D12_LOGIC_OP_NOOP,
+            RenderTargetWriteMask: d3d12::D3D12_COLOR_WRITE_ENABLE_ALL as _,
+        })
+    }
+}
+
+impl RenderTargetBlend {
+    /// Blending of non-premultiplied colors over the render target.
+    pub fn alpha_blending() -> Self {
+        Self::default()
+            .color(
+                d3d12::D3D12_BLEND_SRC_ALPHA,
+                d3d12::D3D12_BLEND_INV_SRC_ALPHA,
+                d3d12::D3D12_BLEND_OP_ADD,
+            )
+            .alpha(
+                d3d12::D3D12_BLEND_ONE,
+                d3d12::D3D12_BLEND_INV_SRC_ALPHA,
+                d3d12::D3D12_BLEND_OP_ADD,
+            )
+    }
+
+    /// Enables blending, with `src` and `dst` factors for the color channels.
+    ///
+    /// Disables the logic operation, both can't be enabled at once.
+    pub fn color(
+        mut self,
+        src: d3d12::D3D12_BLEND,
+        dst: d3d12::D3D12_BLEND,
+        op: d3d12::D3D12_BLEND_OP,
+    ) -> Self {
+        self.0.BlendEnable = TRUE;
+        self.0.LogicOpEnable = FALSE;
+        self.0.SrcBlend = src;
+        self.0.DestBlend = dst;
+        self.0.BlendOp = op;
+        self
+    }
+
+    /// Enables blending, with `src` and `dst` factors for the alpha channel.
+    ///
+    /// Disables the logic operation, both can't be enabled at once.
+    pub fn alpha(
+        mut self,
+        src: d3d12::D3D12_BLEND,
+        dst: d3d12::D3D12_BLEND,
+        op: d3d12::D3D12_BLEND_OP,
+    ) -> Self {
+        self.0.BlendEnable = TRUE;
+        self.0.LogicOpEnable = FALSE;
+        self.0.SrcBlendAlpha = src;
+        self.0.DestBlendAlpha = dst;
+        self.0.BlendOpAlpha = op;
+        self
+    }
+
+    /// Replaces blending by a logic operation, only valid for integer formats.
+    pub fn logic_op(mut self, op: d3d12::D3D12_LOGIC_OP) -> Self {
+        self.0.BlendEnable = FALSE;
+        self.0.LogicOpEnable = TRUE;
+        self.0.LogicOp = op;
+        self
+    }
+
+    /// Bits of `D3D12_COLOR_WRITE_ENABLE`.
+    pub fn write_mask(mut self, mask: u8) -> Self {
+        self.0.RenderTargetWriteMask = mask;
+        self
+    }
+}
+
+/// Blend state of a pipeline, defaulting to `CD3DX12_BLEND_DESC(D3D12_DEFAULT)`.
+#[derive(Clone, Copy)]
+pub struct BlendDesc(d3d12::D3D12_BLEND_DESC);
+
+impl Default for BlendDesc {
+    fn default() -> Self {
+        BlendDesc(d3d12::D3D12_BLEND_DESC {
+            AlphaToCoverageEnable: FALSE,
+            IndependentBlendEnable: FALSE,
+            RenderTarget: [RenderTargetBlend::default().0; 8],
+        })
+    }
+}
+
+impl BlendDesc {
+    pub fn alpha_to_coverage(mut self, enable: bool) -> Self {
+        self.0.AlphaToCoverageEnable = enable as _;
+        self
+    }
+
+    /// Blends all render targets the same way.
+    pub fn all_render_targets(mut self, blend: RenderTargetBlend) -> Self {
+        self.0.IndependentBlendEnable = FALSE;
+        self.0.RenderTarget = [blend.0; 8];
+        self
+    }
+
+    /// Blends render target `index` on its own, enabling independent blending for
+    /// any index but 0, as only the first entry is used otherwise.
+    ///
+    /// `None` if `index` isn't below `D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT`.
+    pub fn render_target(mut self, index: usize, blend: RenderTargetBlend) -> Option<Self> {
+        *self.0.RenderTarget.get_mut(index)? = blend.0;
+        if index != 0 {
+            self.0.IndependentBlendEnable = TRUE;
+        }
+        Some(self)
+    }
+}
+
+impl From<BlendDesc> for d3d12::D3D12_BLEND_DESC {
+    fn from(desc: BlendDesc) -> Self {
+        desc.0
+    }
+}
+
+/// Rasterizer state of a pipeline, defaulting to `CD3DX12_RASTERIZER_DESC(D3D12_DEFAULT)`.
+#[derive(Clone, Copy)]
+pub struct RasterizerDesc(d3d12::D3D12_RASTERIZER_DESC);
+
+impl Default for RasterizerDesc {
+    fn default() -> Self {
+        RasterizerDesc(d3d12::D3D12_RASTERIZER_DESC {
+            FillMode: d3d12::D3D12_FILL_MODE_SOLID,
+            CullMode: d3d12::D3D12_CULL_MODE_BACK,
+            FrontCounterClockwise: FALSE,
+            DepthBias: 0,
+            DepthBiasClamp: 0.0,
+            SlopeScaledDepthBias: 0.0,
+            DepthClipEnable: TRUE,
+            MultisampleEnable: FALSE,
+            AntialiasedLineEnable: FALSE,
+            ForcedSampleCount: 0,
+            ConservativeRaster: d3d12::D3D12_CONSERVATIVE_RASTERIZATION_MODE_OFF,
+        })
+    }
+}
+
+impl RasterizerDesc {
+    pub fn fill_mode(mut self, mode: d3d12::D3D12_FILL_MODE) -> Self {
+        self.0.FillMode = mode;
+        self
+    }
+
+    pub fn cull_mode(mut self, mode: d3d12::D3D12_CULL_MODE) -> Self {
+        self.0.CullMode = mode;
+        self
+    }
+
+    pub fn front_counter_clockwise(mut self, ccw: bool) -> Self {
+        self.0.FrontCounterClockwise = ccw as _;
+        self
+    }
+
+    pub fn depth_bias(mut self, bias: i32, clamp: f32, slope_scaled: f32) -> Self {
+        self.0.DepthBias = bias;
+        self.0.DepthBiasClamp = clamp;
+        self.0.SlopeScaledDepthBias = slope_scaled;
+        self
+    }
+
+    pub fn depth_clip(mut self, enable: bool) -> Self {
+        self.0.DepthClipEnable = enable as _;
+        self
+    }
+
+    /// Selects the quadrilateral line anti-aliasing algorithm on MSAA targets.
+    pub fn multisample(mut self, enable: bool) -> Self {
+        self.0.MultisampleEnable = enable as _;
+        self
+    }
+
+    pub fn antialiased_lines(mut self, enable: bool) -> Self {
+        self.0.AntialiasedLineEnable = enable as _;
+        self
+    }
+
+    pub fn forced_sample_count(mut self, count: u32) -> Self {
+        self.0.ForcedSampleCount = count;
+        self
+    }
+
+    pub fn conservative(mut self, enable: bool) -> Self {
+        self.0.ConservativeRaster = if enable {
+            d3d12::D3D12_CONSERVATIVE_RASTERIZATION_MODE_ON
+        } else {
+            d3d12::D3D12_CONSERVATIVE_RASTERIZATION_MODE_OFF
+        };
+        self
+    }
+}
+
+impl From<RasterizerDesc> for d3d12::D3D12_RASTERIZER_DESC {
+    fn from(desc: RasterizerDesc) -> Self {
+        desc.0
+    }
+}
+
+/// Depth and stencil state of a pipeline, defaulting to
+/// `CD3DX12_DEPTH_STENCIL_DESC(D3D12_DEFAULT)`: depth tested with `LESS` and written,
+/// without stencil.
+#[derive(Clone, Copy)]
+pub struct DepthStencilDesc(d3d12::D3D12_DEPTH_STENCIL_DESC);
+
+impl Default for DepthStencilDesc {
+    fn default() -> Self {
+        let stencil_op = d3d12::D3D12_DEPTH_STENCILOP_DESC {
+            StencilFailOp: d3d12::D3D12_STENCIL_OP_KEEP,
+            StencilDepthFailOp: d3d12::D3D12_STENCIL_OP_KEEP,
+            StencilPassOp: d3d12::D3D12_STENCIL_OP_KEEP,
+            StencilFunc: d3d12::D3D12_COMPARISON_FUNC_ALWAYS,
+        };
+        DepthStencilDesc(d3d12::D3D12_DEPTH_STENCIL_DESC {
+            DepthEnable: TRUE,
+            DepthWriteMask: d3d12::D3D12_DEPTH_WRITE_MASK_ALL,
+            DepthFunc: d3d12::D3D12_COMPARISON_FUNC_LESS,
+            StencilEnable: FALSE,
+            StencilReadMask: d3d12::D3D12_DEFAULT_STENCIL_READ_MASK as _,
+            StencilWriteMask: d3d12::D3D12_DEFAULT_STENCIL_WRITE_MASK as _,
+            FrontFace: stencil_op,
+            BackFace: stencil_op,
+        })
+    }
+}
+
+impl DepthStencilDesc {
+    pub fn depth_enable(mut self, enable: bool) -> Self {
+        self.0.DepthEnable = enable as _;
+        self
+    }
+
+    pub fn depth_write(mut self, enable: bool) -> Self {
+        self.0.DepthWriteMask = if enable {
+            d3d12::D3D12_DEPTH_WRITE_MASK_ALL
+        } else {
+            d3d12::D3D12_DEPTH_WRITE_MASK_ZERO
+        };
+        self
+    }
+
+    pub fn depth_func(mut self, func: d3d12::D3D12_COMPARISON_FUNC) -> Self {
+        self.0.DepthFunc = func;
+        self
+    }
+
+    pub fn stencil_enable(mut self, enable: bool) -> Self {
+        self.0.StencilEnable = enable as _;
+        self
+    }
+
+    pub fn stencil_masks(mut self, read: u8, write: u8) -> Self {
+        self.0.StencilReadMask = read;
+        self.0.StencilWriteMask = write;
+        self
+    }
+
+    /// Sets the stencil operations of both faces.
+    pub fn stencil_ops(self, ops: d3d12::D3D12_DEPTH_STENCILOP_DESC) -> Self {
+        self.front_face(ops).back_face(ops)
+    }
+
+    pub fn front_face(mut self, ops: d3d12::D3D12_DEPTH_STENCILOP_DESC) -> Self {
+        self.0.FrontFace = ops;
+        self
+    }
+
+    pub fn back_face(mut self, ops: d3d12::D3D12_DEPTH_STENCILOP_DESC) -> Self {
+        self.0.BackFace = ops;
+        self
+    }
+}
+
+impl From<DepthStencilDesc> for d3d12::D3D12_DEPTH_STENCIL_DESC {
+    fn from(desc: DepthStencilDesc) -> Self {
+        desc.0
+    }
+}
+
+/// Parameters of a graphics pipeline, see [`Device::create_graphics_pipeline`](crate::Device::create_graphics_pipeline).
+///
+/// Defaults to triangles rendered without blending, culling back faces, and without
+/// depth or stencil tests.
+pub struct GraphicsPipelineBuilder<'a> {
+    root_signature: Option<&'a RootSignature>,
+    vs: Shader<'a>,
+    ps: Shader<'a>,
+    gs: Shader<'a>,
+    hs: Shader<'a>,
+    ds: Shader<'a>,
+    input_layout: Vec<InputElement>,
+    blend: d3d12::D3D12_BLEND_DESC,
+    sample_mask: u32,
+    rasterizer: d3d12::D3D12_RASTERIZER_DESC,
+    depth_stencil: d3d12::D3D12_DEPTH_STENCIL_DESC,
+    primitive_topology_type: d3d12::D3D12_PRIMITIVE_TOPOLOGY_TYPE,
+    render_target_formats: Vec<Format>,
+    depth_stencil_format: Format,
+    sample_desc: SampleDesc,
+    node_mask: NodeMask,
+    cached_pso: CachedPSO<'a>,
+    flags: PipelineStateFlags,
+}
+
+impl<'a> Default for GraphicsPipelineBuilder<'a> {
+    fn default() -> Self {
+        GraphicsPipelineBuilder {
+            root_signature: None,
+            vs: Shader::null(),
+            ps: Shader::null(),
+            gs: Shader::null(),
+            hs: Shader::null(),
+            ds: Shader::null(),
+            input_layout: Vec::new(),
+            blend: BlendDesc::default().0,
+            sample_mask: !0,
+            rasterizer: RasterizerDesc::default().0,
+            depth_stencil: DepthStencilDesc::default().depth_enable(false).0,
+            primitive_topology_type: d3d12::D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+            render_target_formats: Vec::new(),
+            depth_stencil_format: dxgiformat::DXGI_FORMAT_UNKNOWN,
+            sample_desc: SampleDesc {
+                count: 1,
+                quality: 0,
+            },
+            node_mask: 0,
+            cached_pso: CachedPSO::null(),
+            flags: PipelineStateFlags::empty(),
+        }
+    }
+}
+
+impl<'a> GraphicsPipelineBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn root_signature(mut self, root_signature: &'a RootSignature) -> Self {
+        self.root_signature = Some(root_signature);
+        self
+    }
+
+    pub fn vertex_shader(mut self, shader: Shader<'a>) -> Self {
+        self.vs = shader;
+        self
+    }
+
+    pub fn pixel_shader(mut self, shader: Shader<'a>) -> Self {
+        self.ps = shader;
+        self
+    }
+
+    pub fn geometry_shader(mut self, shader: Shader<'a>) -> Self {
+        self.gs = shader;
+        self
+    }
+
+    pub fn hull_shader(mut self, shader: Shader<'a>) -> Self {
+        self.hs = shader;
+        self
+    }
+
+    pub fn domain_shader(mut self, shader: Shader<'a>) -> Self {
+        self.ds = shader;
+        self
+    }
+
+    pub fn input_layout(mut self, elements: &[InputElement]) -> Self {
+        self.input_layout = elements.to_vec();
+        self
+    }
+
+    /// Panics if more than `D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT` formats are given.
+    pub fn render_target_formats(mut self, formats: &[Format]) -> Self {
+        assert!(
+            formats.len() <= d3d12::D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT as usize,
+            "Too many render targets: {}",
+            formats.len()
+        );
+        self.render_target_formats = formats.to_vec();
+        self
+    }
+
+    pub fn depth_stencil_format(mut self, format: Format) -> Self {
+        self.depth_stencil_format = format;
+        self
+    }
+
+    pub fn depth_stencil(mut self, desc: impl Into<d3d12::D3D12_DEPTH_STENCIL_DESC>) -> Self {
+        self.depth_stencil = desc.into();
+        self
+    }
+
+    pub fn rasterizer(mut self, desc: impl Into<d3d12::D3D12_RASTERIZER_DESC>) -> Self {
+        self.rasterizer = desc.into();
+        self
+    }
+
+    pub fn blend(mut self, desc: impl Into<d3d12::D3D12_BLEND_DESC>) -> Self {
+        self.blend = desc.into();
+        self
+    }
+
+    pub fn sample_mask(mut self, mask: u32) -> Self {
+        self.sample_mask = mask;
+        self
+    }
+
+    pub fn sample_desc(mut self, desc: SampleDesc) -> Self {
+        self.sample_desc = desc;
+        self
+    }
+
+    pub fn primitive_topology_type(
+        mut self,
+        topology_type: d3d12::D3D12_PRIMITIVE_TOPOLOGY_TYPE,
+    ) -> Self {
+        self.primitive_topology_type = topology_type;
+        self
+    }
+
+    pub fn node_mask(mut self, node_mask: NodeMask) -> Self {
+        self.node_mask = node_mask;
+        self
+    }
+
+    pub fn cached_pso(mut self, cached_pso: CachedPSO<'a>) -> Self {
+        self.cached_pso = cached_pso;
+        self
+    }
+
+    pub fn flags(mut self, flags: PipelineStateFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Calls `f` with the raw description, which borrows the input layout of `self`.
+    pub(crate) fn with_raw<R>(
+        &self,
+        f: impl FnOnce(&d3d12::D3D12_GRAPHICS_PIPELINE_STATE_DESC) -> R,
+    ) -> R {
+        let input_elements = self
+            .input_layout
+            .iter()
+            .map(InputElement::to_raw)
+            .collect::<Vec<_>>();
+        let mut rtv_formats = [dxgiformat::DXGI_FORMAT_UNKNOWN; 8];
+        rtv_formats[..self.render_target_formats.len()]
+            .copy_from_slice(&self.render_target_formats);
+
+        let desc = d3d12::D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+            pRootSignature: self
+                .root_signature
+                .map_or(ptr::null_mut(), |signature| signature.as_mut_ptr()),
+            VS: *self.vs,
+            PS: *self.ps,
+            DS: *self.ds,
+            HS: *self.hs,
+            GS: *self.gs,
+            StreamOutput: unsafe { mem::zeroed() },
+            BlendState: self.blend,
+            SampleMask: self.sample_mask,
+            RasterizerState: self.rasterizer,
+            DepthStencilState: self.depth_stencil,
+            InputLayout: d3d12::D3D12_INPUT_LAYOUT_DESC {
+                pInputElementDescs: input_elements.as_ptr(),
+                NumElements: input_elements.len() as _,
+            },
+            IBStripCutValue: d3d12::D3D12_INDEX_BUFFER_STRIP_CUT_VALUE_DISABLED,
+            PrimitiveTopologyType: self.primitive_topology_type,
+            NumRenderTargets: self.render_target_formats.len() as _,
+            RTVFormats: rtv_formats,
+            DSVFormat: self.depth_stencil_format,
+            SampleDesc: dxgitype::DXGI_SAMPLE_DESC {
+                Count: self.sample_desc.count,
+                Quality: self.sample_desc.quality,
+            },
+            NodeMask: self.node_mask,
+            CachedPSO: *self.cached_pso,
+            Flags: self.flags.bits(),
+        };
+
+        f(&desc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_blend_eq(
+        a: &d3d12::D3D12_RENDER_TARGET_BLEND_DESC,
+        b: &d3d12::D3D12_RENDER_TARGET_BLEND_DESC,
+    ) {
+        assert_eq!(
+            (
+                a.BlendEnable,
+                a.LogicOpEnable,
+                a.LogicOp,
+                a.RenderTargetWriteMask
+            ),
+            (
+                b.BlendEnable,
+                b.LogicOpEnable,
+                b.LogicOp,
+                b.RenderTargetWriteMask
+            )
+        );
+        assert_eq!(
+            (a.SrcBlend, a.DestBlend, a.BlendOp),
+            (b.SrcBlend, b.DestBlend, b.BlendOp)
+        );
+        assert_eq!(
+            (a.SrcBlendAlpha, a.DestBlendAlpha, a.BlendOpAlpha),
+            (b.SrcBlendAlpha, b.DestBlendAlpha, b.BlendOpAlpha)
+        );
+    }
+
+    #[test]
+    fn default_states() {
+        // `CD3DX12_BLEND_DESC(D3D12_DEFAULT)`
+        let blend = BlendDesc::default().0;
+        assert_eq!(blend.AlphaToCoverageEnable, FALSE);
+        assert_eq!(blend.IndependentBlendEnable, FALSE);
+        let default_rt = d3d12::D3D12_RENDER_TARGET_BLEND_DESC {
+            BlendEnable: FALSE,
+            LogicOpEnable: FALSE,
+            SrcBlend: d3d12::D3D12_BLEND_ONE,
+            DestBlend: d3d12::D3D12_BLEND_ZERO,
+            BlendOp: d3d12::D3D12_BLEND_OP_ADD,
+            SrcBlendAlpha: d3d12::D3D12_BLEND_ONE,
+            DestBlendAlpha: d3d12::D3D12_BLEND_ZERO,
+            BlendOpAlpha: d3d12::D3D12_BLEND_OP_ADD,
+            LogicOp: d3d12::D3D12_LOGIC_OP_NOOP,
+            RenderTargetWriteMask: d3d12::D3D12_COLOR_WRITE_ENABLE_ALL as _,
+        };
+        for rt in blend.RenderTarget.iter() {
+            assert_blend_eq(rt, &default_rt);
+        }
+
+        // `CD3DX12_RASTERIZER_DESC(D3D12_DEFAULT)`
+        let raster = RasterizerDesc::default().0;
+        assert_eq!(raster.FillMode, d3d12::D3D12_FILL_MODE_SOLID);
+        assert_eq!(raster.CullMode, d3d12::D3D12_CULL_MODE_BACK);
+        assert_eq!(raster.FrontCounterClockwise, FALSE);
+        assert_eq!(raster.DepthBias, 0);
+        assert_eq!(raster.DepthBiasClamp, 0.0);
+        assert_eq!(raster.SlopeScaledDepthBias, 0.0);
+        assert_eq!(raster.DepthClipEnable, TRUE);
+        assert_eq!(raster.MultisampleEnable, FALSE);
+        assert_eq!(raster.AntialiasedLineEnable, FALSE);
+        assert_eq!(raster.ForcedSampleCount, 0);
+        assert_eq!(
+            raster.ConservativeRaster,
+            d3d12::D3D12_CONSERVATIVE_RASTERIZATION_MODE_OFF
+        );
+
+        // `CD3DX12_DEPTH_STENCIL_DESC(D3D12_DEFAULT)`
+        let depth = DepthStencilDesc::default().0;
+        assert_eq!(depth.DepthEnable, TRUE);
+        assert_eq!(depth.DepthWriteMask, d3d12::D3D12_DEPTH_WRITE_MASK_ALL);
+        assert_eq!(depth.DepthFunc, d3d12::D3D12_COMPARISON_FUNC_LESS);
+        assert_eq!(depth.StencilEnable, FALSE);
+        assert_eq!(depth.StencilReadMask, 0xFF);
+        assert_eq!(depth.StencilWriteMask, 0xFF);
+        for face in [depth.FrontFace, depth.BackFace].iter() {
+            assert_eq!(face.StencilFailOp, d3d12::D3D12_STENCIL_OP_KEEP);
+            assert_eq!(face.StencilDepthFailOp, d3d12::D3D12_STENCIL_OP_KEEP);
+            assert_eq!(face.StencilPassOp, d3d12::D3D12_STENCIL_OP_KEEP);
+            assert_eq!(face.StencilFunc, d3d12::D3D12_COMPARISON_FUNC_ALWAYS);
+        }
+    }
+
+    #[test]
+    fn alpha_blending_preset() {
+        let rt = RenderTargetBlend::alpha_blending().0;
+        assert_eq!(rt.BlendEnable, TRUE);
+        assert_eq!(rt.LogicOpEnable, FALSE);
+        assert_eq!(rt.SrcBlend, d3d12::D3D12_BLEND_SRC_ALPHA);
+        assert_eq!(rt.DestBlend, d3d12::D3D12_BLEND_INV_SRC_ALPHA);
+        assert_eq!(rt.BlendOp, d3d12::D3D12_BLEND_OP_ADD);
+        assert_eq!(rt.SrcBlendAlpha, d3d12::D3D12_BLEND_ONE);
+        assert_eq!(rt.DestBlendAlpha, d3d12::D3D12_BLEND_INV_SRC_ALPHA);
+        assert_eq!(rt.BlendOpAlpha, d3d12::D3D12_BLEND_OP_ADD);
+        assert_eq!(
+            rt.RenderTargetWriteMask,
+            d3d12::D3D12_COLOR_WRITE_ENABLE_ALL as u8
+        );
+    }
+
+    #[test]
+    fn blend_after_logic_op() {
+        let rt = RenderTargetBlend::default()
+            .logic_op(d3d12::D3D12_LOGIC_OP_XOR)
+            .color(
+                d3d12::D3D12_BLEND_ONE,
+                d3d12::D3D12_BLEND_ONE,
+                d3d12::D3D12_BLEND_OP_ADD,
+            )
+            .0;
+        assert_eq!((rt.BlendEnable, rt.LogicOpEnable), (TRUE, FALSE));
+
+        let rt = RenderTargetBlend::alpha_blending()
+            .logic_op(d3d12::D3D12_LOGIC_OP_XOR)
+            .0;
+        assert_eq!((rt.BlendEnable, rt.LogicOpEnable), (FALSE, TRUE));
+    }
+
+    #[test]
+    fn independent_render_targets() {
+        let blend = BlendDesc::default()
+            .render_target(0, RenderTargetBlend::alpha_blending())
+            .unwrap();
+        assert_eq!(blend.0.IndependentBlendEnable, FALSE);
+
+        let blend = blend
+            .render_target(7, RenderTargetBlend::alpha_blending())
+            .unwrap();
+        assert_eq!(blend.0.IndependentBlendEnable, TRUE);
+        assert_blend_eq(
+            &blend.0.RenderTarget[7],
+            &RenderTargetBlend::alpha_blending().0,
+        );
+        assert_blend_eq(&blend.0.RenderTarget[1], &RenderTargetBlend::default().0);
+
+        assert!(blend
+            .render_target(8, RenderTargetBlend::default())
+            .is_none());
+    }
+
+    #[test]
+    fn nul_in_define() {
+        let target = ffi::CString::new("ps_5_0").unwrap();
+        let entry = ffi::CString::new("main").unwrap();
+        let defines = [("VALUE".to_string(), "1\0".to_string())];
+        let result = Shader::compile_from_file(
+            Path::new("missing.hlsl"),
+            &target,
+            &entry,
+            &defines,
+            ShaderCompileFlags::empty(),
+        );
+        assert_eq!(result.unwrap_err().hr, winerror::E_INVALIDARG);
+    }
+
+    fn poll_once(future: &mut CompileFuture) -> Poll<Result<Blob, BlobError>> {
+        use std::task::{RawWaker, RawWakerVTable};
+
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let waker = unsafe { Waker::from_raw(clone(ptr::null())) };
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn compile_completion_without_result() {
+        let shared = Arc::new(Mutex::new(CompileState {
+            result: None,
+            waker: None,
+        }));
+        let mut future = CompileFuture {
+            shared: Arc::clone(&shared),
+        };
+        let completion = CompileCompletion {
+            shared,
+            result: None,
+        };
+        assert!(poll_once(&mut future).is_pending());
+
+        drop(completion);
+        match poll_once(&mut future) {
+            Poll::Ready(Err(error)) => assert_eq!(error.hr, winerror::E_FAIL),
+            _ => panic!("Dropped completion didn't fail the future"),
+        }
+    }
+
+    #[test]
+    fn compile_error_message() {
+        let code = b"float4 main() : SV_Target { return undefined_color; }";
+        let target = ffi::CString::new("ps_5_0").unwrap();
+        let entry = ffi::CString::new("main").unwrap();
+        let error =
+            Shader::compile(code, &target, &entry, ShaderCompileFlags::empty()).unwrap_err();
+        assert!(winerror::FAILED(error.hr));
+        let message = error.message.unwrap();
+        assert!(message.contains("undefined_color"), "{}", message);
+    }
+
+    #[test]
+    fn compile_from_file_with_include() {
+        let dir = std::env::temp_dir().join(format!("d3d12-include-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("common.hlsli"),
+            "float4 color() { return float4(COLOR, 1.0); }\n",
+        )
+        .unwrap();
+        let path = dir.join("main.hlsl");
+        std::fs::write(
+            &path,
+            "#include \"common.hlsli\"\nfloat4 main() : SV_Target { return color(); }\n",
+        )
+        .unwrap();
+
+        let target = ffi::CString::new("ps_5_0").unwrap();
+        let entry = ffi::CString::new("main").unwrap();
+        let compile = |defines: &[(String, String)]| {
+            Shader::compile_from_file(&path, &target, &entry, defines, ShaderCompileFlags::empty())
+        };
+        let result = compile(&[("COLOR".to_string(), "0.5, 0.5, 0.5".to_string())]);
+        // The header needs the define, the error names the missing identifier.
+        let error = compile(&[]).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let blob = result.unwrap();
+        assert_ne!(unsafe { blob.GetBufferSize() }, 0);
+        assert!(winerror::FAILED(error.hr));
+        assert!(error.message.unwrap().contains("COLOR"));
+    }
+
+    #[test]
+    fn compile_async_concurrently() {
+        let entry = ffi::CString::new("main").unwrap();
+        let shaders: [(&[u8], &str); 3] = [
+            (b"float4 main() : SV_Target { return 1.0; }", "ps_5_0"),
+            (
+                b"float4 main(float4 p : POSITION) : SV_Position { return p; }",
+                "vs_5_0",
+            ),
+            (b"[numthreads(8, 8, 1)] void main() {}", "cs_5_0"),
+        ];
+        let mut futures = shaders
+            .iter()
+            .map(|&(code, target)| {
+                Shader::compile_async(
+                    code.to_vec(),
+                    ffi::CString::new(target).unwrap(),
+                    entry.clone(),
+                    ShaderCompileFlags::empty(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // The threads all compile meanwhile, each result is only awaited here.
+        for future in &mut futures {
+            let blob = loop {
+                match poll_once(future) {
+                    Poll::Ready(result) => break result.unwrap(),
+                    Poll::Pending => thread::sleep(std::time::Duration::from_millis(1)),
+                }
+            };
+            assert_ne!(unsafe { blob.GetBufferSize() }, 0);
+        }
+    }
+
+    fn pos_color_builder<'a>() -> GraphicsPipelineBuilder<'a> {
+        // The elements are dropped before the builder is used, it has to own the semantic names.
+        let elements = vec![
+            InputElement::new("POSITION", 0, dxgiformat::DXGI_FORMAT_R32G32B32_FLOAT, 0, 0),
+            InputElement::new(
+                "COLOR",
+                0,
+                dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM,
+                0,
+                d3d12::D3D12_APPEND_ALIGNED_ELEMENT,
+            ),
+        ];
+        GraphicsPipelineBuilder::new()
+            .input_layout(&elements)
+            .render_target_formats(&[dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM])
+            .depth_stencil(DepthStencilDesc::default().depth_enable(false))
+            .primitive_topology_type(d3d12::D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE)
+    }
+
+    #[test]
+    fn pos_color_desc() {
+        pos_color_builder().with_raw(|desc| {
+            let layout = unsafe {
+                std::slice::from_raw_parts(
+                    desc.InputLayout.pInputElementDescs,
+                    desc.InputLayout.NumElements as usize,
+                )
+            };
+            let names = layout
+                .iter()
+                .map(|element| unsafe { ffi::CStr::from_ptr(element.SemanticName) }.to_bytes())
+                .collect::<Vec<_>>();
+            assert_eq!(names, [&b"POSITION"[..], b"COLOR"]);
+            assert_eq!(
+                layout[1].AlignedByteOffset,
+                d3d12::D3D12_APPEND_ALIGNED_ELEMENT
+            );
+            assert_eq!(desc.NumRenderTargets, 1);
+            assert_eq!(desc.RTVFormats[0], dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM);
+            assert_eq!(desc.RTVFormats[1], dxgiformat::DXGI_FORMAT_UNKNOWN);
+            assert_eq!(desc.DSVFormat, dxgiformat::DXGI_FORMAT_UNKNOWN);
+        });
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn create_pos_color_pipeline() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let blob = RootSignature::serialize(
+            crate::RootSignatureVersion::V1_0,
+            &[],
+            &[],
+            crate::RootSignatureFlags::ALLOW_IA_INPUT_LAYOUT,
+        )
+        .unwrap();
+        let (signature, hr) = device.create_root_signature(blob, 0);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let entry = ffi::CString::new("main").unwrap();
+        let compile = |code: &[u8], target: &str| {
+            let target = ffi::CString::new(target).unwrap();
+            Shader::compile(code, &target, &entry, ShaderCompileFlags::empty()).unwrap()
+        };
+        let vs = compile(
+            b"struct Out { float4 pos : SV_Position; float4 color : COLOR; };
+            Out main(float3 pos : POSITION, float4 color : COLOR) {
+                Out out = { float4(pos, 1), color };
+                return out;
+            }",
+            "vs_5_0",
+        );
+        let ps = compile(
+            b"float4 main(float4 pos : SV_Position, float4 color : COLOR) : SV_Target {
+                return color;
+            }",
+            "ps_5_0",
+        );
+
+        let builder = pos_color_builder()
+            .root_signature(&signature)
+            .vertex_shader(Shader::from_blob(&vs))
+            .pixel_shader(Shader::from_blob(&ps));
+        let (pipeline, hr) = device.create_graphics_pipeline(&builder);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(!pipeline.is_null());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::com::ComPtr;
+use crate::{com::ComPtr, D3DResult, Device};
+use std::{mem, ptr};
 use winapi::um::d3d12;
 
 #[repr(u32)]
@@ -9,7 +10,401 @@ pub enum QueryHeapType {
     PipelineStatistics = d3d12::D3D12_QUERY_HEAP_TYPE_PIPELINE_STATISTICS,
     SOStatistics = d3d12::D3D12_QUERY_HEAP_TYPE_SO_STATISTICS,
     // VideoDecodeStatistcs = d3d12::D3D12_QUERY_HEAP_TYPE_VIDEO_DECODE_STATISTICS,
-    // CopyQueueTimestamp = d3d12::D3D12_QUERY_HEAP_TYPE_COPY_QUEUE_TIMESTAMP,
+    /// Timestamps on copy queues, see [`Device::copy_queue_timestamp_queries_supported`].
+    CopyQueueTimestamp = crate::types::D3D12_QUERY_HEAP_TYPE_COPY_QUEUE_TIMESTAMP,
+}
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone)]
+pub enum QueryType {
+    Occlusion = d3d12::D3D12_QUERY_TYPE_OCCLUSION,
+    BinaryOcclusion = d3d12::D3D12_QUERY_TYPE_BINARY_OCCLUSION,
+    Timestamp = d3d12::D3D12_QUERY_TYPE_TIMESTAMP,
+    PipelineStatistics = d3d12::D3D12_QUERY_TYPE_PIPELINE_STATISTICS,
+    SOStatisticsStream0 = d3d12::D3D12_QUERY_TYPE_SO_STATISTICS_STREAM0,
+    SOStatisticsStream1 = d3d12::D3D12_QUERY_TYPE_SO_STATISTICS_STREAM1,
+    SOStatisticsStream2 = d3d12::D3D12_QUERY_TYPE_SO_STATISTICS_STREAM2,
+    SOStatisticsStream3 = d3d12::D3D12_QUERY_TYPE_SO_STATISTICS_STREAM3,
+}
+
+/// Stream output stream counted by a stream output statistics query.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StreamOutputStream {
+    Stream0 = 0,
+    Stream1 = 1,
+    Stream2 = 2,
+    Stream3 = 3,
+}
+
+impl StreamOutputStream {
+    /// Query type to begin, end and resolve statistics of this stream with.
+    pub fn query_type(self) -> QueryType {
+        match self {
+            StreamOutputStream::Stream0 => QueryType::SOStatisticsStream0,
+            StreamOutputStream::Stream1 => QueryType::SOStatisticsStream1,
+            StreamOutputStream::Stream2 => QueryType::SOStatisticsStream2,
+            StreamOutputStream::Stream3 => QueryType::SOStatisticsStream3,
+        }
+    }
 }
 
 pub type QueryHeap = ComPtr<d3d12::ID3D12QueryHeap>;
+
+impl QueryHeap {
+    pub fn new_timestamp(device: &Device, count: u32) -> D3DResult<Self> {
+        device.create_query_heap(QueryHeapType::Timestamp, count, 0)
+    }
+}
+
+/// Converts a difference of timestamps to seconds, `frequency` being the
+/// timestamp frequency of the queue in ticks per second.
+pub fn ticks_to_seconds(delta: u64, frequency: u64) -> f64 {
+    delta as f64 / frequency as f64
+}
+
+/// Reads the passed sample count of an occlusion query from resolved query data,
+/// 0 or 1 for binary queries.
+pub fn occlusion_result_from_bytes(bytes: &[u8]) -> u64 {
+    assert!(bytes.len() >= mem::size_of::<u64>());
+    unsafe { ptr::read_unaligned(bytes.as_ptr() as *const u64) }
+}
+
+/// Result of a stream output statistics query, laid out as `D3D12_QUERY_DATA_SO_STATISTICS`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct StreamOutputStatistics {
+    pub num_primitives_written: u64,
+    /// Primitives that would have been written with unlimited buffer space.
+    pub primitives_storage_needed: u64,
+}
+
+impl StreamOutputStatistics {
+    /// Reads the statistics from resolved query data.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert!(bytes.len() >= mem::size_of::<d3d12::D3D12_QUERY_DATA_SO_STATISTICS>());
+        unsafe { ptr::read_unaligned(bytes.as_ptr() as *const Self) }
+    }
+}
+
+/// Result of a pipeline statistics query, laid out as `D3D12_QUERY_DATA_PIPELINE_STATISTICS`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PipelineStatistics {
+    pub ia_vertices: u64,
+    pub ia_primitives: u64,
+    pub vs_invocations: u64,
+    pub gs_invocations: u64,
+    pub gs_primitives: u64,
+    pub c_invocations: u64,
+    pub c_primitives: u64,
+    pub ps_invocations: u64,
+    pub hs_invocations: u64,
+    pub ds_invocations: u64,
+    pub cs_invocations: u64,
+}
+
+impl PipelineStatistics {
+    /// Reads the statistics from resolved query data.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert!(bytes.len() >= mem::size_of::<d3d12::D3D12_QUERY_DATA_PIPELINE_STATISTICS>());
+        unsafe { ptr::read_unaligned(bytes.as_ptr() as *const Self) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_query_types() {
+        let streams = [
+            StreamOutputStream::Stream0,
+            StreamOutputStream::Stream1,
+            StreamOutputStream::Stream2,
+            StreamOutputStream::Stream3,
+        ];
+        for (i, stream) in streams.iter().enumerate() {
+            assert_eq!(
+                stream.query_type() as u32,
+                d3d12::D3D12_QUERY_TYPE_SO_STATISTICS_STREAM0 + i as u32
+            );
+        }
+    }
+
+    #[test]
+    fn stream_output_statistics_from_bytes() {
+        let mut bytes = vec![0u8; 1];
+        bytes.extend_from_slice(&7u64.to_ne_bytes());
+        bytes.extend_from_slice(&9u64.to_ne_bytes());
+        // Resolved data can sit at any offset of a mapped buffer.
+        let statistics = StreamOutputStatistics::from_bytes(&bytes[1..]);
+        assert_eq!(
+            statistics,
+            StreamOutputStatistics {
+                num_primitives_written: 7,
+                primitives_storage_needed: 9,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn stream_output_statistics_from_short_bytes() {
+        StreamOutputStatistics::from_bytes(&[0; 8]);
+    }
+
+    #[test]
+    fn occlusion_results() {
+        let mut bytes = vec![0u8; 3];
+        bytes.extend_from_slice(&16u64.to_ne_bytes());
+        bytes.extend_from_slice(&1u64.to_ne_bytes());
+        assert_eq!(occlusion_result_from_bytes(&bytes[3..]), 16);
+        assert_eq!(occlusion_result_from_bytes(&bytes[11..]), 1);
+    }
+
+    #[test]
+    fn pipeline_statistics_from_bytes() {
+        let size = mem::size_of::<d3d12::D3D12_QUERY_DATA_PIPELINE_STATISTICS>();
+        assert_eq!(size, 11 * 8);
+        assert_eq!(
+            PipelineStatistics::from_bytes(&vec![0; size]),
+            PipelineStatistics::default()
+        );
+
+        // Fields in the order of `D3D12_QUERY_DATA_PIPELINE_STATISTICS`, after an unaligned start.
+        let mut bytes = vec![0u8; 5];
+        for value in 1..=11u64 {
+            bytes.extend_from_slice(&value.to_ne_bytes());
+        }
+        assert_eq!(
+            PipelineStatistics::from_bytes(&bytes[5..]),
+            PipelineStatistics {
+                ia_vertices: 1,
+                ia_primitives: 2,
+                vs_invocations: 3,
+                gs_invocations: 4,
+                gs_primitives: 5,
+                c_invocations: 6,
+                c_primitives: 7,
+                ps_invocations: 8,
+                hs_invocations: 9,
+                ds_invocations: 10,
+                cs_invocations: 11,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn pipeline_statistics_from_short_bytes() {
+        PipelineStatistics::from_bytes(&[0; 87]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn occlusion_result_from_short_bytes() {
+        occlusion_result_from_bytes(&[0; 7]);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn resolve_stream_output_statistics() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (heap, hr) = device.create_query_heap(QueryHeapType::SOStatistics, 1, 0);
+        assert!(winapi::shared::winerror::SUCCEEDED(hr));
+        let size = mem::size_of::<StreamOutputStatistics>();
+        let builder = crate::CommittedResourceBuilder::readback_buffer(size as u64);
+        let (readback, hr) = device.create_committed_resource(builder);
+        assert!(winapi::shared::winerror::SUCCEEDED(hr));
+
+        // Nothing is drawn, the query counts no primitives.
+        let (_allocator, list) = crate::test_command_list(&device);
+        let stream = StreamOutputStream::Stream1;
+        list.begin_so_statistics_query(&heap, 0, stream);
+        list.end_so_statistics_query(&heap, 0, stream);
+        list.resolve_query_data(&heap, stream.query_type(), 0, 1, &readback, 0);
+        crate::test_execute(&device, &list);
+
+        let (bytes, hr) = readback.read_to_vec(0, 0..size);
+        assert!(winapi::shared::winerror::SUCCEEDED(hr));
+        assert_eq!(
+            StreamOutputStatistics::from_bytes(&bytes),
+            StreamOutputStatistics::default()
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn resolve_occlusion() {
+        use winapi::shared::{dxgiformat, winerror};
+
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let blob = crate::RootSignature::serialize(
+            crate::RootSignatureVersion::V1_0,
+            &[],
+            &[],
+            crate::RootSignatureFlags::empty(),
+        )
+        .unwrap();
+        let (signature, hr) = device.create_root_signature(blob, 0);
+        assert!(winerror::SUCCEEDED(hr));
+
+        // Triangle covering the whole target.
+        let vs_code = b"float4 main(uint id : SV_VertexID) : SV_Position {
+            float2 uv = float2((id << 1) & 2, id & 2);
+            return float4(uv * float2(2, -2) + float2(-1, 1), 0, 1);
+        }";
+        let ps_code = b"float4 main() : SV_Target { return 1; }";
+        let entry = std::ffi::CString::new("main").unwrap();
+        let compile = |code: &[u8], target: &str| {
+            let target = std::ffi::CString::new(target).unwrap();
+            crate::Shader::compile(code, &target, &entry, crate::ShaderCompileFlags::empty())
+                .unwrap()
+        };
+        let vs = compile(vs_code, "vs_5_0");
+        let ps = compile(ps_code, "ps_5_0");
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let builder = crate::GraphicsPipelineBuilder::new()
+            .root_signature(&signature)
+            .vertex_shader(crate::Shader::from_blob(&vs))
+            .pixel_shader(crate::Shader::from_blob(&ps))
+            .render_target_formats(&[format]);
+        let (pso, hr) = device.create_graphics_pipeline(&builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let target = crate::CommittedResourceBuilder::texture2d(
+            format,
+            4,
+            4,
+            1,
+            crate::ResourceFlags::ALLOW_RENDER_TARGET,
+        )
+        .initial_state(crate::ResourceStates::RENDER_TARGET);
+        let (target, hr) = device.create_committed_resource(target);
+        assert!(winerror::SUCCEEDED(hr));
+        let (rtv_heap, hr) = device.create_descriptor_heap(
+            1,
+            crate::DescriptorHeapType::Rtv,
+            crate::DescriptorHeapFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let rtv = rtv_heap.start_cpu_descriptor();
+        device.create_render_target_view(
+            target.clone(),
+            &crate::RenderTargetViewDesc::texture_2d(format, 0, 0),
+            rtv,
+        );
+
+        let (heap, hr) = device.create_query_heap(QueryHeapType::Occlusion, 2, 0);
+        assert!(winerror::SUCCEEDED(hr));
+        let size = 2 * mem::size_of::<u64>();
+        let builder = crate::CommittedResourceBuilder::readback_buffer(size as u64);
+        let (readback, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.set_pipeline_state(&pso);
+        list.set_graphics_root_signature(&signature);
+        list.set_primitive_topology(crate::PrimitiveTopology::TriangleList);
+        list.set_render_targets(&[rtv], None, false);
+        list.set_viewports(&[crate::Viewport::from_size(4.0, 4.0)]);
+        list.set_scissor_rects(&[crate::Rect {
+            left: 0,
+            top: 0,
+            right: 4,
+            bottom: 4,
+        }]);
+        list.begin_occlusion_query(&heap, 0, false);
+        list.draw(3, 1, 0, 0);
+        list.end_occlusion_query(&heap, 0, false);
+        list.begin_occlusion_query(&heap, 1, true);
+        list.draw(3, 1, 0, 0);
+        list.end_occlusion_query(&heap, 1, true);
+        list.resolve_query_data(&heap, QueryType::Occlusion, 0, 1, &readback, 0);
+        list.resolve_query_data(&heap, QueryType::BinaryOcclusion, 1, 1, &readback, 8);
+        crate::test_execute(&device, &list);
+
+        // Without multisampling, every one of the 4x4 pixels passes a single sample.
+        let (bytes, hr) = readback.read_to_vec(0, 0..size);
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(occlusion_result_from_bytes(&bytes[..8]), 16);
+        assert_eq!(occlusion_result_from_bytes(&bytes[8..]), 1);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn occlusion_and_timestamp_heaps() {
+        use winapi::shared::winerror;
+
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        for &ty in &[QueryHeapType::Occlusion, QueryHeapType::Timestamp] {
+            let (heap, hr) = device.create_query_heap(ty, 16, 0);
+            assert!(winerror::SUCCEEDED(hr));
+            assert!(!heap.is_null());
+        }
+
+        let (supported, _) = device.copy_queue_timestamp_queries_supported();
+        let (heap, hr) = device.create_query_heap(QueryHeapType::CopyQueueTimestamp, 16, 0);
+        if supported {
+            assert!(winerror::SUCCEEDED(hr));
+        } else {
+            assert!(winerror::FAILED(hr));
+            assert!(heap.is_null());
+        }
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn resolve_two_timestamps() {
+        use winapi::shared::winerror;
+
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (heap, hr) = QueryHeap::new_timestamp(&device, 2);
+        assert!(winerror::SUCCEEDED(hr));
+        let size = 2 * mem::size_of::<u64>();
+        let builder = crate::CommittedResourceBuilder::readback_buffer(size as u64);
+        let (readback, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.end_timestamp_query(&heap, 0);
+        list.end_timestamp_query(&heap, 1);
+        list.resolve_query_data(&heap, QueryType::Timestamp, 0, 2, &readback, 0);
+        crate::test_execute(&device, &list);
+
+        let (bytes, hr) = readback.read_to_vec(0, 0..size);
+        assert!(winerror::SUCCEEDED(hr));
+        let ticks: Vec<u64> = bytes
+            .chunks(mem::size_of::<u64>())
+            .map(|chunk| unsafe { ptr::read_unaligned(chunk.as_ptr() as *const u64) })
+            .collect();
+        assert_ne!(ticks[0], 0);
+        assert!(ticks[1] >= ticks[0]);
+
+        let (queue, hr) = device.create_command_queue(
+            crate::CmdListType::Direct,
+            crate::Priority::Normal,
+            crate::CommandQueueFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let (frequency, hr) = queue.timestamp_frequency();
+        assert!(winerror::SUCCEEDED(hr));
+        assert_ne!(frequency, 0);
+        assert!(ticks_to_seconds(ticks[1] - ticks[0], frequency) >= 0.0);
+    }
+}
@@ -1,7 +1,12 @@
-use crate::{com::ComPtr, sync::Fence, CommandList, HRESULT};
+use crate::{
+    com::ComPtr, sync::Fence, CmdListType, CommandList, D3DResult, GraphicsCommandList, Heap,
+    NodeMask, Resource, TileRange, TileRegion, HRESULT,
+};
+use std::ptr;
 use winapi::um::d3d12;
 
 #[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Priority {
     Normal = d3d12::D3D12_COMMAND_QUEUE_PRIORITY_NORMAL,
     High = d3d12::D3D12_COMMAND_QUEUE_PRIORITY_HIGH,
@@ -15,6 +20,43 @@ bitflags::bitflags! {
     }
 }
 
+/// Parameters of a command queue, see [`Device::create_command_queue_with_desc`](crate::Device::create_command_queue_with_desc).
+#[derive(Clone, Copy, Debug)]
+pub struct CommandQueueDesc {
+    pub(crate) list_type: CmdListType,
+    pub(crate) priority: Priority,
+    pub(crate) flags: CommandQueueFlags,
+    pub(crate) node_mask: NodeMask,
+}
+
+impl CommandQueueDesc {
+    /// Normal priority queue on the single node.
+    pub fn new(list_type: CmdListType) -> Self {
+        CommandQueueDesc {
+            list_type,
+            priority: Priority::Normal,
+            flags: CommandQueueFlags::empty(),
+            node_mask: 0,
+        }
+    }
+
+    /// `GlobalRealtime` requires the process to have realtime privileges.
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn flags(mut self, flags: CommandQueueFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn node_mask(mut self, node_mask: NodeMask) -> Self {
+        self.node_mask = node_mask;
+        self
+    }
+}
+
 pub type CommandQueue = ComPtr<d3d12::ID3D12CommandQueue>;
 
 impl CommandQueue {
@@ -26,7 +68,259 @@ impl CommandQueue {
         unsafe { self.ExecuteCommandLists(command_lists.len() as _, command_lists.as_ptr()) }
     }
 
+    /// Submits graphics command lists, which have to be closed.
+    ///
+    /// Debug builds assert this for lists created, closed and reset through this crate.
+    pub fn execute_graphics_command_lists(&self, command_lists: &[&GraphicsCommandList]) {
+        for list in command_lists {
+            debug_assert_ne!(
+                list.is_closed(),
+                Some(false),
+                "Executing a command list which is still open"
+            );
+        }
+        let command_lists = command_lists
+            .iter()
+            .map(|list| list.as_mut_ptr() as *mut d3d12::ID3D12CommandList)
+            .collect::<Box<[_]>>();
+        unsafe { self.ExecuteCommandLists(command_lists.len() as _, command_lists.as_ptr()) }
+    }
+
     pub fn signal(&self, fence: &Fence, value: u64) -> HRESULT {
         unsafe { self.Signal(fence.as_mut_ptr(), value) }
     }
+
+    /// Makes the GPU wait until `fence` reaches `value` before executing further work of this queue.
+    pub fn wait(&self, fence: &Fence, value: u64) -> HRESULT {
+        unsafe { self.Wait(fence.as_mut_ptr(), value) }
+    }
+
+    /// Maps the tiles of `regions` of a reserved resource to the tiles of `ranges` in `heap`.
+    ///
+    /// Regions and ranges are consumed in order, tile by tile, so their total tile
+    /// counts should match. `heap` may be `None` if all ranges are `NULL` or `SKIP`.
+    pub fn update_tile_mappings(
+        &self,
+        resource: &Resource,
+        regions: &[TileRegion],
+        heap: Option<&Heap>,
+        ranges: &[TileRange],
+    ) {
+        let coordinates = regions.iter().map(|r| r.coordinate).collect::<Box<[_]>>();
+        let sizes = regions.iter().map(|r| r.size).collect::<Box<[_]>>();
+        let flags = ranges.iter().map(|r| r.flags.bits()).collect::<Box<[_]>>();
+        let offsets = ranges.iter().map(|r| r.heap_offset).collect::<Box<[_]>>();
+        let counts = ranges.iter().map(|r| r.tile_count).collect::<Box<[_]>>();
+        unsafe {
+            self.UpdateTileMappings(
+                resource.as_mut_ptr(),
+                regions.len() as _,
+                coordinates.as_ptr(),
+                sizes.as_ptr(),
+                heap.map_or(ptr::null_mut(), |h| h.as_mut_ptr()),
+                ranges.len() as _,
+                flags.as_ptr(),
+                offsets.as_ptr(),
+                counts.as_ptr(),
+                d3d12::D3D12_TILE_MAPPING_FLAG_NONE,
+            )
+        }
+    }
+
+    /// Rate in ticks per second at which timestamps of this queue increment.
+    pub fn timestamp_frequency(&self) -> D3DResult<u64> {
+        let mut frequency = 0;
+        let hr = unsafe { self.GetTimestampFrequency(&mut frequency) };
+
+        (frequency, hr)
+    }
+
+    /// Samples the GPU timestamp counter and the CPU `QueryPerformanceCounter` together.
+    ///
+    /// Returns `(gpu, cpu)`, to map GPU timestamps onto the CPU timeline along with
+    /// [`timestamp_frequency`](Self::timestamp_frequency).
+    pub fn clock_calibration(&self) -> D3DResult<(u64, u64)> {
+        let (mut gpu, mut cpu) = (0, 0);
+        let hr = unsafe { self.GetClockCalibration(&mut gpu, &mut cpu) };
+
+        ((gpu, cpu), hr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winapi::shared::winerror;
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn execute_two_lists() {
+        use crate::{CommandQueueFlags, OwnedEvent, Viewport, WaitStatus};
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (_allocator_a, a) = crate::test_command_list(&device);
+        let (_allocator_b, b) = crate::test_command_list(&device);
+        for list in &[&a, &b] {
+            list.set_viewports(&[Viewport::from_size(4.0, 4.0)]);
+            assert_eq!(list.is_closed(), Some(false));
+            assert!(winerror::SUCCEEDED(list.close()));
+            assert_eq!(list.is_closed(), Some(true));
+        }
+
+        let (queue, hr) = device.create_command_queue(
+            CmdListType::Direct,
+            Priority::Normal,
+            CommandQueueFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        queue.execute_graphics_command_lists(&[&a, &b]);
+
+        let (fence, hr) = device.create_fence(0);
+        assert!(winerror::SUCCEEDED(hr));
+        let (event, hr) = OwnedEvent::new(false);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(winerror::SUCCEEDED(
+            fence.set_event_on_completion(&event, 1)
+        ));
+        assert!(winerror::SUCCEEDED(queue.signal(&fence, 1)));
+        assert_eq!(event.wait(1000), WaitStatus::Signaled);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn signal_and_wait() {
+        use crate::{CommandQueueFlags, OwnedEvent, WaitStatus};
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (queue, hr) = device.create_command_queue(
+            CmdListType::Direct,
+            Priority::Normal,
+            CommandQueueFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let (fence, hr) = device.create_fence(0);
+        assert!(winerror::SUCCEEDED(hr));
+        let (event, hr) = OwnedEvent::new(false);
+        assert!(winerror::SUCCEEDED(hr));
+
+        assert!(winerror::SUCCEEDED(queue.signal(&fence, 1)));
+        assert!(winerror::SUCCEEDED(
+            fence.set_event_on_completion(&event, 1)
+        ));
+        assert_eq!(event.wait(1000), WaitStatus::Signaled);
+        let (completed, hr) = fence.completed_value();
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(completed >= 1);
+
+        // The queue holds back its signal of 3 until the CPU signals 2.
+        assert!(winerror::SUCCEEDED(queue.wait(&fence, 2)));
+        assert!(winerror::SUCCEEDED(queue.signal(&fence, 3)));
+        assert!(winerror::SUCCEEDED(
+            fence.set_event_on_completion(&event, 3)
+        ));
+        assert_eq!(event.wait(100), WaitStatus::Timeout);
+        assert!(winerror::SUCCEEDED(fence.signal(2)));
+        assert_eq!(event.wait(1000), WaitStatus::Signaled);
+        assert_eq!(fence.completed_value(), (3, winerror::S_OK));
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn clock_calibration_is_monotonic() {
+        use crate::CommandQueueFlags;
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (queue, hr) = device.create_command_queue(
+            CmdListType::Direct,
+            Priority::Normal,
+            CommandQueueFlags::empty(),
+            0,
+        );
+        assert!(winerror::SUCCEEDED(hr));
+        let (frequency, hr) = queue.timestamp_frequency();
+        assert!(winerror::SUCCEEDED(hr));
+        assert_ne!(frequency, 0);
+
+        let ((gpu_a, cpu_a), hr) = queue.clock_calibration();
+        assert!(winerror::SUCCEEDED(hr));
+        // More than the resolution of `QueryPerformanceCounter`.
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        let ((gpu_b, cpu_b), hr) = queue.clock_calibration();
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(cpu_b > cpu_a, "{} is not after {}", cpu_b, cpu_a);
+        assert!(gpu_b >= gpu_a, "{} is before {}", gpu_b, gpu_a);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn queue_priorities() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (_direct, hr) =
+            device.create_command_queue_with_desc(&CommandQueueDesc::new(CmdListType::Direct));
+        assert!(winerror::SUCCEEDED(hr));
+
+        // Drivers may refuse higher priorities or disabling the timeout, only failing is fine.
+        let desc = CommandQueueDesc::new(CmdListType::Compute)
+            .priority(Priority::High)
+            .flags(CommandQueueFlags::DISABLE_GPU_TIMEOUT);
+        let (compute, hr) = device.create_command_queue_with_desc(&desc);
+        assert_eq!(compute.is_null(), winerror::FAILED(hr));
+        // Without realtime privileges this fails rather than falling back.
+        let desc = CommandQueueDesc::new(CmdListType::Compute).priority(Priority::GlobalRealtime);
+        let (realtime, hr) = device.create_command_queue_with_desc(&desc);
+        assert_eq!(realtime.is_null(), winerror::FAILED(hr));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Executing a command list which is still open")]
+    fn execute_open_list() {
+        use crate::mock::{slot, Mock};
+        use winapi::{ctypes::c_void, shared::guiddef::REFGUID};
+
+        /// Reports the list open through its private data.
+        unsafe extern "system" fn open_list_data(
+            _this: *mut d3d12::ID3D12GraphicsCommandList,
+            _guid: REFGUID,
+            size: *mut u32,
+            data: *mut c_void,
+        ) -> HRESULT {
+            *size = 4;
+            *(data as *mut u32) = 0;
+            winerror::S_OK
+        }
+
+        unsafe extern "system" fn execute(
+            _this: *mut d3d12::ID3D12CommandQueue,
+            _count: u32,
+            _lists: *const *mut d3d12::ID3D12CommandList,
+        ) {
+        }
+
+        type ListVtbl = d3d12::ID3D12GraphicsCommandListVtbl;
+        let list = Mock::new::<d3d12::ID3D12GraphicsCommandList, ListVtbl>(
+            (),
+            &[(
+                slot!(ListVtbl, parent.parent.parent.GetPrivateData),
+                open_list_data as usize,
+            )],
+        );
+        type QueueVtbl = d3d12::ID3D12CommandQueueVtbl;
+        let queue = Mock::new::<d3d12::ID3D12CommandQueue, QueueVtbl>(
+            (),
+            &[(slot!(QueueVtbl, ExecuteCommandLists), execute as usize)],
+        );
+        queue.execute_graphics_command_lists(&[&list]);
+    }
 }
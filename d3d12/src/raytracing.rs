@@ -0,0 +1,210 @@
+//! Ray tracing acceleration structures
+
+use crate::{types, Device5, Format, GpuAddress, GraphicsCommandList4};
+use std::{marker::PhantomData, mem, ptr};
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct AccelStructBuildFlags: u32 {
+        const NONE = types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_NONE;
+        const ALLOW_UPDATE = types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_ALLOW_UPDATE;
+        const ALLOW_COMPACTION = types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_ALLOW_COMPACTION;
+        const PREFER_FAST_TRACE = types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_PREFER_FAST_TRACE;
+        const PREFER_FAST_BUILD = types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_PREFER_FAST_BUILD;
+        const MINIMIZE_MEMORY = types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_MINIMIZE_MEMORY;
+        const PERFORM_UPDATE = types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_PERFORM_UPDATE;
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct GeometryFlags: u32 {
+        const NONE = types::D3D12_RAYTRACING_GEOMETRY_FLAG_NONE;
+        const OPAQUE = types::D3D12_RAYTRACING_GEOMETRY_FLAG_OPAQUE;
+        const NO_DUPLICATE_ANYHIT_INVOCATION = types::D3D12_RAYTRACING_GEOMETRY_FLAG_NO_DUPLICATE_ANYHIT_INVOCATION;
+    }
+}
+
+/// Geometry of a bottom-level acceleration structure.
+#[repr(transparent)]
+pub struct GeometryDesc(types::D3D12_RAYTRACING_GEOMETRY_DESC);
+
+impl GeometryDesc {
+    /// Triangle geometry, `index_buffer` is 0 for non-indexed geometry and
+    /// `transform` is 0 or the address of a 3x4 row-major matrix.
+    pub fn triangles(
+        flags: GeometryFlags,
+        vertex_buffer: GpuAddress,
+        vertex_stride: u64,
+        vertex_count: u32,
+        vertex_format: Format,
+        index_buffer: GpuAddress,
+        index_count: u32,
+        index_format: Format,
+        transform: GpuAddress,
+    ) -> Self {
+        let mut desc = types::D3D12_RAYTRACING_GEOMETRY_DESC {
+            Type: types::D3D12_RAYTRACING_GEOMETRY_TYPE_TRIANGLES,
+            Flags: flags.bits(),
+            ..unsafe { mem::zeroed() }
+        };
+        *unsafe { desc.u.Triangles_mut() } = types::D3D12_RAYTRACING_GEOMETRY_TRIANGLES_DESC {
+            Transform3x4: transform.0,
+            IndexFormat: index_format,
+            VertexFormat: vertex_format,
+            IndexCount: index_count,
+            VertexCount: vertex_count,
+            IndexBuffer: index_buffer.0,
+            VertexBuffer: types::D3D12_GPU_VIRTUAL_ADDRESS_AND_STRIDE {
+                StartAddress: vertex_buffer.0,
+                StrideInBytes: vertex_stride,
+            },
+        };
+
+        GeometryDesc(desc)
+    }
+
+    /// Procedural geometry bounded by `count` boxes of `D3D12_RAYTRACING_AABB`.
+    pub fn aabbs(flags: GeometryFlags, aabbs: GpuAddress, stride: u64, count: u64) -> Self {
+        let mut desc = types::D3D12_RAYTRACING_GEOMETRY_DESC {
+            Type: types::D3D12_RAYTRACING_GEOMETRY_TYPE_PROCEDURAL_PRIMITIVE_AABBS,
+            Flags: flags.bits(),
+            ..unsafe { mem::zeroed() }
+        };
+        *unsafe { desc.u.AABBs_mut() } = types::D3D12_RAYTRACING_GEOMETRY_AABBS_DESC {
+            AABBCount: count,
+            AABBs: types::D3D12_GPU_VIRTUAL_ADDRESS_AND_STRIDE {
+                StartAddress: aabbs.0,
+                StrideInBytes: stride,
+            },
+        };
+
+        GeometryDesc(desc)
+    }
+}
+
+/// Inputs of an acceleration structure build, borrowing the geometry descriptions
+/// so they stay alive for the duration of the calls.
+pub struct AccelStructInputs<'a> {
+    raw: types::D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS,
+    marker: PhantomData<&'a [GeometryDesc]>,
+}
+
+impl<'a> AccelStructInputs<'a> {
+    pub fn bottom_level(geometries: &'a [GeometryDesc], flags: AccelStructBuildFlags) -> Self {
+        let mut raw = types::D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS {
+            Type: types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE_BOTTOM_LEVEL,
+            Flags: flags.bits(),
+            NumDescs: geometries.len() as _,
+            DescsLayout: types::D3D12_ELEMENTS_LAYOUT_ARRAY,
+            ..unsafe { mem::zeroed() }
+        };
+        *unsafe { raw.u.pGeometryDescs_mut() } = geometries.as_ptr() as *const _;
+
+        AccelStructInputs {
+            raw,
+            marker: PhantomData,
+        }
+    }
+
+    /// `instances` is the address of `count` contiguous `D3D12_RAYTRACING_INSTANCE_DESC`.
+    pub fn top_level(instances: GpuAddress, count: u32, flags: AccelStructBuildFlags) -> Self {
+        let mut raw = types::D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS {
+            Type: types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE_TOP_LEVEL,
+            Flags: flags.bits(),
+            NumDescs: count,
+            DescsLayout: types::D3D12_ELEMENTS_LAYOUT_ARRAY,
+            ..unsafe { mem::zeroed() }
+        };
+        *unsafe { raw.u.InstanceDescs_mut() } = instances.0;
+
+        AccelStructInputs {
+            raw,
+            marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrebuildInfo {
+    pub result_data_max_size: u64,
+    pub scratch_data_size: u64,
+    pub update_scratch_data_size: u64,
+}
+
+pub struct BuildDesc<'a> {
+    pub inputs: AccelStructInputs<'a>,
+    pub dest: GpuAddress,
+    /// Structure to update, only used with `AccelStructBuildFlags::PERFORM_UPDATE`.
+    pub source: Option<GpuAddress>,
+    pub scratch: GpuAddress,
+}
+
+impl Device5 {
+    pub fn acceleration_structure_prebuild_info(&self, inputs: &AccelStructInputs) -> PrebuildInfo {
+        let mut info: types::D3D12_RAYTRACING_ACCELERATION_STRUCTURE_PREBUILD_INFO =
+            unsafe { mem::zeroed() };
+        unsafe { self.GetRaytracingAccelerationStructurePrebuildInfo(&inputs.raw, &mut info) };
+
+        PrebuildInfo {
+            result_data_max_size: info.ResultDataMaxSizeInBytes,
+            scratch_data_size: info.ScratchDataSizeInBytes,
+            update_scratch_data_size: info.UpdateScratchDataSizeInBytes,
+        }
+    }
+}
+
+impl GraphicsCommandList4 {
+    /// `desc.dest` and `desc.scratch` have to be sized according to the prebuild info.
+    pub fn build_acceleration_structure(&self, desc: &BuildDesc) {
+        let raw = types::D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_DESC {
+            DestAccelerationStructureData: desc.dest.0,
+            Inputs: desc.inputs.raw,
+            SourceAccelerationStructureData: desc.source.map_or(0, GpuAddress::raw),
+            ScratchAccelerationStructureData: desc.scratch.0,
+        };
+        unsafe { self.BuildRaytracingAccelerationStructure(&raw, 0, ptr::null()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winapi::shared::{dxgiformat, winerror};
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn single_triangle_prebuild_info() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let mut options: types::D3D12_FEATURE_DATA_D3D12_OPTIONS5 = unsafe { mem::zeroed() };
+        let hr = device.check_feature_support(types::D3D12_FEATURE_D3D12_OPTIONS5, &mut options);
+        if winerror::FAILED(hr) || options.RaytracingTier == 0 {
+            return;
+        }
+        let (device5, hr) = unsafe { device.cast::<types::ID3D12Device5>() };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let stride = 3 * mem::size_of::<f32>() as u64;
+        let builder = crate::CommittedResourceBuilder::upload_buffer(3 * stride);
+        let (vertices, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        let geometries = [GeometryDesc::triangles(
+            GeometryFlags::OPAQUE,
+            vertices.gpu_virtual_address(),
+            stride,
+            3,
+            dxgiformat::DXGI_FORMAT_R32G32B32_FLOAT,
+            GpuAddress(0),
+            0,
+            dxgiformat::DXGI_FORMAT_UNKNOWN,
+            GpuAddress(0),
+        )];
+        let inputs = AccelStructInputs::bottom_level(&geometries, AccelStructBuildFlags::NONE);
+        let info = device5.acceleration_structure_prebuild_info(&inputs);
+        assert_ne!(info.result_data_max_size, 0);
+        assert_ne!(info.scratch_data_size, 0);
+    }
+}
@@ -0,0 +1,272 @@
+//! Shader reflection
+
+use crate::{com::ComPtr, types, Binding, D3DResult, Shader};
+use std::{ffi::CStr, mem};
+use winapi::{
+    ctypes::c_char,
+    shared::winerror,
+    um::{d3d12shader, d3dcommon, d3dcompiler},
+    Interface,
+};
+
+pub type ShaderReflector = ComPtr<d3d12shader::ID3D12ShaderReflection>;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderInputType {
+    CBuffer = d3dcommon::D3D_SIT_CBUFFER,
+    TBuffer = d3dcommon::D3D_SIT_TBUFFER,
+    Texture = d3dcommon::D3D_SIT_TEXTURE,
+    Sampler = d3dcommon::D3D_SIT_SAMPLER,
+    UavRwTyped = d3dcommon::D3D_SIT_UAV_RWTYPED,
+    Structured = d3dcommon::D3D_SIT_STRUCTURED,
+    UavRwStructured = d3dcommon::D3D_SIT_UAV_RWSTRUCTURED,
+    ByteAddress = d3dcommon::D3D_SIT_BYTEADDRESS,
+    UavRwByteAddress = d3dcommon::D3D_SIT_UAV_RWBYTEADDRESS,
+    UavAppendStructured = d3dcommon::D3D_SIT_UAV_APPEND_STRUCTURED,
+    UavConsumeStructured = d3dcommon::D3D_SIT_UAV_CONSUME_STRUCTURED,
+    UavRwStructuredWithCounter = d3dcommon::D3D_SIT_UAV_RWSTRUCTURED_WITH_COUNTER,
+    RtAccelerationStructure = types::D3D_SIT_RTACCELERATIONSTRUCTURE,
+    UavFeedbackTexture = types::D3D_SIT_UAV_FEEDBACKTEXTURE,
+}
+
+impl ShaderInputType {
+    fn from_raw(raw: d3dcommon::D3D_SHADER_INPUT_TYPE) -> Option<Self> {
+        Some(match raw {
+            d3dcommon::D3D_SIT_CBUFFER => Self::CBuffer,
+            d3dcommon::D3D_SIT_TBUFFER => Self::TBuffer,
+            d3dcommon::D3D_SIT_TEXTURE => Self::Texture,
+            d3dcommon::D3D_SIT_SAMPLER => Self::Sampler,
+            d3dcommon::D3D_SIT_UAV_RWTYPED => Self::UavRwTyped,
+            d3dcommon::D3D_SIT_STRUCTURED => Self::Structured,
+            d3dcommon::D3D_SIT_UAV_RWSTRUCTURED => Self::UavRwStructured,
+            d3dcommon::D3D_SIT_BYTEADDRESS => Self::ByteAddress,
+            d3dcommon::D3D_SIT_UAV_RWBYTEADDRESS => Self::UavRwByteAddress,
+            d3dcommon::D3D_SIT_UAV_APPEND_STRUCTURED => Self::UavAppendStructured,
+            d3dcommon::D3D_SIT_UAV_CONSUME_STRUCTURED => Self::UavConsumeStructured,
+            d3dcommon::D3D_SIT_UAV_RWSTRUCTURED_WITH_COUNTER => Self::UavRwStructuredWithCounter,
+            types::D3D_SIT_RTACCELERATIONSTRUCTURE => Self::RtAccelerationStructure,
+            types::D3D_SIT_UAV_FEEDBACKTEXTURE => Self::UavFeedbackTexture,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BoundResource {
+    pub name: String,
+    pub ty: ShaderInputType,
+    pub binding: Binding,
+    /// Number of registers, 0 for unbounded arrays.
+    pub count: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShaderVariable {
+    pub name: String,
+    /// Offset in bytes from the start of the constant buffer.
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConstantBufferReflection {
+    pub name: String,
+    pub size: u32,
+    pub variables: Vec<ShaderVariable>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SignatureParameter {
+    pub semantic_name: String,
+    pub semantic_index: u32,
+    pub register: u32,
+    pub system_value: d3dcommon::D3D_NAME,
+    pub component_type: d3dcommon::D3D_REGISTER_COMPONENT_TYPE,
+    /// Components of the register in use.
+    pub mask: u8,
+}
+
+/// Bindings and inputs of a compiled shader.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderReflection {
+    pub bound_resources: Vec<BoundResource>,
+    pub constant_buffers: Vec<ConstantBufferReflection>,
+    pub input_parameters: Vec<SignatureParameter>,
+}
+
+unsafe fn name(raw: *const c_char) -> String {
+    if raw.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(raw).to_string_lossy().into_owned()
+    }
+}
+
+impl ShaderReflection {
+    fn from_reflector(reflector: &ShaderReflector) -> D3DResult<Self> {
+        let mut reflection = ShaderReflection::default();
+
+        let mut desc: d3d12shader::D3D12_SHADER_DESC = unsafe { mem::zeroed() };
+        let hr = unsafe { reflector.GetDesc(&mut desc) };
+        if winerror::FAILED(hr) {
+            return (reflection, hr);
+        }
+
+        for index in 0..desc.BoundResources {
+            let mut bind: d3d12shader::D3D12_SHADER_INPUT_BIND_DESC = unsafe { mem::zeroed() };
+            let hr = unsafe { reflector.GetResourceBindingDesc(index, &mut bind) };
+            if winerror::FAILED(hr) {
+                return (ShaderReflection::default(), hr);
+            }
+            let ty = match ShaderInputType::from_raw(bind.Type) {
+                Some(ty) => ty,
+                None => return (ShaderReflection::default(), winerror::E_UNEXPECTED),
+            };
+            reflection.bound_resources.push(BoundResource {
+                name: unsafe { name(bind.Name) },
+                ty,
+                binding: Binding {
+                    space: bind.Space,
+                    register: bind.BindPoint,
+                },
+                count: bind.BindCount,
+            });
+        }
+
+        // Constant buffers and variables are owned by the reflector, they aren't reference counted.
+        for index in 0..desc.ConstantBuffers {
+            let buffer = unsafe { reflector.GetConstantBufferByIndex(index) };
+            let mut buffer_desc: d3d12shader::D3D12_SHADER_BUFFER_DESC = unsafe { mem::zeroed() };
+            let hr = unsafe { (*buffer).GetDesc(&mut buffer_desc) };
+            if winerror::FAILED(hr) {
+                return (ShaderReflection::default(), hr);
+            }
+
+            let mut variables = Vec::with_capacity(buffer_desc.Variables as usize);
+            for var_index in 0..buffer_desc.Variables {
+                let variable = unsafe { (*buffer).GetVariableByIndex(var_index) };
+                let mut var_desc: d3d12shader::D3D12_SHADER_VARIABLE_DESC =
+                    unsafe { mem::zeroed() };
+                let hr = unsafe { (*variable).GetDesc(&mut var_desc) };
+                if winerror::FAILED(hr) {
+                    return (ShaderReflection::default(), hr);
+                }
+                variables.push(ShaderVariable {
+                    name: unsafe { name(var_desc.Name) },
+                    offset: var_desc.StartOffset,
+                    size: var_desc.Size,
+                });
+            }
+
+            reflection.constant_buffers.push(ConstantBufferReflection {
+                name: unsafe { name(buffer_desc.Name) },
+                size: buffer_desc.Size,
+                variables,
+            });
+        }
+
+        for index in 0..desc.InputParameters {
+            let mut param: d3d12shader::D3D12_SIGNATURE_PARAMETER_DESC = unsafe { mem::zeroed() };
+            let hr = unsafe { reflector.GetInputParameterDesc(index, &mut param) };
+            if winerror::FAILED(hr) {
+                return (ShaderReflection::default(), hr);
+            }
+            reflection.input_parameters.push(SignatureParameter {
+                semantic_name: unsafe { name(param.SemanticName) },
+                semantic_index: param.SemanticIndex,
+                register: param.Register,
+                system_value: param.SystemValueType,
+                component_type: param.ComponentType,
+                mask: param.Mask,
+            });
+        }
+
+        (reflection, hr)
+    }
+}
+
+impl Shader<'_> {
+    /// Reflects the bytecode, which has to be DXBC as produced by [`Shader::compile`].
+    pub fn reflect(&self) -> D3DResult<ShaderReflection> {
+        let mut reflector = ShaderReflector::null();
+        let hr = unsafe {
+            d3dcompiler::D3DReflect(
+                self.pShaderBytecode,
+                self.BytecodeLength,
+                &d3d12shader::ID3D12ShaderReflection::uuidof(),
+                reflector.mut_void(),
+            )
+        };
+        if winerror::FAILED(hr) {
+            return (ShaderReflection::default(), hr);
+        }
+
+        ShaderReflection::from_reflector(&reflector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShaderCompileFlags;
+    use std::ffi::CString;
+
+    #[test]
+    fn cbuffer_and_textures() {
+        let code = b"
+            cbuffer Params : register(b0) { float4 tint; float2 scale; };
+            Texture2D base_tex : register(t0);
+            Texture2D detail_tex : register(t1, space1);
+            SamplerState samp : register(s0);
+            float4 main(float2 uv : TEXCOORD0) : SV_Target {
+                return base_tex.Sample(samp, uv * scale) * detail_tex.Sample(samp, uv) * tint;
+            }";
+        let blob = Shader::compile(
+            code,
+            &CString::new("ps_5_1").unwrap(),
+            &CString::new("main").unwrap(),
+            ShaderCompileFlags::empty(),
+        )
+        .unwrap();
+        let (reflection, hr) = Shader::from_blob(&blob).reflect();
+        assert!(winerror::SUCCEEDED(hr));
+
+        let resource = |name: &str| {
+            reflection
+                .bound_resources
+                .iter()
+                .find(|resource| resource.name == name)
+                .unwrap_or_else(|| panic!("{} is not bound", name))
+        };
+        for &(name, ty, space, register) in &[
+            ("Params", ShaderInputType::CBuffer, 0, 0),
+            ("base_tex", ShaderInputType::Texture, 0, 0),
+            ("detail_tex", ShaderInputType::Texture, 1, 1),
+            ("samp", ShaderInputType::Sampler, 0, 0),
+        ] {
+            let resource = resource(name);
+            assert_eq!(resource.ty, ty);
+            assert_eq!(
+                (resource.binding.space, resource.binding.register),
+                (space, register)
+            );
+            assert_eq!(resource.count, 1);
+        }
+        assert_eq!(reflection.bound_resources.len(), 4);
+
+        assert_eq!(reflection.constant_buffers.len(), 1);
+        let params = &reflection.constant_buffers[0];
+        assert_eq!(params.name, "Params");
+        assert_eq!(params.size, 32);
+        let variables = params
+            .variables
+            .iter()
+            .map(|var| (var.name.as_str(), var.offset, var.size))
+            .collect::<Vec<_>>();
+        assert_eq!(variables, [("tint", 0, 16), ("scale", 16, 8)]);
+
+        assert_eq!(reflection.input_parameters.len(), 1);
+        assert_eq!(reflection.input_parameters[0].semantic_name, "TEXCOORD");
+    }
+}
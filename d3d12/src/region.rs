@@ -0,0 +1,119 @@
+//! Rectangle and box constructors
+
+use crate::Rect;
+use winapi::um::d3d12;
+
+/// Copy region, a plain `D3D12_BOX`.
+pub type TextureBox = d3d12::D3D12_BOX;
+
+/// Constructors for [`Rect`], which is a plain `D3D12_RECT` with exclusive `right` and `bottom`.
+pub trait RectExt: Sized {
+    /// Bounds past `i32::MAX` are clamped to it.
+    fn from_size(x: i32, y: i32, width: u32, height: u32) -> Self;
+    /// 0 for inverted rectangles.
+    fn width(&self) -> u32;
+    /// 0 for inverted rectangles.
+    fn height(&self) -> u32;
+}
+
+/// Exclusive end of `size` units from `start`, clamped to `i32::MAX`.
+fn rect_end(start: i32, size: u32) -> i32 {
+    (start as i64 + size as i64).min(i32::MAX as i64) as i32
+}
+
+/// Units between `start` and an exclusive `end`, 0 if `end` comes first.
+fn rect_size(start: i32, end: i32) -> u32 {
+    (end as i64 - start as i64).max(0) as u32
+}
+
+impl RectExt for Rect {
+    fn from_size(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Rect {
+            left: x,
+            top: y,
+            right: rect_end(x, width),
+            bottom: rect_end(y, height),
+        }
+    }
+
+    fn width(&self) -> u32 {
+        rect_size(self.left, self.right)
+    }
+
+    fn height(&self) -> u32 {
+        rect_size(self.top, self.bottom)
+    }
+}
+
+/// Constructors for [`TextureBox`], whose `right`, `bottom` and `back` are exclusive.
+pub trait TextureBoxExt: Sized {
+    /// Bounds past `u32::MAX` are clamped to it.
+    fn new(x: u32, y: u32, z: u32, width: u32, height: u32, depth: u32) -> Self;
+    /// Box covering a whole subresource of the given size.
+    fn from_extent(width: u32, height: u32, depth: u32) -> Self;
+}
+
+impl TextureBoxExt for TextureBox {
+    fn new(x: u32, y: u32, z: u32, width: u32, height: u32, depth: u32) -> Self {
+        TextureBox {
+            left: x,
+            top: y,
+            front: z,
+            right: x.saturating_add(width),
+            bottom: y.saturating_add(height),
+            back: z.saturating_add(depth),
+        }
+    }
+
+    fn from_extent(width: u32, height: u32, depth: u32) -> Self {
+        Self::new(0, 0, 0, width, height, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_bounds() {
+        let rect = Rect::from_size(-2, 3, 10, 5);
+        assert_eq!(
+            (rect.left, rect.top, rect.right, rect.bottom),
+            (-2, 3, 8, 8)
+        );
+        assert_eq!((rect.width(), rect.height()), (10, 5));
+
+        let empty = Rect::from_size(4, 4, 0, 0);
+        assert_eq!((empty.right, empty.bottom), (4, 4));
+        assert_eq!((empty.width(), empty.height()), (0, 0));
+
+        let clamped = Rect::from_size(i32::MAX - 1, 0, u32::MAX, u32::MAX);
+        assert_eq!((clamped.right, clamped.bottom), (i32::MAX, i32::MAX));
+        assert_eq!(clamped.width(), 1);
+        let widest = Rect::from_size(i32::MIN, 0, u32::MAX, 1);
+        assert_eq!(widest.right, i32::MAX);
+        assert_eq!(widest.width(), u32::MAX);
+
+        let inverted = Rect {
+            left: 4,
+            top: 4,
+            right: 2,
+            bottom: 2,
+        };
+        assert_eq!((inverted.width(), inverted.height()), (0, 0));
+    }
+
+    #[test]
+    fn texture_box_bounds() {
+        let region = TextureBox::new(1, 2, 3, 4, 5, 6);
+        assert_eq!((region.left, region.top, region.front), (1, 2, 3));
+        assert_eq!((region.right, region.bottom, region.back), (5, 7, 9));
+
+        let full = TextureBox::from_extent(256, 128, 1);
+        assert_eq!((full.left, full.top, full.front), (0, 0, 0));
+        assert_eq!((full.right, full.bottom, full.back), (256, 128, 1));
+
+        let clamped = TextureBox::new(u32::MAX - 1, 0, 0, 2, u32::MAX, 1);
+        assert_eq!((clamped.right, clamped.bottom), (u32::MAX, u32::MAX));
+    }
+}
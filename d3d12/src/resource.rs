@@ -1,8 +1,14 @@
 //! GPU Resource
 
-use crate::{com::ComPtr, D3DResult, Rect};
-use std::{ops::Range, ptr};
-use winapi::um::d3d12;
+use crate::{
+    com::ComPtr, D3DResult, Format, FormatExt, GpuAddress, HeapFlags, HeapProperties, HeapType,
+    NodeMask, Rect, HRESULT,
+};
+use std::{convert::TryFrom, ops::Range, ptr, slice, sync::Mutex};
+use winapi::{
+    shared::{dxgiformat, dxgitype, winerror},
+    um::d3d12,
+};
 
 pub type Subresource = u32;
 
@@ -13,6 +19,30 @@ pub struct DiscardRegion<'a> {
 
 pub type Resource = ComPtr<d3d12::ID3D12Resource>;
 
+/// Bytes spanned by `width` x `height` x `depth` texels of `format` at the given pitches,
+/// `None` for formats of unknown size or on overflow.
+fn region_size(
+    format: Format,
+    (width, height, depth): (u64, u32, u32),
+    row_pitch: u32,
+    depth_pitch: u32,
+) -> Option<usize> {
+    let (width, rows) = if format.is_block_compressed() {
+        ((width + 3) / 4, (height + 3) / 4)
+    } else {
+        (width, height)
+    };
+    if width == 0 || rows == 0 || depth == 0 {
+        return Some(0);
+    }
+    let row_size = width.checked_mul(format.bytes_per_pixel()? as u64)?;
+    let size = (depth as u64 - 1)
+        .checked_mul(depth_pitch as u64)?
+        .checked_add((rows as u64 - 1) * row_pitch as u64)?
+        .checked_add(row_size)?;
+    usize::try_from(size).ok()
+}
+
 impl Resource {
     ///
     pub fn map(
@@ -47,7 +77,1058 @@ impl Resource {
         unsafe { self.Unmap(subresource, write) };
     }
 
-    pub fn gpu_virtual_address(&self) -> u64 {
-        unsafe { self.GetGPUVirtualAddress() }
+    /// Bytes of CPU memory covered by `region` of a subresource, or by all of it.
+    fn subresource_region_size(
+        &self,
+        subresource: Subresource,
+        region: Option<&d3d12::D3D12_BOX>,
+        row_pitch: u32,
+        depth_pitch: u32,
+    ) -> Option<usize> {
+        let desc = self.desc();
+        let extent = match region {
+            Some(region) => (
+                region.right.saturating_sub(region.left) as u64,
+                region.bottom.saturating_sub(region.top),
+                region.back.saturating_sub(region.front),
+            ),
+            None => {
+                let mip = subresource % desc.MipLevels.max(1) as u32;
+                let depth = if desc.Dimension == d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE3D {
+                    (desc.DepthOrArraySize as u32 >> mip).max(1)
+                } else {
+                    1
+                };
+                (
+                    (desc.Width >> mip).max(1),
+                    (desc.Height >> mip).max(1),
+                    depth,
+                )
+            }
+        };
+        region_size(desc.Format, extent, row_pitch, depth_pitch)
+    }
+
+    /// Copies `src` into a subresource with the CPU, without an upload buffer.
+    ///
+    /// The texture has to be mapped with [`map`](Self::map) and live in a CPU-accessible
+    /// custom heap, its layout being `D3D12_TEXTURE_LAYOUT_ROW_MAJOR` or undefined.
+    /// `dst_box` defaults to the whole subresource. Returns `E_INVALIDARG` if `src` is
+    /// too small for it at the given pitches, or the format size is unknown.
+    pub fn write_to_subresource(
+        &self,
+        subresource: Subresource,
+        dst_box: Option<&d3d12::D3D12_BOX>,
+        src: &[u8],
+        src_row_pitch: u32,
+        src_depth_pitch: u32,
+    ) -> HRESULT {
+        match self.subresource_region_size(subresource, dst_box, src_row_pitch, src_depth_pitch) {
+            Some(size) if size <= src.len() => {}
+            _ => return winerror::E_INVALIDARG,
+        }
+        let dst_box = dst_box.map_or(ptr::null(), |region| region as *const _);
+        unsafe {
+            self.WriteToSubresource(
+                subresource,
+                dst_box,
+                src.as_ptr() as *const _,
+                src_row_pitch,
+                src_depth_pitch,
+            )
+        }
+    }
+
+    /// Copies a subresource to `dst` with the CPU, the counterpart of
+    /// [`write_to_subresource`](Self::write_to_subresource) with the same requirements
+    /// and checks on `dst`.
+    pub fn read_from_subresource(
+        &self,
+        dst: &mut [u8],
+        dst_row_pitch: u32,
+        dst_depth_pitch: u32,
+        subresource: Subresource,
+        src_box: Option<&d3d12::D3D12_BOX>,
+    ) -> HRESULT {
+        match self.subresource_region_size(subresource, src_box, dst_row_pitch, dst_depth_pitch) {
+            Some(size) if size <= dst.len() => {}
+            _ => return winerror::E_INVALIDARG,
+        }
+        let src_box = src_box.map_or(ptr::null(), |region| region as *const _);
+        unsafe {
+            self.ReadFromSubresource(
+                dst.as_mut_ptr() as *mut _,
+                dst_row_pitch,
+                dst_depth_pitch,
+                subresource,
+                src_box,
+            )
+        }
+    }
+
+    /// Copies `byte_range` of a subresource to the CPU.
+    ///
+    /// The resource must live in a readback heap, and the GPU writes to it must be complete.
+    /// `byte_range` has to lie within the width of the resource, `E_INVALIDARG` is returned
+    /// otherwise.
+    pub fn read_to_vec(
+        &self,
+        subresource: Subresource,
+        byte_range: Range<usize>,
+    ) -> D3DResult<Vec<u8>> {
+        if byte_range.start > byte_range.end || byte_range.end as u64 > self.width() {
+            return (Vec::new(), winerror::E_INVALIDARG);
+        }
+        let (ptr, hr) = self.map(subresource, Some(byte_range.clone()));
+        if ptr.is_null() {
+            return (Vec::new(), hr);
+        }
+
+        let data = unsafe {
+            slice::from_raw_parts((ptr as *const u8).add(byte_range.start), byte_range.len())
+        }
+        .to_vec();
+        // Nothing was written by the CPU.
+        self.unmap(subresource, Some(0..0));
+
+        (data, hr)
+    }
+
+    /// Maps `range` bytes of a subresource, unmapping it once the guard is dropped.
+    ///
+    /// `range` has to lie within the width of the resource, `E_INVALIDARG` is returned
+    /// otherwise. Guards of the same subresource may nest, each one unmapping once,
+    /// but debug builds warn about it as it usually hints at a forgotten guard.
+    pub fn map_guard(
+        &self,
+        subresource: Subresource,
+        range: Range<usize>,
+    ) -> D3DResult<Option<MappedResource>> {
+        if range.start > range.end || range.end as u64 > self.width() {
+            return (None, winerror::E_INVALIDARG);
+        }
+        let (ptr, hr) = self.map(subresource, Some(range.clone()));
+        if ptr.is_null() {
+            return (None, hr);
+        }
+
+        if cfg!(debug_assertions) {
+            let key = (self.as_ptr() as usize, subresource);
+            let mut mapped = MAPPED_SUBRESOURCES
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            match mapped.iter_mut().find(|entry| entry.0 == key) {
+                Some(entry) => {
+                    log::warn!(
+                        "Subresource {} mapped again while {} guard(s) of it are alive",
+                        subresource,
+                        entry.1
+                    );
+                    entry.1 += 1;
+                }
+                None => mapped.push((key, 1)),
+            }
+        }
+
+        let mapping = MappedResource {
+            resource: self,
+            subresource,
+            ptr: unsafe { (ptr as *mut u8).add(range.start) },
+            range,
+        };
+        (Some(mapping), hr)
+    }
+
+    /// GPU virtual address of a buffer.
+    ///
+    /// Querying it on textures is valid but returns 0, which has no meaning.
+    pub fn gpu_virtual_address(&self) -> GpuAddress {
+        GpuAddress(unsafe { self.GetGPUVirtualAddress() })
+    }
+
+    pub fn desc(&self) -> d3d12::D3D12_RESOURCE_DESC {
+        unsafe { self.GetDesc() }
+    }
+
+    /// Size in bytes for buffers.
+    pub fn width(&self) -> u64 {
+        self.desc().Width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.desc().Height
+    }
+
+    pub fn mip_levels(&self) -> u16 {
+        self.desc().MipLevels
+    }
+
+    pub fn format(&self) -> Format {
+        self.desc().Format
+    }
+
+    pub fn dimension(&self) -> ResourceDimension {
+        ResourceDimension::from_raw(self.desc().Dimension)
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceDimension {
+    Unknown = d3d12::D3D12_RESOURCE_DIMENSION_UNKNOWN,
+    Buffer = d3d12::D3D12_RESOURCE_DIMENSION_BUFFER,
+    Texture1D = d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE1D,
+    Texture2D = d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+    Texture3D = d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE3D,
+}
+
+impl ResourceDimension {
+    fn from_raw(raw: d3d12::D3D12_RESOURCE_DIMENSION) -> Self {
+        match raw {
+            d3d12::D3D12_RESOURCE_DIMENSION_BUFFER => Self::Buffer,
+            d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE1D => Self::Texture1D,
+            d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE2D => Self::Texture2D,
+            d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE3D => Self::Texture3D,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Tile layout of a reserved resource, see [`Device::resource_tiling`](crate::Device::resource_tiling).
+#[derive(Clone, Debug)]
+pub struct ResourceTilingInfo {
+    pub num_tiles: u32,
+    /// Tile size in texels of the non-packed mips.
+    pub tile_shape: [u32; 3],
+    pub num_standard_mips: u8,
+    pub num_packed_mips: u8,
+    pub num_tiles_for_packed_mips: u32,
+    pub start_tile_index_in_overall_resource: u32,
+    /// Per subresource, packed mips have a tile count of 0.
+    pub subresource_tilings: Vec<d3d12::D3D12_SUBRESOURCE_TILING>,
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct TileRangeFlags: u32 {
+        const NONE = d3d12::D3D12_TILE_RANGE_FLAG_NONE;
+        const NULL = d3d12::D3D12_TILE_RANGE_FLAG_NULL;
+        const SKIP = d3d12::D3D12_TILE_RANGE_FLAG_SKIP;
+        const REUSE_SINGLE_TILE = d3d12::D3D12_TILE_RANGE_FLAG_REUSE_SINGLE_TILE;
+    }
+}
+
+/// Run of `tile_count` heap tiles starting at tile `heap_offset`.
+///
+/// With `TileRangeFlags::NULL` the tiles are unmapped and the offset is ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct TileRange {
+    pub flags: TileRangeFlags,
+    pub heap_offset: u32,
+    pub tile_count: u32,
+}
+
+/// Region of a reserved resource to (re)map, starting at `coordinate`.
+#[derive(Clone, Copy)]
+pub struct TileRegion {
+    pub coordinate: d3d12::D3D12_TILED_RESOURCE_COORDINATE,
+    pub size: d3d12::D3D12_TILE_REGION_SIZE,
+}
+
+/// Subresources currently mapped through a [`MappedResource`] with their map count,
+/// only tracked in debug builds.
+///
+/// Maps nest like in D3D12, the count catches mapping again and guards unmapping more
+/// than they mapped.
+static MAPPED_SUBRESOURCES: Mutex<Vec<((usize, Subresource), u32)>> = Mutex::new(Vec::new());
+
+/// Mapped range of a subresource, see [`Resource::map_guard`].
+pub struct MappedResource<'a> {
+    resource: &'a Resource,
+    subresource: Subresource,
+    ptr: *mut u8,
+    range: Range<usize>,
+}
+
+impl<'a> MappedResource<'a> {
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.range.len()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.range.len()) }
+    }
+}
+
+impl<'a> Drop for MappedResource<'a> {
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) {
+            let key = (self.resource.as_ptr() as usize, self.subresource);
+            let mut mapped = MAPPED_SUBRESOURCES
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            match mapped.iter().position(|&(other, _)| other == key) {
+                Some(index) => {
+                    mapped[index].1 -= 1;
+                    if mapped[index].1 == 0 {
+                        mapped.swap_remove(index);
+                    }
+                }
+                None => log::warn!(
+                    "Unbalanced unmap of subresource {}, it isn't mapped by any guard",
+                    self.subresource
+                ),
+            }
+        }
+        self.resource
+            .unmap(self.subresource, Some(self.range.clone()));
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct ResourceStates: u32 {
+        const COMMON = d3d12::D3D12_RESOURCE_STATE_COMMON;
+        const VERTEX_AND_CONSTANT_BUFFER = d3d12::D3D12_RESOURCE_STATE_VERTEX_AND_CONSTANT_BUFFER;
+        const INDEX_BUFFER = d3d12::D3D12_RESOURCE_STATE_INDEX_BUFFER;
+        const RENDER_TARGET = d3d12::D3D12_RESOURCE_STATE_RENDER_TARGET;
+        const UNORDERED_ACCESS = d3d12::D3D12_RESOURCE_STATE_UNORDERED_ACCESS;
+        const DEPTH_WRITE = d3d12::D3D12_RESOURCE_STATE_DEPTH_WRITE;
+        const DEPTH_READ = d3d12::D3D12_RESOURCE_STATE_DEPTH_READ;
+        const NON_PIXEL_SHADER_RESOURCE = d3d12::D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE;
+        const PIXEL_SHADER_RESOURCE = d3d12::D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE;
+        const STREAM_OUT = d3d12::D3D12_RESOURCE_STATE_STREAM_OUT;
+        const INDIRECT_ARGUMENT = d3d12::D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT;
+        const COPY_DEST = d3d12::D3D12_RESOURCE_STATE_COPY_DEST;
+        const COPY_SOURCE = d3d12::D3D12_RESOURCE_STATE_COPY_SOURCE;
+        const RESOLVE_DEST = d3d12::D3D12_RESOURCE_STATE_RESOLVE_DEST;
+        const RESOLVE_SOURCE = d3d12::D3D12_RESOURCE_STATE_RESOLVE_SOURCE;
+        const RAYTRACING_ACCELERATION_STRUCTURE = crate::types::D3D12_RESOURCE_STATE_RAYTRACING_ACCELERATION_STRUCTURE;
+        const SHADING_RATE_SOURCE = crate::types::D3D12_RESOURCE_STATE_SHADING_RATE_SOURCE;
+        const GENERIC_READ = d3d12::D3D12_RESOURCE_STATE_GENERIC_READ;
+        const ALL_SHADER_RESOURCE = Self::NON_PIXEL_SHADER_RESOURCE.bits() | Self::PIXEL_SHADER_RESOURCE.bits();
+        const PRESENT = d3d12::D3D12_RESOURCE_STATE_PRESENT;
+        const PREDICATION = d3d12::D3D12_RESOURCE_STATE_PREDICATION;
+        const VIDEO_DECODE_READ = d3d12::D3D12_RESOURCE_STATE_VIDEO_DECODE_READ;
+        const VIDEO_DECODE_WRITE = d3d12::D3D12_RESOURCE_STATE_VIDEO_DECODE_WRITE;
+        const VIDEO_PROCESS_READ = d3d12::D3D12_RESOURCE_STATE_VIDEO_PROCESS_READ;
+        const VIDEO_PROCESS_WRITE = d3d12::D3D12_RESOURCE_STATE_VIDEO_PROCESS_WRITE;
+    }
+}
+
+/// Invalid combination of [`ResourceStates`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+    /// More than one write state is set.
+    MultipleWrites(ResourceStates),
+    /// A state that can only be used alone, like a write state, is combined with others.
+    NotExclusive(ResourceStates),
+}
+
+impl ResourceStates {
+    /// States that can't be combined with any other.
+    const EXCLUSIVE: Self = Self::RENDER_TARGET
+        .union(Self::UNORDERED_ACCESS)
+        .union(Self::DEPTH_WRITE)
+        .union(Self::STREAM_OUT)
+        .union(Self::COPY_DEST)
+        .union(Self::RESOLVE_DEST)
+        .union(Self::RAYTRACING_ACCELERATION_STRUCTURE)
+        .union(Self::VIDEO_DECODE_WRITE)
+        .union(Self::VIDEO_PROCESS_WRITE);
+
+    /// Checks for combinations rejected by the runtime.
+    ///
+    /// `PRESENT` is `COMMON`, i.e. no bits, so combining it can't be detected here.
+    pub fn validate(self) -> Result<(), StateError> {
+        let exclusive = self & Self::EXCLUSIVE;
+        if exclusive.bits().count_ones() > 1 {
+            Err(StateError::MultipleWrites(exclusive))
+        } else if !exclusive.is_empty() && exclusive != self {
+            Err(StateError::NotExclusive(self))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl From<d3d12::D3D12_RESOURCE_STATES> for ResourceStates {
+    fn from(raw: d3d12::D3D12_RESOURCE_STATES) -> Self {
+        Self::from_bits_retain(raw)
+    }
+}
+
+impl From<ResourceStates> for d3d12::D3D12_RESOURCE_STATES {
+    fn from(states: ResourceStates) -> Self {
+        states.bits()
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct ResourceFlags: u32 {
+        const NONE = d3d12::D3D12_RESOURCE_FLAG_NONE;
+        const ALLOW_RENDER_TARGET = d3d12::D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET;
+        const ALLOW_DEPTH_STENCIL = d3d12::D3D12_RESOURCE_FLAG_ALLOW_DEPTH_STENCIL;
+        const ALLOW_UNORDERED_ACCESS = d3d12::D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS;
+        const DENY_SHADER_RESOURCE = d3d12::D3D12_RESOURCE_FLAG_DENY_SHADER_RESOURCE;
+        const ALLOW_CROSS_ADAPTER = d3d12::D3D12_RESOURCE_FLAG_ALLOW_CROSS_ADAPTER;
+        const ALLOW_SIMULTANEOUS_ACCESS = d3d12::D3D12_RESOURCE_FLAG_ALLOW_SIMULTANEOUS_ACCESS;
+    }
+}
+
+impl From<d3d12::D3D12_RESOURCE_FLAGS> for ResourceFlags {
+    fn from(raw: d3d12::D3D12_RESOURCE_FLAGS) -> Self {
+        Self::from_bits_retain(raw)
+    }
+}
+
+impl From<ResourceFlags> for d3d12::D3D12_RESOURCE_FLAGS {
+    fn from(flags: ResourceFlags) -> Self {
+        flags.bits()
+    }
+}
+
+/// Parameters of a committed resource, see [`Device::create_committed_resource`](crate::Device::create_committed_resource).
+pub struct CommittedResourceBuilder {
+    pub(crate) heap_properties: HeapProperties,
+    pub(crate) heap_flags: HeapFlags,
+    pub(crate) desc: d3d12::D3D12_RESOURCE_DESC,
+    pub(crate) initial_state: ResourceStates,
+    pub(crate) clear_value: Option<d3d12::D3D12_CLEAR_VALUE>,
+}
+
+impl CommittedResourceBuilder {
+    pub fn new(heap_properties: HeapProperties, desc: d3d12::D3D12_RESOURCE_DESC) -> Self {
+        CommittedResourceBuilder {
+            heap_properties,
+            heap_flags: HeapFlags::NONE,
+            desc,
+            initial_state: ResourceStates::COMMON,
+            clear_value: None,
+        }
+    }
+
+    /// CPU-writable buffer, e.g. for staging uploads or per-frame constants.
+    pub fn upload_buffer(size: u64) -> Self {
+        Self::new(
+            HeapProperties::standard(HeapType::Upload),
+            buffer_desc(size),
+        )
+        .initial_state(ResourceStates::GENERIC_READ)
+    }
+
+    /// CPU-readable buffer, to copy GPU results into.
+    pub fn readback_buffer(size: u64) -> Self {
+        Self::new(
+            HeapProperties::standard(HeapType::Readback),
+            buffer_desc(size),
+        )
+        .initial_state(ResourceStates::COPY_DEST)
+    }
+
+    /// GPU-local buffer.
+    pub fn default_buffer(size: u64) -> Self {
+        Self::new(
+            HeapProperties::standard(HeapType::Default),
+            buffer_desc(size),
+        )
+    }
+
+    /// GPU-local single-sampled 2D texture, `mip_levels` of 0 allocates the full chain.
+    pub fn texture2d(
+        format: Format,
+        width: u64,
+        height: u32,
+        mip_levels: u16,
+        flags: ResourceFlags,
+    ) -> Self {
+        let desc = d3d12::D3D12_RESOURCE_DESC {
+            Dimension: d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+            Alignment: 0,
+            Width: width,
+            Height: height,
+            DepthOrArraySize: 1,
+            MipLevels: mip_levels,
+            Format: format,
+            SampleDesc: dxgitype::DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Layout: d3d12::D3D12_TEXTURE_LAYOUT_UNKNOWN,
+            Flags: flags.bits(),
+        };
+        Self::new(HeapProperties::standard(HeapType::Default), desc)
+    }
+
+    pub fn heap_flags(mut self, flags: HeapFlags) -> Self {
+        self.heap_flags = flags;
+        self
+    }
+
+    pub fn initial_state(mut self, state: ResourceStates) -> Self {
+        self.initial_state = state;
+        self
+    }
+
+    /// Optimized clear value, only accepted for render target and depth-stencil resources.
+    pub fn clear_value(mut self, clear_value: d3d12::D3D12_CLEAR_VALUE) -> Self {
+        self.clear_value = Some(clear_value);
+        self
+    }
+
+    /// Replaces the properties of the implicit heap, e.g. by [`HeapProperties::custom`] ones.
+    pub fn heap_properties(mut self, heap_properties: HeapProperties) -> Self {
+        self.heap_properties = heap_properties;
+        self
+    }
+
+    /// Places the implicit heap on `creation_node_mask` and makes it visible to
+    /// `visible_node_mask`, overriding the heap properties. Both default to the single node.
+    pub fn node_masks(mut self, creation_node_mask: NodeMask, visible_node_mask: NodeMask) -> Self {
+        self.heap_properties.0.CreationNodeMask = creation_node_mask;
+        self.heap_properties.0.VisibleNodeMask = visible_node_mask;
+        self
+    }
+
+    /// Allows the resource to be used for direct scanout, e.g. by a compositor.
+    ///
+    /// Only single-sampled, non-array 2D textures in a scanout format and with
+    /// an undefined layout are accepted.
+    pub fn allow_display(mut self) -> Self {
+        self.heap_flags |= HeapFlags::ALLOW_DISPLAY;
+        self
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        is_layout_valid(&self.desc) && self.is_display_compatible() && self.is_clear_value_valid()
+    }
+
+    fn is_clear_value_valid(&self) -> bool {
+        let targets = d3d12::D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET
+            | d3d12::D3D12_RESOURCE_FLAG_ALLOW_DEPTH_STENCIL;
+        self.clear_value.is_none() || self.desc.Flags & targets != 0
+    }
+
+    fn is_display_compatible(&self) -> bool {
+        if !self.heap_flags.contains(HeapFlags::ALLOW_DISPLAY) {
+            return true;
+        }
+
+        let desc = &self.desc;
+        desc.Dimension == d3d12::D3D12_RESOURCE_DIMENSION_TEXTURE2D
+            && desc.Layout == d3d12::D3D12_TEXTURE_LAYOUT_UNKNOWN
+            && desc.DepthOrArraySize == 1
+            && desc.SampleDesc.Count == 1
+            && is_display_format(desc.Format)
+    }
+}
+
+fn buffer_desc(size: u64) -> d3d12::D3D12_RESOURCE_DESC {
+    d3d12::D3D12_RESOURCE_DESC {
+        Dimension: d3d12::D3D12_RESOURCE_DIMENSION_BUFFER,
+        Alignment: 0,
+        Width: size,
+        Height: 1,
+        DepthOrArraySize: 1,
+        MipLevels: 1,
+        Format: dxgiformat::DXGI_FORMAT_UNKNOWN,
+        SampleDesc: dxgitype::DXGI_SAMPLE_DESC {
+            Count: 1,
+            Quality: 0,
+        },
+        Layout: d3d12::D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+        Flags: d3d12::D3D12_RESOURCE_FLAG_NONE,
+    }
+}
+
+/// Buffers have to be row-major, while textures may only be row-major when shared across adapters.
+pub(crate) fn is_layout_valid(desc: &d3d12::D3D12_RESOURCE_DESC) -> bool {
+    if desc.Dimension == d3d12::D3D12_RESOURCE_DIMENSION_BUFFER {
+        desc.Layout == d3d12::D3D12_TEXTURE_LAYOUT_ROW_MAJOR
+    } else {
+        desc.Layout != d3d12::D3D12_TEXTURE_LAYOUT_ROW_MAJOR
+            || desc.Flags & d3d12::D3D12_RESOURCE_FLAG_ALLOW_CROSS_ADAPTER != 0
+    }
+}
+
+fn is_display_format(format: Format) -> bool {
+    matches!(
+        format,
+        dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM
+            | dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM
+            | dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+            | dxgiformat::DXGI_FORMAT_R10G10B10A2_UNORM
+            | dxgiformat::DXGI_FORMAT_R10G10B10_XR_BIAS_A2_UNORM
+            | dxgiformat::DXGI_FORMAT_R16G16B16A16_FLOAT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{slot, Mock};
+    use std::{mem, sync::atomic::Ordering};
+
+    #[test]
+    fn composite_resource_states() {
+        // Values from d3d12.h.
+        assert_eq!(ResourceStates::COMMON.bits(), 0);
+        assert_eq!(ResourceStates::PRESENT.bits(), 0);
+        assert_eq!(ResourceStates::GENERIC_READ.bits(), 0xAC3);
+        assert_eq!(ResourceStates::ALL_SHADER_RESOURCE.bits(), 0xC0);
+        assert_eq!(
+            ResourceStates::GENERIC_READ,
+            ResourceStates::VERTEX_AND_CONSTANT_BUFFER
+                | ResourceStates::INDEX_BUFFER
+                | ResourceStates::NON_PIXEL_SHADER_RESOURCE
+                | ResourceStates::PIXEL_SHADER_RESOURCE
+                | ResourceStates::INDIRECT_ARGUMENT
+                | ResourceStates::COPY_SOURCE
+        );
+        assert!(ResourceStates::COMMON.is_empty());
+        assert_eq!(
+            d3d12::D3D12_RESOURCE_STATES::from(ResourceStates::GENERIC_READ),
+            d3d12::D3D12_RESOURCE_STATE_GENERIC_READ
+        );
+    }
+
+    fn display_texture(format: Format) -> CommittedResourceBuilder {
+        CommittedResourceBuilder::texture2d(
+            format,
+            1920,
+            1080,
+            1,
+            ResourceFlags::ALLOW_RENDER_TARGET,
+        )
+        .allow_display()
+    }
+
+    #[test]
+    fn display_compatible_texture() {
+        let builder = display_texture(dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM);
+        assert!(builder.heap_flags.contains(HeapFlags::ALLOW_DISPLAY));
+        assert!(builder.is_valid());
+        assert!(display_texture(dxgiformat::DXGI_FORMAT_R10G10B10A2_UNORM).is_valid());
+    }
+
+    #[test]
+    fn display_incompatible_format() {
+        assert!(!display_texture(dxgiformat::DXGI_FORMAT_R32_FLOAT).is_valid());
+        assert!(!display_texture(dxgiformat::DXGI_FORMAT_BC1_UNORM).is_valid());
+        // The format only matters for display-capable textures.
+        assert!(CommittedResourceBuilder::texture2d(
+            dxgiformat::DXGI_FORMAT_R32_FLOAT,
+            1920,
+            1080,
+            1,
+            ResourceFlags::ALLOW_RENDER_TARGET,
+        )
+        .is_valid());
+
+        let mut array = display_texture(dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM);
+        array.desc.DepthOrArraySize = 2;
+        assert!(!array.is_valid());
+        assert!(!CommittedResourceBuilder::default_buffer(1024)
+            .allow_display()
+            .is_valid());
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn display_incompatible_resource_creation() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (resource, hr) =
+            device.create_committed_resource(display_texture(dxgiformat::DXGI_FORMAT_R32_FLOAT));
+        assert_eq!(hr, winerror::E_INVALIDARG);
+        assert!(resource.is_null());
+    }
+
+    #[test]
+    fn region_size_of_texels() {
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        assert_eq!(region_size(format, (4, 2, 1), 256, 512), Some(256 + 16));
+        assert_eq!(
+            region_size(format, (4, 2, 3), 256, 512),
+            Some(2 * 512 + 256 + 16)
+        );
+        assert_eq!(region_size(format, (0, 2, 1), 256, 512), Some(0));
+    }
+
+    #[test]
+    fn region_size_of_blocks() {
+        // 8x8 texels of BC1 are 2x2 blocks of 8 bytes.
+        let format = dxgiformat::DXGI_FORMAT_BC1_UNORM;
+        assert_eq!(region_size(format, (8, 8, 1), 64, 128), Some(64 + 16));
+        assert_eq!(region_size(format, (1, 1, 1), 64, 128), Some(8));
+    }
+
+    #[test]
+    fn region_size_of_unknown_format() {
+        let format = dxgiformat::DXGI_FORMAT_UNKNOWN;
+        assert_eq!(region_size(format, (4, 4, 1), 64, 64), None);
+    }
+
+    struct MockBuffer {
+        data: std::cell::UnsafeCell<[u8; 64]>,
+        maps: std::sync::atomic::AtomicU32,
+        unmaps: std::sync::atomic::AtomicU32,
+    }
+
+    unsafe extern "system" fn buffer_desc_of(
+        _this: *mut d3d12::ID3D12Resource,
+        ret: *mut d3d12::D3D12_RESOURCE_DESC,
+    ) -> *mut d3d12::D3D12_RESOURCE_DESC {
+        *ret = buffer_desc(64);
+        ret
+    }
+
+    unsafe extern "system" fn map_buffer(
+        this: *mut d3d12::ID3D12Resource,
+        _subresource: u32,
+        _read_range: *const d3d12::D3D12_RANGE,
+        data: *mut *mut winapi::ctypes::c_void,
+    ) -> HRESULT {
+        let buffer = Mock::<MockBuffer>::state(this);
+        buffer.maps.fetch_add(1, Ordering::Relaxed);
+        *data = buffer.data.get() as *mut _;
+        winerror::S_OK
+    }
+
+    unsafe extern "system" fn unmap_buffer(
+        this: *mut d3d12::ID3D12Resource,
+        _subresource: u32,
+        _written_range: *const d3d12::D3D12_RANGE,
+    ) {
+        let buffer = Mock::<MockBuffer>::state(this);
+        buffer.unmaps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Mocked 64 byte buffer counting its maps and unmaps.
+    fn mock_buffer() -> Resource {
+        Mock::new::<d3d12::ID3D12Resource, d3d12::ID3D12ResourceVtbl>(
+            MockBuffer {
+                data: std::cell::UnsafeCell::new([0; 64]),
+                maps: Default::default(),
+                unmaps: Default::default(),
+            },
+            &[
+                (
+                    slot!(d3d12::ID3D12ResourceVtbl, GetDesc),
+                    buffer_desc_of as usize,
+                ),
+                (slot!(d3d12::ID3D12ResourceVtbl, Map), map_buffer as usize),
+                (
+                    slot!(d3d12::ID3D12ResourceVtbl, Unmap),
+                    unmap_buffer as usize,
+                ),
+            ],
+        )
+    }
+
+    fn map_counts(buffer: &Resource) -> (u32, u32) {
+        let state = unsafe { Mock::<MockBuffer>::state(buffer.as_ptr()) };
+        (
+            state.maps.load(Ordering::Relaxed),
+            state.unmaps.load(Ordering::Relaxed),
+        )
+    }
+
+    #[test]
+    fn map_guard_unmaps_on_drop() {
+        let buffer = mock_buffer();
+        let (guard, hr) = buffer.map_guard(0, 16..32);
+        assert_eq!(hr, winerror::S_OK);
+        let mut guard = guard.unwrap();
+        guard.as_mut_slice().copy_from_slice(&[7; 16]);
+        assert_eq!(guard.as_slice().len(), 16);
+        assert_eq!(map_counts(&buffer), (1, 0));
+
+        drop(guard);
+        assert_eq!(map_counts(&buffer), (1, 1));
+        let data = unsafe { &*Mock::<MockBuffer>::state(buffer.as_ptr()).data.get() };
+        assert_eq!((data[15], &data[16..32], data[32]), (0, &[7; 16][..], 0));
+    }
+
+    #[test]
+    fn map_guard_out_of_range() {
+        let buffer = mock_buffer();
+        let (guard, hr) = buffer.map_guard(0, 32..65);
+        assert!(guard.is_none());
+        assert_eq!(hr, winerror::E_INVALIDARG);
+        #[allow(clippy::reversed_empty_ranges)]
+        let (guard, hr) = buffer.map_guard(0, 8..4);
+        assert!(guard.is_none());
+        assert_eq!(hr, winerror::E_INVALIDARG);
+        assert_eq!(map_counts(&buffer), (0, 0));
+
+        let (guard, _) = buffer.map_guard(0, 0..64);
+        assert_eq!(guard.unwrap().as_slice().len(), 64);
+    }
+
+    #[test]
+    fn state_combinations() {
+        type S = ResourceStates;
+        for &legal in &[
+            S::COMMON,
+            S::GENERIC_READ,
+            S::ALL_SHADER_RESOURCE | S::COPY_SOURCE,
+            S::DEPTH_READ | S::PIXEL_SHADER_RESOURCE,
+            S::INDEX_BUFFER | S::VERTEX_AND_CONSTANT_BUFFER | S::INDIRECT_ARGUMENT,
+            S::RENDER_TARGET,
+            S::UNORDERED_ACCESS,
+            S::COPY_DEST,
+        ] {
+            assert_eq!(legal.validate(), Ok(()), "{:?}", legal);
+        }
+
+        assert_eq!(
+            (S::RENDER_TARGET | S::COPY_DEST).validate(),
+            Err(StateError::MultipleWrites(S::RENDER_TARGET | S::COPY_DEST))
+        );
+        assert_eq!(
+            (S::UNORDERED_ACCESS | S::DEPTH_WRITE | S::COPY_SOURCE).validate(),
+            Err(StateError::MultipleWrites(
+                S::UNORDERED_ACCESS | S::DEPTH_WRITE
+            ))
+        );
+        assert_eq!(
+            (S::COPY_DEST | S::COPY_SOURCE).validate(),
+            Err(StateError::NotExclusive(S::COPY_DEST | S::COPY_SOURCE))
+        );
+        assert_eq!(
+            (S::DEPTH_WRITE | S::PIXEL_SHADER_RESOURCE).validate(),
+            Err(StateError::NotExclusive(
+                S::DEPTH_WRITE | S::PIXEL_SHADER_RESOURCE
+            ))
+        );
+    }
+
+    #[test]
+    fn layouts() {
+        let mut buffer = buffer_desc(256);
+        assert!(is_layout_valid(&buffer));
+        buffer.Layout = d3d12::D3D12_TEXTURE_LAYOUT_UNKNOWN;
+        assert!(!is_layout_valid(&buffer));
+        buffer.Layout = d3d12::D3D12_TEXTURE_LAYOUT_64KB_UNDEFINED_SWIZZLE;
+        assert!(!is_layout_valid(&buffer));
+
+        let mut texture = CommittedResourceBuilder::texture2d(
+            dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM,
+            64,
+            64,
+            1,
+            ResourceFlags::empty(),
+        )
+        .desc;
+        assert!(is_layout_valid(&texture));
+        texture.Layout = d3d12::D3D12_TEXTURE_LAYOUT_ROW_MAJOR;
+        assert!(!is_layout_valid(&texture));
+        texture.Flags |= d3d12::D3D12_RESOURCE_FLAG_ALLOW_CROSS_ADAPTER;
+        assert!(is_layout_valid(&texture));
+    }
+
+    #[test]
+    fn clear_value_needs_a_target() {
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let mut clear_value: d3d12::D3D12_CLEAR_VALUE = unsafe { mem::zeroed() };
+        clear_value.Format = format;
+        let texture = |flags| {
+            CommittedResourceBuilder::texture2d(format, 16, 16, 1, flags).clear_value(clear_value)
+        };
+        assert!(texture(ResourceFlags::ALLOW_RENDER_TARGET).is_valid());
+        assert!(!texture(ResourceFlags::empty()).is_valid());
+        assert!(!CommittedResourceBuilder::upload_buffer(256)
+            .clear_value(clear_value)
+            .is_valid());
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn preset_resources() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        for &(builder, heap_type) in &[
+            (
+                CommittedResourceBuilder::upload_buffer as fn(u64) -> CommittedResourceBuilder,
+                d3d12::D3D12_HEAP_TYPE_UPLOAD,
+            ),
+            (
+                CommittedResourceBuilder::readback_buffer,
+                d3d12::D3D12_HEAP_TYPE_READBACK,
+            ),
+            (
+                CommittedResourceBuilder::default_buffer,
+                d3d12::D3D12_HEAP_TYPE_DEFAULT,
+            ),
+        ] {
+            let (buffer, hr) = device.create_committed_resource(builder(1024));
+            assert!(winerror::SUCCEEDED(hr));
+            assert_eq!(buffer.width(), 1024);
+            let mut properties: d3d12::D3D12_HEAP_PROPERTIES = unsafe { mem::zeroed() };
+            let hr = unsafe { buffer.GetHeapProperties(&mut properties, ptr::null_mut()) };
+            assert!(winerror::SUCCEEDED(hr));
+            assert_eq!(properties.Type, heap_type);
+        }
+
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let mut clear_value: d3d12::D3D12_CLEAR_VALUE = unsafe { mem::zeroed() };
+        clear_value.Format = format;
+        *unsafe { clear_value.u.Color_mut() } = [0.0, 0.0, 0.0, 1.0];
+        let builder = CommittedResourceBuilder::texture2d(
+            format,
+            64,
+            32,
+            1,
+            ResourceFlags::ALLOW_RENDER_TARGET,
+        )
+        .initial_state(ResourceStates::RENDER_TARGET)
+        .clear_value(clear_value);
+        let (texture, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        let desc = texture.desc();
+        assert_eq!((desc.Width, desc.Height), (64, 32));
+        assert_eq!(desc.Flags, d3d12::D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn decoded_texture_desc() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let builder =
+            CommittedResourceBuilder::texture2d(format, 256, 128, 3, ResourceFlags::empty());
+        let (texture, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(texture.width(), 256);
+        assert_eq!(texture.height(), 128);
+        assert_eq!(texture.mip_levels(), 3);
+        assert_eq!(texture.format(), format);
+        assert_eq!(texture.dimension(), ResourceDimension::Texture2D);
+        assert_eq!(texture.desc().DepthOrArraySize, 1);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn read_to_vec_round_trip() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let size = 256;
+        let (upload, hr) =
+            device.create_committed_resource(CommittedResourceBuilder::upload_buffer(size as u64));
+        assert!(winerror::SUCCEEDED(hr));
+        let (readback, hr) = device
+            .create_committed_resource(CommittedResourceBuilder::readback_buffer(size as u64));
+        assert!(winerror::SUCCEEDED(hr));
+
+        let data: Vec<u8> = (0..size).map(|i| i as u8).collect();
+        let (mapping, hr) = upload.map_guard(0, 0..size);
+        assert!(winerror::SUCCEEDED(hr));
+        mapping.unwrap().as_mut_slice().copy_from_slice(&data);
+
+        let (_allocator, list) = crate::test_command_list(&device);
+        list.copy_resource(&readback, &upload);
+        crate::test_execute(&device, &list);
+
+        let (bytes, hr) = readback.read_to_vec(0, 0..size);
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(bytes, data);
+        let (bytes, hr) = readback.read_to_vec(0, 16..32);
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(bytes, &data[16..32]);
+
+        let (bytes, hr) = readback.read_to_vec(0, 0..size + 1);
+        assert_eq!(hr, winerror::E_INVALIDARG);
+        assert!(bytes.is_empty());
+        #[allow(clippy::reversed_empty_ranges)]
+        let (_, hr) = readback.read_to_vec(0, 32..16);
+        assert_eq!(hr, winerror::E_INVALIDARG);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn subresource_round_trip_on_custom_heap() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let heap_properties = HeapProperties::custom(
+            crate::CpuPageProperty::WriteBack,
+            crate::MemoryPool::L0,
+            1,
+            1,
+        );
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let builder =
+            CommittedResourceBuilder::texture2d(format, 16, 16, 1, ResourceFlags::empty())
+                .heap_properties(heap_properties)
+                .initial_state(ResourceStates::COMMON);
+        let (texture, hr) = device.create_committed_resource(builder);
+        // Not every adapter offers CPU-visible textures.
+        if winerror::FAILED(hr) {
+            return;
+        }
+        // Textures of undefined layout are mapped without a pointer.
+        let hr = unsafe { texture.Map(0, ptr::null(), ptr::null_mut()) };
+        assert!(winerror::SUCCEEDED(hr));
+
+        let region = d3d12::D3D12_BOX {
+            left: 4,
+            top: 2,
+            front: 0,
+            right: 8,
+            bottom: 6,
+            back: 1,
+        };
+        let row_pitch = 4 * 4;
+        let texels: Vec<u8> = (0..row_pitch * 4).map(|i| i as u8).collect();
+        let hr = texture.write_to_subresource(0, Some(&region), &texels, row_pitch, row_pitch * 4);
+        assert!(winerror::SUCCEEDED(hr));
+
+        let mut read = vec![0; texels.len()];
+        let hr =
+            texture.read_from_subresource(&mut read, row_pitch, row_pitch * 4, 0, Some(&region));
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(read, texels);
+
+        let hr = texture.write_to_subresource(0, Some(&region), &texels[1..], row_pitch, 0);
+        assert_eq!(hr, winerror::E_INVALIDARG);
+        texture.unmap(0, None);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn upload_buffer_gpu_address() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (buffer, hr) =
+            device.create_committed_resource(CommittedResourceBuilder::upload_buffer(1024));
+        assert!(winerror::SUCCEEDED(hr));
+        let address = buffer.gpu_virtual_address();
+        assert!(!address.is_null());
+        // Committed buffers are placed at the start of a 64KB aligned heap.
+        assert!(address.is_aligned(d3d12::D3D12_DEFAULT_RESOURCE_PLACEMENT_ALIGNMENT as u64));
+        assert_eq!(address.checked_add(1024), Some(address.offset(1024)));
+
+        // Textures have no meaningful address.
+        let format = dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+        let builder =
+            CommittedResourceBuilder::texture2d(format, 16, 16, 1, ResourceFlags::empty());
+        let (texture, hr) = device.create_committed_resource(builder);
+        assert!(winerror::SUCCEEDED(hr));
+        assert!(texture.gpu_virtual_address().is_null());
     }
 }
@@ -0,0 +1,254 @@
+//! Resource state tracking
+
+use crate::{Resource, ResourceBarrier, ResourceStates, Subresource};
+use std::collections::HashMap;
+use winapi::um::d3d12;
+
+struct SubresourceState {
+    /// State the GPU will see once all flushed barriers executed.
+    flushed: ResourceStates,
+    /// Last requested state.
+    desired: ResourceStates,
+}
+
+struct TrackedResource {
+    /// Keeps the resource alive while barriers for it may still be generated.
+    resource: Resource,
+    subresources: Vec<SubresourceState>,
+}
+
+/// Records the last known state of each subresource and generates the transitions
+/// needed to reach newly requested states.
+///
+/// Transitions are only resolved on [`flush`](Self::flush), so transitions back
+/// and forth in between cancel out.
+#[derive(Default)]
+pub struct ResourceStateTracker {
+    resources: HashMap<*mut d3d12::ID3D12Resource, TrackedResource>,
+}
+
+impl ResourceStateTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking `resource`, all of its `subresource_count` subresources being in `state`.
+    pub fn register(&mut self, resource: &Resource, subresource_count: u32, state: ResourceStates) {
+        let subresources = (0..subresource_count)
+            .map(|_| SubresourceState {
+                flushed: state,
+                desired: state,
+            })
+            .collect();
+        self.resources.insert(
+            resource.as_mut_ptr(),
+            TrackedResource {
+                resource: resource.clone(),
+                subresources,
+            },
+        );
+    }
+
+    /// Stops tracking `resource`, dropping any pending transition.
+    pub fn unregister(&mut self, resource: &Resource) {
+        self.resources.remove(&resource.as_mut_ptr());
+    }
+
+    /// Last requested state of `subresource`.
+    ///
+    /// For `D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES`, only returns a state all subresources share.
+    pub fn state(&self, resource: &Resource, subresource: Subresource) -> Option<ResourceStates> {
+        let tracked = self.resources.get(&resource.as_mut_ptr())?;
+        if subresource == d3d12::D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES {
+            let first = tracked.subresources.first()?.desired;
+            if tracked.subresources.iter().all(|sub| sub.desired == first) {
+                Some(first)
+            } else {
+                None
+            }
+        } else {
+            tracked
+                .subresources
+                .get(subresource as usize)
+                .map(|sub| sub.desired)
+        }
+    }
+
+    /// Requests `subresource` of `resource` to be in `state` from the next flush on.
+    ///
+    /// `resource` has to be registered.
+    pub fn transition(
+        &mut self,
+        resource: &Resource,
+        subresource: Subresource,
+        state: ResourceStates,
+    ) {
+        let tracked = self
+            .resources
+            .get_mut(&resource.as_mut_ptr())
+            .expect("transition of an untracked resource");
+        if subresource == d3d12::D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES {
+            for sub in tracked.subresources.iter_mut() {
+                sub.desired = state;
+            }
+        } else {
+            tracked.subresources[subresource as usize].desired = state;
+        }
+    }
+
+    /// Returns the barriers bringing every subresource to its requested state.
+    ///
+    /// Resources with all subresources moving between the same states get a single
+    /// barrier for all subresources.
+    pub fn flush(&mut self) -> Vec<ResourceBarrier> {
+        let mut barriers = Vec::new();
+        for tracked in self.resources.values_mut() {
+            let first = match tracked.subresources.first() {
+                Some(sub) => (sub.flushed, sub.desired),
+                None => continue,
+            };
+            let uniform = tracked
+                .subresources
+                .iter()
+                .all(|sub| (sub.flushed, sub.desired) == first);
+
+            if uniform {
+                if first.0 != first.1 {
+                    barriers.push(ResourceBarrier::transition(
+                        tracked.resource.clone(),
+                        d3d12::D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+                        first.0.bits(),
+                        first.1.bits(),
+                        d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+                    ));
+                }
+            } else {
+                for (index, sub) in tracked.subresources.iter().enumerate() {
+                    if sub.flushed != sub.desired {
+                        barriers.push(ResourceBarrier::transition(
+                            tracked.resource.clone(),
+                            index as Subresource,
+                            sub.flushed.bits(),
+                            sub.desired.bits(),
+                            d3d12::D3D12_RESOURCE_BARRIER_FLAG_NONE,
+                        ));
+                    }
+                }
+            }
+
+            for sub in tracked.subresources.iter_mut() {
+                sub.flushed = sub.desired;
+            }
+        }
+        barriers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::Mock;
+
+    fn resource() -> Resource {
+        Mock::new::<d3d12::ID3D12Resource, d3d12::ID3D12ResourceVtbl>((), &[])
+    }
+
+    /// Subresource and states of transition barriers.
+    fn transitions(
+        barriers: &[ResourceBarrier],
+    ) -> Vec<(Subresource, ResourceStates, ResourceStates)> {
+        barriers
+            .iter()
+            .map(|barrier| {
+                assert_eq!(
+                    barrier.0.Type,
+                    d3d12::D3D12_RESOURCE_BARRIER_TYPE_TRANSITION
+                );
+                let transition = unsafe { barrier.0.u.Transition() };
+                (
+                    transition.Subresource,
+                    ResourceStates::from(transition.StateBefore),
+                    ResourceStates::from(transition.StateAfter),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn redundant_transitions_elided() {
+        let texture = resource();
+        let mut tracker = ResourceStateTracker::new();
+        tracker.register(&texture, 1, ResourceStates::COMMON);
+
+        tracker.transition(&texture, 0, ResourceStates::COPY_DEST);
+        tracker.transition(&texture, 0, ResourceStates::COMMON);
+        assert!(tracker.flush().is_empty());
+
+        tracker.transition(&texture, 0, ResourceStates::COPY_DEST);
+        tracker.transition(&texture, 0, ResourceStates::COPY_DEST);
+        assert_eq!(
+            transitions(&tracker.flush()),
+            [(
+                d3d12::D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+                ResourceStates::COMMON,
+                ResourceStates::COPY_DEST
+            )]
+        );
+
+        tracker.transition(&texture, 0, ResourceStates::COPY_DEST);
+        assert!(tracker.flush().is_empty());
+        assert_eq!(tracker.state(&texture, 0), Some(ResourceStates::COPY_DEST));
+    }
+
+    #[test]
+    fn subresources_tracked_independently() {
+        let all = d3d12::D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES;
+        let texture = resource();
+        let mut tracker = ResourceStateTracker::new();
+        tracker.register(&texture, 3, ResourceStates::COMMON);
+
+        tracker.transition(&texture, 1, ResourceStates::RENDER_TARGET);
+        assert_eq!(tracker.state(&texture, all), None);
+        assert_eq!(
+            transitions(&tracker.flush()),
+            [(1, ResourceStates::COMMON, ResourceStates::RENDER_TARGET)]
+        );
+
+        // Subresources come from different states, each needs its own barrier.
+        tracker.transition(&texture, all, ResourceStates::PIXEL_SHADER_RESOURCE);
+        assert_eq!(
+            tracker.state(&texture, all),
+            Some(ResourceStates::PIXEL_SHADER_RESOURCE)
+        );
+        assert_eq!(
+            transitions(&tracker.flush()),
+            [
+                (
+                    0,
+                    ResourceStates::COMMON,
+                    ResourceStates::PIXEL_SHADER_RESOURCE
+                ),
+                (
+                    1,
+                    ResourceStates::RENDER_TARGET,
+                    ResourceStates::PIXEL_SHADER_RESOURCE
+                ),
+                (
+                    2,
+                    ResourceStates::COMMON,
+                    ResourceStates::PIXEL_SHADER_RESOURCE
+                ),
+            ]
+        );
+
+        tracker.transition(&texture, all, ResourceStates::COPY_SOURCE);
+        assert_eq!(
+            transitions(&tracker.flush()),
+            [(
+                all,
+                ResourceStates::PIXEL_SHADER_RESOURCE,
+                ResourceStates::COPY_SOURCE
+            )]
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use crate::{com::ComPtr, HRESULT};
+use crate::{com::ComPtr, D3DResult, Device, HRESULT};
 use std::ptr;
-use winapi::um::{d3d12, synchapi, winnt};
+use winapi::{
+    shared::winerror,
+    um::{d3d12, errhandlingapi, handleapi, synchapi, winbase, winnt},
+    Interface,
+};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 #[repr(transparent)]
 pub struct Event(pub winnt::HANDLE);
 impl Event {
@@ -21,19 +25,218 @@ impl Event {
     pub fn wait(&self, timeout_ms: u32) -> u32 {
         unsafe { synchapi::WaitForSingleObject(self.0, timeout_ms) }
     }
+
+    /// Puts a manual-reset event back into the non-signaled state.
+    pub fn reset(&self) -> bool {
+        unsafe { synchapi::ResetEvent(self.0) != 0 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitStatus {
+    Signaled,
+    Timeout,
+    Failed,
+}
+
+/// Event closing its handle on drop, so it can be kept around and rearmed,
+/// e.g. once per frame in flight.
+#[derive(Debug)]
+pub struct OwnedEvent(Event);
+
+impl OwnedEvent {
+    pub fn new(manual_reset: bool) -> D3DResult<Self> {
+        let event = Event::create(manual_reset, false);
+        let hr = if event.0.is_null() {
+            winerror::HRESULT_FROM_WIN32(unsafe { errhandlingapi::GetLastError() })
+        } else {
+            winerror::S_OK
+        };
+
+        (OwnedEvent(event), hr)
+    }
+
+    /// Takes ownership of an event `handle`, closing it on drop.
+    pub unsafe fn from_raw(handle: winnt::HANDLE) -> Self {
+        OwnedEvent(Event(handle))
+    }
+
+    /// Raw handle, valid as long as `self` lives.
+    pub fn event(&self) -> Event {
+        self.0
+    }
+
+    pub fn reset(&self) -> bool {
+        self.0.reset()
+    }
+
+    /// Waits for the event, `timeout_ms` may be `INFINITE`.
+    pub fn wait(&self, timeout_ms: u32) -> WaitStatus {
+        match self.0.wait(timeout_ms) {
+            winbase::WAIT_OBJECT_0 => WaitStatus::Signaled,
+            winerror::WAIT_TIMEOUT => WaitStatus::Timeout,
+            _ => WaitStatus::Failed,
+        }
+    }
+}
+
+impl Drop for OwnedEvent {
+    fn drop(&mut self) {
+        if !self.0 .0.is_null() {
+            unsafe { handleapi::CloseHandle(self.0 .0) };
+        }
+    }
 }
 
 pub type Fence = ComPtr<d3d12::ID3D12Fence>;
 impl Fence {
-    pub fn set_event_on_completion(&self, event: Event, value: u64) -> HRESULT {
-        unsafe { self.SetEventOnCompletion(value, event.0) }
+    /// Signals `event` once the fence reaches `value`, immediately if it already did.
+    ///
+    /// The event can be rearmed for a later value, after a [`OwnedEvent::reset`] if it
+    /// is a manual-reset one.
+    pub fn set_event_on_completion(&self, event: &OwnedEvent, value: u64) -> HRESULT {
+        unsafe { self.SetEventOnCompletion(value, event.event().0) }
     }
 
     pub fn get_value(&self) -> u64 {
         unsafe { self.GetCompletedValue() }
     }
 
+    /// Value of the last completed signal.
+    ///
+    /// Fences read as `u64::MAX` once the device is removed, in which case the
+    /// removal reason is returned instead of `S_OK`.
+    pub fn completed_value(&self) -> D3DResult<u64> {
+        let value = self.get_value();
+        if value != u64::MAX {
+            return (value, winerror::S_OK);
+        }
+
+        let mut device = Device::null();
+        let hr = unsafe { self.GetDevice(&d3d12::ID3D12Device::uuidof(), device.mut_void()) };
+        if winerror::FAILED(hr) {
+            return (value, hr);
+        }
+
+        (value, unsafe { device.GetDeviceRemovedReason() })
+    }
+
+    /// Sets the fence to `value` from the CPU, immediately.
+    ///
+    /// To signal once the GPU reaches a point, see [`CommandQueue::signal`](crate::CommandQueue::signal).
     pub fn signal(&self, value: u64) -> HRESULT {
         unsafe { self.Signal(value) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{slot, Mock};
+    use winapi::{ctypes::c_void, shared::guiddef::REFIID};
+
+    /// Completed value of the mocked fence and removal reason of its device.
+    struct MockFence {
+        value: u64,
+        reason: HRESULT,
+    }
+
+    unsafe extern "system" fn completed_value_of(this: *mut d3d12::ID3D12Fence) -> u64 {
+        Mock::<MockFence>::state(this).value
+    }
+
+    unsafe extern "system" fn get_device(
+        this: *mut d3d12::ID3D12Fence,
+        _riid: REFIID,
+        device: *mut *mut c_void,
+    ) -> HRESULT {
+        let mock = Mock::new::<d3d12::ID3D12Device, d3d12::ID3D12DeviceVtbl>(
+            Mock::<MockFence>::state(this).reason,
+            &[(
+                slot!(d3d12::ID3D12DeviceVtbl, GetDeviceRemovedReason),
+                removed_reason as usize,
+            )],
+        );
+        *device = mock.as_mut_ptr() as *mut c_void;
+        std::mem::forget(mock);
+        winerror::S_OK
+    }
+
+    unsafe extern "system" fn removed_reason(this: *mut d3d12::ID3D12Device) -> HRESULT {
+        *Mock::<HRESULT>::state(this)
+    }
+
+    fn mock_fence(value: u64, reason: HRESULT) -> Fence {
+        Mock::new::<d3d12::ID3D12Fence, d3d12::ID3D12FenceVtbl>(
+            MockFence { value, reason },
+            &[
+                (
+                    slot!(d3d12::ID3D12FenceVtbl, GetCompletedValue),
+                    completed_value_of as usize,
+                ),
+                (
+                    slot!(d3d12::ID3D12FenceVtbl, parent.parent.GetDevice),
+                    get_device as usize,
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn completed_value() {
+        let fence = mock_fence(5, winerror::S_OK);
+        assert_eq!(fence.completed_value(), (5, winerror::S_OK));
+    }
+
+    #[test]
+    fn completed_value_device_removed() {
+        let fence = mock_fence(u64::MAX, winerror::DXGI_ERROR_DEVICE_HUNG);
+        assert_eq!(
+            fence.completed_value(),
+            (u64::MAX, winerror::DXGI_ERROR_DEVICE_HUNG)
+        );
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn rearm_event() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (fence, hr) = device.create_fence(0);
+        assert!(winerror::SUCCEEDED(hr));
+        let (event, hr) = OwnedEvent::new(true);
+        assert!(winerror::SUCCEEDED(hr));
+
+        assert!(winerror::SUCCEEDED(
+            fence.set_event_on_completion(&event, 1)
+        ));
+        assert_eq!(event.wait(0), WaitStatus::Timeout);
+        assert!(winerror::SUCCEEDED(fence.signal(1)));
+        assert_eq!(event.wait(1000), WaitStatus::Signaled);
+
+        // The same event, rearmed for the next value.
+        assert!(event.reset());
+        assert!(winerror::SUCCEEDED(
+            fence.set_event_on_completion(&event, 2)
+        ));
+        assert_eq!(event.wait(0), WaitStatus::Timeout);
+        assert!(winerror::SUCCEEDED(fence.signal(2)));
+        assert_eq!(event.wait(1000), WaitStatus::Signaled);
+    }
+
+    #[cfg(feature = "implicit-link")]
+    #[test]
+    fn cpu_signal() {
+        let device = match crate::test_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let (fence, hr) = device.create_fence(0);
+        assert!(winerror::SUCCEEDED(hr));
+        assert_eq!(fence.completed_value(), (0, winerror::S_OK));
+        assert!(winerror::SUCCEEDED(fence.signal(3)));
+        assert_eq!(fence.completed_value(), (3, winerror::S_OK));
+    }
+}
@@ -0,0 +1,201 @@
+//! Command lists tagged with their type
+
+use crate::{
+    CmdListType, CommandAllocator, CpuDescriptor, D3DResult, Device, GraphicsCommandList,
+    IndexCount, InstanceCount, NodeMask, PipelineState, PrimitiveTopology, Rect, Resource,
+    ResourceBarrier, RootSignature, VertexCount, VertexOffset, Viewport, WorkGroupCount, HRESULT,
+};
+use std::marker::PhantomData;
+
+/// Type of a [`TypedCommandList`].
+pub trait ListKind {
+    const TYPE: CmdListType;
+}
+
+/// Lists recording draws: direct lists and bundles.
+pub trait GraphicsListKind: ListKind {}
+/// Lists recording dispatches: direct lists, compute lists and bundles.
+pub trait ComputeListKind: ListKind {}
+/// Lists recording copies and barriers: all but bundles.
+pub trait CopyListKind: ListKind {}
+
+pub enum DirectList {}
+pub enum ComputeList {}
+pub enum CopyList {}
+pub enum BundleList {}
+
+impl ListKind for DirectList {
+    const TYPE: CmdListType = CmdListType::Direct;
+}
+impl ListKind for ComputeList {
+    const TYPE: CmdListType = CmdListType::Compute;
+}
+impl ListKind for CopyList {
+    const TYPE: CmdListType = CmdListType::Copy;
+}
+impl ListKind for BundleList {
+    const TYPE: CmdListType = CmdListType::Bundle;
+}
+
+impl GraphicsListKind for DirectList {}
+impl GraphicsListKind for BundleList {}
+impl ComputeListKind for DirectList {}
+impl ComputeListKind for ComputeList {}
+impl ComputeListKind for BundleList {}
+impl CopyListKind for DirectList {}
+impl CopyListKind for ComputeList {}
+impl CopyListKind for CopyList {}
+
+/// [`GraphicsCommandList`] only exposing the commands valid for its type `K`.
+///
+/// Commands not wrapped here are reachable through [`raw`](Self::raw), their validity
+/// is then up to the caller.
+pub struct TypedCommandList<K: ListKind> {
+    list: GraphicsCommandList,
+    kind: PhantomData<K>,
+}
+
+impl Device {
+    pub fn create_typed_command_list<K: ListKind>(
+        &self,
+        allocator: &CommandAllocator,
+        initial: PipelineState,
+        node_mask: NodeMask,
+    ) -> D3DResult<TypedCommandList<K>> {
+        let (list, hr) = self.create_graphics_command_list(K::TYPE, allocator, initial, node_mask);
+        let list = TypedCommandList {
+            list,
+            kind: PhantomData,
+        };
+        (list, hr)
+    }
+}
+
+impl<K: ListKind> TypedCommandList<K> {
+    /// `list` has to be of type `K`.
+    pub unsafe fn from_raw(list: GraphicsCommandList) -> Self {
+        TypedCommandList {
+            list,
+            kind: PhantomData,
+        }
+    }
+
+    pub fn raw(&self) -> &GraphicsCommandList {
+        &self.list
+    }
+
+    pub fn into_raw(self) -> GraphicsCommandList {
+        self.list
+    }
+
+    pub fn close(&self) -> HRESULT {
+        self.list.close()
+    }
+
+    pub fn reset(&self, allocator: &CommandAllocator, initial_pso: PipelineState) -> HRESULT {
+        self.list.reset(allocator, initial_pso)
+    }
+
+    pub fn set_pipeline_state(&self, pso: &PipelineState) {
+        self.list.set_pipeline_state(pso)
+    }
+}
+
+impl<K: GraphicsListKind> TypedCommandList<K> {
+    pub fn set_graphics_root_signature(&self, signature: &RootSignature) {
+        self.list.set_graphics_root_signature(signature)
+    }
+
+    pub fn set_primitive_topology(&self, topology: PrimitiveTopology) {
+        self.list.set_primitive_topology(topology)
+    }
+
+    pub fn draw(
+        &self,
+        num_vertices: VertexCount,
+        num_instances: InstanceCount,
+        first_vertex: VertexCount,
+        first_instance: InstanceCount,
+    ) {
+        self.list
+            .draw(num_vertices, num_instances, first_vertex, first_instance)
+    }
+
+    pub fn draw_indexed(
+        &self,
+        num_indices: IndexCount,
+        num_instances: InstanceCount,
+        first_index: IndexCount,
+        base_vertex: VertexOffset,
+        first_instance: InstanceCount,
+    ) {
+        self.list.draw_indexed(
+            num_indices,
+            num_instances,
+            first_index,
+            base_vertex,
+            first_instance,
+        )
+    }
+}
+
+impl<K: ComputeListKind> TypedCommandList<K> {
+    pub fn set_compute_root_signature(&self, signature: &RootSignature) {
+        self.list.set_compute_root_signature(signature)
+    }
+
+    pub fn dispatch(&self, count: WorkGroupCount) {
+        self.list.dispatch(count)
+    }
+}
+
+impl<K: CopyListKind> TypedCommandList<K> {
+    pub fn resource_barrier(&self, barriers: &[ResourceBarrier]) {
+        self.list.resource_barrier(barriers)
+    }
+
+    pub fn copy_resource(&self, dst: &Resource, src: &Resource) {
+        self.list.copy_resource(dst, src)
+    }
+
+    pub fn copy_buffer_region(
+        &self,
+        dst: &Resource,
+        dst_offset: u64,
+        src: &Resource,
+        src_offset: u64,
+        num_bytes: u64,
+    ) {
+        self.list
+            .copy_buffer_region(dst, dst_offset, src, src_offset, num_bytes)
+    }
+}
+
+impl TypedCommandList<DirectList> {
+    /// See [`GraphicsCommandList::set_render_targets`].
+    pub fn set_render_targets(
+        &self,
+        rtvs: &[CpuDescriptor],
+        dsv: Option<CpuDescriptor>,
+        rts_single_handle_to_descriptor_range: bool,
+    ) {
+        self.list
+            .set_render_targets(rtvs, dsv, rts_single_handle_to_descriptor_range)
+    }
+
+    pub fn clear_render_target_view(&self, rtv: CpuDescriptor, color: [f32; 4], rects: &[Rect]) {
+        self.list.clear_render_target_view(rtv, color, rects)
+    }
+
+    pub fn set_viewports(&self, viewports: &[Viewport]) {
+        self.list.set_viewports(viewports)
+    }
+
+    pub fn set_scissor_rects(&self, rects: &[Rect]) {
+        self.list.set_scissor_rects(rects)
+    }
+
+    pub fn execute_bundle(&self, bundle: &TypedCommandList<BundleList>) {
+        self.list.execute_bundle(bundle.list.clone())
+    }
+}
@@ -0,0 +1,489 @@
+//! Interfaces and structures missing from `winapi`.
+
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+// use here so that the recursive RIDL macro can find the crate
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::RIDL;
+
+use winapi::{
+    ctypes::{c_char, c_void, wchar_t},
+    shared::{
+        basetsd::SIZE_T,
+        guiddef::{REFGUID, REFIID},
+        minwindef::{BOOL, UINT},
+    },
+    um::{
+        d3d12::{
+            ID3D12CommandQueue, ID3D12Device2, ID3D12Device2Vtbl, ID3D12DeviceChild,
+            ID3D12DeviceChildVtbl, ID3D12Fence, ID3D12GraphicsCommandList,
+            ID3D12GraphicsCommandList1, ID3D12GraphicsCommandList1Vtbl, ID3D12Pageable,
+            ID3D12Resource, D3D12_CLEAR_VALUE, D3D12_COMMAND_LIST_TYPE, D3D12_FEATURE,
+            D3D12_GPU_VIRTUAL_ADDRESS, D3D12_HEAP_DESC, D3D12_HEAP_FLAGS, D3D12_HEAP_PROPERTIES,
+            D3D12_QUERY_HEAP_TYPE, D3D12_RESOURCE_ALLOCATION_INFO, D3D12_RESOURCE_DESC,
+            D3D12_RESOURCE_STATES, D3D12_ROOT_SIGNATURE_FLAGS, D3D12_SHADER_VISIBILITY,
+            D3D_SHADER_MODEL,
+        },
+        d3dcommon::D3D_SHADER_INPUT_TYPE,
+        winnt::{HANDLE, HRESULT},
+    },
+};
+use winapi::{shared::dxgiformat::DXGI_FORMAT, STRUCT, UNION};
+
+/// `D3D12_ENCODE_SHADER_4_COMPONENT_MAPPING(0, 1, 2, 3)`, a function-like macro in the headers.
+pub const D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING: UINT = 0x1688;
+
+pub const D3D12_RESOURCE_STATE_RAYTRACING_ACCELERATION_STRUCTURE: D3D12_RESOURCE_STATES = 0x400000;
+pub const D3D12_RESOURCE_STATE_SHADING_RATE_SOURCE: D3D12_RESOURCE_STATES = 0x1000000;
+
+pub const D3D12_ROOT_SIGNATURE_FLAG_LOCAL_ROOT_SIGNATURE: D3D12_ROOT_SIGNATURE_FLAGS = 0x80;
+
+pub const D3D12_QUERY_HEAP_TYPE_COPY_QUEUE_TIMESTAMP: D3D12_QUERY_HEAP_TYPE = 5;
+
+pub const D3D12_FEATURE_D3D12_OPTIONS3: D3D12_FEATURE = 21;
+
+STRUCT! {
+    struct D3D12_FEATURE_DATA_D3D12_OPTIONS3 {
+        CopyQueueTimestampQueriesSupported: BOOL,
+        CastingFullyTypedFormatSupported: BOOL,
+        WriteBufferImmediateSupportFlags: UINT,
+        ViewInstancingTier: UINT,
+        BarycentricsSupported: BOOL,
+    }
+}
+
+pub const D3D12_FEATURE_D3D12_OPTIONS5: D3D12_FEATURE = 27;
+
+STRUCT! {
+    struct D3D12_FEATURE_DATA_D3D12_OPTIONS5 {
+        SRVOnlyTiledResourceTier3: BOOL,
+        RenderPassesTier: UINT,
+        RaytracingTier: UINT,
+    }
+}
+
+pub const D3D12_FEATURE_D3D12_OPTIONS7: D3D12_FEATURE = 32;
+
+STRUCT! {
+    struct D3D12_FEATURE_DATA_D3D12_OPTIONS7 {
+        MeshShaderTier: UINT,
+        SamplerFeedbackTier: UINT,
+    }
+}
+
+pub const D3D_SHADER_MODEL_6_1: D3D_SHADER_MODEL = 0x61;
+pub const D3D_SHADER_MODEL_6_2: D3D_SHADER_MODEL = 0x62;
+pub const D3D_SHADER_MODEL_6_3: D3D_SHADER_MODEL = 0x63;
+pub const D3D_SHADER_MODEL_6_4: D3D_SHADER_MODEL = 0x64;
+pub const D3D_SHADER_MODEL_6_5: D3D_SHADER_MODEL = 0x65;
+pub const D3D_SHADER_MODEL_6_6: D3D_SHADER_MODEL = 0x66;
+pub const D3D_SHADER_MODEL_6_7: D3D_SHADER_MODEL = 0x67;
+
+pub const D3D_SIT_RTACCELERATIONSTRUCTURE: D3D_SHADER_INPUT_TYPE = 12;
+pub const D3D_SIT_UAV_FEEDBACKTEXTURE: D3D_SHADER_INPUT_TYPE = 13;
+
+pub const D3D12_SHADER_VISIBILITY_AMPLIFICATION: D3D12_SHADER_VISIBILITY = 6;
+pub const D3D12_SHADER_VISIBILITY_MESH: D3D12_SHADER_VISIBILITY = 7;
+
+pub type D3D12_RESIDENCY_FLAGS = UINT;
+pub const D3D12_RESIDENCY_FLAG_NONE: D3D12_RESIDENCY_FLAGS = 0;
+pub const D3D12_RESIDENCY_FLAG_DENY_OVERBUDGET: D3D12_RESIDENCY_FLAGS = 0x1;
+
+RIDL! {#[uuid(0x81dadc15, 0x2bad, 0x4392, 0x93, 0xc5, 0x10, 0x13, 0x45, 0xc4, 0xaa, 0x98)]
+interface ID3D12Device3(ID3D12Device3Vtbl): ID3D12Device2(ID3D12Device2Vtbl) {
+    fn OpenExistingHeapFromAddress(
+        pAddress: *const c_void,
+        riid: REFIID,
+        ppvHeap: *mut *mut c_void,
+    ) -> HRESULT,
+    fn OpenExistingHeapFromFileMapping(
+        hFileMapping: HANDLE,
+        riid: REFIID,
+        ppvHeap: *mut *mut c_void,
+    ) -> HRESULT,
+    fn EnqueueMakeResident(
+        Flags: D3D12_RESIDENCY_FLAGS,
+        NumObjects: UINT,
+        ppObjects: *const *mut ID3D12Pageable,
+        pFenceToSignal: *mut ID3D12Fence,
+        FenceValueToSignal: u64,
+    ) -> HRESULT,
+}}
+
+pub type D3D12_COMMAND_LIST_FLAGS = UINT;
+pub const D3D12_COMMAND_LIST_FLAG_NONE: D3D12_COMMAND_LIST_FLAGS = 0;
+
+RIDL! {#[uuid(0xe865df17, 0xa9ee, 0x46f9, 0xa4, 0x63, 0x30, 0x98, 0x31, 0x5a, 0xa2, 0xe5)]
+interface ID3D12Device4(ID3D12Device4Vtbl): ID3D12Device3(ID3D12Device3Vtbl) {
+    fn CreateCommandList1(
+        nodeMask: UINT,
+        Type: D3D12_COMMAND_LIST_TYPE,
+        flags: D3D12_COMMAND_LIST_FLAGS,
+        riid: REFIID,
+        ppCommandList: *mut *mut c_void,
+    ) -> HRESULT,
+    fn CreateProtectedResourceSession(
+        pDesc: *const c_void,
+        riid: REFIID,
+        ppSession: *mut *mut c_void,
+    ) -> HRESULT,
+    fn CreateCommittedResource1(
+        pHeapProperties: *const D3D12_HEAP_PROPERTIES,
+        HeapFlags: D3D12_HEAP_FLAGS,
+        pDesc: *const D3D12_RESOURCE_DESC,
+        InitialResourceState: D3D12_RESOURCE_STATES,
+        pOptimizedClearValue: *const D3D12_CLEAR_VALUE,
+        pProtectedSession: *mut c_void,
+        riidResource: REFIID,
+        ppvResource: *mut *mut c_void,
+    ) -> HRESULT,
+    fn CreateHeap1(
+        pDesc: *const D3D12_HEAP_DESC,
+        pProtectedSession: *mut c_void,
+        riid: REFIID,
+        ppvHeap: *mut *mut c_void,
+    ) -> HRESULT,
+    fn CreateReservedResource1(
+        pDesc: *const D3D12_RESOURCE_DESC,
+        InitialState: D3D12_RESOURCE_STATES,
+        pOptimizedClearValue: *const D3D12_CLEAR_VALUE,
+        pProtectedSession: *mut c_void,
+        riid: REFIID,
+        ppvResource: *mut *mut c_void,
+    ) -> HRESULT,
+    fn GetResourceAllocationInfo1(
+        __ret_val: *mut D3D12_RESOURCE_ALLOCATION_INFO,
+        visibleMask: UINT,
+        numResourceDescs: UINT,
+        pResourceDescs: *const D3D12_RESOURCE_DESC,
+        pResourceAllocationInfo1: *mut c_void,
+    ) -> *mut D3D12_RESOURCE_ALLOCATION_INFO,
+}}
+
+pub type D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE = UINT;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE_TOP_LEVEL:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE = 0;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE_BOTTOM_LEVEL:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE = 1;
+
+pub type D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = UINT;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_NONE:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = 0;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_ALLOW_UPDATE:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = 0x1;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_ALLOW_COMPACTION:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = 0x2;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_PREFER_FAST_TRACE:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = 0x4;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_PREFER_FAST_BUILD:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = 0x8;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_MINIMIZE_MEMORY:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = 0x10;
+pub const D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAG_PERFORM_UPDATE:
+    D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS = 0x20;
+
+pub type D3D12_ELEMENTS_LAYOUT = UINT;
+pub const D3D12_ELEMENTS_LAYOUT_ARRAY: D3D12_ELEMENTS_LAYOUT = 0;
+pub const D3D12_ELEMENTS_LAYOUT_ARRAY_OF_POINTERS: D3D12_ELEMENTS_LAYOUT = 1;
+
+pub type D3D12_RAYTRACING_GEOMETRY_TYPE = UINT;
+pub const D3D12_RAYTRACING_GEOMETRY_TYPE_TRIANGLES: D3D12_RAYTRACING_GEOMETRY_TYPE = 0;
+pub const D3D12_RAYTRACING_GEOMETRY_TYPE_PROCEDURAL_PRIMITIVE_AABBS:
+    D3D12_RAYTRACING_GEOMETRY_TYPE = 1;
+
+pub type D3D12_RAYTRACING_GEOMETRY_FLAGS = UINT;
+pub const D3D12_RAYTRACING_GEOMETRY_FLAG_NONE: D3D12_RAYTRACING_GEOMETRY_FLAGS = 0;
+pub const D3D12_RAYTRACING_GEOMETRY_FLAG_OPAQUE: D3D12_RAYTRACING_GEOMETRY_FLAGS = 0x1;
+pub const D3D12_RAYTRACING_GEOMETRY_FLAG_NO_DUPLICATE_ANYHIT_INVOCATION:
+    D3D12_RAYTRACING_GEOMETRY_FLAGS = 0x2;
+
+STRUCT! {
+    struct D3D12_GPU_VIRTUAL_ADDRESS_AND_STRIDE {
+        StartAddress: D3D12_GPU_VIRTUAL_ADDRESS,
+        StrideInBytes: u64,
+    }
+}
+
+STRUCT! {
+    struct D3D12_RAYTRACING_GEOMETRY_TRIANGLES_DESC {
+        Transform3x4: D3D12_GPU_VIRTUAL_ADDRESS,
+        IndexFormat: DXGI_FORMAT,
+        VertexFormat: DXGI_FORMAT,
+        IndexCount: UINT,
+        VertexCount: UINT,
+        IndexBuffer: D3D12_GPU_VIRTUAL_ADDRESS,
+        VertexBuffer: D3D12_GPU_VIRTUAL_ADDRESS_AND_STRIDE,
+    }
+}
+
+STRUCT! {
+    struct D3D12_RAYTRACING_GEOMETRY_AABBS_DESC {
+        AABBCount: u64,
+        AABBs: D3D12_GPU_VIRTUAL_ADDRESS_AND_STRIDE,
+    }
+}
+
+UNION! {
+    union D3D12_RAYTRACING_GEOMETRY_DESC_u {
+        [u64; 6],
+        Triangles Triangles_mut: D3D12_RAYTRACING_GEOMETRY_TRIANGLES_DESC,
+        AABBs AABBs_mut: D3D12_RAYTRACING_GEOMETRY_AABBS_DESC,
+    }
+}
+
+STRUCT! {
+    struct D3D12_RAYTRACING_GEOMETRY_DESC {
+        Type: D3D12_RAYTRACING_GEOMETRY_TYPE,
+        Flags: D3D12_RAYTRACING_GEOMETRY_FLAGS,
+        u: D3D12_RAYTRACING_GEOMETRY_DESC_u,
+    }
+}
+
+UNION! {
+    union D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS_u {
+        [u64; 1],
+        InstanceDescs InstanceDescs_mut: D3D12_GPU_VIRTUAL_ADDRESS,
+        pGeometryDescs pGeometryDescs_mut: *const D3D12_RAYTRACING_GEOMETRY_DESC,
+        ppGeometryDescs ppGeometryDescs_mut: *const *const D3D12_RAYTRACING_GEOMETRY_DESC,
+    }
+}
+
+STRUCT! {
+    struct D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS {
+        Type: D3D12_RAYTRACING_ACCELERATION_STRUCTURE_TYPE,
+        Flags: D3D12_RAYTRACING_ACCELERATION_STRUCTURE_BUILD_FLAGS,
+        NumDescs: UINT,
+        DescsLayout: D3D12_ELEMENTS_LAYOUT,
+        u: D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS_u,
+    }
+}
+
+STRUCT! {
+    struct D3D12_RAYTRACING_ACCELERATION_STRUCTURE_PREBUILD_INFO {
+        ResultDataMaxSizeInBytes: u64,
+        ScratchDataSizeInBytes: u64,
+        UpdateScratchDataSizeInBytes: u64,
+    }
+}
+
+STRUCT! {
+    struct D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_DESC {
+        DestAccelerationStructureData: D3D12_GPU_VIRTUAL_ADDRESS,
+        Inputs: D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS,
+        SourceAccelerationStructureData: D3D12_GPU_VIRTUAL_ADDRESS,
+        ScratchAccelerationStructureData: D3D12_GPU_VIRTUAL_ADDRESS,
+    }
+}
+
+pub type D3D12_LIFETIME_STATE = UINT;
+pub const D3D12_LIFETIME_STATE_IN_USE: D3D12_LIFETIME_STATE = 0;
+pub const D3D12_LIFETIME_STATE_NOT_IN_USE: D3D12_LIFETIME_STATE = 1;
+
+RIDL! {#[uuid(0xe667af9f, 0xcd56, 0x4f46, 0x83, 0xce, 0x03, 0x2e, 0x59, 0x5d, 0x70, 0xa8)]
+interface ID3D12LifetimeOwner(ID3D12LifetimeOwnerVtbl): IUnknown(IUnknownVtbl) {
+    fn LifetimeStateUpdated(
+        NewState: D3D12_LIFETIME_STATE,
+    ) -> (),
+}}
+
+RIDL! {#[uuid(0x3fd03d36, 0x4eb1, 0x424a, 0xa5, 0x82, 0x49, 0x4e, 0xcb, 0x8b, 0xa8, 0x13)]
+interface ID3D12LifetimeTracker(ID3D12LifetimeTrackerVtbl): ID3D12DeviceChild(ID3D12DeviceChildVtbl) {
+    fn DestroyOwnedObject(
+        pObject: *mut ID3D12DeviceChild,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x8b4f173b, 0x2fea, 0x4b80, 0x8f, 0x58, 0x43, 0x07, 0x19, 0x1a, 0xb9, 0x5d)]
+interface ID3D12Device5(ID3D12Device5Vtbl): ID3D12Device4(ID3D12Device4Vtbl) {
+    fn CreateLifetimeTracker(
+        pOwner: *mut ID3D12LifetimeOwner,
+        riid: REFIID,
+        ppvTracker: *mut *mut c_void,
+    ) -> HRESULT,
+    fn RemoveDevice() -> (),
+    fn EnumerateMetaCommands(
+        pNumMetaCommands: *mut UINT,
+        pDescs: *mut c_void,
+    ) -> HRESULT,
+    fn EnumerateMetaCommandParameters(
+        CommandId: REFGUID,
+        Stage: UINT,
+        pTotalStructureSizeInBytes: *mut UINT,
+        pParameterCount: *mut UINT,
+        pParameterDescs: *mut c_void,
+    ) -> HRESULT,
+    fn CreateMetaCommand(
+        CommandId: REFGUID,
+        NodeMask: UINT,
+        pCreationParametersData: *const c_void,
+        CreationParametersDataSizeInBytes: SIZE_T,
+        riid: REFIID,
+        ppMetaCommand: *mut *mut c_void,
+    ) -> HRESULT,
+    fn CreateStateObject(
+        pDesc: *const c_void,
+        riid: REFIID,
+        ppStateObject: *mut *mut c_void,
+    ) -> HRESULT,
+    fn GetRaytracingAccelerationStructurePrebuildInfo(
+        pDesc: *const D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_INPUTS,
+        pInfo: *mut D3D12_RAYTRACING_ACCELERATION_STRUCTURE_PREBUILD_INFO,
+    ) -> (),
+    fn CheckDriverMatchingIdentifier(
+        SerializedDataType: UINT,
+        pIdentifierToCheck: *const c_void,
+    ) -> UINT,
+}}
+
+pub type D3D12_DRED_ENABLEMENT = UINT;
+pub const D3D12_DRED_ENABLEMENT_SYSTEM_CONTROLLED: D3D12_DRED_ENABLEMENT = 0;
+pub const D3D12_DRED_ENABLEMENT_FORCED_OFF: D3D12_DRED_ENABLEMENT = 1;
+pub const D3D12_DRED_ENABLEMENT_FORCED_ON: D3D12_DRED_ENABLEMENT = 2;
+
+pub type D3D12_AUTO_BREADCRUMB_OP = UINT;
+pub type D3D12_DRED_ALLOCATION_TYPE = UINT;
+
+STRUCT! {
+    struct D3D12_AUTO_BREADCRUMB_NODE {
+        pCommandListDebugNameA: *const c_char,
+        pCommandListDebugNameW: *const wchar_t,
+        pCommandQueueDebugNameA: *const c_char,
+        pCommandQueueDebugNameW: *const wchar_t,
+        pCommandList: *mut ID3D12GraphicsCommandList,
+        pCommandQueue: *mut ID3D12CommandQueue,
+        BreadcrumbCount: u32,
+        pLastBreadcrumbValue: *const u32,
+        pCommandHistory: *const D3D12_AUTO_BREADCRUMB_OP,
+        pNext: *const D3D12_AUTO_BREADCRUMB_NODE,
+    }
+}
+
+STRUCT! {
+    struct D3D12_DRED_AUTO_BREADCRUMBS_OUTPUT {
+        pHeadAutoBreadcrumbNode: *const D3D12_AUTO_BREADCRUMB_NODE,
+    }
+}
+
+STRUCT! {
+    struct D3D12_DRED_ALLOCATION_NODE {
+        ObjectNameA: *const c_char,
+        ObjectNameW: *const wchar_t,
+        AllocationType: D3D12_DRED_ALLOCATION_TYPE,
+        pNext: *const D3D12_DRED_ALLOCATION_NODE,
+    }
+}
+
+STRUCT! {
+    struct D3D12_DRED_PAGE_FAULT_OUTPUT {
+        PageFaultVA: D3D12_GPU_VIRTUAL_ADDRESS,
+        pHeadExistingAllocationNode: *const D3D12_DRED_ALLOCATION_NODE,
+        pHeadRecentFreedAllocationNode: *const D3D12_DRED_ALLOCATION_NODE,
+    }
+}
+
+RIDL! {#[uuid(0x82bc481c, 0x6b9b, 0x4030, 0xae, 0xdb, 0x7e, 0xe3, 0xd1, 0xdf, 0x1e, 0x63)]
+interface ID3D12DeviceRemovedExtendedDataSettings(ID3D12DeviceRemovedExtendedDataSettingsVtbl): IUnknown(IUnknownVtbl) {
+    fn SetAutoBreadcrumbsEnablement(
+        Enablement: D3D12_DRED_ENABLEMENT,
+    ) -> (),
+    fn SetPageFaultEnablement(
+        Enablement: D3D12_DRED_ENABLEMENT,
+    ) -> (),
+    fn SetWatsonDumpEnablement(
+        Enablement: D3D12_DRED_ENABLEMENT,
+    ) -> (),
+}}
+
+RIDL! {#[uuid(0x98931d33, 0x5ae8, 0x4791, 0xaa, 0x3c, 0x1a, 0x73, 0xa2, 0x93, 0x4e, 0x71)]
+interface ID3D12DeviceRemovedExtendedData(ID3D12DeviceRemovedExtendedDataVtbl): IUnknown(IUnknownVtbl) {
+    fn GetAutoBreadcrumbsOutput(
+        pOutput: *mut D3D12_DRED_AUTO_BREADCRUMBS_OUTPUT,
+    ) -> HRESULT,
+    fn GetPageFaultAllocationOutput(
+        pOutput: *mut D3D12_DRED_PAGE_FAULT_OUTPUT,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x38c3e585, 0xff17, 0x412c, 0x91, 0x50, 0x4f, 0xc6, 0xf9, 0xd7, 0x2a, 0x28)]
+interface ID3D12GraphicsCommandList2(ID3D12GraphicsCommandList2Vtbl):
+    ID3D12GraphicsCommandList1(ID3D12GraphicsCommandList1Vtbl) {
+    fn WriteBufferImmediate(
+        Count: UINT,
+        pParams: *const c_void,
+        pModes: *const UINT,
+    ) -> (),
+}}
+
+RIDL! {#[uuid(0x6fda83a7, 0xb84c, 0x4e38, 0x9a, 0xc8, 0xc7, 0xbd, 0x22, 0x01, 0x6b, 0x3d)]
+interface ID3D12GraphicsCommandList3(ID3D12GraphicsCommandList3Vtbl):
+    ID3D12GraphicsCommandList2(ID3D12GraphicsCommandList2Vtbl) {
+    fn SetProtectedResourceSession(
+        pProtectedResourceSession: *mut c_void,
+    ) -> (),
+}}
+
+RIDL! {#[uuid(0x8754318e, 0xd3a9, 0x4541, 0x98, 0xcf, 0x64, 0x5b, 0x50, 0xdc, 0x48, 0x74)]
+interface ID3D12GraphicsCommandList4(ID3D12GraphicsCommandList4Vtbl):
+    ID3D12GraphicsCommandList3(ID3D12GraphicsCommandList3Vtbl) {
+    fn BeginRenderPass(
+        NumRenderTargets: UINT,
+        pRenderTargets: *const c_void,
+        pDepthStencil: *const c_void,
+        Flags: UINT,
+    ) -> (),
+    fn EndRenderPass() -> (),
+    fn InitializeMetaCommand(
+        pMetaCommand: *mut c_void,
+        pInitializationParametersData: *const c_void,
+        InitializationParametersDataSizeInBytes: SIZE_T,
+    ) -> (),
+    fn ExecuteMetaCommand(
+        pMetaCommand: *mut c_void,
+        pExecutionParametersData: *const c_void,
+        ExecutionParametersDataSizeInBytes: SIZE_T,
+    ) -> (),
+    fn BuildRaytracingAccelerationStructure(
+        pDesc: *const D3D12_BUILD_RAYTRACING_ACCELERATION_STRUCTURE_DESC,
+        NumPostbuildInfoDescs: UINT,
+        pPostbuildInfoDescs: *const c_void,
+    ) -> (),
+    fn EmitRaytracingAccelerationStructurePostbuildInfo(
+        pDesc: *const c_void,
+        NumSourceAccelerationStructures: UINT,
+        pSourceAccelerationStructureData: *const D3D12_GPU_VIRTUAL_ADDRESS,
+    ) -> (),
+    fn CopyRaytracingAccelerationStructure(
+        DestAccelerationStructureData: D3D12_GPU_VIRTUAL_ADDRESS,
+        SourceAccelerationStructureData: D3D12_GPU_VIRTUAL_ADDRESS,
+        Mode: UINT,
+    ) -> (),
+    fn SetPipelineState1(
+        pStateObject: *mut c_void,
+    ) -> (),
+    fn DispatchRays(
+        pDesc: *const c_void,
+    ) -> (),
+}}
+
+RIDL! {#[uuid(0x55050859, 0x4024, 0x474c, 0x87, 0xf5, 0x64, 0x72, 0xea, 0xee, 0x44, 0xea)]
+interface ID3D12GraphicsCommandList5(ID3D12GraphicsCommandList5Vtbl):
+    ID3D12GraphicsCommandList4(ID3D12GraphicsCommandList4Vtbl) {
+    fn RSSetShadingRate(
+        baseShadingRate: UINT,
+        combiners: *const UINT,
+    ) -> (),
+    fn RSSetShadingRateImage(
+        shadingRateImage: *mut ID3D12Resource,
+    ) -> (),
+}}
+
+RIDL! {#[uuid(0xc3827890, 0xe548, 0x4cfa, 0x96, 0xcf, 0x56, 0x89, 0xa9, 0x37, 0x0f, 0x80)]
+interface ID3D12GraphicsCommandList6(ID3D12GraphicsCommandList6Vtbl):
+    ID3D12GraphicsCommandList5(ID3D12GraphicsCommandList5Vtbl) {
+    fn DispatchMesh(
+        ThreadGroupCountX: UINT,
+        ThreadGroupCountY: UINT,
+        ThreadGroupCountZ: UINT,
+    ) -> (),
+}}
@@ -0,0 +1,228 @@
+//! Ring buffer for per-frame upload data
+
+use crate::{CommittedResourceBuilder, D3DResult, Device, GpuAddress, Resource};
+use std::{collections::VecDeque, slice};
+use winapi::shared::winerror;
+
+/// Persistently mapped upload buffer, sub-allocated in a ring.
+///
+/// Allocations of a frame stay valid until the fence value given to
+/// [`end_frame`](Self::end_frame) is reached, as reported to a later
+/// [`begin_frame`](Self::begin_frame).
+pub struct UploadRing {
+    resource: Resource,
+    data: *mut u8,
+    gpu_address: GpuAddress,
+    capacity: u64,
+    /// Offsets only ever grow, the position in the buffer is the offset modulo the capacity.
+    head: u64,
+    tail: u64,
+    /// Fence value and end offset of the frames possibly still read by the GPU.
+    frames: VecDeque<(u64, u64)>,
+}
+
+impl UploadRing {
+    /// Creates and maps an upload buffer of `capacity` bytes.
+    pub fn new(device: &Device, capacity: u64) -> D3DResult<Option<Self>> {
+        let (resource, hr) =
+            device.create_committed_resource(CommittedResourceBuilder::upload_buffer(capacity));
+        if winerror::FAILED(hr) {
+            return (None, hr);
+        }
+
+        // Nothing is ever read back on the CPU.
+        let (data, hr) = resource.map(0, Some(0..0));
+        if winerror::FAILED(hr) {
+            return (None, hr);
+        }
+
+        let ring = UploadRing {
+            gpu_address: resource.gpu_virtual_address(),
+            resource,
+            data: data as *mut u8,
+            capacity,
+            head: 0,
+            tail: 0,
+            frames: VecDeque::new(),
+        };
+        (Some(ring), hr)
+    }
+
+    pub fn resource(&self) -> &Resource {
+        &self.resource
+    }
+
+    /// Frees the allocations of all frames with a fence value up to `completed_value`.
+    pub fn begin_frame(&mut self, completed_value: u64) {
+        while let Some(&(value, end)) = self.frames.front() {
+            if value > completed_value {
+                break;
+            }
+            self.tail = end;
+            self.frames.pop_front();
+        }
+    }
+
+    /// Marks the allocations made so far as in use until the GPU reaches `fence_value`.
+    pub fn end_frame(&mut self, fence_value: u64) {
+        self.frames.push_back((fence_value, self.head));
+    }
+
+    /// Allocates `size` bytes aligned to `align`, `None` if the ring is full.
+    ///
+    /// Allocations never straddle the end of the buffer, the rest is skipped instead.
+    pub fn allocate(&mut self, size: usize, align: usize) -> Option<(GpuAddress, &mut [u8])> {
+        debug_assert!(align > 0);
+        let (size, align) = (size as u64, align as u64);
+        if size > self.capacity {
+            return None;
+        }
+
+        let wraps = self.head / self.capacity;
+        let mut position = (self.head % self.capacity + align - 1) / align * align;
+        let mut start = wraps * self.capacity + position;
+        if position + size > self.capacity {
+            position = 0;
+            start = (wraps + 1) * self.capacity;
+        }
+        if start + size - self.tail > self.capacity {
+            return None;
+        }
+
+        self.head = start + size;
+        let data =
+            unsafe { slice::from_raw_parts_mut(self.data.add(position as usize), size as usize) };
+        Some((self.gpu_address.offset(position), data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{slot, Mock};
+    use std::cell::UnsafeCell;
+    use winapi::{
+        ctypes::c_void,
+        shared::{guiddef::REFIID, winerror::HRESULT},
+        um::d3d12,
+    };
+
+    const BASE: u64 = 0x10_0000;
+
+    struct MappedBuffer {
+        data: UnsafeCell<Box<[u8]>>,
+    }
+
+    unsafe extern "system" fn map(
+        this: *mut d3d12::ID3D12Resource,
+        _subresource: u32,
+        _read_range: *const d3d12::D3D12_RANGE,
+        data: *mut *mut c_void,
+    ) -> HRESULT {
+        let buffer = Mock::<MappedBuffer>::state(this);
+        *data = (*buffer.data.get()).as_mut_ptr() as *mut c_void;
+        winerror::S_OK
+    }
+
+    unsafe extern "system" fn get_gpu_virtual_address(
+        _this: *mut d3d12::ID3D12Resource,
+    ) -> d3d12::D3D12_GPU_VIRTUAL_ADDRESS {
+        BASE
+    }
+
+    unsafe extern "system" fn create_committed_resource(
+        _this: *mut d3d12::ID3D12Device,
+        _heap_properties: *const d3d12::D3D12_HEAP_PROPERTIES,
+        _heap_flags: d3d12::D3D12_HEAP_FLAGS,
+        desc: *const d3d12::D3D12_RESOURCE_DESC,
+        _initial_state: d3d12::D3D12_RESOURCE_STATES,
+        _clear_value: *const d3d12::D3D12_CLEAR_VALUE,
+        _riid: REFIID,
+        resource: *mut *mut c_void,
+    ) -> HRESULT {
+        let created = Mock::new::<d3d12::ID3D12Resource, d3d12::ID3D12ResourceVtbl>(
+            MappedBuffer {
+                data: UnsafeCell::new(vec![0; (*desc).Width as usize].into_boxed_slice()),
+            },
+            &[
+                (slot!(d3d12::ID3D12ResourceVtbl, Map), map as usize),
+                (
+                    slot!(d3d12::ID3D12ResourceVtbl, GetGPUVirtualAddress),
+                    get_gpu_virtual_address as usize,
+                ),
+            ],
+        );
+        *resource = created.as_mut_ptr() as *mut c_void;
+        std::mem::forget(created);
+        winerror::S_OK
+    }
+
+    fn ring(capacity: u64) -> UploadRing {
+        let device = Mock::new::<d3d12::ID3D12Device, d3d12::ID3D12DeviceVtbl>(
+            (),
+            &[(
+                slot!(d3d12::ID3D12DeviceVtbl, CreateCommittedResource),
+                create_committed_resource as usize,
+            )],
+        );
+        let (ring, hr) = UploadRing::new(&device, capacity);
+        assert_eq!(hr, winerror::S_OK);
+        ring.unwrap()
+    }
+
+    /// Offset of `data` in the mapped memory of `ring`.
+    fn offset_of(ring: &UploadRing, data: *const u8) -> usize {
+        let buffer = unsafe { Mock::<MappedBuffer>::state(ring.resource().as_ptr()) };
+        data as usize - unsafe { (*buffer.data.get()).as_ptr() } as usize
+    }
+
+    #[test]
+    fn allocation() {
+        let mut ring = ring(1024);
+        let (address, data) = ring.allocate(100, 1).unwrap();
+        assert_eq!(address, GpuAddress(BASE));
+        assert_eq!(data.len(), 100);
+        data.fill(0xAB);
+
+        let (address, data) = ring.allocate(24, 4).unwrap();
+        assert_eq!(address, GpuAddress(BASE + 100));
+        assert_eq!(data.len(), 24);
+        assert!(data.iter().all(|&byte| byte == 0));
+        let data = data.as_ptr();
+        assert_eq!(offset_of(&ring, data), 100);
+
+        assert!(ring.allocate(2048, 1).is_none());
+    }
+
+    #[test]
+    fn alignment() {
+        let mut ring = ring(1024);
+        ring.allocate(1, 1).unwrap();
+        let (address, data) = ring.allocate(16, 256).unwrap();
+        let data = data.as_ptr();
+        assert_eq!(address, GpuAddress(BASE + 256));
+        assert!(address.is_aligned(256));
+        assert_eq!(offset_of(&ring, data), 256);
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut ring = ring(1024);
+        assert_eq!(ring.allocate(512, 1).unwrap().0, GpuAddress(BASE));
+        assert_eq!(ring.allocate(256, 1).unwrap().0, GpuAddress(BASE + 512));
+        ring.end_frame(1);
+
+        // Doesn't fit before the end, and the start is still in use by frame 1.
+        ring.begin_frame(0);
+        assert!(ring.allocate(512, 1).is_none());
+
+        ring.begin_frame(1);
+        let (address, data) = ring.allocate(512, 1).unwrap();
+        let data = data.as_ptr();
+        assert_eq!(address, GpuAddress(BASE));
+        assert_eq!(offset_of(&ring, data), 0);
+        // Frame 1 is done, the end of the buffer can be reused.
+        assert_eq!(ring.allocate(256, 1).unwrap().0, GpuAddress(BASE + 512));
+        assert!(ring.allocate(512, 1).is_none());
+    }
+}
@@ -0,0 +1,8 @@
+use d3d12::{ComputeList, TypedCommandList};
+
+// Draws are only valid on direct lists and bundles.
+fn record(list: TypedCommandList<ComputeList>) {
+    list.draw(3, 1, 0, 0);
+}
+
+fn main() {}
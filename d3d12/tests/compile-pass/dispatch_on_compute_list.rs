@@ -0,0 +1,9 @@
+use d3d12::{ComputeList, TypedCommandList};
+
+fn record(list: TypedCommandList<ComputeList>) {
+    list.dispatch([1, 1, 1]);
+}
+
+fn main() {
+    let _ = record;
+}
@@ -0,0 +1,8 @@
+#![cfg(windows)]
+
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/compile-pass/*.rs");
+    t.compile_fail("tests/compile-fail/*.rs");
+}
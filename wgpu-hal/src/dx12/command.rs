@@ -895,7 +895,7 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
             log::trace!("\tBind element[{}] = dynamic", root_index);
             self.pass.root_elements[root_index] = super::RootElement::DynamicOffsetBuffer {
                 kind,
-                address: gpu_base + offset as d3d12::GpuAddress,
+                address: gpu_base.offset(offset as u64),
             };
             root_index += 1;
         }
@@ -986,7 +986,7 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
         format: wgt::IndexFormat,
     ) {
         self.list.as_ref().unwrap().set_index_buffer(
-            binding.resolve_address(),
+            d3d12::GpuAddress(binding.resolve_address()),
             binding.resolve_size() as u32,
             auxil::dxgi::conv::map_index_format(format),
         );
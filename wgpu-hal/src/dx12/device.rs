@@ -41,6 +41,8 @@ impl super::Device {
             )
         };
         hr.into_device_result("Idle fence creation")?;
+        let (idle_event, hr) = d3d12::OwnedEvent::new(false);
+        hr.into_device_result("Idle event creation")?;
 
         let mut zero_buffer = d3d12::Resource::null();
         unsafe {
@@ -150,7 +152,7 @@ impl super::Device {
             present_queue,
             idler: super::Idler {
                 fence: idle_fence,
-                event: d3d12::Event::create(false, false),
+                event: idle_event,
             },
             private_caps,
             shared: Arc::new(shared),
@@ -191,9 +193,9 @@ impl super::Device {
         let hr = self
             .idler
             .fence
-            .set_event_on_completion(self.idler.event, value);
+            .set_event_on_completion(&self.idler.event, value);
         hr.into_device_result("Set event")?;
-        unsafe { synchapi::WaitForSingleObject(self.idler.event.0, winbase::INFINITE) };
+        unsafe { synchapi::WaitForSingleObject(self.idler.event.event().0, winbase::INFINITE) };
         Ok(())
     }
 
@@ -1007,7 +1009,7 @@ impl crate::Device<super::Api> for super::Device {
         log::trace!("{:#?}", parameters);
         log::trace!("Bindings {:#?}", binding_map);
 
-        let (blob, error) = self
+        let blob = self
             .library
             .serialize_root_signature(
                 d3d12::RootSignatureVersion::V1_0,
@@ -1019,15 +1021,14 @@ impl crate::Device<super::Api> for super::Device {
                 log::error!("Unable to find serialization function: {:?}", e);
                 crate::DeviceError::Lost
             })?
-            .into_device_result("Root signature serialization")?;
-
-        if !error.is_null() {
-            log::error!(
-                "Root signature serialization error: {:?}",
-                unsafe { error.as_c_str() }.to_str().unwrap()
-            );
-            return Err(crate::DeviceError::Lost);
-        }
+            .map_err(|e| {
+                log::error!("Root signature serialization failed: {}", e);
+                if e.hr == winerror::E_OUTOFMEMORY {
+                    crate::DeviceError::OutOfMemory
+                } else {
+                    crate::DeviceError::Lost
+                }
+            })?;
 
         let raw = self
             .raw
@@ -1097,7 +1098,7 @@ impl crate::Device<super::Api> for super::Device {
                     let start = entry.resource_index as usize;
                     let end = start + entry.count as usize;
                     for data in &desc.buffers[start..end] {
-                        dynamic_buffers.push(data.resolve_address());
+                        dynamic_buffers.push(d3d12::GpuAddress(data.resolve_address()));
                     }
                 }
                 wgt::BindingType::Buffer { ty, .. } => {
@@ -1540,10 +1541,10 @@ impl crate::Device<super::Api> for super::Device {
         if unsafe { fence.raw.GetCompletedValue() } >= value {
             return Ok(true);
         }
-        let hr = fence.raw.set_event_on_completion(self.idler.event, value);
+        let hr = fence.raw.set_event_on_completion(&self.idler.event, value);
         hr.into_device_result("Set event")?;
 
-        match unsafe { synchapi::WaitForSingleObject(self.idler.event.0, timeout_ms) } {
+        match unsafe { synchapi::WaitForSingleObject(self.idler.event.event().0, timeout_ms) } {
             winbase::WAIT_ABANDONED | winbase::WAIT_FAILED => Err(crate::DeviceError::Lost),
             winbase::WAIT_OBJECT_0 => Ok(true),
             winerror::WAIT_TIMEOUT => Ok(false),
@@ -220,7 +220,7 @@ unsafe impl Sync for Adapter {}
 /// Helper structure for waiting for GPU.
 struct Idler {
     fence: d3d12::Fence,
-    event: d3d12::Event,
+    event: d3d12::OwnedEvent,
 }
 
 struct CommandSignatures {
@@ -409,7 +409,7 @@ impl crate::BufferBinding<'_, Api> {
     }
 
     fn resolve_address(&self) -> wgt::BufferAddress {
-        self.buffer.resource.gpu_virtual_address() + self.offset
+        self.buffer.resource.gpu_virtual_address().raw() + self.offset
     }
 }
 